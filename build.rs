@@ -8,4 +8,6 @@ fn main() {
         .include(&dir)
         .file(dir.join("parser.c"))
         .compile("tree-sitter-sexp");
+
+    println!("cargo:rerun-if-changed={}", dir.join("parser.c").display());
 }
@@ -28,12 +28,22 @@ const delim = (open, x, close) => seq(open, x, close);
 module.exports = grammar({
   name: "sexp",
 
+  extras: ($) => [/\s/, $.comment],
+
   rules: {
     sexp: ($) => $._sexp,
 
     _sexp: ($) => choice($.atom, $.list),
 
-    atom: ($) => /[_@a-zA-Z0-9\xC0-\xD6\xD8-\xDE\xDF-\xF6\xF8-\xFF:-]+/,
+    atom: ($) =>
+      token(
+        choice(
+          /[_@a-zA-Z0-9\xC0-\xD6\xD8-\xDE\xDF-\xF6\xF8-\xFF:!$%&*+./<=>?^~-]+/,
+          /"([^"\\]|\\.)*"/
+        )
+      ),
     list: ($) => delim(PARENS_LEFT, repeat($._sexp), PARENS_RIGHT),
+
+    comment: ($) => token(seq(";", /.*/)),
   },
 });
@@ -0,0 +1,171 @@
+use crate::{Sexp, SexpPath};
+
+/// A reference to a node that doesn't depend on byte offsets or layout, so
+/// it can be stored and looked up again after the file was reformatted or
+/// lightly edited.
+///
+/// The fingerprint is the chain of list heads from the root down to the
+/// node, the node's first argument (which is what usually names a stanza,
+/// as in `(rule (name foo))`), its index among its siblings, and a hash of
+/// the whole subtree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Anchor {
+    heads: Vec<String>,
+    key: Option<String>,
+    index: usize,
+    hash: u64,
+}
+
+struct Candidate {
+    path: SexpPath,
+    key: Option<String>,
+    hash: u64,
+}
+
+fn head_of(node: &Sexp) -> String {
+    match node {
+        Sexp::Atom(atom) => atom.clone(),
+        _ => node.head().unwrap_or("").to_string(),
+    }
+}
+
+fn key_of(node: &Sexp) -> Option<String> {
+    node.items().get(1).map(Sexp::to_compact_string)
+}
+
+// FNV-1a over the compact rendering, which unlike `DefaultHasher` is stable
+// across Rust releases, so anchors can be persisted.
+fn hash_of(node: &Sexp) -> u64 {
+    node.to_compact_string()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+impl Anchor {
+    /// Captures the node at `path` in `root`, or `None` if there's no node
+    /// there.
+    pub fn from_node(root: &Sexp, path: &SexpPath) -> Option<Anchor> {
+        let node = root.get(path)?;
+        let mut heads = vec![head_of(root)];
+        let mut current = root;
+        for &index in path.indices() {
+            current = &current.items()[index];
+            heads.push(head_of(current));
+        }
+
+        Some(Anchor {
+            heads,
+            key: key_of(node),
+            index: path.indices().last().copied().unwrap_or(0),
+            hash: hash_of(node),
+        })
+    }
+
+    /// Finds the anchored node in a (possibly newer) version of the tree.
+    /// Nodes with the same head chain and an identical subtree win; failing
+    /// that, a node with the same head chain and first argument will do, so
+    /// edits inside the stanza are tolerated. Ties go to the sibling index
+    /// closest to the original one. Returns `None` if nothing matches, e.g.
+    /// when the stanza was deleted.
+    pub fn resolve(&self, root: &Sexp) -> Option<SexpPath> {
+        let mut candidates = vec![];
+        self.collect(root, SexpPath::root(), 0, &mut candidates);
+
+        let nearest = |matches: Vec<Candidate>| {
+            matches
+                .into_iter()
+                .min_by_key(|c| {
+                    let index = c.path.indices().last().copied().unwrap_or(0);
+                    (index as isize - self.index as isize).abs()
+                })
+                .map(|c| c.path)
+        };
+
+        let (exact, others): (Vec<Candidate>, Vec<Candidate>) =
+            candidates.into_iter().partition(|c| c.hash == self.hash);
+        nearest(exact)
+            .or_else(|| nearest(others.into_iter().filter(|c| c.key == self.key).collect()))
+    }
+
+    fn collect(&self, node: &Sexp, path: SexpPath, depth: usize, out: &mut Vec<Candidate>) {
+        if self.heads.get(depth) != Some(&head_of(node)) {
+            return;
+        }
+        if depth + 1 == self.heads.len() {
+            out.push(Candidate {
+                key: key_of(node),
+                hash: hash_of(node),
+                path,
+            });
+            return;
+        }
+        for (index, child) in node.items().iter().enumerate() {
+            self.collect(child, path.child(index), depth + 1, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUNE: &str = "(dune
+      (library (name core) (libraries base))
+      (rule (name foo) (deps a.txt b.txt) (action (run gen)))
+      (rule (name bar) (deps c.txt) (action (run gen))))";
+
+    fn foo_anchor() -> (Sexp, Anchor) {
+        let original = Sexp::of_str(DUNE).unwrap();
+        let path = SexpPath::from(vec![2]);
+        assert_eq!(
+            original.get(&vec![2, 1, 1].into()).unwrap().to_string(),
+            "foo"
+        );
+        let anchor = Anchor::from_node(&original, &path).unwrap();
+        (original, anchor)
+    }
+
+    #[test]
+    fn test_resolve_after_reformatting() {
+        let (original, anchor) = foo_anchor();
+        let reformatted = Sexp::of_str(&original.to_string()).unwrap();
+        assert_eq!(anchor.resolve(&reformatted), Some(vec![2].into()));
+    }
+
+    #[test]
+    fn test_resolve_after_inserting_above() {
+        let (_, anchor) = foo_anchor();
+        let edited = Sexp::of_str(&DUNE.replace(
+            "(rule (name foo)",
+            "(rule (name new) (action (run other)))\n(rule (name foo)",
+        ))
+        .unwrap();
+        assert_eq!(anchor.resolve(&edited), Some(vec![3].into()));
+    }
+
+    #[test]
+    fn test_resolve_after_editing_inside() {
+        let (_, anchor) = foo_anchor();
+        let edited = Sexp::of_str(&DUNE.replace("(deps a.txt b.txt)", "(deps a.txt)")).unwrap();
+        assert_eq!(anchor.resolve(&edited), Some(vec![2].into()));
+    }
+
+    #[test]
+    fn test_resolve_after_deleting() {
+        let (_, anchor) = foo_anchor();
+        let edited = Sexp::of_str(&DUNE.replace(
+            "(rule (name foo) (deps a.txt b.txt) (action (run gen)))",
+            "",
+        ))
+        .unwrap();
+        assert_eq!(anchor.resolve(&edited), None);
+    }
+
+    #[test]
+    fn test_from_node_out_of_bounds() {
+        let original = Sexp::of_str(DUNE).unwrap();
+        assert!(Anchor::from_node(&original, &vec![9].into()).is_none());
+    }
+}
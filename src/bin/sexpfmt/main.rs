@@ -0,0 +1,206 @@
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
+use std::time::Duration;
+use tree_sitter_sexp::{
+    format_in_place, format_str, merge3, minimize_with_budget, Budget, MergeOptions, Sexp,
+};
+
+mod server;
+
+const USAGE: &str = "usage: sexpfmt [--in-place [--backup SUFFIX]] [FILE...]
+       sexpfmt minimize --cmd CMD [--max-tests N] [--timeout SECS] [FILE]
+       sexpfmt merge [--union HEAD]... BASE OURS THEIRS
+       sexpfmt --stdin-server";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn read_input(file: Option<&PathBuf>) -> io::Result<String> {
+    match file {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+// A candidate is interesting when `sh -c CMD` exits successfully with the
+// candidate on its stdin, as with creduce and friends.
+fn run_predicate(cmd: &str, candidate: &str) -> bool {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            eprintln!("sexpfmt: could not run {:?}: {}", cmd, err);
+            process::exit(1)
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The command may well exit without reading everything.
+        let _ = stdin.write_all(candidate.as_bytes());
+    }
+    child.wait().map(|status| status.success()).unwrap_or(false)
+}
+
+fn minimize_main(mut args: impl Iterator<Item = String>) {
+    let mut cmd = None;
+    let mut budget = Budget::default();
+    let mut file = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--cmd" => cmd = Some(value()),
+            "--max-tests" => budget.max_tests = value().parse().unwrap_or_else(|_| usage()),
+            "--timeout" => {
+                let secs = value().parse().unwrap_or_else(|_| usage());
+                budget.max_time = Duration::from_secs(secs);
+            }
+            _ if arg.starts_with('-') || file.is_some() => usage(),
+            _ => file = Some(PathBuf::from(arg)),
+        }
+    }
+    let cmd = cmd.unwrap_or_else(|| usage());
+
+    let input = read_input(file.as_ref()).unwrap_or_else(|err| {
+        eprintln!("sexpfmt: {}", err);
+        process::exit(1)
+    });
+    if !run_predicate(&cmd, &input) {
+        eprintln!("sexpfmt: the input doesn't satisfy {:?}", cmd);
+        process::exit(1);
+    }
+    println!(
+        "{}",
+        minimize_with_budget(&input, |candidate| run_predicate(&cmd, candidate), budget)
+    );
+}
+
+// Exits with 1 when there are conflicts, like `git merge-file`, so it can be
+// used as a merge driver.
+fn merge_main(mut args: impl Iterator<Item = String>) {
+    let mut options = MergeOptions::default();
+    let mut files = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--union" => {
+                let head = args.next().unwrap_or_else(|| usage());
+                options.union_heads.insert(head);
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.len() != 3 {
+        usage();
+    }
+
+    let mut sexps = files.iter().map(|path| {
+        read_input(Some(path))
+            .map_err(Into::into)
+            .and_then(|input| Sexp::of_str(&input))
+            .unwrap_or_else(|err| {
+                eprintln!("sexpfmt: {}: {:#}", path.display(), err);
+                process::exit(2)
+            })
+    });
+    let (base, ours, theirs) = (
+        sexps.next().unwrap(),
+        sexps.next().unwrap(),
+        sexps.next().unwrap(),
+    );
+    let result = merge3(&base, &ours, &theirs, &options);
+    print!("{}", result.to_string_with_markers());
+    if !result.is_clean() {
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let mut in_place = false;
+    let mut backup = None;
+    let mut files = vec![];
+
+    let mut args = std::env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("minimize") => {
+            args.next();
+            return minimize_main(args);
+        }
+        Some("merge") => {
+            args.next();
+            return merge_main(args);
+        }
+        _ => {}
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-i" | "--in-place" => in_place = true,
+            "--backup" => backup = Some(args.next().unwrap_or_else(|| usage())),
+            "--stdin-server" => {
+                let stdin = io::stdin();
+                if let Err(err) = server::serve(&mut stdin.lock(), &mut io::stdout()) {
+                    eprintln!("sexpfmt: {}", err);
+                    process::exit(1);
+                }
+                return;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    if backup.is_some() && !in_place {
+        usage();
+    }
+
+    if in_place {
+        let failures = format_in_place(&files, backup.as_deref());
+        for (path, err) in &failures {
+            eprintln!("sexpfmt: {}: {:#}", path.display(), err);
+        }
+        if !failures.is_empty() {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut failed = false;
+    let inputs: Vec<(String, io::Result<String>)> = if files.is_empty() {
+        vec![("<stdin>".to_string(), read_input(None))]
+    } else {
+        files
+            .iter()
+            .map(|path| (path.display().to_string(), read_input(Some(path))))
+            .collect()
+    };
+    for (name, input) in inputs {
+        match input
+            .map_err(Into::into)
+            .and_then(|input| format_str(&input))
+        {
+            Ok(formatted) => print!("{}", formatted),
+            Err(err) => {
+                eprintln!("sexpfmt: {}: {:#}", name, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
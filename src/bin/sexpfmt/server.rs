@@ -0,0 +1,217 @@
+//! `sexpfmt --stdin-server`: a long-running formatter for editors.
+//!
+//! Requests and responses are JSON objects framed as in LSP, each preceded
+//! by a `Content-Length: N` header and a blank line. A request looks like
+//!
+//! ```text
+//! {"id": 1, "op": "format", "document": "dune", "text": "(a b)",
+//!  "config": {"max_width": 80, "indent_size": 2, "align_trailing_comments": true}}
+//! ```
+//!
+//! where `op` is `format`, `check` or `query` (with a `"path"` such as
+//! `"/2/0"`). With a `document` id the text is kept between requests, and
+//! later requests can send `"edits": [{"start": 0, "end": 2, "text": "x"}]`
+//! (byte offsets, applied in order) instead of the whole text. The response
+//! is `{"id": 1, "result": ...}` or `{"id": 1, "error": "..."}`.
+
+use anyhow::{anyhow, Context, Error};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Read, Write};
+use tree_sitter_sexp::{Document, PrettyPrinter, SexpPath};
+
+/// The largest frame body read, so a bad header can't make the server
+/// allocate without bound.
+const MAX_FRAME: usize = 64 << 20;
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, Document>,
+    // Reused for requests that carry their own text and no document id.
+    scratch: Option<Document>,
+}
+
+impl Server {
+    fn handle(&mut self, request: &Map<String, Value>) -> Result<Value, Error> {
+        let text = match request.get("text") {
+            None | Some(Value::Null) => None,
+            Some(text) => Some(text.as_str().context("\"text\" must be a string")?),
+        };
+        let document = match request.get("document") {
+            None | Some(Value::Null) => {
+                let text = text.context("A request needs \"text\" or a \"document\"")?;
+                let scratch = self.scratch.get_or_insert_with(|| Document::new(""));
+                scratch.set_text(text);
+                scratch
+            }
+            Some(Value::String(id)) => {
+                if let Some(text) = text {
+                    match self.documents.get_mut(id) {
+                        Some(document) => document.set_text(text),
+                        None => {
+                            self.documents.insert(id.clone(), Document::new(text));
+                        }
+                    }
+                }
+                let document = self
+                    .documents
+                    .get_mut(id)
+                    .with_context(|| format!("Unknown document {:?}", id))?;
+                if let Some(edits) = request.get("edits") {
+                    apply_edits(document, edits)?;
+                }
+                document
+            }
+            Some(_) => return Err(anyhow!("\"document\" must be a string")),
+        };
+
+        let printer = printer(request.get("config"))?;
+        let (sexp, comments) = document.form()?;
+        let formatted = || format!("{}\n", printer.print_with_comments(&sexp, &comments));
+        match request.get("op").and_then(Value::as_str) {
+            Some("format") => Ok(json!(formatted())),
+            Some("check") => Ok(json!(document.text() == formatted())),
+            Some("query") => {
+                let path: SexpPath = request
+                    .get("path")
+                    .and_then(Value::as_str)
+                    .context("\"query\" needs a \"path\"")?
+                    .parse()?;
+                let node = sexp
+                    .get(&path)
+                    .with_context(|| format!("Nothing at {}", path))?;
+                Ok(json!(printer.print(node)))
+            }
+            Some(op) => Err(anyhow!("Unknown op {:?}", op)),
+            None => Err(anyhow!("A request needs an \"op\"")),
+        }
+    }
+}
+
+fn apply_edits(document: &mut Document, edits: &Value) -> Result<(), Error> {
+    let edits = edits.as_array().context("\"edits\" must be a list")?;
+    for edit in edits {
+        let offset = |key| {
+            edit.get(key)
+                .and_then(Value::as_u64)
+                .map(|offset| offset as usize)
+                .with_context(|| format!("An edit needs a {:?} offset", key))
+        };
+        let text = edit.get("text").and_then(Value::as_str).unwrap_or("");
+        document.edit(offset("start")?..offset("end")?, text)?;
+    }
+    Ok(())
+}
+
+fn printer(config: Option<&Value>) -> Result<PrettyPrinter, Error> {
+    let mut printer = PrettyPrinter::new();
+    let config = match config {
+        None | Some(Value::Null) => return Ok(printer),
+        Some(config) => config.as_object().context("\"config\" must be an object")?,
+    };
+    for (key, value) in config {
+        if key == "align_trailing_comments" {
+            let align = value
+                .as_bool()
+                .with_context(|| format!("{:?} must be true or false", key))?;
+            printer = printer.with_align_trailing_comments(align);
+            continue;
+        }
+        let value = value
+            .as_u64()
+            .and_then(|value| u32::try_from(value).ok())
+            .with_context(|| format!("{:?} must be a small positive number", key))?;
+        printer = match key.as_str() {
+            "max_width" => printer.with_max_width(value),
+            "indent_size" => printer.with_indent_size(value),
+            _ => return Err(anyhow!("Unknown config key {:?}", key)),
+        };
+    }
+    Ok(printer)
+}
+
+/// Reads the next frame's body, or `None` at the end of the input. A frame
+/// with a bad header still has its header consumed, and one longer than
+/// `MAX_FRAME` its body skipped, so the next one can be read.
+fn read_frame(input: &mut impl BufRead) -> io::Result<Option<Result<Vec<u8>, Error>>> {
+    let mut length = None;
+    let mut malformed = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if length.is_none() && malformed.is_none() {
+                // Stray blank lines between frames are harmless.
+                continue;
+            }
+            break;
+        }
+        match header.split_once(':') {
+            Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                match value.trim().parse::<usize>() {
+                    Ok(value) => length = Some(value),
+                    Err(_) => malformed = Some(anyhow!("Invalid Content-Length {:?}", value)),
+                }
+            }
+            Some(_) => {}
+            None => malformed = Some(anyhow!("Invalid header {:?}", header)),
+        }
+    }
+    if let Some(err) = malformed {
+        return Ok(Some(Err(err)));
+    }
+    let length = match length {
+        Some(length) => length,
+        None => return Ok(Some(Err(anyhow!("Missing Content-Length header")))),
+    };
+    if length > MAX_FRAME {
+        let skipped = io::copy(&mut (&mut *input).take(length as u64), &mut io::sink())?;
+        if skipped < length as u64 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        return Ok(Some(Err(anyhow!(
+            "Frame of {} bytes is longer than the limit of {}",
+            length,
+            MAX_FRAME
+        ))));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(Ok(body)))
+}
+
+fn write_frame(output: &mut impl Write, response: &Value) -> io::Result<()> {
+    let body = response.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+pub fn serve(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut server = Server::default();
+    while let Some(frame) = read_frame(input)? {
+        let request =
+            frame.and_then(
+                |body| match serde_json::from_slice(&body).context("Invalid JSON")? {
+                    Value::Object(request) => Ok(request),
+                    _ => Err(anyhow!("A request must be a JSON object")),
+                },
+            );
+        let response = match request {
+            Ok(request) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                match server.handle(&request) {
+                    Ok(result) => json!({ "id": id, "result": result }),
+                    Err(err) => json!({ "id": id, "error": format!("{:#}", err) }),
+                }
+            }
+            Err(err) => json!({ "id": null, "error": format!("{:#}", err) }),
+        };
+        write_frame(output, &response)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::{Document, Sexp, SexpPath};
+use anyhow::Error;
+
+impl Sexp {
+    /// Finds the subtree to blame when the compact rendering of this tree is
+    /// longer than `budget` bytes. Starting from the root, it steps into the
+    /// first child whose compact rendering exceeds the budget on its own, and
+    /// stops at the first node where none does. Returns that node's path and
+    /// `compact_len`, or `None` if the whole tree fits.
+    ///
+    /// Lengths are measured with an early exit, so subtrees that fit are only
+    /// walked once and large ones no further than the budget.
+    pub fn find_budget_violation(&self, budget: usize) -> Option<(SexpPath, usize)> {
+        self.compact_len_within(budget)
+            .is_none()
+            .then(|| self.blame(SexpPath::root(), budget))
+    }
+
+    fn blame(&self, path: SexpPath, budget: usize) -> (SexpPath, usize) {
+        let over = self
+            .items()
+            .iter()
+            .enumerate()
+            .find(|(_, child)| child.compact_len_within(budget).is_none());
+        match over {
+            Some((index, child)) => child.blame(path.child(index), budget),
+            None => (path, self.compact_len()),
+        }
+    }
+}
+
+impl Document {
+    /// `Sexp::find_budget_violation` for the form in this document. The
+    /// grammar reads a single top-level form, so the document's budget is
+    /// that form's.
+    pub fn find_budget_violation(&self, budget: usize) -> Result<Option<(SexpPath, usize)>, Error> {
+        Ok(self.sexp()?.find_budget_violation(budget))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_budget_violation() {
+        let filler = vec!["x"; 600].join(" ");
+        let input = format!(
+            "(config (tenant acme) (services (web (port 80)) (batch (jobs (nightly (args {}))))) (owner ops))",
+            filler
+        );
+        let sexp = Sexp::of_str(&input).unwrap();
+        let (path, len) = sexp.find_budget_violation(1024).unwrap();
+        let stanza = sexp.get(&path).unwrap();
+        assert_eq!(path, vec![2, 2, 1, 1, 1].into());
+        assert_eq!(stanza.head(), Some("args"));
+        assert_eq!(len, stanza.compact_len());
+        assert_eq!(len, stanza.to_compact_string().len());
+    }
+
+    #[test]
+    fn test_within_budget() {
+        let sexp = Sexp::of_str("(config (tenant acme))").unwrap();
+        assert!(sexp.find_budget_violation(1024).is_none());
+        assert_eq!(
+            sexp.find_budget_violation(15),
+            Some((SexpPath::root(), sexp.compact_len()))
+        );
+        let document = Document::new("(config (tenant acme))");
+        assert!(document.find_budget_violation(1024).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_compact_len() {
+        for input in &["a", "()", "(a)", "(a (b c) \"d e\")"] {
+            let sexp = Sexp::of_str(input).unwrap();
+            assert_eq!(sexp.compact_len(), sexp.to_compact_string().len());
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{ffi, Sexp, SexpPath};
+use anyhow::{anyhow, Context, Error};
+use std::collections::BTreeMap;
+use tree_sitter::{Node, Point, Tree};
+
+/// Comments that end the line of an atom or list, like `(name foo) ; from
+/// defaults.sexp`, by the path of that node.
+pub type TrailingComments = BTreeMap<SexpPath, String>;
+
+struct Collector<'a> {
+    text: &'a str,
+    trailing: TrailingComments,
+    stray: Vec<Point>,
+}
+
+impl Collector<'_> {
+    /// Attaches `comment` to the node before it when it starts on the line
+    /// that node ends on.
+    fn comment(&mut self, comment: Node, previous: Option<&(Node, SexpPath)>) {
+        match previous {
+            Some((node, path)) if node.end_position().row == comment.start_position().row => {
+                let text = self.text[comment.byte_range()].trim_end();
+                self.trailing.insert(path.clone(), text.to_string());
+            }
+            _ => self.stray.push(comment.start_position()),
+        }
+    }
+
+    fn list(&mut self, list: Node, path: &SexpPath) {
+        let mut previous = None;
+        let mut index = 0;
+        let mut walker = list.walk();
+        for child in list.children(&mut walker) {
+            match child.kind() {
+                "comment" => self.comment(child, previous.as_ref()),
+                "(" | ")" => previous = None,
+                kind => {
+                    let path = path.child(index);
+                    index += 1;
+                    if kind == "list" {
+                        self.list(child, &path);
+                    }
+                    previous = Some((child, path));
+                }
+            }
+        }
+    }
+}
+
+/// The trailing comments in a parse of `text`. A comment on a line of its
+/// own can't be attached to anything, so the first one is an error rather
+/// than something to lose.
+pub(crate) fn comments(tree: &Tree, text: &str) -> Result<TrailingComments, Error> {
+    let mut collector = Collector {
+        text,
+        trailing: TrailingComments::new(),
+        stray: vec![],
+    };
+    let root = tree.root_node();
+    let mut previous = None;
+    let mut forms = 0;
+    let mut walker = root.walk();
+    for child in root.children(&mut walker) {
+        if child.kind() == "comment" {
+            collector.comment(child, previous.as_ref());
+            continue;
+        }
+        forms += 1;
+        // Like `of_tree`, only the first form is read.
+        previous = None;
+        if forms == 1 {
+            if child.kind() == "list" {
+                collector.list(child, &SexpPath::root());
+            }
+            previous = Some((child, SexpPath::root()));
+        }
+    }
+    if let Some(point) = collector.stray.first() {
+        return Err(anyhow!(
+            "Comment on a line of its own at line {}, column {}",
+            point.row + 1,
+            point.column + 1
+        ));
+    }
+    Ok(collector.trailing)
+}
+
+impl Sexp {
+    /// Like `of_str`, but also keeps the comments that end the line of an
+    /// atom or list, for `PrettyPrinter::print_with_comments`. A comment on
+    /// a line of its own is an error, as it would otherwise be lost.
+    pub fn of_str_with_comments(input: &str) -> Result<(Sexp, TrailingComments), Error> {
+        let mut parser = ffi::parser();
+        let tree = parser
+            .parse(input, None)
+            .context("Could not parse anything")?;
+        let trailing = comments(&tree, input)?;
+        Ok((Sexp::of_tree(&tree, input)?, trailing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PrettyPrinter;
+
+    #[test]
+    fn test_of_str_with_comments() {
+        let (sexp, comments) = Sexp::of_str_with_comments(
+            "(package ; the package\n (name foo)   ; a\n (deps (a b) ; b\n c)) ; c\n",
+        )
+        .unwrap();
+        assert_eq!(sexp.to_string(), "(package (name foo) (deps (a b) c))");
+        let comments: Vec<String> = comments
+            .iter()
+            .map(|(path, comment)| format!("{} {}", path, comment))
+            .collect();
+        assert_eq!(
+            comments,
+            ["/ ; c", "/0 ; the package", "/1 ; a", "/2/1 ; b"]
+        );
+    }
+
+    #[test]
+    fn test_of_str_with_comments_refuses_own_line_comments() {
+        let error = Sexp::of_str_with_comments("(a\n ; keep me\n b)").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Comment on a line of its own at line 2, column 2"
+        );
+        assert!(Sexp::of_str_with_comments("; header\n(a b)").is_err());
+    }
+
+    #[test]
+    fn test_align_trailing_comments() {
+        let input = include_str!("./trailing_comments.in.sexp");
+        let printer = PrettyPrinter::new()
+            .with_max_width(60)
+            .with_align_trailing_comments(true);
+        let format = |input: &str| {
+            let (sexp, comments) = Sexp::of_str_with_comments(input).unwrap();
+            format!("{}\n", printer.print_with_comments(&sexp, &comments))
+        };
+        let formatted = format(input);
+        assert_eq!(formatted, include_str!("./trailing_comments.out.sexp"));
+        assert_eq!(format(&formatted), formatted);
+    }
+
+    #[test]
+    fn test_align_trailing_comments_within_max_width() {
+        let (sexp, comments) = Sexp::of_str_with_comments(
+            "(config (name foo) ; short\n (description \"a longer value\") ; x\n (id 1) ; a comment too long to align\n)",
+        )
+        .unwrap();
+        let printer = PrettyPrinter::new()
+            .with_max_width(50)
+            .with_align_trailing_comments(true);
+        assert_eq!(
+            printer.print_with_comments(&sexp, &comments),
+            "(config\n (name foo)                      ; short\n (description \"a longer value\")  ; x\n (id 1) ; a comment too long to align\n)"
+        );
+        assert_eq!(
+            PrettyPrinter::new().print_with_comments(&sexp, &comments),
+            "(config\n (name foo) ; short\n (description \"a longer value\") ; x\n (id 1) ; a comment too long to align\n)"
+        );
+    }
+}
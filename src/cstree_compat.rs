@@ -0,0 +1,88 @@
+use crate::Sexp;
+use cstree::interning::{Resolver, TokenKey};
+use cstree::syntax::SyntaxNode;
+use cstree::util::NodeOrToken;
+use cstree::Syntax;
+
+impl Sexp {
+    /// The `cstree` counterpart of `from_rowan`, producing the same shape:
+    /// `(KIND children...)` for nodes and `(KIND "text")` for tokens, with
+    /// token text looked up in `resolver`. For a `ResolvedNode`, pass its
+    /// own `resolver()`.
+    pub fn from_cst_node<S: Syntax, D, I>(node: &SyntaxNode<S, D>, resolver: &I) -> Sexp
+    where
+        I: Resolver<TokenKey> + ?Sized,
+    {
+        let mut parts = vec![Sexp::atom(format!("{:?}", node.kind()))];
+        for child in node.children_with_tokens() {
+            parts.push(match child {
+                NodeOrToken::Node(node) => Sexp::from_cst_node(node, resolver),
+                NodeOrToken::Token(token) => Sexp::list(vec![
+                    Sexp::atom(format!("{:?}", token.kind())),
+                    Sexp::string(token.resolve_text(resolver)),
+                ]),
+            });
+        }
+        Sexp::list(parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cstree::build::GreenNodeBuilder;
+    use cstree::syntax::ResolvedNode;
+    use cstree::RawSyntaxKind;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[repr(u32)]
+    #[allow(clippy::upper_case_acronyms)]
+    enum Kind {
+        ROOT,
+        CALL,
+        IDENT,
+        NUMBER,
+        WHITESPACE,
+    }
+
+    impl Syntax for Kind {
+        fn from_raw(raw: RawSyntaxKind) -> Kind {
+            [
+                Kind::ROOT,
+                Kind::CALL,
+                Kind::IDENT,
+                Kind::NUMBER,
+                Kind::WHITESPACE,
+            ][raw.0 as usize]
+        }
+
+        fn into_raw(self) -> RawSyntaxKind {
+            RawSyntaxKind(self as u32)
+        }
+
+        fn static_text(self) -> Option<&'static str> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_from_cst_node() {
+        let mut builder: GreenNodeBuilder<Kind> = GreenNodeBuilder::new();
+        builder.start_node(Kind::ROOT);
+        builder.start_node(Kind::CALL);
+        builder.token(Kind::IDENT, "max");
+        builder.token(Kind::WHITESPACE, " ");
+        builder.token(Kind::NUMBER, "42");
+        builder.finish_node();
+        builder.finish_node();
+        let (green, cache) = builder.finish();
+        let interner = cache.unwrap().into_interner().unwrap();
+        let root: ResolvedNode<Kind> = SyntaxNode::new_root_with_resolver(green, interner);
+
+        // The same tree as in the `rowan_compat` tests.
+        assert_eq!(
+            Sexp::from_cst_node(&root, &**root.resolver()).to_string(),
+            r#"(ROOT (CALL (IDENT "max") (WHITESPACE " ") (NUMBER "42")))"#
+        );
+    }
+}
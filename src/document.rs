@@ -0,0 +1,148 @@
+use crate::comment::comments;
+use crate::{ffi, format, Sexp, TrailingComments};
+use anyhow::{anyhow, Context, Error};
+use std::ops::Range;
+use tree_sitter::{InputEdit, Parser, Point, Tree};
+
+/// A source text that stays parsed across edits. Each `edit` tells
+/// tree-sitter which bytes changed, so only the affected part of the tree is
+/// reparsed instead of starting over.
+pub struct Document {
+    text: String,
+    parser: Parser,
+    tree: Option<Tree>,
+}
+
+impl Document {
+    pub fn new<S: Into<String>>(text: S) -> Document {
+        let text = text.into();
+        let mut parser = ffi::parser();
+        let tree = parser.parse(&text, None);
+        Document { text, parser, tree }
+    }
+
+    /// Replaces the whole text, reusing the parser but not the old tree.
+    pub fn set_text<S: Into<String>>(&mut self, text: S) {
+        self.text = text.into();
+        self.tree = self.parser.parse(&self.text, None);
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the bytes in `range` with `replacement` and reparses.
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<(), Error> {
+        if range.start > range.end
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(anyhow!(
+                "Invalid edit range {:?} for a document of {} bytes",
+                range,
+                self.text.len()
+            ));
+        }
+
+        let start_position = position(&self.text, range.start);
+        let old_end_position = position(&self.text, range.end);
+        self.text.replace_range(range.clone(), replacement);
+        let new_end_byte = range.start + replacement.len();
+        let edit = InputEdit {
+            start_byte: range.start,
+            old_end_byte: range.end,
+            new_end_byte,
+            start_position,
+            old_end_position,
+            new_end_position: position(&self.text, new_end_byte),
+        };
+
+        if let Some(tree) = self.tree.as_mut() {
+            tree.edit(&edit);
+        }
+        self.tree = self.parser.parse(&self.text, self.tree.as_ref());
+        Ok(())
+    }
+
+    pub(crate) fn tree(&self) -> Option<&Tree> {
+        self.tree.as_ref()
+    }
+
+    pub fn sexp(&self) -> Result<Sexp, Error> {
+        let tree = self.tree.as_ref().context("Could not parse anything")?;
+        Sexp::of_tree(tree, &self.text)
+    }
+
+    /// The tree along with the comments that end the line of an atom or
+    /// list in it, as `Sexp::of_str_with_comments` reads them. A comment on
+    /// a line of its own is an error there too.
+    pub fn sexp_with_comments(&self) -> Result<(Sexp, TrailingComments), Error> {
+        let tree = self.tree.as_ref().context("Could not parse anything")?;
+        let trailing = comments(tree, &self.text)?;
+        Ok((Sexp::of_tree(tree, &self.text)?, trailing))
+    }
+
+    /// Like `sexp_with_comments`, but the text must be exactly one complete
+    /// form, as `format_str` requires: a parse that had to recover, a second
+    /// form or no form at all is an error.
+    pub fn form(&self) -> Result<(Sexp, TrailingComments), Error> {
+        let tree = self.tree.as_ref().context("Could not parse anything")?;
+        format::form(tree, &self.text)
+    }
+}
+
+fn position(text: &str, byte: usize) -> Point {
+    let before = &text[..byte];
+    let row = before.matches('\n').count();
+    let column = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1);
+    Point::new(row, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_reparses() {
+        let mut document = Document::new("(a (b c)\n d)");
+        document.edit(4..5, "x y").unwrap();
+        assert_eq!(document.text(), "(a (x y c)\n d)");
+        assert_eq!(document.sexp().unwrap().to_string(), "(a (x y c) d)");
+        document.edit(12..13, "(e)").unwrap();
+        assert_eq!(document.sexp().unwrap().to_string(), "(a (x y c) (e))");
+    }
+
+    #[test]
+    fn test_edit_out_of_range() {
+        let mut document = Document::new("(a)");
+        assert!(document.edit(2..10, "b").is_err());
+        assert_eq!(document.text(), "(a)");
+    }
+
+    #[test]
+    fn test_set_text() {
+        let mut document = Document::new("(a b)");
+        document.set_text("(c (d))");
+        assert_eq!(document.sexp().unwrap().to_string(), "(c (d))");
+    }
+
+    #[test]
+    fn test_form() {
+        let mut document = Document::new("(a (b c))");
+        assert_eq!(document.form().unwrap().0.to_string(), "(a (b c))");
+        document.edit(8..9, "").unwrap();
+        assert_eq!(
+            document.form().unwrap_err().to_string(),
+            "Missing \")\" at line 1, column 9"
+        );
+        document.set_text("(a b) (c d)");
+        assert!(document.form().is_err());
+        assert!(document.sexp().is_ok());
+    }
+
+    #[test]
+    fn test_position() {
+        assert_eq!(position("ab\ncd", 4), Point::new(1, 1));
+        assert_eq!(position("ab\ncd", 2), Point::new(0, 2));
+    }
+}
@@ -0,0 +1,116 @@
+use super::yaml::{is_mapping, sequence};
+use crate::Sexp;
+
+/// The forms that stand for a mapping of their fields, named by the atom
+/// they may start with.
+const NAMED: &[&str] = &["handler", "play", "task"];
+
+/// `sexp` with each of the [`NAMED`] forms in it as the mapping of its
+/// fields, its name the first of them.
+fn mappings(sexp: &Sexp) -> Sexp {
+    let items = match sexp {
+        Sexp::List(_) => sexp.items(),
+        _ => return sexp.clone(),
+    };
+    match items.split_first() {
+        Some((Sexp::Atom(head), rest)) if NAMED.contains(&head.as_str()) => {
+            let mut fields = vec![];
+            let rest = match rest.split_first() {
+                Some((name @ Sexp::Atom(_), rest)) => {
+                    fields.push(Sexp::list(vec![Sexp::atom("name"), name.clone()]));
+                    rest
+                }
+                _ => rest,
+            };
+            fields.extend(rest.iter().map(mappings));
+            Sexp::list(fields)
+        }
+        _ => Sexp::list(items.iter().map(mappings).collect()),
+    }
+}
+
+impl Sexp {
+    /// The tree as an Ansible playbook in YAML: `(playbook (play "Configure
+    /// web" (hosts web) (vars (port 80)) (tasks ...)) ...)` is a sequence
+    /// of plays, each a mapping of its fields, ones like `(name value)` as
+    /// [`Sexp::to_kubernetes_yaml`] writes them. `(task "Install nginx"
+    /// (apt (name nginx) (state present)))` is a task in `tasks`, and
+    /// `handler` one in `handlers`, each named by the atom it starts with.
+    /// A field of the playbook itself, like `(import_playbook site.yml)`,
+    /// is an entry of its own.
+    pub fn to_ansible_playbook(&self) -> String {
+        let plays = match self.items().split_first() {
+            Some((Sexp::Atom(head), plays)) if head == "playbook" => plays,
+            Some((Sexp::Atom(head), _)) if head == "play" => std::slice::from_ref(self),
+            _ => self.items(),
+        };
+        let plays: Vec<Sexp> = plays
+            .iter()
+            .map(|play| match mappings(play) {
+                field if is_mapping(std::slice::from_ref(&field)) => Sexp::list(vec![field]),
+                play => play,
+            })
+            .collect();
+        let mut yaml = "---\n".to_string();
+        sequence(&plays, 0, &mut yaml);
+        yaml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ansible_playbook() {
+        let sexp = Sexp::of_str(
+            r#"(playbook
+                 (play "Configure web servers"
+                   (hosts web)
+                   (become true)
+                   (vars (http_port 80) (packages nginx git))
+                   (tasks
+                     (task "Install packages"
+                       (ansible.builtin.apt (name "{{ item }}") (state present))
+                       (loop "{{ packages }}"))
+                     (task "Start nginx"
+                       (ansible.builtin.service (name nginx) (state started))
+                       (notify "Restart nginx")))
+                   (handlers
+                     (handler "Restart nginx"
+                       (ansible.builtin.service (name nginx) (state restarted)))))
+                 (import_playbook db.yml))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_ansible_playbook(),
+            r#"---
+- name: "Configure web servers"
+  hosts: web
+  become: true
+  vars:
+    http_port: 80
+    packages:
+    - nginx
+    - git
+  tasks:
+  - name: "Install packages"
+    ansible.builtin.apt:
+      name: "{{ item }}"
+      state: present
+    loop: "{{ packages }}"
+  - name: "Start nginx"
+    ansible.builtin.service:
+      name: nginx
+      state: started
+    notify: "Restart nginx"
+  handlers:
+  - name: "Restart nginx"
+    ansible.builtin.service:
+      name: nginx
+      state: restarted
+- import_playbook: db.yml
+"#
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// The directives that are always sections, even of no directives, like
+/// `<IfModule mod_ssl.c>`. Apache reads their names in any case.
+const SECTIONS: &[&str] = &[
+    "Directory",
+    "DirectoryMatch",
+    "Else",
+    "ElseIf",
+    "Files",
+    "FilesMatch",
+    "If",
+    "IfDefine",
+    "IfModule",
+    "IfVersion",
+    "Limit",
+    "LimitExcept",
+    "Location",
+    "LocationMatch",
+    "Proxy",
+    "ProxyMatch",
+    "RequireAll",
+    "RequireAny",
+    "RequireNone",
+    "VirtualHost",
+];
+
+/// Whether `text` is an argument Apache reads as it is: not empty, with no
+/// space to split it, quote, `#` to start a comment, or angle bracket to
+/// end a section's opening.
+fn is_plain(text: &str) -> bool {
+    !text.is_empty()
+        && !text.starts_with('#')
+        && !text
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '<' | '>'))
+}
+
+fn argument(sexp: &Sexp) -> String {
+    let text = match sexp {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => return number.to_string(),
+            Atom::String(text) => text,
+            Atom::Symbol(text) => text.to_string(),
+        },
+        _ => sexp.to_compact_string(),
+    };
+    if is_plain(&text) {
+        text
+    } else {
+        // Apache takes `\"` for a quote in one, but a backslash as it is.
+        format!("\"{}\"", text.replace('"', "\\\""))
+    }
+}
+
+fn directive(sexp: &Sexp) -> String {
+    let (name, rest) = match sexp.items().split_first() {
+        Some((Sexp::Atom(name), rest)) => (name.as_str(), rest),
+        Some(_) => return directives(sexp.items()),
+        None => return String::new(),
+    };
+    // The arguments are the atoms before the section's directives.
+    let split = rest
+        .iter()
+        .position(|item| matches!(item, Sexp::List(_)))
+        .unwrap_or(rest.len());
+    let (args, body) = rest.split_at(split);
+    let mut opening = name.to_string();
+    for arg in args {
+        opening.push(' ');
+        opening.push_str(&argument(arg));
+    }
+    let section = SECTIONS
+        .iter()
+        .any(|section| section.eq_ignore_ascii_case(name));
+    if body.is_empty() && !section {
+        return opening;
+    }
+    let mut lines = vec![format!("<{}>", opening)];
+    lines.extend(directives(body).lines().map(|line| format!("    {}", line)));
+    lines.push(format!("</{}>", name));
+    lines.join("\n")
+}
+
+fn directives(forms: &[Sexp]) -> String {
+    forms.iter().map(directive).collect::<Vec<_>>().join("\n")
+}
+
+impl Sexp {
+    /// The tree as an Apache HTTPD configuration: `(Directive args...)`
+    /// becomes the line `Directive arg1 arg2`, and one with lists after its
+    /// arguments, like `(VirtualHost *:80 (ServerName example.com))`, the
+    /// section `<VirtualHost *:80>` of those directives, closed by
+    /// `</VirtualHost>`, as `Directory`, `IfModule` and the other sections
+    /// always are. A list of directives is a file of them in turn.
+    /// Arguments with spaces or quotes are double-quoted.
+    pub fn to_apache_config(&self) -> String {
+        directive(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_apache_config() {
+        let sexp = Sexp::of_str(
+            r#"((Listen 80)
+                (VirtualHost *:80
+                  (ServerName example.com)
+                  (ServerAlias www.example.com)
+                  (DocumentRoot /var/www/html)
+                  (ErrorLog "${APACHE_LOG_DIR}/error.log")
+                  (Directory /var/www/html
+                    (Options Indexes FollowSymLinks)
+                    (AllowOverride None)
+                    (Require all granted))
+                  (FilesMatch "\\.php$" (SetHandler "proxy:unix:/run/php/php-fpm.sock|fcgi://localhost"))
+                  (If "%{HTTP_HOST} != 'example.com'"
+                    (Redirect permanent / https://example.com/))
+                  (IfModule mod_headers.c)))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_apache_config(),
+            r#"Listen 80
+<VirtualHost *:80>
+    ServerName example.com
+    ServerAlias www.example.com
+    DocumentRoot /var/www/html
+    ErrorLog ${APACHE_LOG_DIR}/error.log
+    <Directory /var/www/html>
+        Options Indexes FollowSymLinks
+        AllowOverride None
+        Require all granted
+    </Directory>
+    <FilesMatch \.php$>
+        SetHandler proxy:unix:/run/php/php-fpm.sock|fcgi://localhost
+    </FilesMatch>
+    <If "%{HTTP_HOST} != 'example.com'">
+        Redirect permanent / https://example.com/
+    </If>
+    <IfModule mod_headers.c>
+    </IfModule>
+</VirtualHost>"#
+        );
+    }
+}
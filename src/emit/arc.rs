@@ -0,0 +1,132 @@
+use super::lisp::{write, Dialect};
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+
+/// Whether Arc reads `name` back as this symbol: no delimiters, nothing it
+/// would take for a number, and none of the characters its symbol syntax
+/// expands, like the `.` of `a.b` for `(a b)`.
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let second = chars.next();
+    let numeric = first.is_ascii_digit()
+        || ("+-".contains(first) && second.is_some_and(|c| c.is_ascii_digit()));
+    !numeric
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !"()[]{}\"';`,|#\\.!:~&".contains(c))
+}
+
+/// Arc's booleans are `t` and `nil`, so Scheme's are written as those.
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => quoted(&text, '"'),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "t".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "nil".to_string(),
+        Atom::Symbol(name) if is_symbol(name) => name.to_string(),
+        Atom::Symbol(name) => quoted(name, '"'),
+    }
+}
+
+/// `obj` quotes its keys, so a key is written as it is.
+fn key(key: &Sexp) -> String {
+    write(key, &ARC)
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let prefix = match head {
+        "quote" => Some("'"),
+        "quasiquote" => Some("`"),
+        "unquote" => Some(","),
+        "unquote-splicing" => Some(",@"),
+        _ => None,
+    };
+    match (head, prefix, rest) {
+        (_, Some(prefix), [quoted]) => Some(format!("{}{}", prefix, write(quoted, &ARC))),
+        // `[... _ ...]` is Arc's shorthand for a function of one `_`.
+        ("lambda", _, [params, body @ Sexp::List(_)])
+        | ("fn", _, [params, body @ Sexp::List(_)])
+            if params.items() == [Sexp::atom("_")] && !body.items().is_empty() =>
+        {
+            let items: Vec<String> = body.items().iter().map(|item| write(item, &ARC)).collect();
+            Some(format!("[{}]", items.join(" ")))
+        }
+        ("define-macro", _, [signature @ Sexp::List(_), body @ ..]) => {
+            let (name, params) = signature.items().split_first()?;
+            let mut form = vec![write(name, &ARC), write(&Sexp::list(params.to_vec()), &ARC)];
+            form.extend(body.iter().map(|form| write(form, &ARC)));
+            Some(format!("(mac {})", form.join(" ")))
+        }
+        // A single binding goes without the parentheses around it.
+        ("let", _, [bindings, body @ ..]) => match bindings.items() {
+            [binding] => match binding.items() {
+                [name @ Sexp::Atom(_), init] => {
+                    let mut form = vec![write(name, &ARC), write(init, &ARC)];
+                    form.extend(body.iter().map(|form| write(form, &ARC)));
+                    Some(format!("(let {})", form.join(" ")))
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+const ARC: Dialect = Dialect {
+    atom: atom_text,
+    key,
+    forms,
+    params: ("(", ")"),
+    defun: "def",
+    define: "=",
+    set: "=",
+    begin: "do",
+    bindings: "with",
+    cond: "if",
+    table: ("(obj ", ")"),
+    array: ("(list ", ")"),
+};
+
+impl Sexp {
+    /// The tree as Arc, reading it as Scheme-style code: `lambda` becomes
+    /// `fn`, `(define (f x) ...)` `(def f (x) ...)`, `define-macro` `mac`,
+    /// and `define` and `set!` of a variable `=`. `let` with one binding
+    /// becomes `(let x 1 ...)` and with more `(with (x 1 y 2) ...)`, `begin`
+    /// `do`, and `cond` a flat `if`. Quoting forms use their `'`, `` ` ``,
+    /// `,` and `,@` abbreviations, and a function of a single `_` the
+    /// `[... _ ...]` one. `(table (key value)...)` becomes `(obj key value)`
+    /// and `(array ...)` `(list ...)`. Booleans become `t` and `nil`.
+    pub fn to_arc_string(&self) -> String {
+        write(self, &ARC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_arc_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (average x y) (/ (+ x y) 2))
+                 (define-macro (unless test body) (quasiquote (if (unquote test) nil (unquote body))))
+                 (define total 0)
+                 (let ((x 1)) (set! total (+ total x)))
+                 (let ((x 1) (y 2)) (cond ((> x y) x) (else y)))
+                 (map (lambda (_) (* _ 2)) (quote (1 2 3)))
+                 (map (lambda (n) (average n n)) (array 1 2))
+                 (obj-like (table (name "demo") (port 80)) true false a.b))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_arc_string(),
+            r#"(do (def average (x y) (/ (+ x y) 2)) (mac unless (test body) `(if ,test nil ,body)) (= total 0) (let x 1 (= total (+ total x))) (with (x 1 y 2) (if (> x y) x y)) (map [* _ 2] '(1 2 3)) (map (fn (n) (average n n)) (list 1 2)) (obj-like (obj name "demo" port 80) t nil "a.b"))"#
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use super::outline;
+use crate::Sexp;
+use anyhow::{anyhow, Error};
+
+impl Sexp {
+    /// The tree as a nested AsciiDoc list: every list is an item named
+    /// after its head with its elements nested one level deeper, and every
+    /// other atom a leaf item.
+    pub fn to_asciidoc(&self) -> String {
+        let mut doc = String::new();
+        outline(self, 1, &mut |text, depth| {
+            doc.push_str(&format!("{} {}\n", "*".repeat(depth), text))
+        });
+        doc
+    }
+
+    /// Reads a nested AsciiDoc list back: items with nested items become
+    /// lists headed by their text, and the others atoms (string atoms when
+    /// the text isn't a bare atom). Several top-level items are wrapped in
+    /// a list. This inverts `to_asciidoc` for trees where every list has an
+    /// atom head and at least one element; a list like `(a)` comes back as
+    /// the atom `a`. Lines that aren't `*` list items are skipped.
+    pub fn of_asciidoc(input: &str) -> Result<Sexp, Error> {
+        // Each open item's text and the children read so far.
+        let mut stack: Vec<(String, Vec<Sexp>)> = vec![(String::new(), vec![])];
+        let close = |stack: &mut Vec<(String, Vec<Sexp>)>| {
+            let (text, children) = stack.pop().unwrap();
+            let head = Sexp::atom_or_string(&text);
+            let node = if children.is_empty() {
+                head
+            } else {
+                let mut parts = vec![head];
+                parts.extend(children);
+                Sexp::list(parts)
+            };
+            stack.last_mut().unwrap().1.push(node);
+        };
+
+        for (number, line) in input.lines().enumerate() {
+            let text = line.trim_start_matches('*');
+            let depth = line.len() - text.len();
+            let text = match text.strip_prefix(' ') {
+                Some(text) if depth > 0 => text.trim(),
+                _ => continue,
+            };
+            if depth > stack.len() {
+                return Err(anyhow!(
+                    "Line {} is nested more than one level deeper",
+                    number + 1
+                ));
+            }
+            while stack.len() > depth {
+                close(&mut stack);
+            }
+            stack.push((text.to_string(), vec![]));
+        }
+        while stack.len() > 1 {
+            close(&mut stack);
+        }
+
+        let (_, mut top) = stack.pop().unwrap();
+        match top.len() {
+            0 => Err(anyhow!("There are no list items")),
+            1 => Ok(top.remove(0)),
+            _ => Ok(Sexp::list(top)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_asciidoc() {
+        let sexp =
+            Sexp::of_str(r#"(project (library (name core) (modules a b)) "Read me")"#).unwrap();
+        assert_eq!(
+            sexp.to_asciidoc(),
+            "* project\n** library\n*** name\n**** core\n*** modules\n**** a\n**** b\n** Read me\n"
+        );
+    }
+
+    #[test]
+    fn test_of_asciidoc_round_trip() {
+        let sexp =
+            Sexp::of_str(r#"(project (library (name core) (modules a b)) "Read me")"#).unwrap();
+        assert_eq!(Sexp::of_asciidoc(&sexp.to_asciidoc()).unwrap(), sexp);
+
+        let doc = "= Title\n\n* a\n** b\n* c\n";
+        assert_eq!(Sexp::of_asciidoc(doc).unwrap().to_string(), "((a b) c)");
+        assert!(Sexp::of_asciidoc("* a\n*** b\n").is_err());
+        assert!(Sexp::of_asciidoc("no list here").is_err());
+    }
+}
@@ -0,0 +1,296 @@
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// How a shell in the Bourne family writes what differs between them.
+pub(super) struct Shell {
+    /// Whether an unquoted variable's value is split into words, so one is
+    /// double-quoted to keep it one argument.
+    pub(super) splits: bool,
+    /// Writes the assignment of an associative array to a name, from the
+    /// words of its keys and values.
+    pub(super) table: fn(&str, &[(String, String)]) -> String,
+    /// The shell's own commands, tried before the shared ones.
+    pub(super) forms: fn(&str, &[Sexp]) -> Option<String>,
+}
+
+/// Whether `text` reads as the one word it is without quoting.
+fn is_plain(text: &str) -> bool {
+    !text.is_empty()
+        && text.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | ',' | '+' | '@' | '%')
+        })
+}
+
+/// `text` as a single word, in single quotes unless it's plain. Nothing in
+/// single quotes is special, so a quote in one ends it for an escaped
+/// quote before starting again.
+fn quoted(text: &str) -> String {
+    if is_plain(text) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+/// `sexp` as an argument. Strings are literal text, while symbols are
+/// written as they are, so that `*.txt` or `~/bin` expand, except that one
+/// starting with `$` is double-quoted where the shell would split its
+/// value. A list is a command whose output is substituted.
+pub(super) fn word(sexp: &Sexp, shell: &Shell) -> String {
+    match sexp {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => number.to_string(),
+            Atom::String(text) => quoted(&text),
+            Atom::Symbol(name) if shell.splits && name.starts_with('$') => format!("\"{}\"", name),
+            Atom::Symbol(name) => name.to_string(),
+        },
+        Sexp::List(_) => format!("\"$({})\"", write(sexp, shell)),
+        Sexp::Nil => "''".to_string(),
+    }
+}
+
+pub(super) fn words(items: &[Sexp], shell: &Shell) -> String {
+    let words: Vec<String> = items.iter().map(|item| word(item, shell)).collect();
+    words.join(" ")
+}
+
+/// `commands` one to a line, indented in a compound command.
+fn body(commands: &[Sexp], shell: &Shell) -> String {
+    commands
+        .iter()
+        .map(|sexp| write(sexp, shell))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .lines()
+        .map(|line| format!("  {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `sexp` as a command in a list joined by `operator`, in braces where it's
+/// a list itself that would otherwise run into the operators around it.
+/// The first of an `&&` or `||` list can be another of them, since those
+/// group from the left.
+fn operand(sexp: &Sexp, first: bool, operator: &str, shell: &Shell) -> String {
+    match sexp.head() {
+        Some("and") | Some("or") if !first || operator == "|" => {
+            format!("{{ {}; }}", write(sexp, shell))
+        }
+        _ => write(sexp, shell),
+    }
+}
+
+fn list(commands: &[Sexp], operator: &str, shell: &Shell) -> String {
+    commands
+        .iter()
+        .enumerate()
+        .map(|(index, sexp)| operand(sexp, index == 0, operator, shell))
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", operator))
+}
+
+/// The assignment of `value` to `name`: an `(array ...)` is an indexed
+/// array, and a `(table (key value)...)` an associative one.
+fn assignment(name: &str, value: &Sexp, shell: &Shell) -> String {
+    match value.items().split_first() {
+        Some((Sexp::Atom(head), items)) if head == "array" => {
+            format!("{}=({})", name, words(items, shell))
+        }
+        Some((Sexp::Atom(head), fields)) if head == "table" => {
+            let mut pairs = vec![];
+            for field in fields {
+                match field.items() {
+                    [key, value] => pairs.push((word(key, shell), word(value, shell))),
+                    _ => return format!("{}={}", name, word(value, shell)),
+                }
+            }
+            (shell.table)(name, &pairs)
+        }
+        _ => format!("{}={}", name, word(value, shell)),
+    }
+}
+
+fn conditional(clauses: &[(Option<&Sexp>, &[Sexp])], shell: &Shell) -> String {
+    let mut lines = vec![];
+    for (index, (test, consequent)) in clauses.iter().enumerate() {
+        lines.push(match test {
+            None => "else".to_string(),
+            Some(test) if index == 0 => format!("if {}; then", write(test, shell)),
+            Some(test) => format!("elif {}; then", write(test, shell)),
+        });
+        lines.push(body(consequent, shell));
+    }
+    lines.push("fi".to_string());
+    lines.join("\n")
+}
+
+/// Writes `sexp` as a command in `shell`.
+pub(super) fn write(sexp: &Sexp, shell: &Shell) -> String {
+    let (head, rest) = match sexp.items().split_first() {
+        Some((Sexp::Atom(head), rest)) => (head.as_str(), rest),
+        Some(_) => return words(sexp.items(), shell),
+        None if matches!(sexp, Sexp::List(_)) => return String::new(),
+        None => return word(sexp, shell),
+    };
+    if let Some(command) = (shell.forms)(head, rest) {
+        return command;
+    }
+    match (head, rest) {
+        ("begin", _) => {
+            let commands: Vec<String> = rest.iter().map(|sexp| write(sexp, shell)).collect();
+            commands.join("\n")
+        }
+        ("pipe", [_, ..]) => list(rest, "|", shell),
+        ("and", [_, ..]) => list(rest, "&&", shell),
+        ("or", [_, ..]) => list(rest, "||", shell),
+        ("not", [single]) => format!("! {}", write(single, shell)),
+        (">", [cmd, file]) | (">>", [cmd, file]) | ("<", [cmd, file]) => {
+            format!("{} {} {}", write(cmd, shell), head, word(file, shell))
+        }
+        ("define", [signature @ Sexp::List(_), commands @ ..]) => match signature.items() {
+            [Sexp::Atom(name)] => format!("{}() {{\n{}\n}}", name, body(commands, shell)),
+            _ => format!("{} {}", head, words(rest, shell)),
+        },
+        ("define", [Sexp::Atom(name), value]) | ("set!", [Sexp::Atom(name), value]) => {
+            assignment(name, value, shell)
+        }
+        // Not `export -f name`, which takes options before its names.
+        ("export", [Sexp::Atom(name), value]) | ("local", [Sexp::Atom(name), value])
+            if !name.starts_with('-') =>
+        {
+            format!("{} {}", head, assignment(name, value, shell))
+        }
+        ("if", [test, consequent]) => {
+            conditional(&[(Some(test), std::slice::from_ref(consequent))], shell)
+        }
+        ("if", [test, consequent, alternative]) => conditional(
+            &[
+                (Some(test), std::slice::from_ref(consequent)),
+                (None, std::slice::from_ref(alternative)),
+            ],
+            shell,
+        ),
+        ("cond", [_, ..]) => {
+            let clauses: Vec<(Option<&Sexp>, &[Sexp])> = rest
+                .iter()
+                .enumerate()
+                .map(|(index, clause)| match clause.items().split_first() {
+                    Some((Sexp::Atom(test), consequent)) if test == "else" && index > 0 => {
+                        (None, consequent)
+                    }
+                    Some((test, consequent)) => (Some(test), consequent),
+                    None => (Some(clause), &[][..]),
+                })
+                .collect();
+            conditional(&clauses, shell)
+        }
+        ("while", [test, commands @ ..]) => {
+            format!(
+                "while {}; do\n{}\ndone",
+                write(test, shell),
+                body(commands, shell)
+            )
+        }
+        ("for", [Sexp::Atom(name), items @ Sexp::List(_), commands @ ..]) => format!(
+            "for {} in {}; do\n{}\ndone",
+            name,
+            words(items.items(), shell),
+            body(commands, shell)
+        ),
+        _ => words(sexp.items(), shell),
+    }
+}
+
+#[cfg(feature = "bash")]
+const BASH: Shell = Shell {
+    splits: true,
+    table: |name, fields| {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("[{}]={}", key, value))
+            .collect();
+        format!("declare -A {}=({})", name, fields.join(" "))
+    },
+    forms: |_, _| None,
+};
+
+impl Sexp {
+    /// The tree as a Bash script: `(command args...)` becomes the command
+    /// `command arg1 arg2`, where strings are literal text, quoted as they
+    /// need, and symbols are shell words as they are, so globs and `~`
+    /// expand. A symbol starting with `$` is a variable, double-quoted as
+    /// in `"$VAR"`, and a list as an argument a command substitution
+    /// `"$(...)"`. `(pipe a b)` becomes `a | b`, `and`, `or` and `not` `&&`,
+    /// `||` and `!`, and `(> cmd file)`, `>>` and `<` redirections.
+    /// `(define NAME value)` and `set!` assign a variable, an `(array ...)`
+    /// value making an array and `(table (key value)...)` an associative
+    /// one, with `export` and `local` alike. `(define (name) commands...)`
+    /// becomes a function, `if` and `cond` `if`/`elif`/`else`, `(while test
+    /// commands...)` and `(for x (items...) commands...)` loops, and
+    /// `begin` its commands one to a line.
+    #[cfg(feature = "bash")]
+    pub fn to_bash_string(&self) -> String {
+        write(self, &BASH)
+    }
+}
+
+#[cfg(all(test, feature = "bash"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_to_bash_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define NAME "world's")
+                 (define FILES (array *.txt "my notes.md"))
+                 (define CONFIG (table (name demo) ("max size" 10)))
+                 (define (greet)
+                   (local who $1)
+                   (echo "Hello," $who))
+                 (export PATH $HOME/bin:$PATH)
+                 (if (test -f config.toml) (source config.toml) (echo "no config"))
+                 (pipe (cat log.txt) (grep -v DEBUG) (or (wc -l) (echo 0)))
+                 (> (date +%s) stamp.txt)
+                 (for f (*.md ~/notes) (echo $f))
+                 (and (mkdir -p build) (cd build) (not (cmake ..)))
+                 (echo "built at" (date)))"#,
+        )
+        .unwrap();
+        let script = sexp.to_bash_string();
+        assert_eq!(
+            script,
+            r#"NAME='world'\''s'
+FILES=(*.txt 'my notes.md')
+declare -A CONFIG=([name]=demo ['max size']=10)
+greet() {
+  local who="$1"
+  echo Hello, "$who"
+}
+export PATH="$HOME/bin:$PATH"
+if test -f config.toml; then
+  source config.toml
+else
+  echo 'no config'
+fi
+cat log.txt | grep -v DEBUG | { wc -l || echo 0; }
+date +%s > stamp.txt
+for f in *.md ~/notes; do
+  echo "$f"
+done
+mkdir -p build && cd build && ! cmake ..
+echo 'built at' "$(date)""#
+        );
+
+        // Check that it parses, where there's a Bash to do that.
+        if let Ok(output) = Command::new("bash").args(["-n", "-c", &script]).output() {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use super::yaml::{is_mapping, mapping};
+use crate::Sexp;
+
+/// The fields of a job that say where and how it runs, rather than being
+/// its steps.
+const SETTINGS: &[&str] = &[
+    "docker",
+    "environment",
+    "executor",
+    "machine",
+    "macos",
+    "parallelism",
+    "parameters",
+    "resource_class",
+    "shell",
+    "working_directory",
+];
+
+/// The fields of a workflow that say when it runs, rather than being its
+/// jobs.
+const TRIGGERS: &[&str] = &["triggers", "unless", "when"];
+
+fn field(name: &str, elements: Vec<Sexp>) -> Sexp {
+    let mut field = vec![Sexp::atom(name)];
+    field.extend(elements);
+    Sexp::list(field)
+}
+
+fn array(items: Vec<Sexp>) -> Sexp {
+    field("array", items)
+}
+
+/// A step: `checkout` as it is, `(run "command")` or `(run "Name"
+/// "command")` a `run` of the command, and any other `(step fields...)` a
+/// mapping of that one field.
+fn step(form: &Sexp) -> Sexp {
+    match form.items() {
+        [Sexp::Atom(head), name @ Sexp::Atom(_), command @ Sexp::Atom(_)] if head == "run" => {
+            Sexp::list(vec![field(
+                "run",
+                vec![
+                    field("name", vec![name.clone()]),
+                    field("command", vec![command.clone()]),
+                ],
+            )])
+        }
+        [_, ..] => Sexp::list(vec![form.clone()]),
+        _ => form.clone(),
+    }
+}
+
+/// A job as the field under `jobs` it is: its settings, an atom in
+/// `(docker ...)` being the image of a container, and then its steps.
+fn job(name: &Sexp, items: &[Sexp]) -> Sexp {
+    let (settings, steps): (Vec<&Sexp>, Vec<&Sexp>) = items
+        .iter()
+        .partition(|item| matches!(item.head(), Some(head) if SETTINGS.contains(&head)));
+    let mut job = vec![name.clone()];
+    for setting in settings {
+        job.push(match setting.items().split_first() {
+            Some((Sexp::Atom(head), images)) if head == "docker" => field(
+                "docker",
+                vec![array(
+                    images
+                        .iter()
+                        .map(|image| match image {
+                            Sexp::Atom(_) => Sexp::list(vec![field("image", vec![image.clone()])]),
+                            _ => image.clone(),
+                        })
+                        .collect(),
+                )],
+            ),
+            _ => setting.clone(),
+        });
+    }
+    job.push(field(
+        "steps",
+        vec![array(steps.into_iter().map(step).collect())],
+    ));
+    Sexp::list(job)
+}
+
+/// A workflow as the field under `workflows` it is: its triggers, and then
+/// its jobs, where `(test (requires build))` runs `test` after `build`.
+fn workflow(name: &Sexp, items: &[Sexp]) -> Sexp {
+    let (triggers, jobs): (Vec<&Sexp>, Vec<&Sexp>) = items
+        .iter()
+        .partition(|item| matches!(item.head(), Some(head) if TRIGGERS.contains(&head)));
+    let jobs: Vec<Sexp> = jobs
+        .into_iter()
+        .map(|job| match job.items().split_first() {
+            Some((name, [])) => name.clone(),
+            Some((name, fields)) if is_mapping(fields) => {
+                let mut entry = vec![name.clone()];
+                entry.extend(
+                    fields
+                        .iter()
+                        .map(|field| match field.items().split_first() {
+                            Some((Sexp::Atom(head), jobs)) if head == "requires" => {
+                                self::field("requires", vec![array(jobs.to_vec())])
+                            }
+                            _ => field.clone(),
+                        }),
+                );
+                Sexp::list(vec![Sexp::list(entry)])
+            }
+            _ => job.clone(),
+        })
+        .collect();
+    let mut workflow = vec![name.clone()];
+    workflow.extend(triggers.into_iter().cloned());
+    workflow.push(field("jobs", vec![array(jobs)]));
+    Sexp::list(workflow)
+}
+
+impl Sexp {
+    /// The tree as a CircleCI 2.1 configuration in YAML: `(pipeline (orbs
+    /// ...) (job build ...) (workflow main ...))`. Each `(job name
+    /// items...)` is one of `jobs`, with settings like `(docker
+    /// cimg/rust:1.75)`, its atoms the images of its containers, and the
+    /// rest of its items its `steps`, in order: `checkout` as it is, `(run
+    /// "command")` or `(run "Name" "command")` a `run`, and `(save_cache
+    /// (key ...))` and the like mappings of their fields. Each `(workflow
+    /// name jobs...)` is one of `workflows`, where a job is its name or
+    /// `(name (requires other...))`, and `triggers`, `when` and `unless`
+    /// are its settings. Other fields are written as
+    /// [`Sexp::to_kubernetes_yaml`] writes them, after `version: 2.1`
+    /// unless the configuration gives its own. A `job` or `workflow` that
+    /// isn't named by an atom is just a field, and what isn't a list headed
+    /// by its name is left out.
+    pub fn to_circleci_config(&self) -> String {
+        let forms = match self.items().split_first() {
+            Some((Sexp::Atom(head), forms)) if head == "pipeline" => forms,
+            _ => self.items(),
+        };
+        // The section of `jobs` or `workflows` a form is in, if any.
+        let kind = |form: &Sexp| match form.items() {
+            [Sexp::Atom(head), Sexp::Atom(_), ..] if head == "job" || head == "workflow" => {
+                Some(head.clone())
+            }
+            _ => None,
+        };
+        let sections = |kind_of: &str, section: fn(&Sexp, &[Sexp]) -> Sexp| -> Vec<Sexp> {
+            forms
+                .iter()
+                .filter(|form| kind(form).as_deref() == Some(kind_of))
+                .map(|form| section(&form.items()[1], &form.items()[2..]))
+                .collect()
+        };
+        let jobs = sections("job", job);
+        let workflows = sections("workflow", workflow);
+        let mut yaml = String::new();
+        if !forms.iter().any(|form| form.head() == Some("version")) {
+            yaml.push_str("version: 2.1\n");
+        }
+        let (mut jobs, mut workflows) = (Some(jobs), Some(workflows));
+        for form in forms {
+            let (key, section) = match kind(form).as_deref() {
+                Some("job") => ("jobs", &mut jobs),
+                Some("workflow") => ("workflows", &mut workflows),
+                _ => {
+                    mapping(std::slice::from_ref(form), 0, &mut yaml);
+                    continue;
+                }
+            };
+            // Each section goes where the first of its forms is.
+            if let Some(fields) = section.take() {
+                yaml.push_str(&format!("{}:\n", key));
+                mapping(&fields, 2, &mut yaml);
+            }
+        }
+        yaml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_circleci_config() {
+        let sexp = Sexp::of_str(
+            r#"(pipeline
+                 (orbs (codecov codecov/codecov@3.3.0))
+                 (job build
+                   (docker cimg/rust:1.75)
+                   checkout
+                   (restore_cache (keys (array cargo-v1)))
+                   (run "cargo build")
+                   (save_cache (key cargo-v1) (paths (array ~/.cargo))))
+                 (job test
+                   (docker cimg/rust:1.75 ((image cimg/postgres:14) (environment (POSTGRES_USER ci))))
+                   (parallelism 2)
+                   checkout
+                   (run "Test" "cargo test --all-features"))
+                 (workflow main
+                   build
+                   (test (requires build))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_circleci_config(),
+            r#"version: 2.1
+orbs:
+  codecov: codecov/codecov@3.3.0
+jobs:
+  build:
+    docker:
+    - image: cimg/rust:1.75
+    steps:
+    - checkout
+    - restore_cache:
+        keys:
+        - cargo-v1
+    - run: "cargo build"
+    - save_cache:
+        key: cargo-v1
+        paths:
+        - "~/.cargo"
+  test:
+    docker:
+    - image: cimg/rust:1.75
+    - image: cimg/postgres:14
+      environment:
+        POSTGRES_USER: ci
+    parallelism: 2
+    steps:
+    - checkout
+    - run:
+        name: Test
+        command: "cargo test --all-features"
+workflows:
+  main:
+    jobs:
+    - build
+    - test:
+        requires:
+        - build
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_circleci_config_malformed() {
+        let job = Sexp::of_str("((job (x) checkout))").unwrap();
+        assert_eq!(
+            job.to_circleci_config(),
+            "version: 2.1\njob:\n- - x\n- checkout\n"
+        );
+        let workflow = Sexp::of_str("((workflow))").unwrap();
+        assert_eq!(
+            workflow.to_circleci_config(),
+            "version: 2.1\nworkflow: []\n"
+        );
+        let fields = Sexp::of_str("(jobs (x) y)").unwrap();
+        assert_eq!(fields.to_circleci_config(), "version: 2.1\nx: []\n");
+        assert_eq!(
+            Sexp::of_str("(a)").unwrap().to_circleci_config(),
+            "version: 2.1\n"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+fn quoted(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn argument(arg: &Sexp) -> String {
+    match arg {
+        Sexp::Nil => String::new(),
+        Sexp::Atom(text) => match atom(text) {
+            Atom::String(text) => quoted(&text),
+            Atom::Number(text) | Atom::Symbol(text) => {
+                let special =
+                    |c: char| c.is_whitespace() || matches!(c, '(' | ')' | '#' | '"' | '\\');
+                if text.contains(special) {
+                    quoted(text)
+                } else {
+                    text.to_string()
+                }
+            }
+        },
+        // A nested list is a parenthesized group, as in `if((A OR B) AND C)`.
+        Sexp::List(_) => {
+            let args: Vec<String> = arg.items().iter().map(argument).collect();
+            format!("({})", args.join(" "))
+        }
+    }
+}
+
+impl Sexp {
+    /// The tree as CMake commands: `(target_link_libraries app PRIVATE core)`
+    /// becomes `target_link_libraries(app PRIVATE core)`, and a list that
+    /// isn't headed by a command name is a sequence of commands, one per
+    /// line. String atoms are quoted, and other atoms only when CMake would
+    /// otherwise split them, such as at spaces. Nested lists in arguments
+    /// become parenthesized groups.
+    pub fn to_cmake_string(&self) -> String {
+        match self.items().split_first() {
+            Some((Sexp::Atom(command), args)) if is_identifier(command) => {
+                let args: Vec<String> = args.iter().map(argument).collect();
+                format!("{}({})\n", command, args.join(" "))
+            }
+            _ if matches!(self, Sexp::List(_)) => {
+                self.items().iter().map(Sexp::to_cmake_string).collect()
+            }
+            _ => format!("{}\n", argument(self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_cmake_string() {
+        let sexp = Sexp::of_str(
+            r#"((cmake_minimum_required VERSION 3.16)
+                (set SOURCES main.c "util lib.c")
+                (if (WIN32 OR APPLE) AND NOT NO_GUI)
+                (message STATUS "${SOURCES}")
+                (target_link_libraries app PRIVATE core))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_cmake_string(),
+            "cmake_minimum_required(VERSION 3.16)\n\
+             set(SOURCES main.c \"util lib.c\")\n\
+             if((WIN32 OR APPLE) AND NOT NO_GUI)\n\
+             message(STATUS \"${SOURCES}\")\n\
+             target_link_libraries(app PRIVATE core)\n"
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use super::infix::{write, Syntax};
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "asm",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "constexpr",
+    "continue",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "nullptr",
+    "operator",
+    "or",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+    "xor",
+];
+
+/// Whether `name` is an identifier, or a qualified one like `std::max`.
+fn is_name(name: &str) -> bool {
+    name.split("::")
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+/// `text` as a C++ string literal. Control characters are octal escapes,
+/// which unlike `\x` stop after three digits whatever follows them.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\{:03o}", c as u32)),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "nullptr".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let arguments = |items: &[Sexp]| -> String {
+        items
+            .iter()
+            .map(|item| write(item, &CPP))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match (head, rest) {
+        ("call", [function, args @ ..]) => {
+            let mut call = vec![function.clone()];
+            call.extend(args.iter().cloned());
+            Some(write(&Sexp::list(call), &CPP))
+        }
+        // The template arguments are types, written as they are: one, or a
+        // list of them as in `(template (K V) f ...)`.
+        ("template", [types, Sexp::Atom(function), args @ ..]) if is_name(function) => {
+            let types: Vec<String> = match types {
+                Sexp::List(_) => types
+                    .items()
+                    .iter()
+                    .map(Sexp::as_text)
+                    .collect::<Option<_>>()?,
+                _ => vec![types.as_text()?],
+            };
+            Some(format!(
+                "{}<{}>({})",
+                function,
+                types.join(", "),
+                arguments(args)
+            ))
+        }
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "auto {} = {}",
+            write(name, &CPP),
+            write(init, &CPP)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("({} = {})", write(name, &CPP), write(init, &CPP)))
+        }
+        // The comma operator evaluates in turn to the last value.
+        ("begin", [_, ..]) => Some(format!("({})", arguments(rest))),
+        _ => None,
+    }
+}
+
+/// A lambda capturing by reference, with a generic parameter for each and
+/// the last form of its body returned.
+fn lambda(params: &[String], body: &[String]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| format!("auto {}", param))
+        .collect();
+    let mut statements: Vec<String> = body.iter().map(|form| format!("{};", form)).collect();
+    if let Some(last) = statements.last_mut() {
+        *last = format!("return {}", last);
+    }
+    format!("[&]({}) {{ {} }}", params.join(", "), statements.join(" "))
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{{{}, {}}}", string(key), value))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+const CPP: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: None,
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("{", "}"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as a C++ expression, reading it as Scheme-style code:
+    /// `(call f a b)` and `(f a b)` become `f(a, b)`, `(template T f arg)`
+    /// `f<T>(arg)`, and `(.method object args...)` and `(.-field object)`
+    /// member access. Operators are C++'s, `if` with an alternative is
+    /// `? :`, `begin` the comma operator, and `lambda` a `[&](auto x) {
+    /// ... }` lambda returning its last form, in which `define` declares an
+    /// `auto` variable. `(array ...)` and `(table (key value)...)` become
+    /// braced initializer lists, `{a, b}` and `{{"key", value}}`. Qualified
+    /// names like `std::max` are kept, and `nil` becomes `nullptr`.
+    pub fn to_cpp_string(&self) -> String {
+        write(self, &CPP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn test_to_cpp_string() {
+        let sexp = Sexp::of_str(
+            r#"(call std::max
+                 (template int twice (+ a 1))
+                 (+ ((lambda (x) (define y (* x 2)) (+ y 1)) a)
+                    (if (and (> (add a b) 0) (not (== (% b 3) 0))) (.size p) (.-x p))
+                    (- (std::stoi "12\tapples"))))"#,
+        )
+        .unwrap();
+        let expression = sexp.to_cpp_string();
+        assert_eq!(
+            expression,
+            r#"std::max(twice<int>(a + 1), ([&](auto x) { auto y = x * 2; return y + 1; })(a) + (add(a, b) > 0 && !(b % 3 == 0) ? p.size() : p.x) + -std::stoi("12\tapples"))"#
+        );
+
+        // Check that it compiles among the declarations it uses, where
+        // there's a compiler to do that.
+        let snippet = format!(
+            r#"#include <algorithm>
+#include <string>
+int add(int a, int b) {{ return a + b; }}
+template <typename T> T twice(T x) {{ return x + x; }}
+struct Point {{ int x; int size() const {{ return 2; }} }};
+int check(Point p, int a, int b) {{ return {}; }}
+"#,
+            expression
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("check.cpp");
+        fs::write(&path, snippet).unwrap();
+        let compiled = Command::new("c++")
+            .args(["-std=c++17", "-fsyntax-only"])
+            .arg(&path)
+            .output();
+        if let Ok(output) = compiled {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+}
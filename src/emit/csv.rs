@@ -0,0 +1,143 @@
+use crate::{Sexp, SexpError};
+
+fn fields(row: &Sexp) -> impl Iterator<Item = String> + '_ {
+    row.items()
+        .iter()
+        .map(|item| item.as_text().unwrap_or_else(|| item.to_compact_string()))
+}
+
+fn csv_error(at: usize, message: String) -> SexpError {
+    SexpError::CsvParseError { at, message }
+}
+
+/// The fields of `row`, each written by `field`, joined by `delimiter` and
+/// ending in a newline.
+fn delimited(row: &Sexp, delimiter: char, field: impl Fn(String) -> String) -> String {
+    let fields: Vec<String> = fields(row).map(field).collect();
+    format!("{}\n", fields.join(&delimiter.to_string()))
+}
+
+impl Sexp {
+    /// The elements of a flat list as one CSV row ending in a newline.
+    /// Fields are quoted when they contain the delimiter, a quote or a line
+    /// break, and empty fields always, with quotes doubled inside. Nested
+    /// lists are written as their compact text.
+    pub fn to_csv_row(&self, delimiter: char) -> String {
+        delimited(self, delimiter, |text| {
+            let special = |c| c == delimiter || c == '"' || c == '\n' || c == '\r';
+            if text.is_empty() || text.contains(special) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text
+            }
+        })
+    }
+
+    /// The elements of a flat list as one tab-separated row ending in a
+    /// newline. TSV has no quoting, so tabs, line breaks and backslashes in
+    /// fields are escaped as `\t`, `\n`, `\r` and `\\` instead.
+    pub fn to_tsv(&self) -> String {
+        delimited(self, '\t', |text| {
+            text.replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+        })
+    }
+
+    /// Reads one CSV row, with or without its line ending, into a flat list
+    /// of atoms (string atoms when a field isn't a bare atom). Quoted fields
+    /// may contain the delimiter, line breaks and doubled quotes.
+    pub fn from_csv_row(line: &str, delimiter: char) -> Result<Sexp, SexpError> {
+        let line = line
+            .strip_suffix("\r\n")
+            .or_else(|| line.strip_suffix('\n'))
+            .unwrap_or(line);
+        let mut fields = vec![];
+        let mut chars = line.char_indices().peekable();
+        loop {
+            let mut field = String::new();
+            if chars.peek().map(|&(_, c)| c) == Some('"') {
+                let (start, _) = chars.next().unwrap();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some((_, '"')) => break,
+                        Some((_, c)) => field.push(c),
+                        None => {
+                            return Err(csv_error(start, "Unterminated quoted field".to_string()))
+                        }
+                    }
+                }
+                fields.push(Sexp::string(&field));
+            } else {
+                while let Some(&(at, c)) = chars.peek() {
+                    if c == delimiter {
+                        break;
+                    }
+                    if c == '"' {
+                        return Err(csv_error(at, "Unexpected quote".to_string()));
+                    }
+                    field.push(c);
+                    chars.next();
+                }
+                fields.push(Sexp::atom_or_string(&field));
+            }
+            match chars.next() {
+                None => break,
+                Some((_, c)) if c == delimiter => {}
+                Some((at, c)) => {
+                    return Err(csv_error(
+                        at,
+                        format!(
+                            "Expected {:?} after a quoted field, found {:?}",
+                            delimiter, c
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(Sexp::list(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_csv_row() {
+        let sexp = Sexp::of_str(r#"(a "b,c" "say \"hi\"" "" "x\ny" (d e))"#).unwrap();
+        assert_eq!(
+            sexp.to_csv_row(','),
+            "a,\"b,c\",\"say \"\"hi\"\"\",\"\",\"x\ny\",(d e)\n"
+        );
+        assert_eq!(
+            sexp.to_csv_row(';'),
+            "a;b,c;\"say \"\"hi\"\"\";\"\";\"x\ny\";(d e)\n"
+        );
+    }
+
+    #[test]
+    fn test_to_tsv() {
+        let sexp = Sexp::of_str(r#"(a "b\tc" "x\ny" "c:\\dir" "" (d e))"#).unwrap();
+        assert_eq!(sexp.to_tsv(), "a\tb\\tc\tx\\ny\tc:\\\\dir\t\t(d e)\n");
+    }
+
+    #[test]
+    fn test_from_csv_row() {
+        let row = Sexp::from_csv_row("a,\"b,c\",\"say \"\"hi\"\"\",,x y\r\n", ',').unwrap();
+        assert_eq!(row.to_string(), r#"(a "b,c" "say \"hi\"" "" "x y")"#);
+        assert_eq!(Sexp::from_csv_row(&row.to_csv_row(';'), ';').unwrap(), row);
+        assert!(Sexp::from_csv_row("a,\"b", ',').is_err());
+        assert!(Sexp::from_csv_row("a,\"b\"c", ',').is_err());
+        assert!(Sexp::from_csv_row("a,b\"c", ',').is_err());
+        assert_eq!(
+            Sexp::from_csv_row("a,\"b\"c", ',').unwrap_err().to_string(),
+            "Expected ',' after a quoted field, found 'c' at byte 5"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use super::{atom, fields, quoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &["for", "if", "import", "in", "let", "package"];
+
+fn label(name: &str) -> String {
+    // `_` and `#` would make hidden fields and definitions.
+    let identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$'))
+        && !KEYWORDS.contains(&name);
+    if identifier {
+        name.to_string()
+    } else {
+        quoted(name, '"')
+    }
+}
+
+impl Sexp {
+    /// The tree as a CUE value: a list whose elements are all fields (lists
+    /// headed by distinct names, like `(name core)`) becomes a struct, any
+    /// other list a CUE list, numbers stay numbers, `true`, `false` and
+    /// `null` stay as they are, and every other atom becomes a string.
+    pub fn to_cue(&self) -> String {
+        match self {
+            Sexp::Nil => "null".to_string(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::Symbol(name @ ("true" | "false" | "null")) => name.to_string(),
+                Atom::Symbol(name) => quoted(name, '"'),
+                Atom::String(text) => quoted(&text, '"'),
+            },
+            Sexp::List(_) => match fields(self) {
+                Some(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|(name, value)| format!("{}: {}", label(name), value.to_cue()))
+                        .collect();
+                    format!("{{{}}}", fields.join(", "))
+                }
+                None => {
+                    let items: Vec<String> = self.items().iter().map(Sexp::to_cue).collect();
+                    format!("[{}]", items.join(", "))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_cue() {
+        let sexp = Sexp::of_str(
+            r#"((apiVersion v1) (metadata (name "web") (labels (app web))) (ports 80 443) ("_hidden" "\\(x)"))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_cue(),
+            r#"{apiVersion: "v1", metadata: {name: "web", labels: {app: "web"}}, ports: [80, 443], "_hidden": "\\(x)"}"#
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::Sexp;
+
+/// A struct's name and its field names, read from `(Name (field Type)...)`.
+/// Only the names matter, so a field is any list with an atom first, and
+/// anything else among the fields is left out.
+fn fields(sexp: &Sexp) -> (String, Vec<String>) {
+    let (name, fields) = match sexp {
+        Sexp::Atom(name) => (name.clone(), &[][..]),
+        _ => match sexp.items().split_first() {
+            Some((name, fields)) => (name.to_compact_string(), fields),
+            None => (String::new(), &[][..]),
+        },
+    };
+    let fields = fields
+        .iter()
+        .filter_map(|field| match field.items().first() {
+            Some(Sexp::Atom(field)) => Some(field.clone()),
+            _ => None,
+        })
+        .collect();
+    (name, fields)
+}
+
+/// `Self { field: value, ... }` for each field, or `Self` for a unit struct.
+fn construct(fields: &[String], value: impl Fn(&str) -> String) -> String {
+    if fields.is_empty() {
+        return "Self".to_string();
+    }
+    let fields: String = fields
+        .iter()
+        .map(|field| format!("            {}: {},\n", field, value(field)))
+        .collect();
+    format!("Self {{\n{}        }}", fields)
+}
+
+impl Sexp {
+    /// Rust source for what `#[derive(Trait)]` would expand to, on a struct
+    /// written as `(Name (field Type)...)`, like `(Point (x i32) (y i32))`.
+    /// `Default`, `Clone`, `Debug`, `PartialEq`, `Eq` and `Hash` are
+    /// supported, and any other trait gets an empty impl as `Eq` does,
+    /// which is all a marker trait like `Copy` needs. The impl refers to
+    /// traits by their full paths, so it compiles wherever the struct is in
+    /// scope. Names are written as they are, for the compiler to check.
+    pub fn generate_derive_impl(&self, trait_name: &str) -> String {
+        let (name, fields) = fields(self);
+        let (path, body) = match trait_name {
+            "Default" => (
+                "Default",
+                format!(
+                    "    fn default() -> Self {{\n        {}\n    }}\n",
+                    construct(&fields, |_| "Default::default()".to_string())
+                ),
+            ),
+            "Clone" => (
+                "Clone",
+                format!(
+                    "    fn clone(&self) -> Self {{\n        {}\n    }}\n",
+                    construct(&fields, |field| format!("Clone::clone(&self.{})", field))
+                ),
+            ),
+            "Debug" => {
+                let fields: String = fields
+                    .iter()
+                    .map(|field| format!("            .field({:?}, &self.{})\n", field, field))
+                    .collect();
+                (
+                    "std::fmt::Debug",
+                    format!(
+                        "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{\n        f.debug_struct({:?})\n{}            .finish()\n    }}\n",
+                        name,
+                        fields
+                    ),
+                )
+            }
+            "PartialEq" => {
+                let (other, comparison) = if fields.is_empty() {
+                    ("_other", "true".to_string())
+                } else {
+                    let comparisons: Vec<String> = fields
+                        .iter()
+                        .map(|field| format!("self.{} == other.{}", field, field))
+                        .collect();
+                    ("other", comparisons.join("\n            && "))
+                };
+                (
+                    "PartialEq",
+                    format!(
+                        "    fn eq(&self, {}: &Self) -> bool {{\n        {}\n    }}\n",
+                        other, comparison
+                    ),
+                )
+            }
+            "Eq" => return format!("impl Eq for {} {{}}\n", name),
+            "Hash" => {
+                let state = if fields.is_empty() { "_state" } else { "state" };
+                let hashes: String = fields
+                    .iter()
+                    .map(|field| {
+                        format!("        std::hash::Hash::hash(&self.{}, state);\n", field)
+                    })
+                    .collect();
+                (
+                    "std::hash::Hash",
+                    format!(
+                        "    fn hash<H: std::hash::Hasher>(&self, {}: &mut H) {{\n{}    }}\n",
+                        state, hashes
+                    ),
+                )
+            }
+            _ => return format!("impl {} for {} {{}}\n", trait_name, name),
+        };
+        format!("impl {} for {} {{\n{}}}\n", path, name, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::derivable_impls)]
+    mod derive_fixture {
+        pub struct Foo {
+            pub x: i32,
+            pub name: String,
+        }
+
+        include!("./derive_fixture.out.rs");
+    }
+
+    const TRAITS: &[&str] = &["Default", "Clone", "Debug", "PartialEq", "Eq", "Hash"];
+
+    #[test]
+    fn test_generate_derive_impl() {
+        let sexp = Sexp::of_str("(Foo (x i32) (name String))").unwrap();
+        assert_eq!(
+            sexp.generate_derive_impl("Default"),
+            "impl Default for Foo {\n    fn default() -> Self {\n        Self {\n            x: Default::default(),\n            name: Default::default(),\n        }\n    }\n}\n"
+        );
+        let impls: Vec<String> = TRAITS
+            .iter()
+            .map(|name| sexp.generate_derive_impl(name))
+            .collect();
+        assert_eq!(impls.join("\n"), include_str!("./derive_fixture.out.rs"));
+
+        use derive_fixture::Foo;
+        let foo = Foo {
+            x: 1,
+            name: "a".to_string(),
+        };
+        assert_eq!(format!("{:?}", Foo::default()), r#"Foo { x: 0, name: "" }"#);
+        assert!(foo.clone() == foo);
+        assert!(foo != Foo::default());
+    }
+
+    #[test]
+    fn test_generate_derive_impl_unit() {
+        let unit = Sexp::of_str("(Unit)").unwrap();
+        assert_eq!(
+            unit.generate_derive_impl("Default"),
+            "impl Default for Unit {\n    fn default() -> Self {\n        Self\n    }\n}\n"
+        );
+        assert_eq!(unit.generate_derive_impl("Copy"), "impl Copy for Unit {}\n");
+        assert_eq!(
+            Sexp::of_str("Unit").unwrap().generate_derive_impl("Eq"),
+            "impl Eq for Unit {}\n"
+        );
+        assert_eq!(
+            Sexp::of_str("(Foo (x) y ((z) u8))")
+                .unwrap()
+                .generate_derive_impl("PartialEq"),
+            "impl PartialEq for Foo {\n    fn eq(&self, other: &Self) -> bool {\n        self.x == other.x\n    }\n}\n"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+impl Default for Foo {
+    fn default() -> Self {
+        Self {
+            x: Default::default(),
+            name: Default::default(),
+        }
+    }
+}
+
+impl Clone for Foo {
+    fn clone(&self) -> Self {
+        Self {
+            x: Clone::clone(&self.x),
+            name: Clone::clone(&self.name),
+        }
+    }
+}
+
+impl std::fmt::Debug for Foo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Foo")
+            .field("x", &self.x)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl PartialEq for Foo {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x
+            && self.name == other.name
+    }
+}
+
+impl Eq for Foo {}
+
+impl std::hash::Hash for Foo {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::hash::Hash::hash(&self.x, state);
+        std::hash::Hash::hash(&self.name, state);
+    }
+}
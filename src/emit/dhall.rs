@@ -0,0 +1,103 @@
+use super::{fields, unquoted};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "assert",
+    "else",
+    "forall",
+    "if",
+    "in",
+    "let",
+    "merge",
+    "missing",
+    "showConstructor",
+    "then",
+    "toMap",
+    "using",
+    "with",
+    "Some",
+];
+
+fn text(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            // `${` would start an interpolation.
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:X}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn label(name: &str) -> String {
+    let simple = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '/'))
+        && !KEYWORDS.contains(&name);
+    if simple {
+        name.to_string()
+    } else {
+        format!("`{}`", name)
+    }
+}
+
+impl Sexp {
+    /// The tree as a Dhall expression: a list whose elements are all fields
+    /// (lists headed by distinct names, like `(name core)`) becomes a
+    /// record, any other list a Dhall list, every atom a `Text` literal and
+    /// `Nil` `None Text`. Dhall lists must hold values of one type, so a
+    /// list mixing atoms with lists or with differently shaped records type
+    /// checks only if it's empty or uniform; an empty list is annotated as
+    /// `List Text`.
+    pub fn to_dhall(&self) -> String {
+        match self {
+            Sexp::Nil => "None Text".to_string(),
+            Sexp::Atom(atom) => text(&unquoted(atom)),
+            Sexp::List(_) => match fields(self) {
+                Some(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|(name, value)| format!("{} = {}", label(name), value.to_dhall()))
+                        .collect();
+                    format!("{{ {} }}", fields.join(", "))
+                }
+                None if self.items().is_empty() => "[] : List Text".to_string(),
+                None => {
+                    let items: Vec<String> = self.items().iter().map(Sexp::to_dhall).collect();
+                    format!("[ {} ]", items.join(", "))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dhall() {
+        let sexp = Sexp::of_str(
+            r#"((name core) (deps a "${b}") (if 1) (none ()) (rules ((x 1)) ((x 2))))"#,
+        )
+        .unwrap();
+        let dhall = sexp.to_dhall();
+        assert_eq!(
+            dhall,
+            r#"{ name = "core", deps = [ "a", "\${b}" ], `if` = "1", none = [] : List Text, rules = [ { x = "1" }, { x = "2" } ] }"#
+        );
+        let value: serde_json::Value = serde_dhall::from_str(&dhall).parse().unwrap();
+        assert_eq!(value["deps"][1], "${b}");
+        assert_eq!(value["rules"][1]["x"], "2");
+    }
+}
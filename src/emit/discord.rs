@@ -0,0 +1,58 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// Lists whose compact form is longer than this get a code block of their
+/// own instead of inline code.
+const INLINE_WIDTH: usize = 60;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\*_~`|>#".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl Sexp {
+    /// The tree as a Discord message: a short list as inline code, a longer
+    /// one pretty-printed in a `lisp` code block, and an atom as escaped
+    /// text. Code can't contain its own fence, so inline code falls back to
+    /// a block when an atom has a backtick, and runs of three backticks in a
+    /// block are broken up with a zero-width space.
+    pub fn to_discord_markdown(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(atom) => escape(&unquoted(atom)),
+            Sexp::List(_) => match self.compact_len_within(INLINE_WIDTH) {
+                Some(_) if !self.to_compact_string().contains('`') => {
+                    format!("`{}`", self.to_compact_string())
+                }
+                _ => format!(
+                    "```lisp\n{}\n```",
+                    self.to_string().replace("```", "`\u{200b}``")
+                ),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_discord_markdown() {
+        let short = Sexp::of_str("(error (missing rparen))").unwrap();
+        assert_eq!(short.to_discord_markdown(), "`(error (missing rparen))`");
+        let atom = Sexp::of_str(r#""*not bold*""#).unwrap();
+        assert_eq!(atom.to_discord_markdown(), "\\*not bold\\*");
+        let fenced = Sexp::of_str(r#"(code "```")"#).unwrap();
+        assert_eq!(
+            fenced.to_discord_markdown(),
+            "```lisp\n(code \"`\u{200b}``\")\n```"
+        );
+    }
+}
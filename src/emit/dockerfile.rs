@@ -0,0 +1,151 @@
+use super::unquoted;
+use crate::Sexp;
+
+const INSTRUCTIONS: &[&str] = &[
+    "ADD",
+    "ARG",
+    "CMD",
+    "COPY",
+    "ENTRYPOINT",
+    "ENV",
+    "EXPOSE",
+    "FROM",
+    "HEALTHCHECK",
+    "LABEL",
+    "MAINTAINER",
+    "ONBUILD",
+    "RUN",
+    "SHELL",
+    "STOPSIGNAL",
+    "USER",
+    "VOLUME",
+    "WORKDIR",
+];
+
+fn word(sexp: &Sexp) -> String {
+    match sexp {
+        Sexp::Atom(atom) => unquoted(atom),
+        _ => sexp.to_compact_string(),
+    }
+}
+
+fn words(args: &[Sexp]) -> String {
+    args.iter().map(word).collect::<Vec<_>>().join(" ")
+}
+
+/// The JSON array of the exec form, as in `CMD ["nginx", "-g", "daemon off;"]`.
+fn exec_form(args: &[Sexp]) -> String {
+    let words: Vec<String> = args.iter().map(word).collect();
+    serde_json::to_string(&words).unwrap()
+}
+
+/// `KEY=value` pairs for `ENV`, `LABEL` and `ARG`, with values quoted when
+/// they contain spaces or quotes. A name alone, or `(KEY)`, is written as
+/// just the name, as `ARG` takes it.
+fn pairs(args: &[Sexp]) -> String {
+    let pairs: Vec<String> = args
+        .iter()
+        .map(|pair| match pair.items() {
+            [key, value] => {
+                let value = word(value);
+                let value = if value.is_empty() || value.contains([' ', '\t', '"', '\'']) {
+                    serde_json::to_string(&value).unwrap()
+                } else {
+                    value
+                };
+                format!("{}={}", word(key), value)
+            }
+            [key] => word(key),
+            _ => word(pair),
+        })
+        .collect();
+    pairs.join(" ")
+}
+
+fn instruction(sexp: &Sexp) -> String {
+    let (name, args) = match sexp.items().split_first() {
+        Some((Sexp::Atom(name), args)) => (name.to_ascii_uppercase(), args),
+        _ => return words(sexp.items()),
+    };
+    if !INSTRUCTIONS.contains(&name.as_str()) {
+        return format!("{} {}", name, words(args));
+    }
+    let args = match (name.as_str(), args) {
+        // A single list is the exec form.
+        ("RUN" | "CMD" | "ENTRYPOINT" | "SHELL", [list @ Sexp::List(_)]) => exec_form(list.items()),
+        ("ADD" | "COPY" | "VOLUME", _)
+            if args
+                .iter()
+                .any(|arg| word(arg).contains(char::is_whitespace)) =>
+        {
+            exec_form(args)
+        }
+        // Several command strings are chained, one per line.
+        ("RUN", [_, _, ..])
+            if args
+                .iter()
+                .all(|arg| matches!(arg, Sexp::Atom(atom) if atom.starts_with('"'))) =>
+        {
+            let commands: Vec<String> = args.iter().map(word).collect();
+            commands.join(" \\\n    && ")
+        }
+        ("ENV" | "LABEL" | "ARG", _) => pairs(args),
+        ("ONBUILD", [trigger @ Sexp::List(_)]) => instruction(trigger),
+        _ => words(args),
+    };
+    format!("{} {}", name, args)
+}
+
+impl Sexp {
+    /// The tree as a Dockerfile: `(FROM rust:1.70)` becomes `FROM rust:1.70`,
+    /// and a list of instructions one per line. `RUN`, `CMD`, `ENTRYPOINT`
+    /// and `SHELL` with a single list use the JSON exec form, as do `ADD`,
+    /// `COPY` and `VOLUME` when a path has spaces, and several strings after
+    /// `RUN` are chained with `&&`. `ENV`, `LABEL` and `ARG` take `(KEY
+    /// value)` pairs. Instructions are upper-cased, and one Docker doesn't
+    /// know is written with its arguments as words, for `docker build` to
+    /// report.
+    pub fn to_dockerfile(&self) -> String {
+        let instructions = match self.items().first() {
+            Some(Sexp::Atom(_)) => vec![instruction(self)],
+            _ => self.items().iter().map(instruction).collect(),
+        };
+        instructions
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dockerfile() {
+        let sexp = Sexp::of_str(
+            r#"((FROM rust:1.70 AS builder)
+                (env (CARGO_HOME /cargo) (GREETING "hello world"))
+                (COPY "my app" /src)
+                (RUN "apt-get update" "apt-get install -y git")
+                (EXPOSE 8080 9090/udp)
+                (CMD (nginx -g "daemon off;")))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_dockerfile(),
+            "FROM rust:1.70 AS builder\n\
+             ENV CARGO_HOME=/cargo GREETING=\"hello world\"\n\
+             COPY [\"my app\",\"/src\"]\n\
+             RUN apt-get update \\\n    && apt-get install -y git\n\
+             EXPOSE 8080 9090/udp\n\
+             CMD [\"nginx\",\"-g\",\"daemon off;\"]\n"
+        );
+        assert_eq!(
+            Sexp::of_str("((build x) (ARG (A) B (C 1)))")
+                .unwrap()
+                .to_dockerfile(),
+            "BUILD x\nARG A B C=1\n"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use super::{atom, quoted, unquoted, Atom};
+use crate::Sexp;
+
+// EDN symbols may use these besides letters and digits, and may not start
+// with a digit (or with `+`, `-` or `.` followed by one).
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let second = chars.next();
+    let valid = |c: char| c.is_alphanumeric() || ".*+!-_?$%&=<>/:#'".contains(c);
+    !first.is_numeric()
+        && first != ':'
+        && first != '#'
+        && first != '\''
+        && !("+-.".contains(first) && second.is_some_and(|c| c.is_numeric()))
+        && name.chars().all(valid)
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => quoted(&text, '"'),
+        Atom::Symbol(name @ ("true" | "false" | "nil")) => name.to_string(),
+        Atom::Symbol(name) => match name.strip_prefix(':') {
+            Some(keyword) if is_symbol(keyword) => name.to_string(),
+            None if is_symbol(name) => name.to_string(),
+            _ => quoted(name, '"'),
+        },
+    }
+}
+
+/// A `#js` literal for `(js ...)`: an object if every element is a `(key
+/// value)` pair, and an array otherwise.
+fn js_literal(items: &[Sexp]) -> String {
+    let pairs: Option<Vec<String>> = items
+        .iter()
+        .map(|item| match item.items() {
+            [Sexp::Atom(key), value] => {
+                let key = unquoted(key);
+                let key = match key.strip_prefix(':').unwrap_or(&key) {
+                    key if is_symbol(key) => format!(":{}", key),
+                    key => quoted(key, '"'),
+                };
+                Some(format!("{} {}", key, edn(value, true)))
+            }
+            _ => None,
+        })
+        .collect();
+    match pairs {
+        Some(pairs) if !pairs.is_empty() => format!("#js {{{}}}", pairs.join(" ")),
+        _ => {
+            let items: Vec<String> = items.iter().map(|item| edn(item, true)).collect();
+            format!("#js [{}]", items.join(" "))
+        }
+    }
+}
+
+fn edn(sexp: &Sexp, cljs: bool) -> String {
+    match sexp {
+        Sexp::Nil => String::new(),
+        Sexp::Atom(text) => atom_text(text),
+        Sexp::List(_) => match sexp.items().split_first() {
+            Some((Sexp::Atom(head), items)) if cljs && head == "js" => js_literal(items),
+            _ => {
+                let items: Vec<String> = sexp.items().iter().map(|item| edn(item, cljs)).collect();
+                format!("({})", items.join(" "))
+            }
+        },
+    }
+}
+
+impl Sexp {
+    /// The tree as EDN. Lists stay lists, `:name` atoms become keywords,
+    /// `true`, `false` and `nil` the matching literals, numbers stay numbers
+    /// and string atoms become strings. Other atoms become symbols, or
+    /// strings when EDN wouldn't read them back as a symbol.
+    pub fn to_clojure_edn(&self) -> String {
+        edn(self, false)
+    }
+
+    /// The tree as ClojureScript, written like `to_clojure_edn`. Interop
+    /// symbols like `js/console.log`, `.method` and `.-field` are symbols
+    /// already, and `(js ...)` becomes a `#js` literal: `#js {:key value}`
+    /// when its elements are all `(key value)` pairs, and `#js [...]`
+    /// otherwise.
+    #[cfg(feature = "cljs")]
+    pub fn to_clojurescript_string(&self) -> String {
+        edn(self, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_clojure_edn() {
+        let sexp =
+            Sexp::of_str(r#"(config :port 80 :debug true :tags (nil "a\"b") str/join ~x +1 : -)"#)
+                .unwrap();
+        assert_eq!(
+            sexp.to_clojure_edn(),
+            r#"(config :port 80 :debug true :tags (nil "a\"b") str/join "~x" +1 ":" -)"#
+        );
+    }
+
+    #[cfg(feature = "cljs")]
+    #[test]
+    fn test_to_clojurescript_string() {
+        let sexp = Sexp::of_str(
+            r#"(do (.log js/console "hi") (set! (.-title js/document) "x")
+                   (js/fetch url (js (method "POST") ("max-age" 10) (headers (js (1 2)))))
+                   (js 1 2))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_clojurescript_string(),
+            r#"(do (.log js/console "hi") (set! (.-title js/document) "x") (js/fetch url #js {:method "POST" :max-age 10 :headers #js {"1" 2}}) #js [1 2])"#
+        );
+        assert_eq!(Sexp::of_str("(js 1)").unwrap().to_clojure_edn(), "(js 1)");
+    }
+}
@@ -0,0 +1,290 @@
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+use anyhow::{anyhow, Error};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// `name` as an Emacs Lisp symbol. Besides letters and digits, only the
+/// characters below read as part of a symbol without a backslash before
+/// them, and a name that `read` would take for a number, or for a
+/// character like `?a`, needs its first character escaped too.
+fn symbol(name: &str) -> String {
+    let mut symbol = String::with_capacity(name.len());
+    let mut chars = name.chars().peekable();
+    if let Some(&first) = chars.peek() {
+        let second = name[first.len_utf8()..].chars().next();
+        let numeric = first.is_ascii_digit()
+            || ("+-.".contains(first) && second.is_some_and(|c| c.is_ascii_digit() || c == '.'));
+        if numeric || first == '?' {
+            symbol.push('\\');
+            symbol.push(first);
+            chars.next();
+        }
+    }
+    for c in chars {
+        if !c.is_alphanumeric() && !"-+=*/_~!@$%^&:<>{}?".contains(c) {
+            symbol.push('\\');
+        }
+        symbol.push(c);
+    }
+    symbol
+}
+
+impl Sexp {
+    /// The tree as Emacs Lisp that `read` gives back: lists stay lists,
+    /// numbers stay numbers and string atoms become strings. Other atoms
+    /// become symbols, with a backslash before any character that would
+    /// otherwise end the symbol or make it read as something else, as in
+    /// `foo\ bar` or `\1+`. Emacs Lisp symbols are case-sensitive, so case
+    /// needs no quoting.
+    pub fn to_emacs_lisp_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::String(text) => quoted(&text, '"'),
+                Atom::Symbol(name) => symbol(name),
+            },
+            Sexp::List(_) => {
+                let items: Vec<String> = self
+                    .items()
+                    .iter()
+                    .map(Sexp::to_emacs_lisp_string)
+                    .collect();
+                format!("({})", items.join(" "))
+            }
+        }
+    }
+
+    /// Reads Emacs Lisp data: lists, strings, numbers and symbols, with
+    /// `'x` and `#'x` becoming `(quote x)` and `(function x)` as they do in
+    /// Emacs, and characters like `?a` their codes. A symbol becomes an
+    /// atom, or a string atom when its name isn't a bare atom here. Vectors,
+    /// dotted pairs, backquotes and other `#` syntax are errors, since
+    /// there's no tree to read them into.
+    pub fn of_emacs_lisp_string(input: &str) -> Result<Sexp, Error> {
+        let mut reader = Reader {
+            chars: input.char_indices().peekable(),
+        };
+        let sexp = reader.value()?;
+        match reader.peek() {
+            None => Ok(sexp),
+            Some(at) => Err(anyhow!("Unexpected input at byte {}", at)),
+        }
+    }
+}
+
+struct Reader<'a> {
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Reader<'_> {
+    /// Skips whitespace and comments, returning where the next token
+    /// starts.
+    fn peek(&mut self) -> Option<usize> {
+        loop {
+            match self.chars.peek() {
+                Some(&(_, c)) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some(&(_, ';')) => while self.chars.next_if(|&(_, c)| c != '\n').is_some() {},
+                Some(&(at, _)) => return Some(at),
+                None => return None,
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Sexp, Error> {
+        let at = match self.peek() {
+            Some(at) => at,
+            None => return Err(anyhow!("Expected a value, found the end of the input")),
+        };
+        let (_, c) = self.chars.next().unwrap();
+        match c {
+            '(' => {
+                let mut items = vec![];
+                loop {
+                    match self.peek() {
+                        Some(_) if self.chars.next_if(|&(_, c)| c == ')').is_some() => {
+                            return Ok(Sexp::list(items))
+                        }
+                        Some(_) => items.push(self.value()?),
+                        None => return Err(anyhow!("Unclosed list at byte {}", at)),
+                    }
+                }
+            }
+            ')' => Err(anyhow!("Unexpected ) at byte {}", at)),
+            '"' => Ok(Sexp::string(&self.string()?)),
+            '\'' => Ok(Sexp::list(vec![Sexp::atom("quote"), self.value()?])),
+            '#' if self.chars.next_if(|&(_, c)| c == '\'').is_some() => {
+                Ok(Sexp::list(vec![Sexp::atom("function"), self.value()?]))
+            }
+            '?' => Ok(Sexp::atom(self.character()?.to_string())),
+            '[' | ']' | '`' | ',' | '#' => Err(anyhow!("Unsupported syntax {} at byte {}", c, at)),
+            c => match self.symbol(c)? {
+                (name, false) if name == "." => {
+                    Err(anyhow!("Dotted pairs aren't supported, at byte {}", at))
+                }
+                (name, _) => Ok(Sexp::atom_or_string(&name)),
+            },
+        }
+    }
+
+    /// A symbol's name, and whether any of it was escaped.
+    fn symbol(&mut self, first: char) -> Result<(String, bool), Error> {
+        let mut name = String::new();
+        let mut escaped = false;
+        let mut c = first;
+        loop {
+            if c == '\\' {
+                escaped = true;
+                match self.chars.next() {
+                    Some((_, c)) => name.push(c),
+                    None => return Err(anyhow!("Unfinished escape at the end of the input")),
+                }
+            } else {
+                name.push(c);
+            }
+            match self
+                .chars
+                .next_if(|&(_, c)| !c.is_whitespace() && !"()[]\";'`,".contains(c))
+            {
+                Some((_, next)) => c = next,
+                None => return Ok((name, escaped)),
+            }
+        }
+    }
+
+    fn character(&mut self) -> Result<u32, Error> {
+        match self.chars.next() {
+            Some((_, '\\')) => self.escape(),
+            Some((_, c)) => Ok(c as u32),
+            None => Err(anyhow!("Expected a character, found the end of the input")),
+        }
+    }
+
+    /// The character after a backslash, in a string or a `?` character.
+    fn escape(&mut self) -> Result<u32, Error> {
+        let c = match self.chars.next() {
+            Some((_, c)) => c,
+            None => return Err(anyhow!("Unfinished escape at the end of the input")),
+        };
+        let hex = |reader: &mut Self, digits: usize| -> Result<u32, Error> {
+            let mut code = String::new();
+            while code.len() < digits {
+                match reader.chars.next_if(|&(_, c)| c.is_ascii_hexdigit()) {
+                    Some((_, c)) => code.push(c),
+                    None => break,
+                }
+            }
+            u32::from_str_radix(&code, 16).map_err(|_| anyhow!("Expected hex digits after \\{}", c))
+        };
+        Ok(match c {
+            'C' | 'M' | 'S' | 'H' | 'A' | 's'
+                if self.chars.next_if(|&(_, c)| c == '-').is_some() =>
+            {
+                return Err(anyhow!(
+                    "Modifier characters like \\{}- aren't supported",
+                    c
+                ))
+            }
+            'n' => '\n' as u32,
+            't' => '\t' as u32,
+            'r' => '\r' as u32,
+            'e' => 0x1b,
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            'd' => 0x7f,
+            's' => ' ' as u32,
+            'x' => hex(self, usize::MAX)?,
+            'u' => hex(self, 4)?,
+            'U' => hex(self, 8)?,
+            '0'..='7' => {
+                let mut code = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match self.chars.next_if(|&(_, c)| c.is_digit(8)) {
+                        Some((_, c)) => code = code * 8 + c.to_digit(8).unwrap(),
+                        None => break,
+                    }
+                }
+                code
+            }
+            c => c as u32,
+        })
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(text),
+                // An escaped line break or space is dropped, so long strings
+                // can be split across lines.
+                Some((_, '\\'))
+                    if self
+                        .chars
+                        .next_if(|&(_, c)| c == '\n' || c == ' ')
+                        .is_some() => {}
+                Some((_, '\\')) => {
+                    let code = self.escape()?;
+                    let c = char::from_u32(code)
+                        .ok_or_else(|| anyhow!("Invalid character {}", code))?;
+                    text.push(c);
+                }
+                Some((_, c)) => text.push(c),
+                None => return Err(anyhow!("Unterminated string")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_emacs_lisp_string() {
+        let sexp =
+            Sexp::of_str(r#"((defvar my-Var 42 "Say \"hi\"\n") (a.b 1+ ?x -1.5e3 +x 1st "") ())"#)
+                .unwrap();
+        assert_eq!(
+            sexp.to_emacs_lisp_string(),
+            r#"((defvar my-Var 42 "Say \"hi\"\n") (a\.b \1+ \?x -1.5e3 +x \1st "") ())"#
+        );
+    }
+
+    #[test]
+    fn test_of_emacs_lisp_string() {
+        let sexp = Sexp::of_emacs_lisp_string(
+            "(defun greet (name) ; says hi\n  \"Greet \\\"NAME\\\".\\n\\x41\\ b\"\n  (message \"Hi, %s!\" name) '(a \\(b\\)) #'car ?A ?\\n foo\\ bar)",
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_string(),
+            r#"(defun greet (name) "Greet \"NAME\".\nAb" (message "Hi, %s!" name) (quote (a "(b)")) (function car) 65 10 "foo bar")"#
+        );
+        for input in &["(a . b)", "[1 2]", "`(a ,b)", "(a", "a)", "?\\C-a"] {
+            assert!(Sexp::of_emacs_lisp_string(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_emacs_lisp_round_trip() {
+        for input in &[
+            r#"(defvar my-Var 42 "Say \"hi\"\n")"#,
+            "(a.b 1+ ?x -1.5e3 +x 1st () (nested (list)))",
+            r#"("x y" "" "\\")"#,
+        ] {
+            let sexp = Sexp::of_str(input).unwrap();
+            let elisp = sexp.to_emacs_lisp_string();
+            assert_eq!(
+                Sexp::of_emacs_lisp_string(&elisp).unwrap(),
+                sexp,
+                "{}",
+                elisp
+            );
+        }
+    }
+}
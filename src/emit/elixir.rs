@@ -0,0 +1,267 @@
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+use anyhow::{anyhow, Error};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+fn is_bare_atom(name: &str) -> bool {
+    let name = name.strip_suffix(['?', '!']).unwrap_or(name);
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+}
+
+fn elixir_atom(name: &str) -> String {
+    if is_bare_atom(name) {
+        format!(":{}", name)
+    } else {
+        format!(":{}", quoted(name, '"'))
+    }
+}
+
+impl Sexp {
+    /// The tree in the shape of Elixir's quoted AST: a list headed by an atom
+    /// becomes `{:head, [], [children]}`, and any other list an Elixir list.
+    /// Atoms become Elixir atoms (`:name`, or `:"quoted"` when they aren't
+    /// valid bare atoms), string atoms become strings and numbers stay
+    /// numbers, so `of_elixir_string` can read the result back.
+    pub fn to_elixir_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::Symbol(name) => elixir_atom(name),
+                Atom::String(text) => quoted(&text, '"'),
+            },
+            Sexp::List(_) => {
+                let items = self.items();
+                match items.split_first() {
+                    Some((Sexp::Atom(head), children)) if !head.starts_with('"') => {
+                        let children: Vec<String> =
+                            children.iter().map(Sexp::to_elixir_string).collect();
+                        format!("{{{}, [], [{}]}}", elixir_atom(head), children.join(", "))
+                    }
+                    _ => {
+                        let items: Vec<String> = items.iter().map(Sexp::to_elixir_string).collect();
+                        format!("[{}]", items.join(", "))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads back the output of `to_elixir_string`. Node metadata is
+    /// accepted but ignored.
+    pub fn of_elixir_string(input: &str) -> Result<Sexp, Error> {
+        let mut reader = Reader {
+            input,
+            chars: input.char_indices().peekable(),
+        };
+        let sexp = reader.term()?;
+        reader.skip_whitespace();
+        match reader.chars.peek() {
+            None => Ok(sexp),
+            Some(&(at, _)) => Err(anyhow!("Unexpected input at byte {}", at)),
+        }
+    }
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+enum Term {
+    Sexp(Sexp),
+    List(Vec<Sexp>),
+}
+
+impl<'a> Reader<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((at, c)) => Err(anyhow!(
+                "Expected {:?} at byte {}, found {:?}",
+                expected,
+                at,
+                c
+            )),
+            None => Err(anyhow!(
+                "Expected {:?}, found the end of the input",
+                expected
+            )),
+        }
+    }
+
+    fn term(&mut self) -> Result<Sexp, Error> {
+        match self.value()? {
+            Term::Sexp(sexp) => Ok(sexp),
+            Term::List(items) => Ok(Sexp::list(items)),
+        }
+    }
+
+    // Comma-separated terms up to `close`, which is consumed.
+    fn terms(&mut self, close: char) -> Result<Vec<Term>, Error> {
+        let mut terms = vec![];
+        self.skip_whitespace();
+        if self.chars.next_if(|&(_, c)| c == close).is_some() {
+            return Ok(terms);
+        }
+        loop {
+            terms.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, c)) if c == close => return Ok(terms),
+                Some((at, c)) => return Err(anyhow!("Unexpected {:?} at byte {}", c, at)),
+                None => return Err(anyhow!("Expected {:?}, found the end of the input", close)),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Term, Error> {
+        self.skip_whitespace();
+        let (at, c) = *self
+            .chars
+            .peek()
+            .ok_or_else(|| anyhow!("Expected a term, found the end of the input"))?;
+        match c {
+            '{' => {
+                self.chars.next();
+                let mut terms = self.terms('}')?.into_iter();
+                match (terms.next(), terms.next(), terms.next(), terms.next()) {
+                    (
+                        Some(Term::Sexp(head @ Sexp::Atom(_))),
+                        Some(_),
+                        Some(Term::List(children)),
+                        None,
+                    ) => {
+                        let mut items = vec![head];
+                        items.extend(children);
+                        Ok(Term::Sexp(Sexp::list(items)))
+                    }
+                    _ => Err(anyhow!(
+                        "Expected a {{:head, meta, [children]}} node at byte {}",
+                        at
+                    )),
+                }
+            }
+            '[' => {
+                self.chars.next();
+                let items = self
+                    .terms(']')?
+                    .into_iter()
+                    .map(|term| match term {
+                        Term::Sexp(sexp) => sexp,
+                        Term::List(items) => Sexp::list(items),
+                    })
+                    .collect();
+                Ok(Term::List(items))
+            }
+            ':' => {
+                self.chars.next();
+                if self.chars.peek().map(|&(_, c)| c) == Some('"') {
+                    return Ok(Term::Sexp(Sexp::atom(self.string()?)));
+                }
+                let name = self.word();
+                if name.is_empty() || !is_bare_atom(name) {
+                    return Err(anyhow!("Invalid atom at byte {}", at));
+                }
+                Ok(Term::Sexp(Sexp::atom(name)))
+            }
+            '"' => Ok(Term::Sexp(Sexp::string(&self.string()?))),
+            _ => {
+                let number = self.word();
+                if is_bare_atom(number) && self.chars.next_if(|&(_, c)| c == ':').is_some() {
+                    // A keyword list entry such as `line: 1` in the metadata.
+                    let key = Sexp::atom(number);
+                    return Ok(Term::Sexp(Sexp::list(vec![key, self.term()?])));
+                }
+                if super::is_number(number) {
+                    Ok(Term::Sexp(Sexp::atom(number)))
+                } else {
+                    Err(anyhow!("Unexpected {:?} at byte {}", c, at))
+                }
+            }
+        }
+    }
+
+    fn word(&mut self) -> &'a str {
+        let start = self.chars.peek().map_or(self.input.len(), |&(at, _)| at);
+        let mut end = start;
+        while let Some((at, c)) = self
+            .chars
+            .next_if(|&(_, c)| c.is_alphanumeric() || "_@?!.+-".contains(c))
+        {
+            end = at + c.len_utf8();
+        }
+        &self.input[start..end]
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.chars.next().map(|(_, c)| c) {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.chars.next().map(|(_, c)| c) {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some(escape @ ('x' | 'u')) => {
+                        let digits = if escape == 'x' { 2 } else { 4 };
+                        let hex: String = (0..digits)
+                            .filter_map(|_| self.chars.next())
+                            .map(|(_, c)| c)
+                            .collect();
+                        let code = u32::from_str_radix(&hex, 16)?;
+                        text.push(char::from_u32(code).ok_or_else(|| anyhow!("Invalid escape"))?);
+                    }
+                    Some(c) => text.push(c),
+                    None => return Err(anyhow!("Unterminated string")),
+                },
+                Some(c) => text.push(c),
+                None => return Err(anyhow!("Unterminated string")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_elixir_string() {
+        let sexp =
+            Sexp::of_str(r#"(call (identifier foo) "bar\n" 42 foo-bar ((x)) (unit))"#).unwrap();
+        assert_eq!(
+            sexp.to_elixir_string(),
+            r#"{:call, [], [{:identifier, [], [:foo]}, "bar\n", 42, :"foo-bar", [{:x, [], []}], {:unit, [], []}]}"#
+        );
+    }
+
+    #[test]
+    fn test_of_elixir_string_round_trip() {
+        for input in &[
+            r#"(call (identifier foo) "bar\n" 42 foo-bar ((x)) (unit))"#,
+            r#"(a "tab\t" -1.5 b? ())"#,
+            "(\"\u{1b}[0m\")",
+            "x",
+        ] {
+            let elixir = Sexp::of_str(input).unwrap().to_elixir_string();
+            let read = Sexp::of_elixir_string(&elixir).unwrap();
+            assert_eq!(read.to_elixir_string(), elixir);
+        }
+        let sexp = Sexp::of_elixir_string("{:a, [line: 1], [:b, 1]}").unwrap();
+        assert_eq!(sexp.to_string(), "(a b 1)");
+        assert!(Sexp::of_elixir_string("{:a, []}").is_err());
+        assert!(Sexp::of_elixir_string("[:a] junk").is_err());
+    }
+}
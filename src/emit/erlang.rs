@@ -0,0 +1,100 @@
+use super::{atom, Atom};
+use crate::Sexp;
+
+const RESERVED: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or",
+    "orelse", "receive", "rem", "try", "when", "xor",
+];
+
+fn quoted(text: &str, quote: char) -> String {
+    let mut quoted = String::new();
+    quoted.push(quote);
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c == quote => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\x{{{:X}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push(quote);
+    quoted
+}
+
+// Bare atoms start with a lowercase letter; anything else needs quotes.
+fn erlang_atom(name: &str) -> String {
+    let bare = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        && !RESERVED.contains(&name);
+    if bare {
+        name.to_string()
+    } else {
+        quoted(name, '\'')
+    }
+}
+
+// Erlang floats need digits on both sides of the point, so `1e3` and `2.`
+// are kept as atoms rather than emitted as invalid numbers.
+fn number(text: &str) -> Option<&str> {
+    let unsigned = text.trim_start_matches('-');
+    let valid = match unsigned.split_once('.') {
+        None => unsigned.chars().all(|c| c.is_ascii_digit()),
+        Some((whole, fraction)) => {
+            let digits = fraction.split(['e', 'E']).next().unwrap();
+            !whole.is_empty() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        }
+    };
+    Some(text).filter(|_| valid && !text.starts_with('+'))
+}
+
+impl Sexp {
+    /// The tree as an Erlang term. A list headed by an atom becomes a tuple
+    /// tagged with it, `(point 1 2)` becoming `{point, 1, 2}`, and any
+    /// other list an Erlang list. String atoms become strings, numbers stay
+    /// numbers, and other atoms become Erlang atoms, quoted when needed.
+    pub fn to_erlang_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(text) => number(text)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| erlang_atom(text)),
+                Atom::Symbol(name) => erlang_atom(name),
+                Atom::String(text) => quoted(&text, '"'),
+            },
+            Sexp::List(_) => {
+                let items: Vec<String> = self.items().iter().map(Sexp::to_erlang_string).collect();
+                match self.items().first() {
+                    Some(Sexp::Atom(_)) => format!("{{{}}}", items.join(", ")),
+                    _ => format!("[{}]", items.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_erlang_string() {
+        let sexp = Sexp::of_str(
+            r#"(config (port 8080) (ratio -0.5 1e3) (name "it's") ((a) (b)) Node end)"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_erlang_string(),
+            r#"{config, {port, 8080}, {ratio, -0.5, '1e3'}, {name, "it's"}, [{a}, {b}], 'Node', 'end'}"#
+        );
+        assert_eq!(Sexp::of_str("()").unwrap().to_erlang_string(), "[]");
+    }
+}
@@ -0,0 +1,96 @@
+use super::lisp::{write, Dialect};
+use super::lua::string;
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// Whether Fennel reads `name` back as this symbol: no delimiters, and no
+/// leading digit or `#`, which starts a hash function.
+fn is_symbol(name: &str) -> bool {
+    name.starts_with(|c: char| !c.is_ascii_digit() && c != '#')
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !"()[]{}\"'`~;,@".contains(c))
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        // `:name` is Fennel's shorthand for the string "name".
+        Atom::Symbol(name) => match name.strip_prefix(':') {
+            Some(key) if is_symbol(key) => name.to_string(),
+            None if is_symbol(name) => name.to_string(),
+            _ => string(name),
+        },
+    }
+}
+
+/// A table key: a `:name` string where it can be one, and otherwise the
+/// key as a value.
+fn key(key: &Sexp) -> String {
+    let name = match key {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => return number.to_string(),
+            Atom::String(text) => text,
+            Atom::Symbol(name) => name.strip_prefix(':').unwrap_or(name).to_string(),
+        },
+        _ => return write(key, &FENNEL),
+    };
+    if is_symbol(&name) && !name.starts_with(':') {
+        format!(":{}", name)
+    } else {
+        string(&name)
+    }
+}
+
+const FENNEL: Dialect = Dialect {
+    atom: atom_text,
+    key,
+    forms: |_, _| None,
+    params: ("[", "]"),
+    defun: "fn",
+    define: "local",
+    set: "set",
+    begin: "do",
+    bindings: "let",
+    cond: "if",
+    table: ("{", "}"),
+    array: ("[", "]"),
+};
+
+impl Sexp {
+    /// The tree as Fennel, reading it as Scheme-style code: `(lambda (x)
+    /// ...)` becomes `(fn [x] ...)`, `(define (f x) ...)` a named `fn` and
+    /// `(define x 1)` a `local`, `(let ((x 1)) ...)` `(let [x 1] ...)`,
+    /// `set!` and `begin` Fennel's `set` and `do`, and `cond` an `if` with
+    /// a branch per clause. `(table (key value)...)` builds a `{:key
+    /// value}` table and `(array ...)` a `[...]` one. Other lists are calls,
+    /// and atoms that aren't numbers or symbols become strings.
+    pub fn to_fennel_string(&self) -> String {
+        write(self, &FENNEL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_fennel_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (greet name) (print (.. "Hi, " name)))
+                 (define count 0)
+                 (let ((x 1) (y (+ count 2)))
+                   (set! count (+ x y)))
+                 (map (lambda (n) (* n n)) (array 1 2 3))
+                 (define config (table (name "demo") ("max size" 10) (1 :first)))
+                 (cond ((> count 2) (print "big")) ((= count 2) (print "two") 2) (else nil)))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_fennel_string(),
+            r#"(do (fn greet [name] (print (.. "Hi, " name))) (local count 0) (let [x 1 y (+ count 2)] (set count (+ x y))) (map (fn [n] (* n n)) [1 2 3]) (local config {:name "demo" "max size" 10 1 :first}) (if (> count 2) (print "big") (= count 2) (do (print "two") 2) nil))"#
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// Whether `text` reads as the one word it is without quoting.
+fn is_plain(text: &str) -> bool {
+    !text.is_empty()
+        && text.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | ',' | '+' | '@' | '=')
+        })
+}
+
+/// `text` as a single word, in single quotes unless it's plain. Unlike a
+/// POSIX shell's, fish's single quotes take `\'` and `\\` escapes.
+fn quoted(text: &str) -> String {
+    if is_plain(text) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'"))
+    }
+}
+
+/// `sexp` as an argument. Strings are literal text and symbols are words
+/// as they are, variables like `$PATH` included, since fish doesn't split
+/// a variable's value on spaces. A list is a command substitution.
+fn word(sexp: &Sexp) -> String {
+    match sexp {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => number.to_string(),
+            Atom::String(text) => quoted(&text),
+            Atom::Symbol(name) => name.to_string(),
+        },
+        Sexp::List(_) => format!("({})", command(sexp)),
+        Sexp::Nil => "''".to_string(),
+    }
+}
+
+fn words(items: &[Sexp]) -> String {
+    items.iter().map(word).collect::<Vec<_>>().join(" ")
+}
+
+/// `commands` one to a line, indented in a block.
+fn body(commands: &[Sexp]) -> String {
+    commands
+        .iter()
+        .map(command)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .lines()
+        .map(|line| format!("  {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `commands` between `opening` and the `end` of the block.
+fn block(opening: &str, commands: &[Sexp]) -> String {
+    format!("{}\n{}\nend", opening, body(commands))
+}
+
+/// `sexp` as a command in a list joined by `operator`, grouped in a `begin`
+/// block where it's a list itself that would otherwise run into the
+/// operators around it. The first of an `&&` or `||` list can be another of
+/// them, since those group from the left.
+fn operand(sexp: &Sexp, first: bool, operator: &str) -> String {
+    match sexp.head() {
+        Some("and") | Some("or") if !first || operator == "|" => {
+            format!("begin; {}; end", command(sexp))
+        }
+        _ => command(sexp),
+    }
+}
+
+fn list(commands: &[Sexp], operator: &str) -> String {
+    commands
+        .iter()
+        .enumerate()
+        .map(|(index, sexp)| operand(sexp, index == 0, operator))
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", operator))
+}
+
+/// `set` with its options, where an `(array ...)` value is the elements
+/// of the list it sets.
+fn set(options: &str, name: &str, values: &[Sexp]) -> String {
+    let values = match values {
+        [array] if array.head() == Some("array") => &array.items()[1..],
+        _ => values,
+    };
+    let mut words = vec!["set".to_string()];
+    words.extend(options.split_whitespace().map(str::to_string));
+    words.push(name.to_string());
+    words.extend(values.iter().map(word));
+    words.join(" ")
+}
+
+fn conditional(clauses: &[(Option<&Sexp>, &[Sexp])]) -> String {
+    let mut lines = vec![];
+    for (index, (test, consequent)) in clauses.iter().enumerate() {
+        lines.push(match test {
+            None => "else".to_string(),
+            Some(test) if index == 0 => format!("if {}", command(test)),
+            Some(test) => format!("else if {}", command(test)),
+        });
+        lines.push(body(consequent));
+    }
+    lines.push("end".to_string());
+    lines.join("\n")
+}
+
+fn command(sexp: &Sexp) -> String {
+    let (head, rest) = match sexp.items().split_first() {
+        Some((Sexp::Atom(head), rest)) => (head.as_str(), rest),
+        // A list of commands is a script of them in turn.
+        Some(_) => {
+            return sexp
+                .items()
+                .iter()
+                .map(command)
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        None if matches!(sexp, Sexp::List(_)) => return String::new(),
+        None => return word(sexp),
+    };
+    match (head, rest) {
+        ("begin", _) => block("begin", rest),
+        ("pipe", [_, ..]) => list(rest, "|"),
+        ("and", [_, ..]) => list(rest, "&&"),
+        ("or", [_, ..]) => list(rest, "||"),
+        ("not", [single]) => format!("not {}", command(single)),
+        (">", [cmd, file]) | (">>", [cmd, file]) | ("<", [cmd, file]) => {
+            format!("{} {} {}", command(cmd), head, word(file))
+        }
+        ("define", [signature @ Sexp::List(_), commands @ ..]) => match signature.items() {
+            [Sexp::Atom(name)] => block(&format!("function {}", name), commands),
+            [Sexp::Atom(name), params @ ..] => block(
+                &format!("function {} --argument-names {}", name, words(params)),
+                commands,
+            ),
+            _ => words(sexp.items()),
+        },
+        ("define", [Sexp::Atom(name), values @ ..]) | ("set!", [Sexp::Atom(name), values @ ..])
+            if !name.starts_with('-') =>
+        {
+            set("", name, values)
+        }
+        ("export", [Sexp::Atom(name), values @ ..]) if !name.starts_with('-') => {
+            set("-gx", name, values)
+        }
+        ("local", [Sexp::Atom(name), values @ ..]) if !name.starts_with('-') => {
+            set("-l", name, values)
+        }
+        ("if", [test, consequent]) => {
+            conditional(&[(Some(test), std::slice::from_ref(consequent))])
+        }
+        ("if", [test, consequent, alternative]) => conditional(&[
+            (Some(test), std::slice::from_ref(consequent)),
+            (None, std::slice::from_ref(alternative)),
+        ]),
+        ("cond", [_, ..]) => {
+            let clauses: Vec<(Option<&Sexp>, &[Sexp])> = rest
+                .iter()
+                .enumerate()
+                .map(|(index, clause)| match clause.items().split_first() {
+                    Some((Sexp::Atom(test), consequent)) if test == "else" && index > 0 => {
+                        (None, consequent)
+                    }
+                    Some((test, consequent)) => (Some(test), consequent),
+                    None => (Some(clause), &[][..]),
+                })
+                .collect();
+            conditional(&clauses)
+        }
+        ("while", [test, commands @ ..]) => block(&format!("while {}", command(test)), commands),
+        ("for", [Sexp::Atom(name), items @ Sexp::List(_), commands @ ..]) => block(
+            &format!("for {} in {}", name, words(items.items())),
+            commands,
+        ),
+        _ => words(sexp.items()),
+    }
+}
+
+impl Sexp {
+    /// The tree as a fish script: `(command args...)` becomes the command
+    /// `command arg1 arg2`, where strings are literal text, quoted as they
+    /// need, and symbols are words as they are, so globs, `~` and variables
+    /// like `$PATH` expand. A list as an argument is a command substitution
+    /// `(...)`, and a list of commands a script of them in turn. `(pipe a
+    /// b)` becomes `a | b`, `and`, `or` and `not` `&&`, `||` and `not`, and
+    /// `(> cmd file)`, `>>` and `<` redirections. `(define NAME values...)`
+    /// and `set!` become `set`, with an `(array ...)` value its elements,
+    /// and `export` and `local` `set -gx` and `set -l`. `(define (name
+    /// params...) commands...)` becomes a function taking them as
+    /// `--argument-names`, and `begin`, `if`, `cond`, `(while test
+    /// commands...)` and `(for x (items...) commands...)` fish's blocks,
+    /// each closed with `end`.
+    pub fn to_fish_string(&self) -> String {
+        command(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_fish_string() {
+        let sexp = Sexp::of_str(
+            r#"((define NAME "world's")
+                (define FILES (array *.txt "my notes.md"))
+                (define (greet who) (echo "Hello," $who))
+                (export PATH $HOME/bin $PATH)
+                (cond ((test -f config.fish) (source config.fish))
+                      ((set -q CI) (echo "in CI"))
+                      (else (echo "no config")))
+                (pipe (cat log.txt) (grep -v DEBUG) (or (wc -l) (echo 0)))
+                (> (date +%s) stamp.txt)
+                (for f (*.md ~/notes) (echo $f))
+                (while (not (test -e done)) (sleep 1))
+                (and (mkdir -p build) (cd build) (not (cmake ..)))
+                (begin (local tmp (mktemp)) (echo "built at" (date) $tmp)))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_fish_string(),
+            r#"set NAME 'world\'s'
+set FILES *.txt 'my notes.md'
+function greet --argument-names who
+  echo Hello, $who
+end
+set -gx PATH $HOME/bin $PATH
+if test -f config.fish
+  source config.fish
+else if set -q CI
+  echo 'in CI'
+else
+  echo 'no config'
+end
+cat log.txt | grep -v DEBUG | begin; wc -l || echo 0; end
+date +%s > stamp.txt
+for f in *.md ~/notes
+  echo $f
+end
+while not test -e done
+  sleep 1
+end
+mkdir -p build && cd build && not cmake ..
+begin
+  set -l tmp (mktemp)
+  echo 'built at' (date) $tmp
+end"#
+        );
+    }
+}
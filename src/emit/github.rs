@@ -0,0 +1,176 @@
+use super::yaml::{is_mapping, mapping, scalar};
+use crate::Sexp;
+
+/// The forms in a job that are its steps, in order, rather than settings.
+const STEPS: &[&str] = &["run", "step", "uses"];
+
+/// A step as a mapping of its fields: `(run "cargo test")` and `(uses
+/// actions/checkout@v4 (with ...))` start with their own, and `(step
+/// "Test" fields...)` with its name.
+fn step(form: &Sexp) -> Sexp {
+    match form.items() {
+        [Sexp::Atom(head), name @ Sexp::Atom(_), fields @ ..] if head == "step" => {
+            let mut step = vec![Sexp::list(vec![Sexp::atom("name"), name.clone()])];
+            step.extend(fields.iter().cloned());
+            Sexp::list(step)
+        }
+        [Sexp::Atom(head), fields @ ..] if head == "step" => Sexp::list(fields.to_vec()),
+        [head, value, fields @ ..] if !is_mapping(std::slice::from_ref(value)) => {
+            let mut step = vec![Sexp::list(vec![head.clone(), value.clone()])];
+            step.extend(fields.iter().cloned());
+            Sexp::list(step)
+        }
+        _ => Sexp::list(vec![form.clone()]),
+    }
+}
+
+/// A job as the field under `jobs` it is, its settings first and then its
+/// steps, running on Ubuntu unless it says otherwise.
+fn job(name: &Sexp, items: &[Sexp]) -> Sexp {
+    let (steps, settings): (Vec<&Sexp>, Vec<&Sexp>) = items
+        .iter()
+        .partition(|item| matches!(item.head(), Some(head) if STEPS.contains(&head)));
+    let mut job = vec![name.clone()];
+    if !settings.iter().any(|item| item.head() == Some("runs-on")) {
+        job.push(Sexp::list(vec![
+            Sexp::atom("runs-on"),
+            Sexp::atom("ubuntu-latest"),
+        ]));
+    }
+    job.extend(settings.into_iter().cloned());
+    let mut steps: Vec<Sexp> = steps.into_iter().map(step).collect();
+    steps.insert(0, Sexp::atom("steps"));
+    job.push(Sexp::list(steps));
+    Sexp::list(job)
+}
+
+impl Sexp {
+    /// The tree as a GitHub Actions workflow in YAML: `(workflow (name CI)
+    /// (on push pull_request) (job build ...))`. A list of events like `(on
+    /// push pull_request)` is written `on: [push, pull_request]`, and other
+    /// fields, `on` with its filters included, as
+    /// [`Sexp::to_kubernetes_yaml`] writes them. Each `(job name items...)`
+    /// is one of `jobs`, where `(run "cargo test")`, `(uses
+    /// actions/checkout@v4 (with ...))` and `(step "Name" fields...)` are
+    /// its `steps`, in order, and any other field a setting of the job.
+    /// A job without `runs-on` runs on `ubuntu-latest`, and a `job` that
+    /// isn't named by an atom is just a field. What isn't a list headed by
+    /// its name is left out, as YAML has no way to write it.
+    pub fn to_github_actions_yaml(&self) -> String {
+        let forms = match self.items().split_first() {
+            Some((Sexp::Atom(head), forms)) if head == "workflow" => forms,
+            _ => self.items(),
+        };
+        let is_job = |form: &Sexp| matches!(form.items(), [Sexp::Atom(head), Sexp::Atom(_), ..] if head == "job");
+        let jobs: Vec<Sexp> = forms
+            .iter()
+            .filter(|form| is_job(form))
+            .map(|form| job(&form.items()[1], &form.items()[2..]))
+            .collect();
+        let mut yaml = String::new();
+        let mut written = false;
+        for form in forms {
+            match form.items() {
+                _ if is_job(form) => {
+                    if !written {
+                        yaml.push_str("jobs:\n");
+                        mapping(&jobs, 2, &mut yaml);
+                        written = true;
+                    }
+                }
+                // `on` is a boolean to YAML 1.1, but not to GitHub.
+                [Sexp::Atom(head), events @ ..]
+                    if head == "on"
+                        && events.iter().all(|event| matches!(event, Sexp::Atom(_))) =>
+                {
+                    let events: Vec<String> = events.iter().map(scalar).collect();
+                    yaml.push_str(&format!("on: [{}]\n", events.join(", ")));
+                }
+                [Sexp::Atom(head), filters @ ..] if head == "on" => {
+                    yaml.push_str("on:\n");
+                    mapping(filters, 2, &mut yaml);
+                }
+                _ => mapping(std::slice::from_ref(form), 0, &mut yaml),
+            }
+        }
+        yaml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_github_actions_yaml() {
+        let sexp = Sexp::of_str(
+            r#"(workflow
+                 (name CI)
+                 (on push pull_request)
+                 (env (CARGO_TERM_COLOR always))
+                 (job build
+                   (uses actions/checkout@v4)
+                   (run "cargo build --verbose")
+                   (step "Test" (run "cargo test") (env (RUST_BACKTRACE 1))))
+                 (job lint
+                   (runs-on "${{ matrix.os }}")
+                   (strategy (matrix (os ubuntu-latest macos-latest)))
+                   (needs build)
+                   (uses actions/checkout@v4 (with (fetch-depth 0)))
+                   (run "cargo clippy -- -D warnings")))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_github_actions_yaml(),
+            r#"name: CI
+on: [push, pull_request]
+env:
+  CARGO_TERM_COLOR: always
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+    - uses: actions/checkout@v4
+    - run: "cargo build --verbose"
+    - name: Test
+      run: "cargo test"
+      env:
+        RUST_BACKTRACE: 1
+  lint:
+    runs-on: "${{ matrix.os }}"
+    strategy:
+      matrix:
+        os:
+        - ubuntu-latest
+        - macos-latest
+    needs: build
+    steps:
+    - uses: actions/checkout@v4
+      with:
+        fetch-depth: 0
+    - run: "cargo clippy -- -D warnings"
+"#
+        );
+
+        let sexp = Sexp::of_str(r#"((on (push (branches main))) (job test (run make)))"#).unwrap();
+        assert_eq!(
+            sexp.to_github_actions_yaml(),
+            "on:\n  push:\n    branches: main\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n    - run: make\n"
+        );
+    }
+
+    #[test]
+    fn test_to_github_actions_yaml_malformed() {
+        let yaml = |input| Sexp::of_str(input).unwrap().to_github_actions_yaml();
+        assert_eq!(yaml("(a)"), "");
+        assert_eq!(yaml("(jobs (x) y)"), "x: []\n");
+        assert_eq!(
+            yaml("((job (x) (run make)))"),
+            "job:\n  x: []\n  run: make\n"
+        );
+        assert_eq!(
+            yaml("((job) (name a) (name b))"),
+            "job: []\nname: a\nname: b\n"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use super::yaml::{is_mapping, mapping};
+use crate::Sexp;
+
+/// The fields GitLab only takes as lists, even of one item, like a
+/// `script` of a single command.
+const LISTS: &[&str] = &[
+    "after_script",
+    "before_script",
+    "changes",
+    "dependencies",
+    "exists",
+    "extends",
+    "needs",
+    "paths",
+    "script",
+    "stages",
+    "tags",
+];
+
+fn field(name: &str, elements: Vec<Sexp>) -> Sexp {
+    let mut field = vec![Sexp::atom(name)];
+    field.extend(elements);
+    Sexp::list(field)
+}
+
+fn array(items: Vec<Sexp>) -> Sexp {
+    field("array", items)
+}
+
+/// A rule as the mapping it is: `(rule fields...)` of its fields, and a
+/// single field like `(when manual)` of that one.
+fn rule(sexp: &Sexp) -> Sexp {
+    match sexp.items().split_first() {
+        Some((Sexp::Atom(head), fields)) if head == "rule" => {
+            Sexp::list(fields.iter().map(lists).collect())
+        }
+        Some((Sexp::Atom(_), _)) => Sexp::list(vec![lists(sexp)]),
+        _ => lists(sexp),
+    }
+}
+
+/// `sexp` with the [`LISTS`] in it written as sequences, and each of the
+/// `rules` as a mapping.
+fn lists(sexp: &Sexp) -> Sexp {
+    let items = match sexp {
+        Sexp::List(_) => sexp.items(),
+        _ => return sexp.clone(),
+    };
+    match items.split_first() {
+        Some((Sexp::Atom(head), rules)) if head == "rules" => {
+            field(head, vec![array(rules.iter().map(rule).collect())])
+        }
+        Some((Sexp::Atom(head), elements))
+            if LISTS.contains(&head.as_str()) && !is_mapping(elements) =>
+        {
+            field(head, vec![array(elements.iter().map(lists).collect())])
+        }
+        _ => Sexp::list(items.iter().map(lists).collect()),
+    }
+}
+
+impl Sexp {
+    /// The tree as a GitLab CI/CD configuration in YAML: `(pipeline
+    /// (stages build test) (job build (stage build) (script "make")) ...)`.
+    /// Each `(job name fields...)` is a job of that name at the top level,
+    /// and other fields, like `stages`, `variables` and `default`, are
+    /// written as [`Sexp::to_kubernetes_yaml`] writes them. Fields GitLab
+    /// only takes as lists, like `script`, `needs` and `stages`, are
+    /// sequences even of one item, and each of the `rules` is a mapping, a
+    /// `(rule (if ...) (when manual))` of its fields or a field like `(when
+    /// never)` of that one. A `job` that isn't named by an atom is just a
+    /// field, and what isn't a list headed by its name is left out.
+    pub fn to_gitlab_ci_yaml(&self) -> String {
+        let forms = match self.items().split_first() {
+            Some((Sexp::Atom(head), forms)) if head == "pipeline" => forms,
+            _ => self.items(),
+        };
+        let fields = forms
+            .iter()
+            .map(|form| match form.items() {
+                [Sexp::Atom(head), name @ Sexp::Atom(_), fields @ ..] if head == "job" => {
+                    let mut job = vec![name.clone()];
+                    job.extend(fields.iter().map(lists));
+                    Sexp::list(job)
+                }
+                _ => lists(form),
+            })
+            .collect::<Vec<_>>();
+        let mut yaml = String::new();
+        mapping(&fields, 0, &mut yaml);
+        yaml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_gitlab_ci_yaml() {
+        let sexp = Sexp::of_str(
+            r#"(pipeline
+                 (stages build test deploy)
+                 (variables (CARGO_HOME "$CI_PROJECT_DIR/.cargo"))
+                 (default (image rust:1.75) (before_script "rustc --version"))
+                 (job build
+                   (stage build)
+                   (script "cargo build --release")
+                   (artifacts (paths target/release/app) (expire_in "1 week")))
+                 (job test
+                   (stage test)
+                   (needs build)
+                   (script "cargo test" "cargo clippy -- -D warnings"))
+                 (job deploy
+                   (stage deploy)
+                   (script "./deploy.sh")
+                   (rules
+                     (rule (if "$CI_COMMIT_BRANCH == \"main\"") (when manual))
+                     (when never))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_gitlab_ci_yaml(),
+            r#"stages:
+- build
+- test
+- deploy
+variables:
+  CARGO_HOME: "$CI_PROJECT_DIR/.cargo"
+default:
+  image: rust:1.75
+  before_script:
+  - "rustc --version"
+build:
+  stage: build
+  script:
+  - "cargo build --release"
+  artifacts:
+    paths:
+    - target/release/app
+    expire_in: "1 week"
+test:
+  stage: test
+  needs:
+  - build
+  script:
+  - "cargo test"
+  - "cargo clippy -- -D warnings"
+deploy:
+  stage: deploy
+  script:
+  - ./deploy.sh
+  rules:
+  - if: "$CI_COMMIT_BRANCH == \"main\""
+    when: manual
+  - when: never
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_gitlab_ci_yaml_malformed() {
+        let job = Sexp::of_str("((job (x) (script make)))").unwrap();
+        assert_eq!(
+            job.to_gitlab_ci_yaml(),
+            "job:\n  x: []\n  script:\n  - make\n"
+        );
+        let job = Sexp::of_str("((job))").unwrap();
+        assert_eq!(job.to_gitlab_ci_yaml(), "job: []\n");
+        let fields = Sexp::of_str("(jobs (x) y)").unwrap();
+        assert_eq!(fields.to_gitlab_ci_yaml(), "x: []\n");
+        assert_eq!(Sexp::of_str("(a)").unwrap().to_gitlab_ci_yaml(), "");
+    }
+}
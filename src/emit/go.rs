@@ -0,0 +1,330 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, quoted, unquoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+/// Whether `name` is an identifier, or a path of them like `fmt.Println`.
+fn is_name(name: &str) -> bool {
+    name.split('.')
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+/// `text` as a Go string: a raw literal where that saves escaping `\` and
+/// `"`, and otherwise an interpreted one. A raw literal can't hold a
+/// backtick at all, so each is concatenated in from an interpreted one.
+fn string(text: &str) -> String {
+    if !text.contains(['\\', '"']) || text.contains(|c: char| c.is_control()) {
+        return quoted(text, '"');
+    }
+    let mut parts = vec![];
+    for (index, part) in text.split('`').enumerate() {
+        if index > 0 {
+            parts.push("\"`\"".to_string());
+        }
+        if !part.is_empty() {
+            parts.push(format!("`{}`", part));
+        }
+    }
+    match parts.as_slice() {
+        [single] => single.clone(),
+        _ => format!("({})", parts.join(" + ")),
+    }
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "nil".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` in braces after `opening`, as in `if ok { ... }`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}{{\n{}\n}}", opening, indented(forms, "\t"))
+}
+
+/// A name with its type, from `(name Type)`, or a bare name taking `any`.
+fn typed(item: &Sexp) -> Option<(String, String)> {
+    match item {
+        Sexp::Atom(name) if is_name(name) => Some((name.to_string(), "any".to_string())),
+        Sexp::Atom(_) => None,
+        _ => match item.items() {
+            [Sexp::Atom(name), Sexp::Atom(ty)] if is_name(name) => {
+                Some((name.to_string(), unquoted(ty)))
+            }
+            _ => None,
+        },
+    }
+}
+
+fn params(params: &[Sexp]) -> Option<String> {
+    let params = params
+        .iter()
+        .map(|param| typed(param).map(|(name, ty)| format!("{} {}", name, ty)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", params.join(", ")))
+}
+
+/// A function literal returning its last form, which is how a Go
+/// expression can hold statements.
+fn literal(params: &str, body: &[String]) -> String {
+    let mut body = body.to_vec();
+    if let Some(last) = body.last_mut() {
+        *last = format!("return {}", last);
+    }
+    block(&format!("func{} any ", params), &body)
+}
+
+/// The `:field value` pairs of a struct literal, if `rest` is only those.
+fn fields(rest: &[Sexp]) -> Option<Vec<String>> {
+    let pairs = rest.chunks_exact(2);
+    if rest.is_empty() || !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| match &pair[0] {
+            Sexp::Atom(name) => match name.strip_prefix(':') {
+                Some(name) if is_name(name) => Some(format!("{}: {}", name, write(&pair[1], &GO))),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn function(name: &Sexp, params: &[Sexp], body: &[Sexp]) -> Option<String> {
+    let (name, returns) = match name {
+        Sexp::Atom(name) if is_name(name) => (name.to_string(), String::new()),
+        _ => typed(name).map(|(name, ty)| (name, format!(" {}", ty)))?,
+    };
+    let signature = format!("func {}{}{} ", name, self::params(params)?, returns);
+    let body: Vec<String> = body.iter().map(|form| write(form, &GO)).collect();
+    Some(block(&signature, &body))
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &GO)).collect() };
+    match (head, rest) {
+        ("package", [Sexp::Atom(name), decls @ ..]) if is_name(name) => {
+            let mut decls = all(decls);
+            decls.insert(0, format!("package {}", name));
+            Some(decls.join("\n\n"))
+        }
+        ("import", [_, ..]) => Some(format!("import (\n{}\n)", indented(&all(rest), "\t"))),
+        ("struct", [Sexp::Atom(name), fields @ ..]) if is_name(name) => {
+            let fields = fields
+                .iter()
+                .map(|field| typed(field).map(|(name, ty)| format!("{} {}", name, ty)))
+                .collect::<Option<Vec<_>>>()?;
+            Some(block(&format!("type {} struct ", name), &fields))
+        }
+        // A typed name, as in `(func (f int) ...)`, returns its type.
+        ("func", [name, params @ Sexp::List(_), body @ ..]) => function(name, params.items(), body),
+        ("define", [signature @ Sexp::List(_), body @ ..]) => {
+            let (name, params) = signature.items().split_first()?;
+            function(name, params, body)
+        }
+        ("lambda", [params @ Sexp::List(_), body @ ..])
+            if params
+                .items()
+                .iter()
+                .any(|param| matches!(param, Sexp::List(_))) =>
+        {
+            Some(literal(&self::params(params.items())?, &all(body)))
+        }
+        ("define", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} := {}", write(name, &GO), write(init, &GO)))
+        }
+        ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &GO), write(init, &GO)))
+        }
+        ("return", []) => Some("return".to_string()),
+        ("return", [value]) => Some(format!("return {}", write(value, &GO))),
+        ("begin", [_, ..]) => Some(block("", &all(rest))),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut forms = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => {
+                        forms.push(format!("{} := {}", write(name, &GO), write(init, &GO)))
+                    }
+                    _ => return None,
+                }
+            }
+            forms.extend(all(body));
+            Some(block("", &forms))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if {} ", write(test, &GO)),
+            &[write(consequent, &GO)],
+        )),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let opening = match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => String::new(),
+                    _ => format!("if {} ", write(test, &GO)),
+                };
+                branches.push(block(&opening, &all(consequent)));
+            }
+            Some(branches.join(" else "))
+        }
+        // Go has no keyword arguments, so a call of nothing but `:field
+        // value` pairs is a struct literal.
+        (_, _) if is_name(head) => {
+            let fields = fields(rest)?;
+            Some(format!("{}{{{}}}", head, fields.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", string(key), value))
+        .collect();
+    format!("map[string]any{{{}}}", fields.join(", "))
+}
+
+const GO: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: None,
+    lambda: |params, body| {
+        let params: Vec<String> = params
+            .iter()
+            .map(|param| format!("{} any", param))
+            .collect();
+        literal(&format!("({})", params.join(", ")), body)
+    },
+    // There's no conditional expression, so it's a function literal that
+    // returns one or the other, called on the spot.
+    conditional: |test, consequent, alternative| {
+        format!(
+            "func() any {{ if {} {{ return {} }}; return {} }}()",
+            test, consequent, alternative
+        )
+    },
+    array: ("[]any{", "}"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as Go, reading it as Scheme-style code: `(package main
+    /// decls...)` becomes a source file, `(import "fmt")` an import list,
+    /// `(struct Point (X int) (Y int))` a struct type, and `(func name
+    /// (params...) body...)` or `(define (name params...) body...)` a
+    /// function declaration, where a typed name like `(norm float64)`
+    /// returns its type and untyped parameters take `any`. A call of only
+    /// `:field value` pairs, as in `(Point :X 1)`, becomes the struct
+    /// literal `Point{X: 1}`. `define` of a variable declares it with `:=`,
+    /// `begin` and `let` become blocks and `cond` an `if`/`else` chain.
+    /// `lambda` and `if` with an alternative become function literals,
+    /// since Go has no conditional expression. `(array ...)` and `(table
+    /// (key value)...)` become `[]any` and `map[string]any` literals.
+    /// Strings with a `\` or `"` are raw literals.
+    pub fn to_go_string(&self) -> String {
+        write(self, &GO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_go_string() {
+        let sexp = Sexp::of_str(
+            r#"(package main
+                 (import "fmt" "math")
+                 (struct Point (X float64) (Y float64))
+                 (func (norm float64) ((p Point))
+                   (return (math.Sqrt (+ (* (.-X p) (.-X p)) (* (.-Y p) (.-Y p))))))
+                 (func main ()
+                   (define origin (Point :X 0 :Y 0))
+                   (define config (table (name "demo") (sizes (array 1 2 3))))
+                   (define pattern "C:\\dir\\`name`")
+                   (cond ((> (norm origin) 0) (fmt.Println "moved"))
+                         (else (fmt.Println "home" pattern config)))
+                   (define double (lambda ((x int)) (* x 2)))
+                   (fmt.Println (double 2) (if (!= (% 7 2) 0) "odd" "even"))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_go_string(),
+            r#"package main
+
+import (
+	"fmt"
+	"math"
+)
+
+type Point struct {
+	X float64
+	Y float64
+}
+
+func norm(p Point) float64 {
+	return math.Sqrt(p.X * p.X + p.Y * p.Y)
+}
+
+func main() {
+	origin := Point{X: 0, Y: 0}
+	config := map[string]any{"name": "demo", "sizes": []any{1, 2, 3}}
+	pattern := (`C:\dir\` + "`" + `name` + "`")
+	if norm(origin) > 0 {
+		fmt.Println("moved")
+	} else {
+		fmt.Println("home", pattern, config)
+	}
+	double := func(x int) any {
+		return x * 2
+	}
+	fmt.Println(double(2), func() any { if 7 % 2 != 0 { return "odd" }; return "even" }())
+}"#
+        );
+    }
+}
@@ -0,0 +1,1144 @@
+//! Grammar notations, read from one grammar language: a grammar is a list of
+//! rules like `(expr (term + expr) term)`, a rule's name followed by its
+//! alternatives. An alternative is a symbol or a list of symbols in
+//! sequence, with `()` matching nothing. String atoms are terminals, and
+//! bare atoms name a rule if the grammar has one by that name and are
+//! terminals otherwise. In a sequence, `(repeat ...)`, `(optional ...)` and
+//! `(group ...)` take alternatives like a rule does, so `(group + -)` is
+//! either sign and `(repeat (, item))` any number of `, item`, and so do
+//! `(repeat1 ...)` for one or more and the lookaheads `(followed-by ...)`
+//! and `(not-followed-by ...)`, which match without consuming input.
+//! `(range a z)` matches one character from `a` to `z`.
+//!
+//! Notations that need a name for the grammar read it from a wrapper like
+//! `(grammar arith (skip ws) rules...)`, where `(skip ...)` names the rules
+//! matching what goes unparsed between tokens, like whitespace.
+
+use super::unquoted;
+use crate::Sexp;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+// Only the grammar and codegen writers look inside lookaheads.
+#[cfg_attr(not(any(feature = "grammar", feature = "codegen")), allow(dead_code))]
+pub(super) enum Expr {
+    Rule(String),
+    Terminal(String),
+    Range(char, char),
+    Sequence(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Repeat(Box<Expr>),
+    Repeat1(Box<Expr>),
+    Optional(Box<Expr>),
+    Group(Box<Expr>),
+    FollowedBy(Box<Expr>),
+    NotFollowedBy(Box<Expr>),
+}
+
+pub(super) struct Rule {
+    pub name: String,
+    pub body: Expr,
+}
+
+pub(super) struct Grammar {
+    pub name: Option<String>,
+    pub skip: Vec<String>,
+    pub rules: Vec<Rule>,
+}
+
+/// A grammar, with or without a `(grammar name ...)` wrapper, or a single
+/// rule. Entries that aren't rules, and skipped names that aren't rules'
+/// names, are left out.
+pub(super) fn grammar(sexp: &Sexp) -> Grammar {
+    let (name, entries) = match sexp.items() {
+        [Sexp::Atom(grammar), Sexp::Atom(name), entries @ ..] if grammar == "grammar" => {
+            (Some(name.clone()), entries)
+        }
+        [Sexp::Atom(_), ..] => (None, std::slice::from_ref(sexp)),
+        entries => (None, entries),
+    };
+    let (skips, entries): (Vec<&Sexp>, Vec<&Sexp>) = entries
+        .iter()
+        .partition(|entry| name.is_some() && entry.head() == Some("skip"));
+    let definitions: Vec<(&String, &[Sexp])> = entries
+        .iter()
+        .filter_map(|entry| match entry.items() {
+            [Sexp::Atom(name), body @ ..] if !name.starts_with('"') && !body.is_empty() => {
+                Some((name, body))
+            }
+            _ => None,
+        })
+        .collect();
+    let names: HashSet<&str> = definitions.iter().map(|(name, _)| name.as_str()).collect();
+    let skip = skips
+        .iter()
+        .flat_map(|skips| &skips.items()[1..])
+        .filter_map(|skipped| match skipped {
+            Sexp::Atom(rule) if names.contains(rule.as_str()) => Some(rule.clone()),
+            _ => None,
+        })
+        .collect();
+    let rules = definitions
+        .iter()
+        .map(|(name, body)| Rule {
+            name: name.to_string(),
+            body: alternatives(body, &names),
+        })
+        .collect();
+    Grammar { name, skip, rules }
+}
+
+fn alternatives(sexps: &[Sexp], names: &HashSet<&str>) -> Expr {
+    let mut alternatives: Vec<Expr> = sexps
+        .iter()
+        .map(|alternative| {
+            operator(alternative, names).unwrap_or_else(|| sequence(alternative, names))
+        })
+        .collect();
+    match alternatives.len() {
+        1 => alternatives.remove(0),
+        _ => Expr::Choice(alternatives),
+    }
+}
+
+/// A symbol, or a list of elements in sequence, where a list that isn't
+/// an operator is a sequence of its own.
+fn sequence(sexp: &Sexp, names: &HashSet<&str>) -> Expr {
+    if let Sexp::Atom(atom) = sexp {
+        return symbol(atom, names);
+    }
+    let mut elements: Vec<Expr> = sexp
+        .items()
+        .iter()
+        .map(|element| operator(element, names).unwrap_or_else(|| sequence(element, names)))
+        .collect();
+    match elements.len() {
+        1 => elements.remove(0),
+        _ => Expr::Sequence(elements),
+    }
+}
+
+fn operator(sexp: &Sexp, names: &HashSet<&str>) -> Option<Expr> {
+    let (operator, body) = match sexp.items().split_first() {
+        Some((Sexp::Atom(operator), body)) if !body.is_empty() => (operator, body),
+        _ => return None,
+    };
+    let wrap = match operator.as_str() {
+        "repeat" => Expr::Repeat,
+        "repeat1" => Expr::Repeat1,
+        "optional" => Expr::Optional,
+        "group" => Expr::Group,
+        "followed-by" => Expr::FollowedBy,
+        "not-followed-by" => Expr::NotFollowedBy,
+        "range" => return range(body),
+        _ => return None,
+    };
+    Some(wrap(Box::new(alternatives(body, names))))
+}
+
+/// `(range from to)` for single characters in order; anything else is a
+/// sequence like any list.
+fn range(bounds: &[Sexp]) -> Option<Expr> {
+    let bound = |bound: &Sexp| {
+        let text = bound.as_text().unwrap_or_default();
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    match bounds {
+        [from, to] => match (bound(from), bound(to)) {
+            (Some(from), Some(to)) if from <= to => Some(Expr::Range(from, to)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn symbol(atom: &str, names: &HashSet<&str>) -> Expr {
+    if names.contains(atom) {
+        Expr::Rule(atom.to_string())
+    } else {
+        Expr::Terminal(unquoted(atom))
+    }
+}
+
+/// A terminal in double quotes, or single quotes if it has a double quote
+/// and no single one.
+#[cfg(feature = "grammar")]
+fn terminal(text: &str) -> String {
+    if text.contains('"') && !text.contains('\'') {
+        format!("'{}'", text)
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+#[cfg(any(feature = "grammar", feature = "codegen"))]
+/// A name for another rule lifted out of `rule`, like `expr_1`, that none
+/// of `names` or `helpers` has.
+fn lifted(rule: &str, names: &HashSet<&str>, helpers: &[(String, Vec<String>)]) -> String {
+    (helpers.len() + 1..)
+        .map(|n| format!("{}_{}", rule, n))
+        .find(|name| {
+            !names.contains(name.as_str()) && helpers.iter().all(|(helper, _)| helper != name)
+        })
+        .unwrap()
+}
+
+#[cfg(feature = "grammar")]
+/// Writes BNF, which has only sequences and alternatives: anything else
+/// inside a rule is lifted into a rule of its own, named after it.
+struct Bnf<'a> {
+    rule: &'a str,
+    names: &'a HashSet<&'a str>,
+    helpers: Vec<(String, Vec<String>)>,
+}
+
+#[cfg(feature = "grammar")]
+impl Bnf<'_> {
+    /// A reference to a new rule with the alternatives `alternatives`
+    /// writes, given the rule's name.
+    fn lift(&mut self, alternatives: impl FnOnce(&mut Self, &str) -> Vec<String>) -> String {
+        let name = lifted(self.rule, self.names, &self.helpers);
+        let index = self.helpers.len();
+        self.helpers.push((name.clone(), vec![]));
+        self.helpers[index].1 = alternatives(self, &name);
+        format!("<{}>", name)
+    }
+
+    fn alternatives(&mut self, expr: &Expr) -> Vec<String> {
+        match expr {
+            Expr::Choice(alternatives) => alternatives
+                .iter()
+                .map(|alternative| self.symbols(alternative))
+                .collect(),
+            _ => vec![self.symbols(expr)],
+        }
+    }
+
+    fn symbols(&mut self, expr: &Expr) -> String {
+        let empty = || "\"\"".to_string();
+        match expr {
+            Expr::Rule(name) => format!("<{}>", name),
+            Expr::Terminal(text) => terminal(text),
+            Expr::Range(from, to) => {
+                self.lift(|_, _| (*from..=*to).map(|c| terminal(&c.to_string())).collect())
+            }
+            Expr::Sequence(elements) if elements.is_empty() => empty(),
+            Expr::Sequence(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| self.symbols(element))
+                    .collect();
+                elements.join(" ")
+            }
+            Expr::Choice(_) => self.lift(|this, _| this.alternatives(expr)),
+            Expr::Group(body) => self.symbols(body),
+            Expr::Optional(body) => self.lift(|this, _| {
+                let mut alternatives = vec![empty()];
+                alternatives.extend(this.alternatives(body));
+                alternatives
+            }),
+            Expr::Repeat(body) => {
+                self.lift(|this, name| vec![empty(), format!("{} <{}>", this.symbols(body), name)])
+            }
+            Expr::Repeat1(body) => self.lift(|this, name| {
+                let body = this.symbols(body);
+                vec![body.clone(), format!("{} <{}>", body, name)]
+            }),
+            Expr::FollowedBy(body) => format!("&{}", self.symbols(body)),
+            Expr::NotFollowedBy(body) => format!("!{}", self.symbols(body)),
+        }
+    }
+}
+
+#[cfg(feature = "grammar")]
+fn ebnf(expr: &Expr) -> String {
+    let wrapped =
+        |open, body: &Expr, close| format!("{} {} {}", open, ebnf(body).trim_end(), close);
+    // What BNF writes with PEG's operators, since EBNF has no lookahead.
+    let lookahead = |prefix: &str, body: &Expr| match body {
+        Expr::Rule(_) | Expr::Terminal(_) => format!("{}{}", prefix, ebnf(body)),
+        _ => format!("{}{}", prefix, wrapped("(", body, ")")),
+    };
+    match expr {
+        Expr::Rule(name) => name.clone(),
+        Expr::Terminal(text) => terminal(text),
+        // EBNF has no ranges either, but their characters are a choice.
+        Expr::Range(from, to) => {
+            let chars: Vec<String> = (*from..=*to).map(|c| terminal(&c.to_string())).collect();
+            format!("( {} )", chars.join(" | "))
+        }
+        Expr::Sequence(elements) => {
+            let elements: Vec<String> = elements.iter().map(ebnf).collect();
+            elements.join(" , ")
+        }
+        // An empty alternative is written as nothing, as in `[ a | ]`.
+        Expr::Choice(alternatives) => {
+            let alternatives: Vec<String> = alternatives.iter().map(ebnf).collect();
+            alternatives.join(" | ")
+        }
+        Expr::Repeat(body) => wrapped("{", body, "}"),
+        Expr::Repeat1(body) => {
+            let once = match **body {
+                Expr::Choice(_) => wrapped("(", body, ")"),
+                _ => ebnf(body),
+            };
+            format!("{} , {}", once, wrapped("{", body, "}"))
+        }
+        Expr::Optional(body) => wrapped("[", body, "]"),
+        Expr::Group(body) => wrapped("(", body, ")"),
+        Expr::FollowedBy(body) => lookahead("&", body),
+        Expr::NotFollowedBy(body) => lookahead("!", body),
+    }
+}
+
+/// `text` between `quote`s, escaping the quote, backslashes and any of
+/// `special`, and writing line breaks and tabs as `\n`, `\r` and `\t`.
+fn escaped(text: &str, quote: &str, special: &str) -> String {
+    let mut escaped = String::from(quote);
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c == '\\' || quote.contains(c) || special.contains(c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push_str(quote);
+    escaped
+}
+
+#[cfg(any(feature = "grammar", feature = "codegen"))]
+/// How a notation that writes operators as PEG does, with suffix `*`, `+`
+/// and `?`, prefix `&` and `!` and parenthesized groups, writes the rest.
+/// A notation without lookahead gets `&` and `!` all the same, for its
+/// tool to reject.
+pub(super) struct Notation {
+    pub sequence: &'static str,
+    pub choice: &'static str,
+    pub empty: &'static str,
+    pub literal: fn(&str) -> String,
+    pub range: fn(char, char) -> String,
+}
+
+#[cfg(feature = "grammar")]
+const PEG: Notation = Notation {
+    sequence: " ",
+    choice: " / ",
+    empty: "\"\"",
+    literal: |text| escaped(text, "\"", ""),
+    range: |from, to| {
+        let class = |c: char| escaped(&c.to_string(), "", "[]");
+        format!("[{}-{}]", class(from), class(to))
+    },
+};
+
+#[cfg(feature = "grammar")]
+const ANTLR: Notation = Notation {
+    sequence: " ",
+    choice: " | ",
+    empty: "",
+    literal: |text| escaped(text, "'", ""),
+    range: |from, to| {
+        let set = |c: char| escaped(&c.to_string(), "", "]-");
+        format!("[{}-{}]", set(from), set(to))
+    },
+};
+
+#[cfg(feature = "codegen")]
+const PEST: Notation = Notation {
+    sequence: " ~ ",
+    choice: " | ",
+    empty: "\"\"",
+    literal: |text| escaped(text, "\"", ""),
+    range: |from, to| {
+        let char = |c: char| escaped(&c.to_string(), "'", "");
+        format!("{}..{}", char(from), char(to))
+    },
+};
+
+#[cfg(any(feature = "grammar", feature = "codegen"))]
+pub(super) fn write(expr: &Expr, notation: &Notation) -> String {
+    // Suffixes apply to a primary and prefixes to a suffixed primary, so
+    // anything looser goes in parentheses.
+    let operand = |body: &Expr, prefix: bool| match body {
+        Expr::Rule(_) | Expr::Terminal(_) | Expr::Range(..) | Expr::Group(_) => {
+            write(body, notation)
+        }
+        Expr::Repeat(_) | Expr::Repeat1(_) | Expr::Optional(_) if prefix => write(body, notation),
+        _ => format!("({})", write(body, notation).trim_end()),
+    };
+    let all = |exprs: &[Expr], separator| {
+        let exprs: Vec<String> = exprs.iter().map(|expr| write(expr, notation)).collect();
+        exprs.join(separator)
+    };
+    match expr {
+        Expr::Rule(name) => name.clone(),
+        Expr::Terminal(text) => (notation.literal)(text),
+        Expr::Range(from, to) => (notation.range)(*from, *to),
+        Expr::Sequence(elements) if elements.is_empty() => notation.empty.to_string(),
+        Expr::Sequence(elements) => all(elements, notation.sequence),
+        Expr::Choice(alternatives) => all(alternatives, notation.choice),
+        Expr::Repeat(body) => format!("{}*", operand(body, false)),
+        Expr::Repeat1(body) => format!("{}+", operand(body, false)),
+        Expr::Optional(body) => format!("{}?", operand(body, false)),
+        Expr::Group(body) => format!("({})", write(body, notation).trim_end()),
+        Expr::FollowedBy(body) => format!("&{}", operand(body, true)),
+        Expr::NotFollowedBy(body) => format!("!{}", operand(body, true)),
+    }
+}
+
+/// A range as a regex character class, like `[a-z]`.
+fn regex_class(from: char, to: char) -> String {
+    let class = |c: char| escaped(&c.to_string(), "", "[]^-");
+    format!("[{}-{}]", class(from), class(to))
+}
+
+fn tree_sitter(expr: &Expr) -> Value {
+    let all = |exprs: &[Expr]| exprs.iter().map(tree_sitter).collect::<Vec<_>>();
+    match expr {
+        Expr::Rule(name) => json!({"type": "SYMBOL", "name": name}),
+        Expr::Terminal(text) => json!({"type": "STRING", "value": text}),
+        Expr::Range(from, to) => json!({"type": "PATTERN", "value": regex_class(*from, *to)}),
+        Expr::Sequence(elements) if elements.is_empty() => json!({"type": "BLANK"}),
+        Expr::Sequence(elements) => json!({"type": "SEQ", "members": all(elements)}),
+        Expr::Choice(alternatives) => json!({"type": "CHOICE", "members": all(alternatives)}),
+        Expr::Repeat(body) => json!({"type": "REPEAT", "content": tree_sitter(body)}),
+        Expr::Repeat1(body) => json!({"type": "REPEAT1", "content": tree_sitter(body)}),
+        // What tree-sitter's `optional(x)` is short for.
+        Expr::Optional(body) => json!({
+            "type": "CHOICE",
+            "members": [tree_sitter(body), {"type": "BLANK"}],
+        }),
+        Expr::Group(body) => tree_sitter(body),
+        // tree-sitter has no lookahead, so these are types it rejects.
+        Expr::FollowedBy(body) => json!({"type": "FOLLOWED_BY", "content": tree_sitter(body)}),
+        Expr::NotFollowedBy(body) => {
+            json!({"type": "NOT_FOLLOWED_BY", "content": tree_sitter(body)})
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+/// `combinator((parsers...))`, nesting the last ones when there are more
+/// than the 21 that nom's `alt` and `tuple` take.
+fn nom_tuple(combinator: &str, mut parsers: Vec<String>) -> String {
+    if parsers.len() > 21 {
+        let rest = parsers.split_off(20);
+        parsers.push(nom_tuple(combinator, rest));
+    }
+    format!("{}(({}))", combinator, parsers.join(", "))
+}
+
+#[cfg(feature = "codegen")]
+/// A nom parser for `expr`, with whether it outputs the input it matched.
+/// Every rule's parser does, and so do the alternatives of an `alt`, which
+/// need the same output.
+fn nom(expr: &Expr) -> (String, bool) {
+    let call = |combinator: &str, body: &Expr| (format!("{}({})", combinator, nom(body).0), false);
+    match expr {
+        Expr::Rule(name) => (parser(name), true),
+        Expr::Terminal(text) => (format!("nom::bytes::complete::tag({:?})", text), true),
+        Expr::Range(from, to) => (
+            format!(
+                "nom::character::complete::satisfy(|c| ({:?}..={:?}).contains(&c))",
+                from, to
+            ),
+            false,
+        ),
+        Expr::Sequence(elements) if elements.is_empty() => {
+            ("nom::bytes::complete::tag(\"\")".to_string(), true)
+        }
+        Expr::Sequence(elements) => {
+            let elements = elements.iter().map(|element| nom(element).0).collect();
+            (nom_tuple("nom::sequence::tuple", elements), false)
+        }
+        Expr::Choice(alternatives) => {
+            let alternatives = alternatives.iter().map(recognized).collect();
+            (nom_tuple("nom::branch::alt", alternatives), true)
+        }
+        Expr::Repeat(body) => call("nom::multi::many0", body),
+        Expr::Repeat1(body) => call("nom::multi::many1", body),
+        Expr::Optional(body) => call("nom::combinator::opt", body),
+        Expr::Group(body) => nom(body),
+        Expr::FollowedBy(body) => call("nom::combinator::peek", body),
+        Expr::NotFollowedBy(body) => call("nom::combinator::not", body),
+    }
+}
+
+#[cfg(feature = "codegen")]
+/// A nom parser for `expr` that outputs the input it matched.
+fn recognized(expr: &Expr) -> String {
+    match nom(expr) {
+        (parser, true) => parser,
+        (parser, false) => format!("nom::combinator::recognize({})", parser),
+    }
+}
+
+#[cfg(feature = "codegen")]
+/// The name of the parser function for `rule`, `parse_rule`, with anything
+/// a Rust identifier can't have as `_`.
+fn parser(rule: &str) -> String {
+    let name: String = rule
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("parse_{}", name)
+}
+
+#[cfg(feature = "codegen")]
+/// Writes LALRPOP alternatives, which can't nest: alternatives inside a
+/// rule are lifted into rules of their own, named after it.
+struct Lalrpop<'a> {
+    rule: &'a str,
+    names: &'a HashSet<&'a str>,
+    helpers: Vec<(String, Vec<String>)>,
+}
+
+#[cfg(feature = "codegen")]
+impl Lalrpop<'_> {
+    fn symbols(&mut self, expr: &Expr) -> String {
+        let suffixed = |this: &mut Self, body: &Expr, suffix| {
+            let symbols = this.symbols(body);
+            match body {
+                Expr::Sequence(_) => format!("({}){}", symbols, suffix),
+                _ => format!("{}{}", symbols, suffix),
+            }
+        };
+        match expr {
+            Expr::Rule(name) => name.clone(),
+            Expr::Terminal(text) => escaped(text, "\"", ""),
+            Expr::Range(from, to) => format!("r#\"{}\"#", regex_class(*from, *to)),
+            Expr::Sequence(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| self.symbols(element))
+                    .collect();
+                elements.join(" ")
+            }
+            Expr::Choice(alternatives) => {
+                let name = lifted(self.rule, self.names, &self.helpers);
+                let index = self.helpers.len();
+                self.helpers.push((name.clone(), vec![]));
+                let alternatives = alternatives
+                    .iter()
+                    .map(|alternative| self.symbols(alternative))
+                    .collect();
+                self.helpers[index].1 = alternatives;
+                name
+            }
+            Expr::Repeat(body) => suffixed(self, body, "*"),
+            Expr::Repeat1(body) => suffixed(self, body, "+"),
+            Expr::Optional(body) => suffixed(self, body, "?"),
+            Expr::Group(body) => self.symbols(body),
+            // LALRPOP has no lookahead, so this is for it to reject.
+            Expr::FollowedBy(body) => format!("&{}", suffixed(self, body, "")),
+            Expr::NotFollowedBy(body) => format!("!{}", suffixed(self, body, "")),
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+/// A LALRPOP rule whose alternatives each produce the span they matched.
+fn lalrpop_rule(public: bool, name: &str, alternatives: &[String]) -> String {
+    let mut rule = format!(
+        "{}{}: (usize, usize) = {{\n",
+        if public { "pub " } else { "" },
+        name
+    );
+    for symbols in alternatives {
+        let symbols = if symbols.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", symbols)
+        };
+        rule.push_str(&format!("    <l:@L> {}<r:@R> => (l, r),\n", symbols));
+    }
+    rule.push_str("};\n");
+    rule
+}
+
+impl Sexp {
+    /// The grammar in BNF, one `<rule> ::= alt1 | alt2` line per rule, with
+    /// rules as `<name>` and terminals quoted. BNF has no repetition,
+    /// options, groups or ranges, so they're lifted into rules of their
+    /// own, named after the rule they're in: `(repeat x)` inside `expr` is
+    /// `<expr_1>`, with `<expr_1> ::= "" | "x" <expr_1>` after it. It has no
+    /// lookahead either, which is written with PEG's `&` and `!` for a BNF
+    /// tool to reject.
+    #[cfg(feature = "grammar")]
+    pub fn to_bnf_string(&self) -> String {
+        let grammar = grammar(self);
+        let names: HashSet<&str> = grammar
+            .rules
+            .iter()
+            .map(|rule| rule.name.as_str())
+            .collect();
+        let mut bnf_rules = String::new();
+        for rule in &grammar.rules {
+            let mut bnf = Bnf {
+                rule: &rule.name,
+                names: &names,
+                helpers: vec![],
+            };
+            let alternatives = bnf.alternatives(&rule.body);
+            for (name, alternatives) in
+                std::iter::once((rule.name.clone(), alternatives)).chain(bnf.helpers)
+            {
+                bnf_rules.push_str(&format!("<{}> ::= {}\n", name, alternatives.join(" | ")));
+            }
+        }
+        bnf_rules
+    }
+
+    /// The grammar in ISO EBNF, one `rule = a , b | c ;` line per rule,
+    /// with `repeat`, `optional` and `group` as `{ ... }`, `[ ... ]` and
+    /// `( ... )`, and ranges as a choice of their characters. Lookaheads
+    /// are written as in `to_bnf_string`, for an EBNF tool to reject.
+    #[cfg(feature = "grammar")]
+    pub fn to_ebnf_string(&self) -> String {
+        let mut ebnf_rules = String::new();
+        for rule in grammar(self).rules {
+            let body = ebnf(&rule.body);
+            ebnf_rules.push_str(&format!("{} = {} ;\n", rule.name, body.trim_end()));
+        }
+        ebnf_rules
+    }
+
+    /// The grammar as a PEG, one `rule <- a b / c` line per rule, with
+    /// alternatives as ordered choices, `repeat`, `repeat1` and `optional`
+    /// as `*`, `+` and `?`, the lookaheads as `&` and `!`, and ranges as
+    /// classes like `[a-z]`. Rule names are written as they are, for a PEG
+    /// tool to check.
+    #[cfg(feature = "grammar")]
+    pub fn to_peg_string(&self) -> String {
+        let mut peg_rules = String::new();
+        for rule in grammar(self).rules {
+            peg_rules.push_str(&format!("{} <- {}\n", rule.name, write(&rule.body, &PEG)));
+        }
+        peg_rules
+    }
+
+    /// The grammar as an ANTLR4 combined grammar, from a `(grammar Name
+    /// rules...)` wrapper. As in ANTLR, rules named with a capital are
+    /// lexer rules and the others parser rules, and skipped rules are
+    /// written with `-> skip`. Names, a lexer rule referring to a parser
+    /// rule, and ranges or skips in parser rules are written as they are,
+    /// for ANTLR to check, and so are lookaheads, as in `to_peg_string`.
+    #[cfg(feature = "grammar")]
+    pub fn to_antlr_grammar(&self) -> String {
+        let grammar = grammar(self);
+        let mut antlr = format!("grammar {};\n\n", grammar.name.unwrap_or_default());
+        for rule in &grammar.rules {
+            let skip = if grammar.skip.contains(&rule.name) {
+                " -> skip"
+            } else {
+                ""
+            };
+            let body = write(&rule.body, &ANTLR);
+            antlr.push_str(&format!("{}: {}{};\n", rule.name, body.trim_end(), skip));
+        }
+        antlr
+    }
+
+    /// The grammar as a tree-sitter `grammar.json`, like the one `tree-sitter
+    /// generate` writes for a `grammar.js`, from a `(grammar name rules...)`
+    /// wrapper. The first rule is the start rule, and skipped rules are the
+    /// `extras`, by default whitespace. `optional` becomes a choice with a
+    /// blank as in `grammar.js`, and ranges become patterns. Names are
+    /// written as they are, a missing grammar name as `null`, and
+    /// lookaheads as `FOLLOWED_BY` and `NOT_FOLLOWED_BY` rules, all for
+    /// `tree-sitter generate` to reject.
+    pub fn to_tree_sitter_grammar_json(&self) -> String {
+        let grammar = grammar(self);
+        let mut rules = Map::new();
+        for rule in &grammar.rules {
+            rules.insert(rule.name.clone(), tree_sitter(&rule.body));
+        }
+        let extras: Vec<Value> = match grammar.skip.as_slice() {
+            [] => vec![json!({"type": "PATTERN", "value": "\\s"})],
+            skip => skip
+                .iter()
+                .map(|name| json!({"type": "SYMBOL", "name": name}))
+                .collect(),
+        };
+        let grammar = json!({
+            "name": grammar.name,
+            "rules": rules,
+            "extras": extras,
+            "conflicts": [],
+            "externals": [],
+            "inline": [],
+            "supertypes": [],
+        });
+        // A Value always serializes.
+        serde_json::to_string_pretty(&grammar).unwrap()
+    }
+
+    /// The grammar in pest's syntax, one `rule = { a ~ b | c }` line per
+    /// rule. As in `to_antlr_grammar`, rules named with a capital are
+    /// tokens, which are atomic so that nothing is skipped inside them.
+    /// Skipped rules are silent, and what pest's `WHITESPACE` rule matches.
+    /// Rule names are written as they are, for pest to check, so a grammar
+    /// with its own `WHITESPACE` rule and skipped rules defines it twice.
+    #[cfg(feature = "codegen")]
+    pub fn to_pest_grammar(&self) -> String {
+        let grammar = grammar(self);
+        let mut pest = String::new();
+        for rule in &grammar.rules {
+            let modifier = if grammar.skip.contains(&rule.name) {
+                "_"
+            } else if rule.name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                "@"
+            } else {
+                ""
+            };
+            let body = write(&rule.body, &PEST);
+            pest.push_str(&format!("{} = {}{{ {} }}\n", rule.name, modifier, body));
+        }
+        if !grammar.skip.is_empty() {
+            pest.push_str(&format!(
+                "WHITESPACE = _{{ {} }}\n",
+                grammar.skip.join(" | ")
+            ));
+        }
+        pest
+    }
+
+    /// The grammar in LALRPOP's syntax, with LALRPOP's own lexer: string
+    /// terminals, ranges as regex terminals, and `repeat`, `repeat1` and
+    /// `optional` as `*`, `+` and `?`. Each rule produces the `(start,
+    /// end)` byte span it matched, and the first rule is `pub`, so it's the
+    /// one LALRPOP generates a parser for. LALRPOP's alternatives can't
+    /// nest, so alternatives inside a rule `expr` become rules `expr_1`,
+    /// `expr_2` and so on, past any names the grammar has. Its lexer skips
+    /// whitespace itself, so skipped rules are just rules. Rule names are
+    /// written as they are, and lookaheads as in `to_peg_string`, for
+    /// LALRPOP to reject.
+    #[cfg(feature = "codegen")]
+    pub fn to_lalrpop_grammar(&self) -> String {
+        let grammar = grammar(self);
+        let names: HashSet<&str> = grammar
+            .rules
+            .iter()
+            .map(|rule| rule.name.as_str())
+            .collect();
+        let mut rules = vec!["grammar;\n".to_string()];
+        for (n, rule) in grammar.rules.iter().enumerate() {
+            let mut lalrpop = Lalrpop {
+                rule: &rule.name,
+                names: &names,
+                helpers: vec![],
+            };
+            let alternatives = match &rule.body {
+                Expr::Choice(alternatives) => alternatives
+                    .iter()
+                    .map(|alternative| lalrpop.symbols(alternative))
+                    .collect(),
+                body => vec![lalrpop.symbols(body)],
+            };
+            rules.push(lalrpop_rule(n == 0, &rule.name, &alternatives));
+            for (name, alternatives) in &lalrpop.helpers {
+                rules.push(lalrpop_rule(false, name, alternatives));
+            }
+        }
+        rules.join("\n")
+    }
+
+    /// Rust source for the grammar as nom 7 parsers: a `parse_rule(input:
+    /// &str) -> IResult<&str, &str>` function for each rule, which outputs
+    /// the input it matched. Sequences become `tuple`, alternatives `alt`,
+    /// `repeat`, `repeat1` and `optional` `many0`, `many1` and `opt`, the
+    /// lookaheads `peek` and `not`, and ranges `satisfy`. nom doesn't skip
+    /// anything between tokens, so skipped rules are just rules. A rule
+    /// name that can't be part of a function's has `_` for what it can't
+    /// have, so `a-b` is `parse_a_b`.
+    #[cfg(feature = "codegen")]
+    pub fn to_nom_parser_code(&self) -> String {
+        let mut code = vec![];
+        for rule in grammar(self).rules {
+            code.push(format!(
+                "pub fn {}(input: &str) -> nom::IResult<&str, &str> {{\n    {}(input)\n}}\n",
+                parser(&rule.name),
+                recognized(&rule.body)
+            ));
+        }
+        code.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "grammar", feature = "codegen"))]
+    use pest::Parser;
+    #[cfg(feature = "grammar")]
+    use pest_derive::Parser;
+
+    // PEG's own grammar, from Ford's paper.
+    #[cfg(feature = "grammar")]
+    #[derive(Parser)]
+    #[grammar_inline = r#"
+        WHITESPACE = _{ " " | "\t" | "\n" }
+        grammar = { SOI ~ definition+ ~ EOI }
+        definition = { identifier ~ "<-" ~ expression }
+        expression = { sequence ~ ("/" ~ sequence)* }
+        sequence = { prefix* }
+        prefix = { ("&" | "!")? ~ suffix }
+        suffix = { primary ~ ("?" | "*" | "+")? }
+        primary = { identifier ~ !"<-" | "(" ~ expression ~ ")" | literal | class | "." }
+        identifier = @{ (ASCII_ALPHA | "_") ~ (ASCII_ALPHANUMERIC | "_")* }
+        literal = @{ "\"" ~ ("\\" ~ ANY | !"\"" ~ ANY)* ~ "\"" }
+        class = @{ "[" ~ ("\\" ~ ANY | !"]" ~ ANY)* ~ "]" }
+    "#]
+    struct Peg;
+
+    #[cfg(feature = "codegen")]
+    mod pest_fixture {
+        use pest_derive::Parser;
+
+        #[derive(Parser)]
+        #[grammar = "emit/pest_fixture.out.pest"]
+        pub struct Arith;
+    }
+
+    // Ranges are written the same way whatever they are, common ones too.
+    #[cfg(feature = "codegen")]
+    #[allow(clippy::manual_is_ascii_check)]
+    mod nom_fixture {
+        include!("./nom_fixture.out.rs");
+    }
+
+    #[cfg(feature = "grammar")]
+    #[test]
+    fn test_to_bnf_string() {
+        let sexp = Sexp::of_str(
+            r#"((expr (term + expr) (term - expr) term)
+                (term (factor * term) (factor / term) factor)
+                (factor ("(" expr ")") number)
+                (number (digit number) digit)
+                (digit 0 1 2 3 4 5 6 7 8 9)
+                (sign + - ()))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_bnf_string(),
+            r#"<expr> ::= <term> "+" <expr> | <term> "-" <expr> | <term>
+<term> ::= <factor> "*" <term> | <factor> "/" <term> | <factor>
+<factor> ::= "(" <expr> ")" | <number>
+<number> ::= <digit> <number> | <digit>
+<digit> ::= "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9"
+<sign> ::= "+" | "-" | ""
+"#
+        );
+        let quote = Sexp::of_str(r#"(string ("\"" chars "\""))"#).unwrap();
+        assert_eq!(quote.to_bnf_string(), "<string> ::= '\"' \"chars\" '\"'\n");
+        let bnf = |input| Sexp::of_str(input).unwrap().to_bnf_string();
+        assert_eq!(bnf("((a b) (a c) (d))"), "<a> ::= \"b\"\n<a> ::= \"c\"\n");
+        assert_eq!(bnf("(a (b (c)))"), "<a> ::= \"b\" \"c\"\n");
+        assert_eq!(
+            bnf("((a (x (optional y z) (repeat1 (range 0 2)))) (a_1 (followed-by y)))"),
+            r#"<a> ::= "x" <a_2> <a_3>
+<a_2> ::= "" | "y" | "z"
+<a_3> ::= <a_4> | <a_4> <a_3>
+<a_4> ::= "0" | "1" | "2"
+<a_1> ::= &"y"
+"#
+        );
+    }
+
+    #[cfg(feature = "grammar")]
+    #[test]
+    fn test_to_ebnf_string() {
+        let sexp = Sexp::of_str(
+            r#"((expr (term (repeat ((group + -) term))))
+                (term (factor (repeat ((group * /) factor))))
+                (factor ("(" expr ")") number)
+                (number ((optional -) digit (repeat digit) (optional (. digit (repeat digit)))))
+                (digit 0 1 2 3 4 5 6 7 8 9)
+                (sign + - ()))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_ebnf_string(),
+            r#"expr = term , { ( "+" | "-" ) , term } ;
+term = factor , { ( "*" | "/" ) , factor } ;
+factor = "(" , expr , ")" | number ;
+number = [ "-" ] , digit , { digit } , [ "." , digit , { digit } ] ;
+digit = "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" ;
+sign = "+" | "-" | ;
+"#
+        );
+        let nested = Sexp::of_str("(a (b (c d)))").unwrap();
+        assert_eq!(nested.to_ebnf_string(), "a = \"b\" , \"c\" , \"d\" ;\n");
+        let lookahead = Sexp::of_str("(a-b ((range 0 2) (not-followed-by x y)))").unwrap();
+        assert_eq!(
+            lookahead.to_ebnf_string(),
+            "a-b = ( \"0\" | \"1\" | \"2\" ) , !( \"x\" | \"y\" ) ;\n"
+        );
+    }
+
+    #[cfg(feature = "grammar")]
+    #[test]
+    fn test_to_peg_string() {
+        let sexp = Sexp::of_str(
+            r#"((expr (term (repeat ((group + -) term))))
+                (term (factor (repeat ((group * /) factor))))
+                (factor ("(" expr ")") number)
+                (number ((optional -) (repeat1 digit)))
+                (digit 0 1 2 3 4 5 6 7 8 9)
+                (keyword ("if" (not-followed-by digit letter)))
+                (letter (range a z) (range "[" "]"))
+                (quoted ("\"" (repeat ((not-followed-by "\"") (optional "\\") letter)) "\"")))"#,
+        )
+        .unwrap();
+        let peg = sexp.to_peg_string();
+        assert_eq!(
+            peg,
+            r#"expr <- term (("+" / "-") term)*
+term <- factor (("*" / "/") factor)*
+factor <- "(" expr ")" / number
+number <- "-"? digit+
+digit <- "0" / "1" / "2" / "3" / "4" / "5" / "6" / "7" / "8" / "9"
+keyword <- "if" !(digit / letter)
+letter <- [a-z] / [\[-\]]
+quoted <- "\"" (!"\"" "\\"? letter)* "\""
+"#
+        );
+        Peg::parse(Rule::grammar, &peg).unwrap();
+        let name = Sexp::of_str("(a-b c)").unwrap().to_peg_string();
+        assert_eq!(name, "a-b <- \"c\"\n");
+        assert!(Peg::parse(Rule::grammar, &name).is_err());
+    }
+
+    #[cfg(feature = "grammar")]
+    #[test]
+    fn test_to_antlr_grammar() {
+        let sexp = Sexp::of_str(
+            r#"(grammar Arith
+                 (skip WS)
+                 (expr (term (repeat ((group + -) term))))
+                 (term (factor (repeat ((group * /) factor))))
+                 (factor ("(" expr ")") NUMBER (- factor))
+                 (NUMBER ((repeat1 DIGIT) (optional (. (repeat1 DIGIT)))))
+                 (DIGIT (range 0 9))
+                 (WS (repeat1 (group " " "\t" "\n" "'"))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_antlr_grammar(),
+            r#"grammar Arith;
+
+expr: term (('+' | '-') term)*;
+term: factor (('*' | '/') factor)*;
+factor: '(' expr ')' | NUMBER | '-' factor;
+NUMBER: DIGIT+ ('.' DIGIT+)?;
+DIGIT: [0-9];
+WS: (' ' | '\t' | '\n' | '\'')+ -> skip;
+"#
+        );
+        let antlr = |input| Sexp::of_str(input).unwrap().to_antlr_grammar();
+        assert_eq!(
+            antlr("((expr NUMBER) (NUMBER 1))"),
+            "grammar ;\n\nexpr: NUMBER;\nNUMBER: '1';\n"
+        );
+        assert_eq!(
+            antlr("(grammar Arith (skip expr) (expr (range 0 9) (followed-by 1)))"),
+            "grammar Arith;\n\nexpr: [0-9] | &'1' -> skip;\n"
+        );
+    }
+
+    #[test]
+    fn test_to_tree_sitter_grammar_json() {
+        let sexp = Sexp::of_str(
+            r#"(grammar arith
+                 (skip _ws)
+                 (expr (term (repeat ((group + -) term))))
+                 (term number ("(" expr ")"))
+                 (number ((optional -) (repeat1 (range 0 9))))
+                 (_ws (repeat1 (group " " "\n"))))"#,
+        )
+        .unwrap();
+        let grammar: Value = serde_json::from_str(&sexp.to_tree_sitter_grammar_json()).unwrap();
+        let symbol = |name| json!({"type": "SYMBOL", "name": name});
+        let string = |value| json!({"type": "STRING", "value": value});
+        assert_eq!(
+            grammar["rules"]["expr"],
+            json!({"type": "SEQ", "members": [
+                symbol("term"),
+                {"type": "REPEAT", "content": {"type": "SEQ", "members": [
+                    {"type": "CHOICE", "members": [string("+"), string("-")]},
+                    symbol("term"),
+                ]}},
+            ]})
+        );
+        assert_eq!(
+            grammar["rules"]["number"],
+            json!({"type": "SEQ", "members": [
+                {"type": "CHOICE", "members": [string("-"), {"type": "BLANK"}]},
+                {"type": "REPEAT1", "content": {"type": "PATTERN", "value": "[0-9]"}},
+            ]})
+        );
+        let names: Vec<&String> = grammar["rules"].as_object().unwrap().keys().collect();
+        assert_eq!(names, ["expr", "term", "number", "_ws"]);
+        assert_eq!(grammar["name"], "arith");
+        assert_eq!(grammar["extras"], json!([symbol("_ws")]));
+        assert_eq!(grammar["externals"], json!([]));
+
+        let single = Sexp::of_str("(grammar a (a b))").unwrap();
+        let single: Value = serde_json::from_str(&single.to_tree_sitter_grammar_json()).unwrap();
+        assert_eq!(
+            single["extras"],
+            json!([{"type": "PATTERN", "value": "\\s"}])
+        );
+        let unnamed = Sexp::of_str("((a (followed-by b)))").unwrap();
+        let unnamed: Value = serde_json::from_str(&unnamed.to_tree_sitter_grammar_json()).unwrap();
+        assert_eq!(unnamed["name"], Value::Null);
+        assert_eq!(
+            unnamed["rules"]["a"],
+            json!({"type": "FOLLOWED_BY", "content": string("b")})
+        );
+    }
+
+    #[cfg(feature = "codegen")]
+    #[test]
+    fn test_to_nom_parser_code() {
+        let sexp = Sexp::of_str(
+            r#"((expr (term (repeat ((group + -) term))))
+                (term (factor (repeat ((group * /) factor))))
+                (factor ("(" expr ")") number)
+                (number ((optional -) (repeat1 (range 0 9)) (not-followed-by (range a z))))
+                (whitespace (repeat (group " " "\t"))))"#,
+        )
+        .unwrap();
+        let code = sexp.to_nom_parser_code();
+        assert_eq!(code, include_str!("./nom_fixture.out.rs"));
+        assert_eq!(
+            nom_fixture::parse_expr("1+(2*-34)x"),
+            Ok(("x", "1+(2*-34)"))
+        );
+        assert!(nom_fixture::parse_expr("12a").is_err());
+        assert_eq!(nom_fixture::parse_whitespace(" \t1"), Ok(("1", " \t")));
+        let names = Sexp::of_str("((a-b (c.d)) (c.d x))").unwrap();
+        assert_eq!(
+            names.to_nom_parser_code(),
+            "pub fn parse_a_b(input: &str) -> nom::IResult<&str, &str> {\n    parse_c_d(input)\n}\n\n\
+             pub fn parse_c_d(input: &str) -> nom::IResult<&str, &str> {\n    nom::bytes::complete::tag(\"x\")(input)\n}\n"
+        );
+    }
+
+    #[cfg(feature = "codegen")]
+    #[test]
+    fn test_to_pest_grammar() {
+        let sexp = Sexp::of_str(
+            r#"(grammar arith
+                 (skip ws)
+                 (expr (term (repeat ((group + -) term))))
+                 (term (factor (repeat ((group * /) factor))))
+                 (factor ("(" expr ")") NUMBER)
+                 (NUMBER ((optional -) (repeat1 (range 0 9)) (not-followed-by (range a z))))
+                 (ws " " "\t"))"#,
+        )
+        .unwrap();
+        let pest = sexp.to_pest_grammar();
+        assert_eq!(pest, include_str!("./pest_fixture.out.pest"));
+        use pest_fixture::{Arith, Rule};
+        let pairs = Arith::parse(Rule::expr, "1 + (2 * -34)").unwrap();
+        assert_eq!(pairs.as_str(), "1 + (2 * -34)");
+        let numbers: Vec<&str> = pairs
+            .flatten()
+            .filter(|pair| pair.as_rule() == Rule::NUMBER)
+            .map(|pair| pair.as_str())
+            .collect();
+        assert_eq!(numbers, ["1", "2", "-34"]);
+        assert!(Arith::parse(Rule::NUMBER, "- 1").is_err());
+        let names = Sexp::of_str("(grammar g (skip b) (a-b c) (b d))").unwrap();
+        assert_eq!(
+            names.to_pest_grammar(),
+            "a-b = { \"c\" }\nb = _{ \"d\" }\nWHITESPACE = _{ b }\n"
+        );
+    }
+
+    #[cfg(feature = "codegen")]
+    #[test]
+    fn test_to_lalrpop_grammar() {
+        let sexp = Sexp::of_str(
+            r#"((expr (term (repeat ((group + -) term))))
+                (term (factor (repeat ((group * /) factor))))
+                (factor ("(" expr ")") number)
+                (number ((optional -) (repeat1 (range 0 9)) (optional (. (repeat1 (range 0 9))))))
+                (sign + - ()))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_lalrpop_grammar(),
+            r##"grammar;
+
+pub expr: (usize, usize) = {
+    <l:@L> term (expr_1 term)* <r:@R> => (l, r),
+};
+
+expr_1: (usize, usize) = {
+    <l:@L> "+" <r:@R> => (l, r),
+    <l:@L> "-" <r:@R> => (l, r),
+};
+
+term: (usize, usize) = {
+    <l:@L> factor (term_1 factor)* <r:@R> => (l, r),
+};
+
+term_1: (usize, usize) = {
+    <l:@L> "*" <r:@R> => (l, r),
+    <l:@L> "/" <r:@R> => (l, r),
+};
+
+factor: (usize, usize) = {
+    <l:@L> "(" expr ")" <r:@R> => (l, r),
+    <l:@L> number <r:@R> => (l, r),
+};
+
+number: (usize, usize) = {
+    <l:@L> "-"? r#"[0-9]"#+ ("." r#"[0-9]"#+)? <r:@R> => (l, r),
+};
+
+sign: (usize, usize) = {
+    <l:@L> "+" <r:@R> => (l, r),
+    <l:@L> "-" <r:@R> => (l, r),
+    <l:@L> <r:@R> => (l, r),
+};
+"##
+        );
+        let clash = Sexp::of_str("((a (group b c) (not-followed-by d e)) (a_1 d))").unwrap();
+        assert_eq!(
+            clash.to_lalrpop_grammar(),
+            r#"grammar;
+
+pub a: (usize, usize) = {
+    <l:@L> a_2 <r:@R> => (l, r),
+    <l:@L> !a_3 <r:@R> => (l, r),
+};
+
+a_2: (usize, usize) = {
+    <l:@L> "b" <r:@R> => (l, r),
+    <l:@L> "c" <r:@R> => (l, r),
+};
+
+a_3: (usize, usize) = {
+    <l:@L> "d" <r:@R> => (l, r),
+    <l:@L> "e" <r:@R> => (l, r),
+};
+
+a_1: (usize, usize) = {
+    <l:@L> "d" <r:@R> => (l, r),
+};
+"#
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// A name as written, for a GraphQL parser to check.
+fn name(sexp: &Sexp) -> String {
+    sexp.to_compact_string()
+}
+
+fn is_string(sexp: &Sexp) -> bool {
+    matches!(sexp, Sexp::Atom(atom) if atom.starts_with('"'))
+}
+
+/// A description before a definition or field, as a block string when it
+/// spans lines.
+fn description(sexp: &Sexp, indent: &str) -> String {
+    let text = unquoted(&sexp.to_compact_string());
+    if text.contains('\n') || text.contains('"') {
+        let text = text.replace("\"\"\"", "\\\"\"\"");
+        format!("{}\"\"\"\n{}{}\n{}\"\"\"\n", indent, indent, text, indent)
+    } else {
+        format!("{}\"{}\"\n", indent, text)
+    }
+}
+
+/// A type like `String!`, `(Post!)` for `[Post!]`, or the text of a string
+/// atom.
+fn type_reference(sexp: &Sexp) -> String {
+    match sexp {
+        Sexp::Atom(atom) if atom.starts_with('"') => unquoted(atom),
+        Sexp::List(_) if sexp.items().len() == 1 => {
+            format!("[{}]", type_reference(&sexp.items()[0]))
+        }
+        _ => name(sexp),
+    }
+}
+
+/// `(name (arg Type)... Type [!] ["description"])`, or nothing for an
+/// empty list.
+fn field(sexp: &Sexp) -> Option<String> {
+    let (field, rest) = sexp.items().split_first()?;
+    let field = name(field);
+    let arguments: Vec<String> = rest
+        .iter()
+        .take_while(|item| item.items().len() >= 2)
+        .map(|argument| {
+            let items = argument.items();
+            format!("{}: {}", name(&items[0]), type_reference(&items[1]))
+        })
+        .collect();
+    let mut rest = &rest[arguments.len()..];
+    let mut ty = match rest.split_first() {
+        Some((ty, tail)) => {
+            rest = tail;
+            type_reference(ty)
+        }
+        None => String::new(),
+    };
+    if let Some((Sexp::Atom(bang), tail)) = rest.split_first() {
+        if bang == "!" {
+            ty.push('!');
+            rest = tail;
+        }
+    }
+    let mut sdl = String::new();
+    match rest {
+        [text] if is_string(text) => sdl.push_str(&description(text, "  ")),
+        // Anything else follows the type, for a GraphQL parser to reject.
+        _ => {
+            for item in rest {
+                ty.push(' ');
+                ty.push_str(&name(item));
+            }
+        }
+    }
+    let signature = if arguments.is_empty() {
+        field
+    } else {
+        format!("{}({})", field, arguments.join(", "))
+    };
+    if ty.is_empty() {
+        sdl.push_str(&format!("  {}\n", signature));
+    } else {
+        sdl.push_str(&format!("  {}: {}\n", signature, ty));
+    }
+    Some(sdl)
+}
+
+fn definition(sexp: &Sexp) -> String {
+    let items = sexp.items();
+    let kind = items.first().map(name).unwrap_or_default();
+    let type_name = items.get(1).map(name).unwrap_or_default();
+    let mut rest = items.get(2..).unwrap_or_default();
+    let mut sdl = String::new();
+    if let Some((text, tail)) = rest.split_first() {
+        if is_string(text) {
+            sdl.push_str(&description(text, ""));
+            rest = tail;
+        }
+    }
+    match kind.as_str() {
+        "scalar" => sdl.push_str(&format!("scalar {}\n", type_name)),
+        "union" => {
+            let members: Vec<String> = rest.iter().map(name).collect();
+            sdl.push_str(&format!("union {} = {}\n", type_name, members.join(" | ")));
+        }
+        "enum" => {
+            let values: Vec<String> = rest
+                .iter()
+                .map(|value| format!("  {}\n", name(value)))
+                .collect();
+            sdl.push_str(&format!("enum {} {{\n{}}}\n", type_name, values.concat()));
+        }
+        _ => {
+            // `implements A B` between the name and the fields.
+            let mut interfaces = vec![];
+            if let Some((Sexp::Atom(keyword), tail)) = rest.split_first() {
+                if keyword == "implements" {
+                    let count = tail
+                        .iter()
+                        .take_while(|item| matches!(item, Sexp::Atom(_)))
+                        .count();
+                    interfaces = tail[..count].iter().map(name).collect();
+                    rest = &tail[count..];
+                }
+            }
+            let fields: Vec<String> = rest.iter().filter_map(field).collect();
+            let implements = if interfaces.is_empty() {
+                String::new()
+            } else {
+                format!(" implements {}", interfaces.join(" & "))
+            };
+            sdl.push_str(&format!(
+                "{} {}{} {{\n{}}}\n",
+                kind,
+                type_name,
+                implements,
+                fields.concat()
+            ));
+        }
+    }
+    sdl
+}
+
+impl Sexp {
+    /// The tree as GraphQL SDL: `(type User "A user" (id ID!) (posts (first
+    /// Int) (Post!) ! "Their posts"))` is an object type with a description
+    /// and the fields `id: ID!` and `posts(first: Int): [Post!]!`. A field
+    /// is its name, any `(argument Type)` lists, its type (an atom like
+    /// `String!`, a one-element list for a list type, or a string holding
+    /// any type), an optional `!` and an optional description. `input`
+    /// and `interface` take fields too, `type` can be followed by
+    /// `implements A B`, `enum` takes values, `union` member types, and
+    /// `scalar` nothing. A list of definitions is a whole schema. Names
+    /// are written as they are, and a definition of another kind like an
+    /// object type, for a GraphQL parser to check.
+    pub fn to_graphql_schema(&self) -> String {
+        let definitions: Vec<String> = match self.items().first() {
+            Some(Sexp::Atom(_)) => vec![definition(self)],
+            _ => self.items().iter().map(definition).collect(),
+        };
+        definitions.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphql_parser::schema::parse_schema;
+
+    #[test]
+    fn test_to_graphql_schema() {
+        let sexp = Sexp::of_str(
+            r#"((scalar Date)
+                (enum Role ADMIN USER)
+                (interface Node (id ID!))
+                (type User "A user of the site" implements Node
+                  (id ID!)
+                  (role Role "What they may do")
+                  (posts (first Int) (after String) (Post!) ! "Their posts"))
+                (type Post implements Node (id ID!) (author User!) (published Date))
+                (union SearchResult User Post)
+                (input NewPost (title String!) (tags "[String!]")))"#,
+        )
+        .unwrap();
+        let schema = sexp.to_graphql_schema();
+        assert_eq!(
+            schema,
+            r#"scalar Date
+
+enum Role {
+  ADMIN
+  USER
+}
+
+interface Node {
+  id: ID!
+}
+
+"A user of the site"
+type User implements Node {
+  id: ID!
+  "What they may do"
+  role: Role
+  "Their posts"
+  posts(first: Int, after: String): [Post!]!
+}
+
+type Post implements Node {
+  id: ID!
+  author: User!
+  published: Date
+}
+
+union SearchResult = User | Post
+
+input NewPost {
+  title: String!
+  tags: [String!]
+}
+"#
+        );
+        let parsed = parse_schema::<String>(&schema).unwrap();
+        assert_eq!(parsed.definitions.len(), 7);
+    }
+
+    #[test]
+    fn test_to_graphql_schema_malformed() {
+        let schema = |input| Sexp::of_str(input).unwrap().to_graphql_schema();
+        assert_eq!(schema("(type User (id) ())"), "type User {\n  id\n}\n");
+        assert_eq!(schema("(type)"), "type  {\n}\n");
+        assert_eq!(
+            schema("(object (a b) (1 Int x y))"),
+            "object (a b) {\n  1: Int x y\n}\n"
+        );
+        for input in &["(type User (id))", "(type)", "(object User (id Int))"] {
+            assert!(parse_schema::<String>(&schema(input)).is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+
+/// Whether Guile reads `name` back as this symbol without `#{...}#`: it
+/// can't hold delimiters, start with `#`, or look like a number.
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let second = chars.next();
+    name != "."
+        && !first.is_ascii_digit()
+        && first != '#'
+        && !("+-.".contains(first) && second.is_some_and(|c| c.is_ascii_digit() || c == '.'))
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !"()[]{}\";'`,|\\".contains(c))
+}
+
+/// `name` written so Guile reads it as a symbol: bare if it can be, and
+/// otherwise in the `#{...}#` syntax, with `}`, `\` and control characters
+/// as hex escapes.
+fn symbol(name: &str) -> String {
+    if is_symbol(name) {
+        return name.to_string();
+    }
+    let mut symbol = String::from("#{");
+    for c in name.chars() {
+        match c {
+            '}' | '\\' => symbol.push_str(&format!("\\x{:x};", c as u32)),
+            c if c.is_control() => symbol.push_str(&format!("\\x{:x};", c as u32)),
+            c => symbol.push(c),
+        }
+    }
+    symbol.push_str("}#");
+    symbol
+}
+
+/// Whether `text` is already Guile's `#` syntax for a literal: a boolean,
+/// a character like `#\a` or a keyword like `#:name`.
+fn is_hash_literal(text: &str) -> bool {
+    matches!(text, "#t" | "#f" | "#true" | "#false")
+        || text.strip_prefix("#\\").is_some_and(|c| !c.is_empty())
+        || text.strip_prefix("#:").is_some_and(is_symbol)
+}
+
+fn is_byte(item: &Sexp) -> bool {
+    matches!(item, Sexp::Atom(atom) if atom.parse::<u8>().is_ok())
+}
+
+impl Sexp {
+    /// The tree as Guile Scheme. Besides R7RS lists, strings, numbers and
+    /// symbols, `:name` atoms become Guile's `#:name` keywords, as used by
+    /// `define-module`'s options and GOOPS slot options like
+    /// `#:init-keyword`, and `(bytevector 1 2 3)` with only bytes becomes
+    /// the literal `#u8(1 2 3)`. Booleans, characters and keywords already
+    /// written in `#` syntax are kept, and other atoms that wouldn't read
+    /// back as a symbol are written as `#{...}#`.
+    pub fn to_guile_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::String(text) => quoted(&text, '"'),
+                Atom::Symbol(name) if is_hash_literal(name) => name.to_string(),
+                Atom::Symbol(name) => match name.strip_prefix(':') {
+                    Some(keyword) if is_symbol(keyword) => format!("#:{}", keyword),
+                    _ => symbol(name),
+                },
+            },
+            Sexp::List(_) => {
+                let (prefix, items) = match self.items().split_first() {
+                    Some((Sexp::Atom(head), bytes))
+                        if head == "bytevector" && bytes.iter().all(is_byte) =>
+                    {
+                        ("#u8", bytes)
+                    }
+                    _ => ("", self.items()),
+                };
+                let items: Vec<String> = items.iter().map(Sexp::to_guile_string).collect();
+                format!("{}({})", prefix, items.join(" "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_guile_string() {
+        let sexp = Sexp::of_str(
+            r#"((define-module (shapes point) :export (make-point) :use-module (oop goops))
+                (define-class <point> () (x :init-keyword :x :getter point-x))
+                (define header (bytevector 137 80 78 71))
+                (define big (bytevector 256))
+                (list "a\"b" 1.5 "x y" 1st))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_guile_string(),
+            r#"((define-module (shapes point) #:export (make-point) #:use-module (oop goops)) (define-class <point> () (x #:init-keyword #:x #:getter point-x)) (define header #u8(137 80 78 71)) (define big (bytevector 256)) (list "a\"b" 1.5 "x y" #{1st}#))"#
+        );
+        let atoms = Sexp::list(
+            ["#t", "#\\a", "#:key", "x y", "a}b", "#u"]
+                .iter()
+                .map(|&atom| Sexp::atom(atom))
+                .collect(),
+        );
+        assert_eq!(
+            atoms.to_guile_string(),
+            r"(#t #\a #:key #{x y}# #{a\x7d;b}# #{#u}#)"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "case", "class", "data", "default", "deriving", "do", "else", "foreign", "if", "import", "in",
+    "infix", "infixl", "infixr", "instance", "let", "module", "newtype", "of", "then", "type",
+    "where",
+];
+
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            // `\&` ends the numeric escape even if a digit follows.
+            c if c.is_control() => quoted.push_str(&format!("\\{}\\&", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// A rendered value and a description of its type, used to decide whether a
+// list's elements can share a Haskell list.
+fn render(sexp: &Sexp) -> (String, String) {
+    match sexp {
+        Sexp::Nil => (String::new(), String::new()),
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) if number.starts_with('-') => {
+                (format!("({})", number), "number".to_string())
+            }
+            Atom::Number(number) => (number.to_string(), "number".to_string()),
+            Atom::Symbol(name)
+                if is_identifier(name)
+                    && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                    && !KEYWORDS.contains(&name) =>
+            {
+                (name.to_string(), "name".to_string())
+            }
+            Atom::Symbol(name) => (string(name), "string".to_string()),
+            Atom::String(text) => (string(&text), "string".to_string()),
+        },
+        Sexp::List(_) => {
+            let (values, types): (Vec<String>, Vec<String>) =
+                sexp.items().iter().map(render).unzip();
+            match types.first() {
+                None => ("[]".to_string(), "[]".to_string()),
+                Some(first) if types.iter().all(|t| t == first) => {
+                    (format!("[{}]", values.join(", ")), format!("[{}]", first))
+                }
+                Some(_) => (
+                    format!("({})", values.join(", ")),
+                    format!("({})", types.join(",")),
+                ),
+            }
+        }
+    }
+}
+
+impl Sexp {
+    /// The tree as a Haskell expression. A list whose elements all have the
+    /// same shape becomes a Haskell list, and any other list a tuple.
+    /// Numbers stay numbers, atoms that are valid lowercase names become
+    /// variables, and everything else becomes a string literal.
+    pub fn to_haskell_string(&self) -> String {
+        render(self).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_haskell_string() {
+        let sexp = Sexp::of_str(r#"(config (ports 80 -443) (hosts "a.com" Web) (x 1) () (data))"#)
+            .unwrap();
+        assert_eq!(
+            sexp.to_haskell_string(),
+            r#"(config, (ports, 80, (-443)), (hosts, "a.com", "Web"), (x, 1), [], ["data"])"#
+        );
+    }
+
+    #[test]
+    fn test_nested_lists_of_the_same_shape() {
+        let sexp = Sexp::of_str("((1 2) (3 4) (5 6))").unwrap();
+        assert_eq!(sexp.to_haskell_string(), "[[1, 2], [3, 4], [5, 6]]");
+    }
+}
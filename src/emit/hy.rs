@@ -0,0 +1,150 @@
+use super::lisp::{write, Dialect};
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+
+/// Whether Hy reads `name` back as this symbol: no delimiters, and no
+/// leading digit or `#`, which starts a reader macro.
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let second = chars.next();
+    let numeric = first.is_ascii_digit()
+        || ("+-.".contains(first) && second.is_some_and(|c| c.is_ascii_digit()));
+    !numeric
+        && first != '#'
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !"()[]{}\"';`~,".contains(c))
+}
+
+/// Hy's constants are Python's, so Scheme's booleans and `nil` become
+/// `True`, `False` and `None`.
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => quoted(&text, '"'),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "True".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "False".to_string(),
+        Atom::Symbol("nil") => "None".to_string(),
+        Atom::Symbol(name) if is_symbol(name) => name.to_string(),
+        Atom::Symbol(name) => quoted(name, '"'),
+    }
+}
+
+/// Dictionary keys are strings, since a Hy keyword isn't one.
+fn key(key: &Sexp) -> String {
+    match key {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => number.to_string(),
+            Atom::String(text) => quoted(&text, '"'),
+            Atom::Symbol(name) => quoted(name.strip_prefix(':').unwrap_or(name), '"'),
+        },
+        _ => write(key, &HY),
+    }
+}
+
+/// One module of an `import`: `os`, `(as numpy np)` for `numpy :as np`,
+/// or `(from os.path join exists)` for `os.path [join exists]`.
+fn import(module: &Sexp) -> Option<String> {
+    match module.items() {
+        [] => Some(write(module, &HY)),
+        [Sexp::Atom(head), module, alias] if head == "as" => {
+            Some(format!("{} :as {}", write(module, &HY), write(alias, &HY)))
+        }
+        [Sexp::Atom(head), module, names @ ..] if head == "from" => {
+            let names: Vec<String> = names.iter().map(|name| write(name, &HY)).collect();
+            Some(format!("{} [{}]", write(module, &HY), names.join(" ")))
+        }
+        _ => None,
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    match (head, rest) {
+        // Hy's own `defn` is written the way `define` of a function is.
+        ("defn", [name @ Sexp::Atom(_), params @ Sexp::List(_), body @ ..]) => {
+            let mut signature = vec![name.clone()];
+            signature.extend(params.items().iter().cloned());
+            let mut define = vec![Sexp::atom("define"), Sexp::list(signature)];
+            define.extend(body.iter().cloned());
+            Some(write(&Sexp::list(define), &HY))
+        }
+        ("import", [_, ..]) => {
+            let modules = rest.iter().map(import).collect::<Option<Vec<_>>>()?;
+            Some(format!("(import {})", modules.join(" ")))
+        }
+        // Hy's `cond` has no default branch, so `else` becomes a `True`
+        // test, and the clauses are rewritten like any other `cond`.
+        ("cond", _) if rest.iter().any(|clause| clause.head() == Some("else")) => {
+            let mut clauses = vec![Sexp::atom("cond")];
+            for clause in rest {
+                match clause.items().split_first() {
+                    Some((Sexp::Atom(test), consequent)) if test == "else" => {
+                        let mut clause = vec![Sexp::atom("True")];
+                        clause.extend(consequent.iter().cloned());
+                        clauses.push(Sexp::list(clause));
+                    }
+                    _ => clauses.push(clause.clone()),
+                }
+            }
+            Some(write(&Sexp::list(clauses), &HY))
+        }
+        _ => None,
+    }
+}
+
+const HY: Dialect = Dialect {
+    atom: atom_text,
+    key,
+    forms,
+    params: ("[", "]"),
+    defun: "defn",
+    define: "setv",
+    set: "setv",
+    begin: "do",
+    bindings: "let",
+    cond: "cond",
+    table: ("{", "}"),
+    array: ("[", "]"),
+};
+
+impl Sexp {
+    /// The tree as Hy, reading it as Scheme-style code: `(defn f (x) ...)`
+    /// and `(define (f x) ...)` become `(defn f [x] ...)`, `lambda` `fn`,
+    /// `define` and `set!` of a variable `setv`, `let` bindings a flat
+    /// `[x 1]`, `begin` `do`, and `cond` Hy's flat `cond` with `True` for
+    /// `else`. `(import os (as numpy np) (from os.path join))` becomes
+    /// `(import os numpy :as np os.path [join])`, `(table (key value)...)`
+    /// a dictionary with string keys, and `(array ...)` a list. Booleans
+    /// and `nil` become Python's `True`, `False` and `None`.
+    pub fn to_hy_string(&self) -> String {
+        write(self, &HY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_hy_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (import os sys (as numpy np) (from os.path join exists))
+                 (defn greet (name) (print (.format "Hi, {}!" name)))
+                 (define (mean xs) (/ (sum xs) (len xs)))
+                 (define config (table (name "demo") (:port 80)))
+                 (let ((xs (array 1 2 3)))
+                   (cond ((> (mean xs) 1) (print "big") true) (else nil)))
+                 (map (lambda (x) (* x x)) (array 1 2)))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_hy_string(),
+            r#"(do (import os sys numpy :as np os.path [join exists]) (defn greet [name] (print (.format "Hi, {}!" name))) (defn mean [xs] (/ (sum xs) (len xs))) (setv config {"name" "demo" "port" 80}) (let [xs [1 2 3]] (cond (> (mean xs) 1) (do (print "big") True) True None)) (map (fn [x] (* x x)) [1 2]))"#
+        );
+    }
+}
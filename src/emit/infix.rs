@@ -0,0 +1,290 @@
+//! Rewriting Scheme-style code as expressions in the languages with infix
+//! operators and `f(x, y)` calls, like R's `function(x) x + 1` for
+//! `(lambda (x) (+ x 1))`.
+
+use super::unquoted;
+use crate::Sexp;
+
+/// How a language spells the expressions that differ between them.
+pub(super) struct Syntax {
+    /// Writes an atom, including choosing between a name and a string.
+    pub(super) atom: fn(&str) -> String,
+    /// The language's own forms, tried before the shared ones. What they
+    /// write is taken for a primary expression, so one that isn't must
+    /// bring its own parentheses.
+    pub(super) forms: fn(&str, &[Sexp]) -> Option<String>,
+    /// The logical operators, as in `&&`, `||` and `!`.
+    pub(super) and: &'static str,
+    pub(super) or: &'static str,
+    pub(super) not: &'static str,
+    /// The inequality and remainder operators, as in `!=` and `%`.
+    pub(super) not_equal: &'static str,
+    pub(super) remainder: &'static str,
+    /// What's between an object and the method it calls or the field it
+    /// reads, as in `xs.push(1)` and `p.x`.
+    pub(super) method: &'static str,
+    pub(super) field: &'static str,
+    /// What's between the name and value of a keyword argument, as in
+    /// `f(sep = ",")`, or `None` if the language has none.
+    pub(super) named: Option<&'static str>,
+    /// Writes a function of the parameters to the forms of its body.
+    pub(super) lambda: fn(&[String], &[String]) -> String,
+    /// Writes `(if test then else)` from its three parts.
+    pub(super) conditional: fn(&str, &str, &str) -> String,
+    /// What an `(array ...)` is written between.
+    pub(super) array: (&'static str, &'static str),
+    /// Writes a `(table (key value)...)` from the text of its keys and
+    /// their values.
+    pub(super) table: fn(&[(String, String)]) -> String,
+}
+
+/// How tightly an expression binds, loosest first. Every level is shared
+/// by the languages here, and where they disagree on how two operators
+/// combine, the expressions are parenthesized whichever way they read.
+const LOWEST: u8 = 0;
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const EQUALITY: u8 = 4;
+const COMPARISON: u8 = 5;
+const ADDITIVE: u8 = 6;
+const MULTIPLICATIVE: u8 = 7;
+const UNARY: u8 = 8;
+const PRIMARY: u8 = 9;
+
+/// `sexp` as an operand that binds at least as tightly as `precedence`.
+fn operand(sexp: &Sexp, syntax: &Syntax, precedence: u8) -> String {
+    let (text, binds) = expression(sexp, syntax);
+    if binds < precedence {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+/// The operands of a left-associative operator joined by it: the first as
+/// tight as the operator, and the rest tighter.
+fn infix(operands: &[Sexp], operator: &str, precedence: u8, syntax: &Syntax) -> String {
+    operands
+        .iter()
+        .enumerate()
+        .map(|(index, item)| operand(item, syntax, precedence + (index > 0) as u8))
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", operator))
+}
+
+/// A prefix operator applied to `sexp`, parenthesized where the two would
+/// otherwise run together, as `- -1` would into `--1`.
+fn prefix(operator: &str, sexp: &Sexp, syntax: &Syntax) -> String {
+    let operand = operand(sexp, syntax, UNARY);
+    if operator.ends_with(|c: char| operand.starts_with(c)) {
+        format!("{}({})", operator, operand)
+    } else {
+        format!("{}{}", operator, operand)
+    }
+}
+
+/// A comparison binding as `precedence`, where more than two operands
+/// compare each neighbouring pair. Its operands are always tighter than any
+/// comparison, since some of the languages don't allow `a < b == c` at all.
+fn comparison(operands: &[Sexp], operator: &str, precedence: u8, syntax: &Syntax) -> (String, u8) {
+    let pairs: Vec<String> = operands
+        .windows(2)
+        .map(|pair| {
+            format!(
+                "{} {} {}",
+                operand(&pair[0], syntax, ADDITIVE),
+                operator,
+                operand(&pair[1], syntax, ADDITIVE)
+            )
+        })
+        .collect();
+    match pairs.as_slice() {
+        [single] => (single.clone(), precedence),
+        _ => {
+            let pairs: Vec<String> = pairs.iter().map(|pair| format!("({})", pair)).collect();
+            (pairs.join(&format!(" {} ", syntax.and)), AND)
+        }
+    }
+}
+
+/// The arguments of a call, with a `:name value` pair as a keyword
+/// argument where the language has them.
+pub(super) fn arguments(items: &[Sexp], syntax: &Syntax) -> String {
+    let mut arguments = vec![];
+    let mut items = items.iter();
+    while let Some(item) = items.next() {
+        let keyword = match (item, syntax.named) {
+            (Sexp::Atom(text), Some(named)) => text
+                .strip_prefix(':')
+                .filter(|name| !name.is_empty())
+                .map(|name| (name, named)),
+            _ => None,
+        };
+        match (keyword, items.as_slice().first()) {
+            (Some((name, named)), Some(value)) => {
+                items.next();
+                arguments.push(format!("{}{}{}", name, named, write(value, syntax)));
+            }
+            _ => arguments.push(write(item, syntax)),
+        }
+    }
+    arguments.join(", ")
+}
+
+/// `forms` one to a line, with each line indented by `indent`, for a block
+/// of statements.
+#[cfg(any(
+    feature = "fennel",
+    feature = "go-lang",
+    feature = "java",
+    feature = "julia",
+    feature = "kotlin",
+    feature = "lua",
+    feature = "php",
+    feature = "ruby",
+    feature = "scala",
+    feature = "swift",
+    feature = "typescript"
+))]
+pub(super) fn indented(forms: &[String], indent: &str) -> String {
+    forms
+        .iter()
+        .flat_map(|form| form.lines())
+        .map(|line| match line {
+            "" => String::new(),
+            line => format!("{}{}", indent, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn call(head: &Sexp, rest: &[Sexp], syntax: &Syntax) -> String {
+    format!(
+        "{}({})",
+        operand(head, syntax, PRIMARY),
+        arguments(rest, syntax)
+    )
+}
+
+/// The shared forms, with how tightly each binds, or `None` to write the
+/// list as a call.
+fn form(head: &str, rest: &[Sexp], syntax: &Syntax) -> Option<(String, u8)> {
+    if let Some(form) = (syntax.forms)(head, rest) {
+        return Some((form, PRIMARY));
+    }
+    let written =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, syntax)).collect() };
+    match (head, rest) {
+        ("+", [_, _, ..]) => Some((infix(rest, "+", ADDITIVE, syntax), ADDITIVE)),
+        ("-", [single]) => Some((prefix("-", single, syntax), UNARY)),
+        ("-", [_, _, ..]) => Some((infix(rest, "-", ADDITIVE, syntax), ADDITIVE)),
+        ("*", [_, _, ..]) => Some((infix(rest, "*", MULTIPLICATIVE, syntax), MULTIPLICATIVE)),
+        ("/", [_, _, ..]) => Some((infix(rest, "/", MULTIPLICATIVE, syntax), MULTIPLICATIVE)),
+        // The remainder operators don't all bind like `*`, so their
+        // operands are kept tighter than either.
+        ("%", [left, right]) | ("modulo", [left, right]) => Some((
+            format!(
+                "{} {} {}",
+                operand(left, syntax, UNARY),
+                syntax.remainder,
+                operand(right, syntax, UNARY)
+            ),
+            MULTIPLICATIVE,
+        )),
+        ("=", [_, _, ..]) | ("==", [_, _, ..]) | ("eq?", [_, _, ..]) | ("equal?", [_, _, ..]) => {
+            Some(comparison(rest, "==", EQUALITY, syntax))
+        }
+        ("!=", [_, _, ..]) | ("not=", [_, _, ..]) => {
+            Some(comparison(rest, syntax.not_equal, EQUALITY, syntax))
+        }
+        ("<", [_, _, ..]) | (">", [_, _, ..]) | ("<=", [_, _, ..]) | (">=", [_, _, ..]) => {
+            Some(comparison(rest, head, COMPARISON, syntax))
+        }
+        ("and", [_, _, ..]) => Some((infix(rest, syntax.and, AND, syntax), AND)),
+        ("or", [_, _, ..]) => Some((infix(rest, syntax.or, OR, syntax), OR)),
+        // Some languages' `!` binds looser than a comparison, so a negation
+        // is parenthesized as an operand of one.
+        ("not", [single]) => Some((prefix(syntax.not, single, syntax), NOT)),
+        ("if", [test, consequent, alternative]) => Some((
+            (syntax.conditional)(
+                &write(test, syntax),
+                &write(consequent, syntax),
+                &write(alternative, syntax),
+            ),
+            LOWEST,
+        )),
+        ("lambda", [params @ Sexp::List(_), body @ ..])
+        | ("fn", [params @ Sexp::List(_), body @ ..]) => Some((
+            (syntax.lambda)(&written(params.items()), &written(body)),
+            LOWEST,
+        )),
+        ("array", _) => {
+            let (open, close) = syntax.array;
+            Some((
+                format!("{}{}{}", open, written(rest).join(", "), close),
+                PRIMARY,
+            ))
+        }
+        ("table", _) => {
+            let mut fields = vec![];
+            for field in rest {
+                match field.items() {
+                    [Sexp::Atom(name), init] => fields.push((
+                        unquoted(name.strip_prefix(':').unwrap_or(name)),
+                        write(init, syntax),
+                    )),
+                    _ => return None,
+                }
+            }
+            Some(((syntax.table)(&fields), PRIMARY))
+        }
+        // `(.method object args...)` and `(.-field object)`, as in Clojure.
+        (_, [object]) if head.starts_with(".-") && head.len() > 2 => Some((
+            format!(
+                "{}{}{}",
+                operand(object, syntax, PRIMARY),
+                syntax.field,
+                &head[2..]
+            ),
+            PRIMARY,
+        )),
+        (_, [object, args @ ..]) if head.starts_with('.') && head.len() > 1 => Some((
+            format!(
+                "{}{}{}({})",
+                operand(object, syntax, PRIMARY),
+                syntax.method,
+                &head[1..],
+                arguments(args, syntax)
+            ),
+            PRIMARY,
+        )),
+        _ => None,
+    }
+}
+
+fn expression(sexp: &Sexp, syntax: &Syntax) -> (String, u8) {
+    match sexp {
+        Sexp::Nil => (String::new(), PRIMARY),
+        Sexp::Atom(text) => ((syntax.atom)(text), PRIMARY),
+        Sexp::List(_) => match sexp.items().split_first() {
+            Some((head @ Sexp::Atom(name), rest)) => {
+                form(name, rest, syntax).unwrap_or_else(|| (call(head, rest, syntax), PRIMARY))
+            }
+            Some((head, rest)) => (call(head, rest, syntax), PRIMARY),
+            None => (String::new(), PRIMARY),
+        },
+    }
+}
+
+/// Writes `sexp` as a Scheme-style expression in `syntax`: arithmetic,
+/// comparisons and `and`, `or` and `not` become infix and prefix operators,
+/// parenthesized as tightly as they bind, `(if test then else)` the
+/// language's conditional expression, and `lambda` its anonymous function.
+/// `(.method object args...)` and `(.-field object)` read from an object,
+/// `(array ...)` and `(table (key value)...)` become literals, and other
+/// lists are calls, with `:name value` pairs as keyword arguments where
+/// there are those.
+pub(super) fn write(sexp: &Sexp, syntax: &Syntax) -> String {
+    expression(sexp, syntax).0
+}
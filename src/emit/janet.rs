@@ -0,0 +1,128 @@
+use super::lisp::{write, Dialect};
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// `text` as a Janet string, with control characters as `\xHH` escapes.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() && (c as u32) < 0x100 => {
+                quoted.push_str(&format!("\\x{:02x}", c as u32))
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Whether `name` is made of the characters Janet allows in symbols and
+/// keywords. Non-ASCII characters are allowed too.
+fn is_name(name: &str) -> bool {
+    name.chars()
+        .all(|c| c.is_alphanumeric() || "!$%&*+-./:<?=>@^_".contains(c))
+}
+
+/// Whether Janet reads `name` back as this symbol rather than as a number.
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let second = chars.next();
+    !first.is_ascii_digit()
+        && first != ':'
+        && !("+-.".contains(first) && second.is_some_and(|c| c.is_ascii_digit()))
+        && is_name(name)
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol(name) => match name.strip_prefix(':') {
+            Some(keyword) if is_name(keyword) => name.to_string(),
+            None if is_symbol(name) => name.to_string(),
+            _ => string(name),
+        },
+    }
+}
+
+/// A table key: a keyword where it can be one, and otherwise the key as a
+/// value.
+fn key(key: &Sexp) -> String {
+    let name = match key {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => return number.to_string(),
+            Atom::String(text) => text,
+            Atom::Symbol(name) => name.strip_prefix(':').unwrap_or(name).to_string(),
+        },
+        _ => return write(key, &JANET),
+    };
+    if is_name(&name) {
+        format!(":{}", name)
+    } else {
+        string(&name)
+    }
+}
+
+const JANET: Dialect = Dialect {
+    atom: atom_text,
+    key,
+    forms: |_, _| None,
+    params: ("[", "]"),
+    defun: "defn",
+    define: "def",
+    set: "set",
+    begin: "do",
+    bindings: "let",
+    cond: "cond",
+    table: ("@{", "}"),
+    array: ("@[", "]"),
+};
+
+impl Sexp {
+    /// The tree as Janet, reading it as Scheme-style code: `(define (f x)
+    /// ...)` becomes `(defn f [x] ...)` and `(define x 1)` `(def x 1)`,
+    /// `lambda` becomes `fn` with its parameters in brackets, `let`
+    /// bindings a flat `[x 1]`, `set!` and `begin` `set` and `do`, and
+    /// `cond` Janet's flat `cond`. `(table (key value)...)` builds a
+    /// mutable `@{:key value}` table and `(array ...)` an `@[...]` array.
+    /// Other lists are calls, and atoms that aren't numbers, keywords or
+    /// symbols become strings.
+    pub fn to_janet_string(&self) -> String {
+        write(self, &JANET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_janet_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (area w h) (* w h))
+                 (define sizes (array 1 2 3))
+                 (define config (table (name "demo") (:port 8080) ("max size" 10)))
+                 (let ((total (reduce + 0 sizes)))
+                   (cond ((> total 5) (print "big")) (else (print "small"))))
+                 (map (lambda (x) (area x x)) sizes)
+                 (print "tab\there" :done +1 -x +x1 "a\"b"))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_janet_string(),
+            r#"(do (defn area [w h] (* w h)) (def sizes @[1 2 3]) (def config @{:name "demo" :port 8080 "max size" 10}) (let [total (reduce + 0 sizes)] (cond (> total 5) (print "big") (print "small"))) (map (fn [x] (area x x)) sizes) (print "tab\there" :done +1 -x +x1 "a\"b"))"#
+        );
+    }
+}
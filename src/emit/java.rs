@@ -0,0 +1,383 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, unquoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "switch",
+    "synchronized",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Whether `name` is an identifier, or a path of them like
+/// `System.out.println`. `this` and `super` are keywords, but read as names.
+fn is_name(name: &str) -> bool {
+    name.split('.')
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+/// `text` as a Java string literal. Java reads `\u` escapes before it
+/// reads any literal, so a `\u000a` would end the line; control characters
+/// are octal escapes instead.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\{:03o}", c as u32)),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "null".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` in braces after `opening`, as in `if (ok) { ... }`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}{{\n{}\n}}", opening, indented(forms, "    "))
+}
+
+/// A name with its type, from `(name Type)`, or a bare name taking
+/// `Object`.
+fn typed(item: &Sexp) -> Option<(String, String)> {
+    match item {
+        Sexp::Atom(name) if is_name(name) => Some((name.to_string(), "Object".to_string())),
+        Sexp::Atom(_) => None,
+        _ => match item.items() {
+            [Sexp::Atom(name), Sexp::Atom(ty)] if is_name(name) => {
+                Some((name.to_string(), unquoted(ty)))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// The statements of a body, with the last one's value returned if
+/// `returns`. A `cond` can't be returned, so each of its branches returns
+/// its own.
+fn statements(forms: &[Sexp], returns: bool) -> Vec<String> {
+    let mut lines = vec![];
+    for (index, form) in forms.iter().enumerate() {
+        let last = returns && index == forms.len() - 1;
+        let statement = match form.items().split_first() {
+            Some((Sexp::Atom(head), clauses)) if head == "cond" && !clauses.is_empty() => {
+                cond(clauses, last)
+            }
+            Some((Sexp::Atom(head), [test, consequent])) if head == "if" => Some(block(
+                &format!("if ({}) ", write(test, &JAVA)),
+                &statements(std::slice::from_ref(consequent), last),
+            )),
+            _ => None,
+        };
+        lines.push(match (statement, last) {
+            (Some(statement), _) => statement,
+            (None, true) => format!("return {};", write(form, &JAVA)),
+            (None, false) => format!("{};", write(form, &JAVA)),
+        });
+    }
+    lines
+}
+
+fn cond(clauses: &[Sexp], returns: bool) -> Option<String> {
+    let mut branches = vec![];
+    for (index, clause) in clauses.iter().enumerate() {
+        let (test, consequent) = clause.items().split_first()?;
+        let opening = match test {
+            Sexp::Atom(test) if test == "else" && index > 0 => String::new(),
+            _ => format!("if ({}) ", write(test, &JAVA)),
+        };
+        branches.push(block(&opening, &statements(consequent, returns)));
+    }
+    Some(branches.join(" else "))
+}
+
+/// A method, from `(define ((name Type) params...) body...)`, or without
+/// a type for one returning `void`.
+fn method(signature: &Sexp, body: &[Sexp]) -> Option<String> {
+    let (name, params) = signature.items().split_first()?;
+    let (name, returns) = match name {
+        Sexp::Atom(name) if is_name(name) => (name.to_string(), None),
+        _ => typed(name).map(|(name, ty)| (name, Some(ty)))?,
+    };
+    let params = params
+        .iter()
+        .map(|param| typed(param).map(|(name, ty)| format!("{} {}", ty, name)))
+        .collect::<Option<Vec<_>>>()?;
+    let opening = format!(
+        "{} {}({}) ",
+        returns.as_deref().unwrap_or("void"),
+        name,
+        params.join(", ")
+    );
+    Some(block(&opening, &statements(body, returns.is_some())))
+}
+
+/// A class of the typed fields, with a constructor setting each of them,
+/// and the methods that follow.
+fn class(name: &str, fields: &Sexp, methods: &[Sexp]) -> Option<String> {
+    let fields = fields
+        .items()
+        .iter()
+        .map(typed)
+        .collect::<Option<Vec<_>>>()?;
+    let mut members = vec![];
+    if !fields.is_empty() {
+        let declarations: Vec<String> = fields
+            .iter()
+            .map(|(field, ty)| format!("{} {};", ty, field))
+            .collect();
+        members.push(declarations.join("\n"));
+        let params: Vec<String> = fields
+            .iter()
+            .map(|(field, ty)| format!("{} {}", ty, field))
+            .collect();
+        let assignments: Vec<String> = fields
+            .iter()
+            .map(|(field, _)| format!("this.{} = {};", field, field))
+            .collect();
+        members.push(block(
+            &format!("{}({}) ", name, params.join(", ")),
+            &assignments,
+        ));
+    }
+    for member in methods {
+        match member.items() {
+            [Sexp::Atom(head), signature @ Sexp::List(_), body @ ..] if head == "define" => {
+                members.push(method(signature, body)?)
+            }
+            _ => return None,
+        }
+    }
+    Some(block(&format!("class {} ", name), &[members.join("\n\n")]))
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    match (head, rest) {
+        ("class", [Sexp::Atom(name), fields @ Sexp::List(_), methods @ ..])
+            if is_name(name) && fields.head() != Some("define") =>
+        {
+            class(name, fields, methods)
+        }
+        ("class", [Sexp::Atom(name), methods @ ..]) if is_name(name) => {
+            class(name, &Sexp::Nil, methods)
+        }
+        ("new", [Sexp::Atom(class), args @ ..]) if is_name(class) => {
+            let args: Vec<String> = args.iter().map(|arg| write(arg, &JAVA)).collect();
+            Some(format!("new {}({})", class, args.join(", ")))
+        }
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "var {} = {}",
+            write(name, &JAVA),
+            write(init, &JAVA)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("({} = {})", write(name, &JAVA), write(init, &JAVA)))
+        }
+        _ => None,
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    let params = match params {
+        [single] => single.clone(),
+        _ => format!("({})", params.join(", ")),
+    };
+    match body {
+        [single] => format!("{} -> {}", params, single),
+        [init @ .., last] => {
+            let mut statements: Vec<String> =
+                init.iter().map(|form| format!("{};", form)).collect();
+            statements.push(format!("return {};", last));
+            format!("{} -> {{ {} }}", params, statements.join(" "))
+        }
+        [] => format!("{} -> {{}}", params),
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}, {}", string(key), value))
+        .collect();
+    format!("Map.of({})", fields.join(", "))
+}
+
+const JAVA: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: None,
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("List.of(", ")"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as Java, reading it as Scheme-style code: `(.method object
+    /// args...)` becomes `object.method(args)`, `(.-field object)` a field
+    /// access, `(new Point 1 2)` a constructor call and other lists calls.
+    /// `(class Point ((x int) (y int)) methods...)` becomes a class with
+    /// those fields and a constructor setting them, where each method is a
+    /// `(define ((name Type) (param Type)...) body...)` returning its last
+    /// form, or without a type `void`, and untyped parameters take
+    /// `Object`. In a body, `define` declares a `var`, and `if` without an
+    /// alternative and `cond` are `if` statements. `lambda` becomes `x ->
+    /// ...`, `if` with an alternative `? :`, and `(array ...)` and `(table
+    /// (key value)...)` `List.of` and `Map.of`.
+    pub fn to_java_string(&self) -> String {
+        write(self, &JAVA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn test_to_java_string() {
+        let sexp = Sexp::of_str(
+            r#"(class Point ((x int) (y int))
+                 (define ((norm int)) (+ (* x x) (* y y)))
+                 (define ((describe String) (limit int))
+                   (define n (.norm this))
+                   (cond ((> n limit) "far") ((== n 0) "origin") (else "near")))
+                 (define ((doubled java.util.List<Integer>))
+                   (.toList (.map (.stream (java.util.List.of x y)) (lambda (n) (* n 2)))))
+                 (define (show)
+                   (if (!= (% x 2) 0) (System.out.println "odd\tx"))
+                   (System.out.println (.describe (new Point 1 (- y)) 10))))"#,
+        )
+        .unwrap();
+        let class = sexp.to_java_string();
+        assert_eq!(
+            class,
+            r#"class Point {
+    int x;
+    int y;
+
+    Point(int x, int y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    int norm() {
+        return x * x + y * y;
+    }
+
+    String describe(int limit) {
+        var n = this.norm();
+        if (n > limit) {
+            return "far";
+        } else if (n == 0) {
+            return "origin";
+        } else {
+            return "near";
+        }
+    }
+
+    java.util.List<Integer> doubled() {
+        return java.util.List.of(x, y).stream().map(n -> n * 2).toList();
+    }
+
+    void show() {
+        if (x % 2 != 0) {
+            System.out.println("odd\tx");
+        }
+        System.out.println(new Point(1, -y).describe(10));
+    }
+}"#
+        );
+
+        // Check that it compiles, where there's a compiler to do that.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Point.java");
+        fs::write(&path, class).unwrap();
+        let compiled = Command::new("javac")
+            .arg("-d")
+            .arg(dir.path())
+            .arg(&path)
+            .output();
+        if let Ok(output) = compiled {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+}
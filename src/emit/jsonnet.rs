@@ -0,0 +1,112 @@
+use super::{atom, fields, is_identifier, quoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "assert",
+    "else",
+    "error",
+    "false",
+    "for",
+    "function",
+    "if",
+    "import",
+    "importbin",
+    "importstr",
+    "in",
+    "local",
+    "null",
+    "self",
+    "super",
+    "tailstrict",
+    "then",
+    "true",
+];
+
+fn is_name(name: &str) -> bool {
+    is_identifier(name) && !KEYWORDS.contains(&name)
+}
+
+/// A head like `std.join` or `f` that names a function.
+fn is_function(head: &str) -> bool {
+    head.split('.')
+        .enumerate()
+        .all(|(n, part)| is_name(part) || (n == 0 && matches!(part, "self" | "super" | "$")))
+}
+
+/// A call like `(std.join "," xs)`, which is never read as a field.
+fn is_method(sexp: &Sexp) -> bool {
+    matches!(sexp.items().first(), Some(Sexp::Atom(head)) if head.contains('.') && is_function(head))
+}
+
+fn array(items: &[Sexp]) -> String {
+    let items: Vec<String> = items.iter().map(Sexp::to_jsonnet).collect();
+    format!("[{}]", items.join(", "))
+}
+
+impl Sexp {
+    /// The tree as a Jsonnet expression. A list whose elements are all
+    /// fields (lists headed by distinct names, like `(name core)`) becomes
+    /// an object, a field with several elements an array of them, any other
+    /// list headed by a function name like `f` a call, and the rest arrays.
+    /// Lists headed by a dotted name like `std.join` are always calls, even
+    /// where they could be read as a nested object's field. Numbers stay numbers, `true`, `false` and `null` stay as they
+    /// are, and every other atom becomes a string.
+    pub fn to_jsonnet(&self) -> String {
+        match self {
+            Sexp::Nil => "null".to_string(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::Symbol(name @ ("true" | "false" | "null")) => name.to_string(),
+                Atom::Symbol(name) => quoted(name, '"'),
+                Atom::String(text) => quoted(&text, '"'),
+            },
+            Sexp::List(_) => {
+                if let Some(record) = fields(self) {
+                    let fields: Vec<String> = record
+                        .iter()
+                        .zip(self.items())
+                        .map(|((name, value), field)| {
+                            let key = if is_name(name) {
+                                name.clone()
+                            } else {
+                                quoted(name, '"')
+                            };
+                            let value = match &field.items()[1..] {
+                                [call] if is_method(call) => call.to_jsonnet(),
+                                [_] => value.to_jsonnet(),
+                                _ if fields(value).is_some() => value.to_jsonnet(),
+                                items => array(items),
+                            };
+                            format!("{}: {}", key, value)
+                        })
+                        .collect();
+                    return format!("{{ {} }}", fields.join(", "));
+                }
+                match self.items().split_first() {
+                    Some((Sexp::Atom(head), args)) if is_function(head) => {
+                        let args: Vec<String> = args.iter().map(Sexp::to_jsonnet).collect();
+                        format!("{}({})", head, args.join(", "))
+                    }
+                    _ => array(self.items()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_jsonnet() {
+        let sexp = Sexp::of_str(
+            r#"((name core) (deps a b) (path (std.join "/" ("usr" lib))) ("local" true) (ports 80))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_jsonnet(),
+            r#"{ name: "core", deps: ["a", "b"], path: std.join("/", ["usr", "lib"]), "local": true, ports: 80 }"#
+        );
+    }
+}
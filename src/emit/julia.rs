@@ -0,0 +1,222 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "baremodule",
+    "begin",
+    "break",
+    "catch",
+    "const",
+    "continue",
+    "do",
+    "else",
+    "elseif",
+    "end",
+    "export",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "global",
+    "if",
+    "import",
+    "let",
+    "local",
+    "macro",
+    "module",
+    "quote",
+    "return",
+    "struct",
+    "true",
+    "try",
+    "using",
+    "while",
+];
+
+/// Whether `name` is a Julia identifier, which may end in the `!` of a
+/// mutating function.
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '!')
+        && !KEYWORDS.contains(&name)
+}
+
+/// `text` as a Julia string, where a `$` would start an interpolation.
+fn string(text: &str) -> String {
+    quoted(text, '"').replace('$', "\\$")
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "nothing".to_string(),
+        Atom::Symbol(name) if is_name(name) || name == "true" || name == "false" => {
+            name.to_string()
+        }
+        // `:name` is a `Symbol`, as it reads in Julia too.
+        Atom::Symbol(name) if name.strip_prefix(':').is_some_and(is_name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` as the body of a block, one to a line, closed by `end`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}\n{}\nend", opening, indented(forms, "    "))
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &JULIA)).collect() };
+    match (head, rest) {
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), params @ ..] => Some(block(
+                &format!(
+                    "function {}({})",
+                    write(name, &JULIA),
+                    all(params).join(", ")
+                ),
+                &all(body),
+            )),
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) | ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &JULIA), write(init, &JULIA)))
+        }
+        ("begin", _) => Some(block("begin", &all(rest))),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut bindings = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => {
+                        bindings.push(format!("{} = {}", write(name, &JULIA), write(init, &JULIA)))
+                    }
+                    _ => return None,
+                }
+            }
+            Some(block(&format!("let {}", bindings.join(", ")), &all(body)))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if {}", write(test, &JULIA)),
+            &[write(consequent, &JULIA)],
+        )),
+        ("cond", [_, ..]) => {
+            let mut lines = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let opening = match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => "else".to_string(),
+                    _ if index == 0 => format!("if {}", write(test, &JULIA)),
+                    _ => format!("elseif {}", write(test, &JULIA)),
+                };
+                lines.push(opening);
+                lines.push(indented(&all(consequent), "    "));
+            }
+            lines.push("end".to_string());
+            Some(lines.join("\n"))
+        }
+        ("tuple", [single]) => Some(format!("({},)", write(single, &JULIA))),
+        ("tuple", _) => Some(format!("({})", all(rest).join(", "))),
+        _ => None,
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    let params = match params {
+        [single] => single.clone(),
+        _ => format!("({})", params.join(", ")),
+    };
+    match body {
+        [single] => format!("{} -> {}", params, single),
+        _ => format!("{} -> {}", params, block("begin", body)),
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{} => {}", string(key), value))
+        .collect();
+    format!("Dict({})", fields.join(", "))
+}
+
+const JULIA: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: Some("="),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("[", "]"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as Julia, reading it as Scheme-style code: `(define (f x)
+    /// ...)` becomes a `function f(x) ... end` block, `define` and `set!`
+    /// of a variable assignments, `begin` and `let` their blocks, `cond` an
+    /// `if`/`elseif`/`else` chain, `lambda` `x -> ...`, and `if` with an
+    /// alternative `? :`. `(tuple ...)` and `(array ...)` become `(a, b)`
+    /// and `[a, b]`, `(table (key value)...)` a `Dict` with string keys, and
+    /// `:name value` in a call a keyword argument. `:name` on its own stays
+    /// a `Symbol`, and `nil` becomes `nothing`.
+    pub fn to_julia_string(&self) -> String {
+        write(self, &JULIA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_julia_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (mean xs) (/ (sum xs) (length xs)))
+                 (define point (tuple 1 2))
+                 (define config (table (name "demo") ("max size" 10)))
+                 (let ((total (mean (array 1 2 3))) (label "cost: $5"))
+                   (cond ((> total 1) (println "big") (println label)) (else (println "small"))))
+                 (map (lambda (x) (if (== (% x 2) 0) (div x 2) (+ (* 3 x) 1))) (array 1 2))
+                 (sort! xs :rev true)
+                 (println (tuple nil) (not (isempty xs)) :done))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_julia_string(),
+            r#"begin
+    function mean(xs)
+        sum(xs) / length(xs)
+    end
+    point = (1, 2)
+    config = Dict("name" => "demo", "max size" => 10)
+    let total = mean([1, 2, 3]), label = "cost: \$5"
+        if total > 1
+            println("big")
+            println(label)
+        else
+            println("small")
+        end
+    end
+    map(x -> x % 2 == 0 ? div(x, 2) : 3 * x + 1, [1, 2])
+    sort!(xs, rev=true)
+    println((nothing,), !isempty(xs), :done)
+end"#
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, quoted, unquoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+/// Whether `name` is an identifier, or a path of them like `kotlin.math.sqrt`.
+fn is_name(name: &str) -> bool {
+    name.split('.')
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+/// `text` as a Kotlin string, where a `$` would start a template.
+fn string(text: &str) -> String {
+    quoted(text, '"').replace('$', "\\$")
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "null".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` as a block after `opening`, as in `run { ... }`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}{{\n{}\n}}", opening, indented(forms, "    "))
+}
+
+/// A parameter: `(name Type)`, or a bare name taking `Any?`.
+fn param(param: &Sexp) -> Option<String> {
+    match param {
+        Sexp::Atom(name) if is_name(name) => Some(format!("{}: Any?", name)),
+        Sexp::Atom(_) => None,
+        _ => match param.items() {
+            [name @ Sexp::Atom(_), Sexp::Atom(ty)] => {
+                Some(format!("{}: {}", write(name, &KOTLIN), unquoted(ty)))
+            }
+            _ => None,
+        },
+    }
+}
+
+fn params(params: &[Sexp], prefix: &str) -> Option<String> {
+    let params = params
+        .iter()
+        .map(|item| Some(format!("{}{}", prefix, param(item)?)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", params.join(", ")))
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &KOTLIN)).collect() };
+    match (head, rest) {
+        ("data-class", [name @ Sexp::Atom(_), fields @ ..]) => Some(format!(
+            "data class {}{}",
+            write(name, &KOTLIN),
+            params(fields, "val ")?
+        )),
+        // A block body wouldn't return its last expression, so several
+        // statements are a `run` block's instead.
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), rest @ ..] => {
+                let signature = format!("fun {}{}", write(name, &KOTLIN), params(rest, "")?);
+                match all(body).as_slice() {
+                    [single] => Some(format!("{} = {}", signature, single)),
+                    body => Some(format!("{} = {}", signature, block("run ", body))),
+                }
+            }
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "val {} = {}",
+            write(name, &KOTLIN),
+            write(init, &KOTLIN)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "{} = {}",
+            write(name, &KOTLIN),
+            write(init, &KOTLIN)
+        )),
+        ("begin", [_, ..]) => Some(block("run ", &all(rest))),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut forms = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => forms.push(format!(
+                        "val {} = {}",
+                        write(name, &KOTLIN),
+                        write(init, &KOTLIN)
+                    )),
+                    _ => return None,
+                }
+            }
+            forms.extend(all(body));
+            Some(block("run ", &forms))
+        }
+        ("if", [test, consequent]) => Some(format!(
+            "if ({}) {}",
+            write(test, &KOTLIN),
+            write(consequent, &KOTLIN)
+        )),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for clause in rest {
+                let (test, consequent) = clause.items().split_first()?;
+                let test = match test {
+                    Sexp::Atom(test) if test == "else" => "else".to_string(),
+                    _ => write(test, &KOTLIN),
+                };
+                match all(consequent).as_slice() {
+                    [single] => branches.push(format!("{} -> {}", test, single)),
+                    consequent => branches.push(block(&format!("{} -> ", test), consequent)),
+                }
+            }
+            Some(block("when ", &branches))
+        }
+        _ => None,
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    let arrow = match params {
+        [] => String::new(),
+        _ => format!(" {} ->", params.join(", ")),
+    };
+    match body {
+        [single] => format!("{{{} {} }}", arrow, single),
+        _ => format!("{{{}\n{}\n}}", arrow, indented(body, "    ")),
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{} to {}", string(key), value))
+        .collect();
+    format!("mapOf({})", fields.join(", "))
+}
+
+const KOTLIN: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: Some(" = "),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("if ({}) {} else {}", test, consequent, alternative)
+    },
+    array: ("listOf(", ")"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as a Kotlin expression, reading it as Scheme-style code:
+    /// calls become `f(args...)` and `(.method object args...)` method
+    /// calls, `(data-class Point (x Int) (y Int))` a `data class`, and
+    /// `(Point :x 1 :y 2)` an instance of it with named arguments. `(define
+    /// (f (x Int)) ...)` becomes a `fun`, with untyped parameters taking
+    /// `Any?`, `define` of a variable a `val`, `begin` and `let` `run`
+    /// blocks, `if` Kotlin's `if (test) ... else ...`, and `cond` a `when`.
+    /// `lambda` becomes `{ x -> ... }`, `(array ...)` `listOf(...)`, `(table
+    /// (key value)...)` `mapOf("key" to value)`, and `nil` `null`.
+    pub fn to_kotlin_string(&self) -> String {
+        write(self, &KOTLIN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_kotlin_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (data-class Point (x Int) (y Int))
+                 (define (norm (p Point)) (kotlin.math.sqrt (.toDouble (+ (* (.-x p) (.-x p)) (* (.-y p) (.-y p))))))
+                 (define (describe (n Int))
+                   (println n)
+                   (cond ((< n 0) "negative") ((== n 0) "zero") (else "$n")))
+                 (define origin (Point :x 0 :y 0))
+                 (define config (table (name "demo") (port 80)))
+                 (.map (array 1 2 3) (lambda (x) (* x 2)))
+                 (if (!= (norm origin) 0.0) (println "moved") nil))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_kotlin_string(),
+            r#"run {
+    data class Point(val x: Int, val y: Int)
+    fun norm(p: Point) = kotlin.math.sqrt((p.x * p.x + p.y * p.y).toDouble())
+    fun describe(n: Int) = run {
+        println(n)
+        when {
+            n < 0 -> "negative"
+            n == 0 -> "zero"
+            else -> "\$n"
+        }
+    }
+    val origin = Point(x = 0, y = 0)
+    val config = mapOf("name" to "demo", "port" to 80)
+    listOf(1, 2, 3).map({ x -> x * 2 })
+    if (norm(origin) != 0.0) println("moved") else null
+}"#
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use super::yaml::{document, is_mapping, key};
+use crate::Sexp;
+
+/// The fields Kubernetes tools print first, in this order.
+const HEADER: &[&str] = &["apiVersion", "kind", "metadata", "spec"];
+
+/// The top-level fields in the order Kubernetes tools print them.
+fn header(manifest: &[Sexp]) -> Vec<&Sexp> {
+    let mut fields: Vec<&Sexp> = manifest.iter().collect();
+    let rank = |field: &&Sexp| {
+        key(field)
+            .and_then(|name| HEADER.iter().position(|header| *header == name))
+            .unwrap_or(HEADER.len())
+    };
+    fields.sort_by_key(rank);
+    fields
+}
+
+impl Sexp {
+    /// The tree as a Kubernetes manifest in YAML: a list of fields like
+    /// `((kind Deployment) (metadata (name web)))` is a mapping, with
+    /// `apiVersion`, `kind`, `metadata` and `spec` first at the top level,
+    /// and a list of manifests several documents. A field's elements are a
+    /// nested mapping when they're fields themselves, a scalar when there's
+    /// a single atom, and a sequence otherwise, so `(containers ((name
+    /// web)))` holds a sequence of one container, as `(args (array
+    /// "--verbose"))` does of one argument. Strings YAML would read as
+    /// something else are quoted. A manifest that isn't a list of fields
+    /// with distinct names is written as a sequence, or a scalar for an
+    /// atom, for kubectl to reject.
+    pub fn to_kubernetes_yaml(&self) -> String {
+        let manifests: Vec<&Sexp> = if is_mapping(self.items()) {
+            vec![self]
+        } else {
+            self.items().iter().collect()
+        };
+        let documents: Vec<String> = manifests
+            .into_iter()
+            .map(|manifest| match manifest {
+                Sexp::List(_) if is_mapping(manifest.items()) => document(&Sexp::list(
+                    header(manifest.items()).into_iter().cloned().collect(),
+                )),
+                _ => document(manifest),
+            })
+            .collect();
+        documents.join("---\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_kubernetes_yaml() {
+        let sexp = Sexp::of_str(
+            r#"((metadata (name web) (labels (app web) (tier "true")))
+                (kind Deployment)
+                (apiVersion apps/v1)
+                (spec
+                  (replicas 3)
+                  (template
+                    (spec
+                      (containers
+                        ((name web)
+                         (image nginx:1.25)
+                         (args "--port" "8080")
+                         (command (array nginx))
+                         (ports ((containerPort 80) (protocol TCP)))
+                         (env ((name GREETING) (value "hello: world")))))
+                      (volumes)))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_kubernetes_yaml(),
+            r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+  labels:
+    app: web
+    tier: "true"
+spec:
+  replicas: 3
+  template:
+    spec:
+      containers:
+      - name: web
+        image: nginx:1.25
+        args:
+        - "--port"
+        - "8080"
+        command:
+        - nginx
+        ports:
+        - containerPort: 80
+          protocol: TCP
+        env:
+        - name: GREETING
+          value: "hello: world"
+      volumes: []
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_kubernetes_yaml_malformed() {
+        let yaml = |input| Sexp::of_str(input).unwrap().to_kubernetes_yaml();
+        assert_eq!(yaml("(a)"), "a\n");
+        assert_eq!(yaml("((a b) c)"), "- a\n- b\n---\nc\n");
+        assert_eq!(yaml("((a 1) (a 2))"), "- a\n- 1\n---\n- a\n- 2\n");
+    }
+}
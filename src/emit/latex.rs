@@ -0,0 +1,65 @@
+use crate::Sexp;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '{' | '}' | '%' | '$' | '#' | '&' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Sexp {
+    /// The pretty-printed tree in a `listings` environment, preceded by
+    /// `\lstset{language=...}` when a `language` is given.
+    ///
+    /// The body of `lstlisting` is read verbatim, so `\`, `{`, `}`, `%` and
+    /// `$` in atoms are printed as they are and must not be escaped there
+    /// (they would show up as `\{`). They are escaped in the language name,
+    /// which LaTeX does interpret. The one thing the body can't contain is
+    /// `\end{lstlisting}`, which would close the listing early, so a space
+    /// is inserted after any `\end` in a string atom.
+    pub fn to_latex_listing(&self, language: Option<&str>) -> String {
+        let mut latex = String::new();
+        if let Some(language) = language {
+            latex.push_str(&format!("\\lstset{{language={}}}\n", escape(language)));
+        }
+        let body = self
+            .to_string()
+            .replace("\\end{lstlisting}", "\\end {lstlisting}");
+        latex.push_str(&format!(
+            "\\begin{{lstlisting}}\n{}\n\\end{{lstlisting}}\n",
+            body
+        ));
+        latex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_latex_listing() {
+        let sexp = Sexp::of_str(r#"(defun cost (x) (format "%d$ {x}" x))"#).unwrap();
+        assert_eq!(
+            sexp.to_latex_listing(Some("Lisp")),
+            "\\lstset{language=Lisp}\n\\begin{lstlisting}\n(defun cost (x) (format \"%d$ {x}\" x))\n\\end{lstlisting}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_latex_listing_escapes() {
+        let sexp = Sexp::of_str(r#"(tex "\\end{lstlisting}")"#).unwrap();
+        let latex = sexp.to_latex_listing(Some("my_lang%"));
+        assert!(latex.starts_with("\\lstset{language=my\\_lang\\%}\n"));
+        assert_eq!(latex.matches("\\end{lstlisting}").count(), 1);
+        assert!(!sexp.to_latex_listing(None).contains("lstset"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Rewriting Scheme-style code for the Lisps whose core forms are spelled
+//! differently, like Fennel's `(fn [x] ...)` for `(lambda (x) ...)`.
+
+use crate::Sexp;
+
+/// How a dialect spells the forms that differ from Scheme.
+pub(super) struct Dialect {
+    /// Writes an atom, including choosing between a symbol and a string.
+    pub(super) atom: fn(&str) -> String,
+    /// Writes a table key.
+    pub(super) key: fn(&Sexp) -> String,
+    /// The dialect's own forms, tried before the shared ones.
+    pub(super) forms: fn(&str, &[Sexp]) -> Option<String>,
+    /// What parameter lists and `let` bindings are written between.
+    pub(super) params: (&'static str, &'static str),
+    /// Defines a named function, as in `(defn f [x] ...)`.
+    pub(super) defun: &'static str,
+    /// Defines a variable, as in `(def x 1)`.
+    pub(super) define: &'static str,
+    /// Assigns to a variable, as in `(set x 1)`.
+    pub(super) set: &'static str,
+    /// Evaluates forms in turn, as in `(do ...)`.
+    pub(super) begin: &'static str,
+    /// Binds variables around a body, as in `(let [x 1] ...)`.
+    pub(super) bindings: &'static str,
+    /// Tests clauses in turn, as one flat list of tests and results with an
+    /// optional default at the end.
+    pub(super) cond: &'static str,
+    /// What a key-value and a sequential table are written between.
+    pub(super) table: (&'static str, &'static str),
+    pub(super) array: (&'static str, &'static str),
+}
+
+fn call(sexp: &Sexp, dialect: &Dialect) -> String {
+    format!("({})", values(sexp.items(), dialect))
+}
+
+fn values(items: &[Sexp], dialect: &Dialect) -> String {
+    items
+        .iter()
+        .map(|item| write(item, dialect))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `items` as a parameter list.
+fn parameters(items: &[Sexp], dialect: &Dialect) -> String {
+    let (open, close) = dialect.params;
+    format!("{}{}{}", open, values(items, dialect), close)
+}
+
+/// Each form with a space before it, for the rest of a form after its
+/// head.
+fn body(body: &[Sexp], dialect: &Dialect) -> String {
+    body.iter()
+        .map(|form| format!(" {}", write(form, dialect)))
+        .collect()
+}
+
+/// The pairs of a `let`'s bindings, flattened into one binding vector.
+fn bindings(bindings: &Sexp, dialect: &Dialect) -> Option<String> {
+    let mut flat = vec![];
+    for binding in bindings.items() {
+        match binding.items() {
+            [name @ Sexp::Atom(_), init] => {
+                flat.extend([write(name, dialect), write(init, dialect)])
+            }
+            _ => return None,
+        }
+    }
+    let (open, close) = dialect.params;
+    Some(format!("{}{}{}", open, flat.join(" "), close))
+}
+
+/// The dialect's version of a Scheme form that reads differently in it, or
+/// `None` to write the list as a call.
+fn form(head: &str, rest: &[Sexp], dialect: &Dialect) -> Option<String> {
+    if let Some(form) = (dialect.forms)(head, rest) {
+        return Some(form);
+    }
+    match (head, rest) {
+        ("lambda", [params @ Sexp::List(_), rest @ ..])
+        | ("fn", [params @ Sexp::List(_), rest @ ..]) => Some(format!(
+            "(fn {}{})",
+            parameters(params.items(), dialect),
+            body(rest, dialect)
+        )),
+        ("fn", [name @ Sexp::Atom(_), params @ Sexp::List(_), rest @ ..]) => Some(format!(
+            "(fn {} {}{})",
+            write(name, dialect),
+            parameters(params.items(), dialect),
+            body(rest, dialect)
+        )),
+        ("define", [signature @ Sexp::List(_), rest @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), params @ ..] => Some(format!(
+                "({} {} {}{})",
+                dialect.defun,
+                write(name, dialect),
+                parameters(params, dialect),
+                body(rest, dialect)
+            )),
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "({} {} {})",
+            dialect.define,
+            write(name, dialect),
+            write(init, dialect)
+        )),
+        ("let", [pairs @ Sexp::List(_), rest @ ..])
+        | ("let*", [pairs @ Sexp::List(_), rest @ ..]) => Some(format!(
+            "({} {}{})",
+            dialect.bindings,
+            bindings(pairs, dialect)?,
+            body(rest, dialect)
+        )),
+        ("set!", _) => Some(format!("({}{})", dialect.set, body(rest, dialect))),
+        ("begin", _) => Some(format!("({}{})", dialect.begin, body(rest, dialect))),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let consequent = match consequent {
+                    [single] => write(single, dialect),
+                    _ => format!("({}{})", dialect.begin, body(consequent, dialect)),
+                };
+                let last = index == rest.len() - 1;
+                match test {
+                    Sexp::Atom(test) if test == "else" && last => branches.push(consequent),
+                    _ => branches.extend([write(test, dialect), consequent]),
+                }
+            }
+            Some(format!("({} {})", dialect.cond, branches.join(" ")))
+        }
+        ("table", _) => {
+            let mut fields = vec![];
+            for field in rest {
+                match field.items() {
+                    [name, init] => {
+                        fields.push(format!("{} {}", (dialect.key)(name), write(init, dialect)))
+                    }
+                    _ => return None,
+                }
+            }
+            let (open, close) = dialect.table;
+            Some(format!("{}{}{}", open, fields.join(" "), close))
+        }
+        ("array", _) => {
+            let (open, close) = dialect.array;
+            Some(format!("{}{}{}", open, values(rest, dialect), close))
+        }
+        _ => None,
+    }
+}
+
+/// Writes `sexp` as Scheme-style code in `dialect`: `(lambda (x) ...)`
+/// becomes `(fn [x] ...)`, `(define (f x) ...)` and `(define x 1)` the
+/// dialect's definitions, `(let ((x 1)) ...)` `(let [x 1] ...)`, `set!`
+/// the dialect's assignment, `begin` its `do`, and `cond` a flat list of tests
+/// and results. `(table (key value)...)` and `(array ...)` become table
+/// literals, and other lists are calls.
+pub(super) fn write(sexp: &Sexp, dialect: &Dialect) -> String {
+    match sexp {
+        Sexp::Nil => String::new(),
+        Sexp::Atom(text) => (dialect.atom)(text),
+        Sexp::List(_) => match sexp.items().split_first() {
+            Some((Sexp::Atom(head), rest)) => {
+                form(head, rest, dialect).unwrap_or_else(|| call(sexp, dialect))
+            }
+            _ => call(sexp, dialect),
+        },
+    }
+}
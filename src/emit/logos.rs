@@ -0,0 +1,107 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// A logos attribute matching `pattern`: a literal token for an atom, and a
+/// regex for the regex trees `to_regex_string` reads.
+fn pattern(pattern: &Sexp) -> (&'static str, String) {
+    match pattern {
+        Sexp::Atom(atom) => ("token", unquoted(atom)),
+        _ => ("regex", pattern.to_regex_string()),
+    }
+}
+
+impl Sexp {
+    /// Rust source for a logos lexer: a `Token` enum deriving `Logos`, with
+    /// a variant for each `(Name patterns...)` in a list of tokens. Atom
+    /// patterns are literal `#[token(...)]`s and lists regexes, written the
+    /// way `to_regex_string` reads them, so `(Number (plus (class (range 0
+    /// 9))))` is `#[regex("[0-9]+")]`. `(skip patterns...)` is what logos
+    /// skips between tokens, like whitespace. Entries that aren't tokens
+    /// are left out, and variant names are written as they are, for the
+    /// compiler to check.
+    pub fn to_logos_lexer_code(&self) -> String {
+        let mut skips = vec![];
+        let mut variants = vec![];
+        for entry in self.items() {
+            let (name, patterns) = match entry.items().split_first() {
+                Some((Sexp::Atom(name), patterns)) if !patterns.is_empty() => (name, patterns),
+                _ => continue,
+            };
+            if name == "skip" {
+                for skip in patterns {
+                    skips.push(format!("#[logos(skip {:?})]\n", pattern(skip).1));
+                }
+                continue;
+            }
+            let mut variant = String::new();
+            for token in patterns {
+                let (kind, pattern) = pattern(token);
+                variant.push_str(&format!("    #[{}({:?})]\n", kind, pattern));
+            }
+            variant.push_str(&format!("    {},\n", name));
+            variants.push(variant);
+        }
+        format!(
+            "use logos::Logos;\n\n#[derive(Logos, Clone, Copy, Debug, PartialEq, Eq)]\n{}pub enum Token {{\n{}}}\n",
+            skips.concat(),
+            variants.join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod logos_fixture {
+        include!("./logos_fixture.out.rs");
+    }
+
+    #[test]
+    fn test_to_logos_lexer_code() {
+        let sexp = Sexp::of_str(
+            r#"((skip (plus (class " " "\t" "\n")))
+                (Let let)
+                (Op + - * /)
+                (Paren "(" ")")
+                (Ident (concat (class _ (range a z)) (star (class _ (range a z) (range 0 9)))))
+                (Number (concat (plus (class (range 0 9)))
+                                (opt (concat . (plus (class (range 0 9))))))))"#,
+        )
+        .unwrap();
+        let code = sexp.to_logos_lexer_code();
+        assert_eq!(code, include_str!("./logos_fixture.out.rs"));
+
+        use logos::Logos;
+        use logos_fixture::Token;
+        let tokens: Result<Vec<Token>, ()> = Token::lexer("let x1 = 2.5").collect();
+        assert!(tokens.is_err());
+        let tokens: Result<Vec<Token>, ()> = Token::lexer("let x1 + (2.5 * y)").collect();
+        assert_eq!(
+            tokens.unwrap(),
+            [
+                Token::Let,
+                Token::Ident,
+                Token::Op,
+                Token::Paren,
+                Token::Number,
+                Token::Op,
+                Token::Ident,
+                Token::Paren
+            ]
+        );
+        let literal = Sexp::of_str("((Op (lookahead a)))").unwrap();
+        let code = literal.to_logos_lexer_code();
+        assert!(
+            code.contains(r#"#[regex("\\(lookahead a\\)")]"#),
+            "{}",
+            code
+        );
+        let entries = Sexp::of_str("((Op +) x () (1a -))")
+            .unwrap()
+            .to_logos_lexer_code();
+        assert!(entries.ends_with(
+            "pub enum Token {\n    #[token(\"+\")]\n    Op,\n\n    #[token(\"-\")]\n    1a,\n}\n"
+        ));
+    }
+}
@@ -0,0 +1,24 @@
+use logos::Logos;
+
+#[derive(Logos, Clone, Copy, Debug, PartialEq, Eq)]
+#[logos(skip "[ \t\n]+")]
+pub enum Token {
+    #[token("let")]
+    Let,
+
+    #[token("+")]
+    #[token("-")]
+    #[token("*")]
+    #[token("/")]
+    Op,
+
+    #[token("(")]
+    #[token(")")]
+    Paren,
+
+    #[regex("[_a-z][_a-z0-9]*")]
+    Ident,
+
+    #[regex("[0-9]+(?:\\.[0-9]+)?")]
+    Number,
+}
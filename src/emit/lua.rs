@@ -0,0 +1,307 @@
+use super::infix::{arguments, indented, write, Syntax};
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+fn is_name(name: &str) -> bool {
+    is_identifier(name) && !KEYWORDS.contains(&name)
+}
+
+/// Whether `name` is a name, or a path of them like `string.format`.
+fn is_path(name: &str) -> bool {
+    name.split('.').all(is_name)
+}
+
+/// `text` as a Lua string literal: control characters become decimal
+/// escapes, the one form every Lua reads, padded to the three digits an
+/// escape reads so a digit after it isn't taken for part of it.
+pub(super) fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\{:03}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "nil".to_string(),
+        Atom::Symbol(name) if is_path(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` indented between `opening` and `end`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}\n{}\nend", opening, indented(forms, "  "))
+}
+
+/// Whether `form` is written as a statement rather than an expression,
+/// which a `return` can't take.
+fn is_statement(form: &Sexp) -> bool {
+    match form.items() {
+        [Sexp::Atom(head), _, _] if head == "if" => true,
+        [Sexp::Atom(head), ..] => matches!(
+            head.as_str(),
+            "define" | "set!" | "return" | "begin" | "let" | "let*" | "cond"
+        ),
+        _ => false,
+    }
+}
+
+fn is_literal(sexp: &Sexp) -> bool {
+    match sexp {
+        Sexp::Atom(text) => !is_path(text),
+        _ => matches!(sexp.head(), Some("array") | Some("table")),
+    }
+}
+
+/// The statements of a body, with the last one's value returned if
+/// `returns`, from within it where it's a block.
+fn statements(forms: &[Sexp], returns: bool) -> Vec<String> {
+    let mut lines = vec![];
+    for (index, form) in forms.iter().enumerate() {
+        let last = returns && index == forms.len() - 1;
+        let block = match form.items().split_first() {
+            Some((Sexp::Atom(head), rest)) => statement(head, rest, last),
+            _ => None,
+        };
+        lines.push(match block {
+            Some(block) => block,
+            None if last && !is_statement(form) => format!("return {}", write(form, &LUA)),
+            None => write(form, &LUA),
+        });
+    }
+    lines
+}
+
+/// The forms written as blocks, returning their last value if `returns`.
+fn statement(head: &str, rest: &[Sexp], returns: bool) -> Option<String> {
+    match (head, rest) {
+        ("begin", [_, ..]) => Some(block("do", &statements(rest, returns))),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut lines = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => lines.push(format!(
+                        "local {} = {}",
+                        write(name, &LUA),
+                        write(init, &LUA)
+                    )),
+                    _ => return None,
+                }
+            }
+            lines.extend(statements(body, returns));
+            Some(block("do", &lines))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if {} then", write(test, &LUA)),
+            &statements(std::slice::from_ref(consequent), returns),
+        )),
+        ("cond", [_, ..]) => {
+            let mut lines = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                lines.push(match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => "else".to_string(),
+                    _ if index == 0 => format!("if {} then", write(test, &LUA)),
+                    _ => format!("elseif {} then", write(test, &LUA)),
+                });
+                lines.push(indented(&statements(consequent, returns), "  "));
+            }
+            lines.push("end".to_string());
+            Some(lines.join("\n"))
+        }
+        _ => None,
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &LUA)).collect() };
+    match (head, rest) {
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), params @ ..] => Some(block(
+                &format!(
+                    "local function {}({})",
+                    write(name, &LUA),
+                    all(params).join(", ")
+                ),
+                &statements(body, true),
+            )),
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "local {} = {}",
+            write(name, &LUA),
+            write(init, &LUA)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &LUA), write(init, &LUA)))
+        }
+        ("return", [value]) => Some(format!("return {}", write(value, &LUA))),
+        // Only a name, a call or a parenthesized expression can have a
+        // method called on it, so a literal is parenthesized.
+        (_, [object, args @ ..])
+            if head.len() > 1 && head.starts_with('.') && is_literal(object) =>
+        {
+            let object = write(object, &LUA);
+            Some(match head.strip_prefix(".-") {
+                Some(field) if args.is_empty() => format!("({}).{}", object, field),
+                _ => format!("({}):{}({})", object, &head[1..], arguments(args, &LUA)),
+            })
+        }
+        // A function ending in a statement returns from within it.
+        ("lambda", [params @ Sexp::List(_), body @ ..])
+            if body.last().is_some_and(is_statement) =>
+        {
+            Some(block(
+                &format!("function({})", all(params.items()).join(", ")),
+                &statements(body, true),
+            ))
+        }
+        _ => statement(head, rest, false),
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    let opening = format!("function({})", params.join(", "));
+    match body {
+        [single] => format!("{} return {} end", opening, single),
+        [init @ .., last] => {
+            let mut lines = init.to_vec();
+            lines.push(format!("return {}", last));
+            block(&opening, &lines)
+        }
+        [] => format!("{} end", opening),
+    }
+}
+
+/// A table constructor, with keys that aren't names in brackets.
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| {
+            if is_name(key) {
+                format!("{} = {}", key, value)
+            } else {
+                format!("[{}] = {}", string(key), value)
+            }
+        })
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+const LUA: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "and",
+    or: "or",
+    not: "not ",
+    not_equal: "~=",
+    remainder: "%",
+    method: ":",
+    field: ".",
+    named: None,
+    lambda,
+    // `test and a or b` gives `b` whenever `a` is false or `nil`, so the
+    // conditional is a function called on the spot instead.
+    conditional: |test, consequent, alternative| {
+        format!(
+            "(function() if {} then return {} else return {} end end)()",
+            test, consequent, alternative
+        )
+    },
+    array: ("{", "}"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as Lua, reading it as Scheme-style code: lists become calls,
+    /// `(.method object args...)` `object:method(args)` and `(.-field
+    /// object)` `object.field`. `(table (key value)...)` becomes the table
+    /// constructor `{key = value}` and `(array ...)` `{a, b}`. `(define (f
+    /// x) ...)` becomes a `local function` returning its last form, `define`
+    /// of a variable a `local`, `begin` and `let` `do ... end` blocks, and
+    /// `if` without an alternative and `cond` `if` statements. `lambda`
+    /// becomes `function(x) return ... end`, and since Lua has no
+    /// conditional expression, `if` with an alternative is a function
+    /// called on the spot.
+    #[cfg(feature = "lua")]
+    pub fn to_lua_string(&self) -> String {
+        write(self, &LUA)
+    }
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_lua_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (area w h) (* w h))
+                 (define (describe n)
+                   (print n)
+                   (cond ((< n 0) "negative") ((== n 0) "zero") (else (.format "%d items" n))))
+                 (define config (table (name "demo") ("max size" 10) (sizes (array 1 2 3))))
+                 (let ((total (area 2 3)))
+                   (set! config.total total)
+                   (if (and (!= total 0) (not (== (% total 2) 1))) (print "even")))
+                 (print (.upper "lua") (.-name config) (if (> (area 1 1) 0) "yes" false))
+                 (table.sort config.sizes (lambda (a b) (> a b))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_lua_string(),
+            r#"do
+  local function area(w, h)
+    return w * h
+  end
+  local function describe(n)
+    print(n)
+    if n < 0 then
+      return "negative"
+    elseif n == 0 then
+      return "zero"
+    else
+      return ("%d items"):format(n)
+    end
+  end
+  local config = {name = "demo", ["max size"] = 10, sizes = {1, 2, 3}}
+  do
+    local total = area(2, 3)
+    config.total = total
+    if total ~= 0 and not (total % 2 == 1) then
+      print("even")
+    end
+  end
+  print(("lua"):upper(), config.name, (function() if area(1, 1) > 0 then return "yes" else return false end end)())
+  table.sort(config.sizes, function(a, b) return a > b end)
+end"#
+        );
+        assert_eq!(
+            Sexp::string("\u{1}2\u{7f}").to_lua_string(),
+            r#""\0012\127""#
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// Whether a rule's element is a recipe line rather than a prerequisite:
+/// strings and lists are commands, and so are atoms starting with one of
+/// make's recipe prefixes `@` (don't echo), `-` (ignore errors) or `+`.
+fn is_recipe(sexp: &Sexp) -> bool {
+    match sexp {
+        Sexp::Atom(atom) => atom.starts_with(['"', '@', '-', '+']),
+        _ => true,
+    }
+}
+
+fn recipe(sexp: &Sexp) -> String {
+    let line = match sexp {
+        Sexp::Atom(atom) => unquoted(atom),
+        _ => {
+            let words: Vec<String> = sexp.items().iter().map(Sexp::to_compact_string).collect();
+            words.join(" ")
+        }
+    };
+    // A recipe line can only go on after a backslash.
+    format!("\t{}\n", line.replace('\n', " \\\n\t"))
+}
+
+fn rule(target: &str, elements: &[Sexp]) -> String {
+    let (recipes, prerequisites): (Vec<&Sexp>, Vec<&Sexp>) =
+        elements.iter().partition(|element| is_recipe(element));
+    let mut rule = format!("{}:", unquoted(target));
+    for prerequisite in prerequisites {
+        rule.push(' ');
+        rule.push_str(&prerequisite.to_compact_string());
+    }
+    rule.push('\n');
+    rule.extend(recipes.into_iter().map(recipe));
+    rule
+}
+
+impl Sexp {
+    /// The tree as GNU Make rules: `(app main.o "cc -o $@ $^")` becomes
+    /// `app: main.o` with the recipe `cc -o $@ $^` on the next line. Atoms
+    /// after the target are prerequisites, and strings, lists (whose
+    /// elements are joined with spaces) and atoms starting with `@`, `-` or
+    /// `+` are recipe lines, in order. A list of rules is written as all of
+    /// them, separated by blank lines.
+    pub fn to_makefile_string(&self) -> String {
+        match self.items().split_first() {
+            Some((Sexp::Atom(target), elements)) => rule(target, elements),
+            _ => {
+                let rules: Vec<String> =
+                    self.items().iter().map(Sexp::to_makefile_string).collect();
+                rules.join("\n")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_makefile_string() {
+        let sexp = Sexp::of_str(
+            r#"((.PHONY all clean)
+                (all app)
+                (app main.o util.o (cc -o $@ $^) @echo-built)
+                (clean "-rm -f app *.o" "@echo \"done\"\nls"))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_makefile_string(),
+            ".PHONY: all clean\n\
+             \n\
+             all: app\n\
+             \n\
+             app: main.o util.o\n\tcc -o $@ $^\n\t@echo-built\n\
+             \n\
+             clean:\n\t-rm -f app *.o\n\t@echo \"done\" \\\n\tls\n"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Renderings of a tree in other languages' syntax, one module per target.
+//! They share the helpers below for reading atoms and quoting text.
+
+use crate::Sexp;
+
+#[cfg(feature = "ansible")]
+mod ansible;
+#[cfg(feature = "apache")]
+mod apache;
+#[cfg(feature = "arc")]
+mod arc;
+mod asciidoc;
+#[cfg(any(feature = "bash", feature = "zsh"))]
+mod bash;
+#[cfg(feature = "ci")]
+mod circleci;
+#[cfg(feature = "cmake")]
+mod cmake;
+#[cfg(feature = "cpp")]
+mod cpp;
+mod csv;
+#[cfg(feature = "cue")]
+mod cue;
+#[cfg(feature = "codegen")]
+mod derive;
+#[cfg(feature = "dhall")]
+mod dhall;
+mod discord;
+#[cfg(feature = "docker")]
+mod dockerfile;
+mod edn;
+mod elisp;
+mod elixir;
+mod erlang;
+#[cfg(feature = "fennel")]
+mod fennel;
+#[cfg(feature = "fish")]
+mod fish;
+#[cfg(feature = "ci")]
+mod github;
+#[cfg(feature = "ci")]
+mod gitlab;
+#[cfg(feature = "go-lang")]
+mod go;
+mod grammar;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "guile")]
+mod guile;
+mod haskell;
+#[cfg(feature = "hy")]
+mod hy;
+#[cfg(any(
+    feature = "cpp",
+    feature = "fennel",
+    feature = "go-lang",
+    feature = "java",
+    feature = "julia",
+    feature = "kotlin",
+    feature = "lua",
+    feature = "php",
+    feature = "r-lang",
+    feature = "ruby",
+    feature = "scala",
+    feature = "swift",
+    feature = "typescript"
+))]
+mod infix;
+#[cfg(feature = "janet")]
+mod janet;
+#[cfg(feature = "java")]
+mod java;
+#[cfg(feature = "jsonnet")]
+mod jsonnet;
+#[cfg(feature = "julia")]
+mod julia;
+#[cfg(feature = "kotlin")]
+mod kotlin;
+#[cfg(feature = "k8s")]
+mod kubernetes;
+mod latex;
+#[cfg(any(
+    feature = "arc",
+    feature = "fennel",
+    feature = "hy",
+    feature = "janet",
+    feature = "pico"
+))]
+mod lisp;
+#[cfg(feature = "codegen")]
+mod logos;
+// Fennel writes its strings as Lua does.
+#[cfg(any(feature = "fennel", feature = "lua"))]
+#[cfg_attr(not(feature = "lua"), allow(dead_code))]
+mod lua;
+#[cfg(feature = "make")]
+mod make;
+#[cfg(feature = "nginx")]
+mod nginx;
+#[cfg(feature = "nix")]
+mod nix;
+mod ocaml;
+#[cfg(feature = "openapi")]
+mod openapi;
+mod org;
+#[cfg(feature = "php")]
+mod php;
+#[cfg(feature = "pico")]
+mod pico;
+#[cfg(feature = "pkl")]
+mod pkl;
+#[cfg(feature = "powershell")]
+mod powershell;
+mod python;
+#[cfg(feature = "r-lang")]
+mod r;
+#[cfg(feature = "regex-gen")]
+mod regex;
+mod rst;
+#[cfg(feature = "ruby")]
+mod ruby;
+#[cfg(feature = "scala")]
+mod scala;
+mod slack;
+#[cfg(feature = "sql")]
+mod sql;
+#[cfg(feature = "starlark")]
+mod starlark;
+#[cfg(feature = "swift")]
+mod swift;
+#[cfg(feature = "systemd")]
+mod systemd;
+mod table;
+#[cfg(feature = "terraform")]
+mod terraform;
+#[cfg(feature = "typescript")]
+mod typescript;
+mod wasm;
+mod wiki;
+#[cfg(any(feature = "ansible", feature = "ci", feature = "k8s"))]
+mod yaml;
+#[cfg(feature = "zsh")]
+mod zsh;
+
+pub use org::OrgStyle;
+
+/// What an atom stands for, once string atoms are unquoted.
+enum Atom<'a> {
+    Number(&'a str),
+    String(String),
+    Symbol(&'a str),
+}
+
+fn atom(text: &str) -> Atom<'_> {
+    if text.starts_with('"') {
+        Atom::String(unquoted(text))
+    } else if is_number(text) {
+        Atom::Number(text)
+    } else {
+        Atom::Symbol(text)
+    }
+}
+
+/// The text of an atom, with string atoms unquoted.
+fn unquoted(atom: &str) -> String {
+    Sexp::Atom(atom.to_string()).as_text().unwrap()
+}
+
+/// Walks the tree as an outline, calling `item` with the text and depth of
+/// every entry: a list is an entry named after its head with its elements
+/// one level deeper, and any other atom a leaf entry. A list without a head
+/// has no entry of its own, its elements belonging to the enclosing one.
+/// Line breaks in atoms become spaces, since outline entries are one line.
+fn outline(sexp: &Sexp, depth: usize, item: &mut dyn FnMut(&str, usize)) {
+    let mut entry = |atom: &str, depth| {
+        let text = unquoted(atom);
+        let lines: Vec<&str> = text.lines().collect();
+        item(&lines.join(" "), depth)
+    };
+    match sexp {
+        Sexp::Nil => {}
+        Sexp::Atom(atom) => entry(atom, depth),
+        Sexp::List(_) => match sexp.items().split_first() {
+            Some((Sexp::Atom(head), children)) => {
+                entry(head, depth);
+                for child in children {
+                    outline(child, depth + 1, item);
+                }
+            }
+            _ => {
+                for child in sexp.items() {
+                    outline(child, depth, item);
+                }
+            }
+        },
+    }
+}
+
+/// The elements of a list read as the fields of a record, when every one
+/// of them is a list headed by a distinct symbol or string, like
+/// `((name core) (deps a b))`. A field's value is the list of its elements,
+/// and so a nested record when they're fields themselves, as in
+/// `(labels (app web))`, or else its only element if it has just one.
+#[cfg(any(
+    feature = "ansible",
+    feature = "ci",
+    feature = "cue",
+    feature = "dhall",
+    feature = "jsonnet",
+    feature = "k8s",
+    feature = "nix",
+    feature = "pkl"
+))]
+fn fields(sexp: &Sexp) -> Option<Vec<(String, Sexp)>> {
+    let mut record: Vec<(String, Sexp)> = vec![];
+    for item in sexp.items() {
+        let (key, values) = match item.items().split_first() {
+            Some((Sexp::Atom(key), values)) if !is_number(key) => (unquoted(key), values),
+            _ => return None,
+        };
+        if record.iter().any(|(seen, _)| *seen == key) {
+            return None;
+        }
+        let list = Sexp::list(values.to_vec());
+        let value = match values {
+            [value] if fields(&list).is_none() => value.clone(),
+            _ => list,
+        };
+        record.push((key, value));
+    }
+    if record.is_empty() {
+        None
+    } else {
+        Some(record)
+    }
+}
+
+/// Decimal integers and floats, with an optional sign and exponent.
+fn is_number(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    unsigned.starts_with(|c: char| c.is_ascii_digit())
+        && unsigned
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'))
+        && text.parse::<f64>().is_ok()
+}
+
+/// ASCII letters, digits and `_`, not starting with a digit.
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `text` between `quote`s, with backslash escapes for the quote, the
+/// backslash itself and control characters, in the JSON style (`\u001b`)
+/// that most C-like languages also accept.
+fn quoted(text: &str, quote: char) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push(quote);
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c == quote => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push(quote);
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_number() {
+        assert!(is_number("42"));
+        assert!(is_number("-1.5e3"));
+        assert!(!is_number("inf"));
+        assert!(!is_number("1-2"));
+        assert!(!is_number("-"));
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "ansible",
+        feature = "ci",
+        feature = "cue",
+        feature = "dhall",
+        feature = "jsonnet",
+        feature = "k8s",
+        feature = "nix",
+        feature = "pkl"
+    ))]
+    fn test_fields() {
+        let record = Sexp::of_str("((name core) (deps a b) (flags) (labels (app web)))").unwrap();
+        let record = fields(&record).unwrap();
+        let names: Vec<&str> = record.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["name", "deps", "flags", "labels"]);
+        assert_eq!(record[0].1, Sexp::atom("core"));
+        assert_eq!(record[1].1.to_string(), "(a b)");
+        assert_eq!(record[3].1.to_string(), "((app web))");
+        assert!(fields(&Sexp::of_str("((a 1) (a 2))").unwrap()).is_none());
+        assert!(fields(&Sexp::of_str("((a 1) b)").unwrap()).is_none());
+        assert!(fields(&Sexp::of_str("((1 a))").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_quoted() {
+        assert_eq!(quoted("a \"b\"\n\\", '"'), r#""a \"b\"\n\\""#);
+        assert_eq!(quoted("it's", '\''), r"'it\'s'");
+        assert_eq!(quoted("\u{1b}[0m", '"'), r#""\u001b[0m""#);
+    }
+}
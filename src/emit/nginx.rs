@@ -0,0 +1,184 @@
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// The directives that are always blocks, even of no directives, like the
+/// `events {}` every configuration needs. Not `server`, which is a block in
+/// `http` but a directive in `upstream`.
+const BLOCKS: &[&str] = &[
+    "events",
+    "geo",
+    "http",
+    "if",
+    "limit_except",
+    "location",
+    "mail",
+    "map",
+    "stream",
+    "types",
+    "upstream",
+];
+
+/// Whether `text` is a token nginx reads as it is: not empty, with nothing
+/// that ends it, starts a comment or quotes.
+fn is_plain(text: &str) -> bool {
+    !text.is_empty()
+        && !text
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, ';' | '{' | '}' | '#' | '"' | '\'' | '\\'))
+}
+
+fn token(sexp: &Sexp) -> String {
+    let text = match sexp {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => return number.to_string(),
+            Atom::String(text) => text,
+            Atom::Symbol(text) => text.to_string(),
+        },
+        _ => sexp.to_compact_string(),
+    };
+    if is_plain(&text) {
+        text
+    } else {
+        format!("\"{}\"", text.replace('\\', r"\\").replace('"', "\\\""))
+    }
+}
+
+fn tokens(items: &[Sexp]) -> String {
+    items.iter().map(token).collect::<Vec<_>>().join(" ")
+}
+
+/// `directives` one to a line, indented in a block.
+fn block(opening: &str, directives: &[Sexp]) -> String {
+    let body: Vec<String> = directives
+        .iter()
+        .map(directive)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect();
+    match body.as_slice() {
+        [] => format!("{} {{\n}}", opening),
+        _ => format!("{} {{\n{}\n}}", opening, body.join("\n")),
+    }
+}
+
+fn directive(sexp: &Sexp) -> String {
+    let (name, rest) = match sexp.items().split_first() {
+        Some((Sexp::Atom(name), rest)) => (name.as_str(), rest),
+        Some(_) => return directives(sexp.items()),
+        None => return String::new(),
+    };
+    // The arguments are the atoms before the block's directives.
+    let split = rest
+        .iter()
+        .position(|item| matches!(item, Sexp::List(_)))
+        .unwrap_or(rest.len());
+    let (args, body) = rest.split_at(split);
+    match (name, body) {
+        ("if", [condition, body @ ..]) if args.is_empty() => {
+            block(&format!("if ({})", tokens(condition.items())), body)
+        }
+        (_, []) if !BLOCKS.contains(&name) => match args {
+            [] => format!("{};", name),
+            _ => format!("{} {};", name, tokens(args)),
+        },
+        _ if args.is_empty() => block(name, body),
+        _ => block(&format!("{} {}", name, tokens(args)), body),
+    }
+}
+
+fn directives(forms: &[Sexp]) -> String {
+    forms.iter().map(directive).collect::<Vec<_>>().join("\n")
+}
+
+impl Sexp {
+    /// The tree as an nginx configuration: `(directive args...)` becomes
+    /// `directive arg1 arg2;`, and one with lists after its arguments, like
+    /// `(location /api (proxy_pass http://app))`, a block of those
+    /// directives, as `events`, `http`, `server` and other contexts always
+    /// are. `(if ($host = example.com) directives...)` puts its condition
+    /// in parentheses. A list of directives is a file of them in turn.
+    /// Arguments nginx would read as something else are double-quoted.
+    pub fn to_nginx_config(&self) -> String {
+        directive(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn test_to_nginx_config() {
+        let sexp = Sexp::of_str(
+            r#"((worker_processes 1)
+                (pid nginx.pid)
+                (error_log stderr)
+                (events (worker_connections 64))
+                (http
+                  (access_log off)
+                  (upstream app (server 127.0.0.1:3000) (keepalive 8))
+                  (server
+                    (listen 8080)
+                    (server_name example.com www.example.com)
+                    (add_header X-Greeting "hello; world")
+                    (location / (proxy_pass http://app) (proxy_set_header Host $host))
+                    (location ~ "\\.php$" (deny all))
+                    (if ($request_method = POST) (return 405)))))"#,
+        )
+        .unwrap();
+        let config = sexp.to_nginx_config();
+        assert_eq!(
+            config,
+            r#"worker_processes 1;
+pid nginx.pid;
+error_log stderr;
+events {
+    worker_connections 64;
+}
+http {
+    access_log off;
+    upstream app {
+        server 127.0.0.1:3000;
+        keepalive 8;
+    }
+    server {
+        listen 8080;
+        server_name example.com www.example.com;
+        add_header X-Greeting "hello; world";
+        location / {
+            proxy_pass http://app;
+            proxy_set_header Host $host;
+        }
+        location ~ "\\.php$" {
+            deny all;
+        }
+        if ($request_method = POST) {
+            return 405;
+        }
+    }
+}"#
+        );
+
+        // Check that nginx takes it, where there's one to do that.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        fs::write(&path, &config).unwrap();
+        let tested = Command::new("nginx")
+            .args(["-t", "-q", "-p"])
+            .arg(dir.path())
+            .arg("-c")
+            .arg(&path)
+            .output();
+        if let Ok(output) = tested {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+}
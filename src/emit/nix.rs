@@ -0,0 +1,107 @@
+use super::{atom, fields, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "assert", "else", "if", "in", "inherit", "let", "or", "rec", "then", "with",
+];
+
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            // `${` would start an interpolation.
+            '$' if chars.peek() == Some(&'{') => quoted.push_str("\\$"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn key(name: &str) -> String {
+    let identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''))
+        && !KEYWORDS.contains(&name);
+    if identifier {
+        name.to_string()
+    } else {
+        string(name)
+    }
+}
+
+fn number(text: &str) -> String {
+    if let Ok(integer) = text.parse::<i64>() {
+        return integer.to_string();
+    }
+    // Nix floats need a decimal point, even with an exponent.
+    let float = format!("{:?}", text.parse::<f64>().unwrap());
+    match float.find('e') {
+        Some(e) if !float[..e].contains('.') => format!("{}.0{}", &float[..e], &float[e..]),
+        _ => float,
+    }
+}
+
+impl Sexp {
+    /// The tree as a Nix expression: a list whose elements are all fields
+    /// (lists headed by distinct names, like `(name core)`) becomes an
+    /// attribute set, any other list a Nix list, numbers stay numbers,
+    /// `true`, `false` and `null` stay as they are, and every other atom
+    /// becomes a string.
+    pub fn to_nix_expr(&self) -> String {
+        match self {
+            Sexp::Nil => "null".to_string(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => self::number(number),
+                Atom::Symbol(name @ ("true" | "false" | "null")) => name.to_string(),
+                Atom::Symbol(name) => string(name),
+                Atom::String(text) => string(&text),
+            },
+            Sexp::List(_) => match fields(self) {
+                Some(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|(name, value)| format!("{} = {};", key(name), value.to_nix_expr()))
+                        .collect();
+                    format!("{{ {} }}", fields.join(" "))
+                }
+                None => {
+                    let items: Vec<String> = self
+                        .items()
+                        .iter()
+                        .map(|item| match item.to_nix_expr() {
+                            // A bare `-1` would be read as a subtraction.
+                            negative if negative.starts_with('-') => format!("({})", negative),
+                            item => item,
+                        })
+                        .collect();
+                    format!("[ {} ]", items.join(" ")).replace("[  ]", "[ ]")
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_nix_expr() {
+        let sexp = Sexp::of_str(
+            r#"((name core) (version 1.5) (deps a "${b}" -1) (enable true) ("with space" 2e3) (in ()))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_nix_expr(),
+            r#"{ name = "core"; version = 1.5; deps = [ "a" "\${b}" (-1) ]; enable = true; "with space" = 2000.0; "in" = [ ]; }"#
+        );
+    }
+}
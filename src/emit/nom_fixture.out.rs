@@ -0,0 +1,19 @@
+pub fn parse_expr(input: &str) -> nom::IResult<&str, &str> {
+    nom::combinator::recognize(nom::sequence::tuple((parse_term, nom::multi::many0(nom::sequence::tuple((nom::branch::alt((nom::bytes::complete::tag("+"), nom::bytes::complete::tag("-"))), parse_term))))))(input)
+}
+
+pub fn parse_term(input: &str) -> nom::IResult<&str, &str> {
+    nom::combinator::recognize(nom::sequence::tuple((parse_factor, nom::multi::many0(nom::sequence::tuple((nom::branch::alt((nom::bytes::complete::tag("*"), nom::bytes::complete::tag("/"))), parse_factor))))))(input)
+}
+
+pub fn parse_factor(input: &str) -> nom::IResult<&str, &str> {
+    nom::branch::alt((nom::combinator::recognize(nom::sequence::tuple((nom::bytes::complete::tag("("), parse_expr, nom::bytes::complete::tag(")")))), parse_number))(input)
+}
+
+pub fn parse_number(input: &str) -> nom::IResult<&str, &str> {
+    nom::combinator::recognize(nom::sequence::tuple((nom::combinator::opt(nom::bytes::complete::tag("-")), nom::multi::many1(nom::character::complete::satisfy(|c| ('0'..='9').contains(&c))), nom::combinator::not(nom::character::complete::satisfy(|c| ('a'..='z').contains(&c))))))(input)
+}
+
+pub fn parse_whitespace(input: &str) -> nom::IResult<&str, &str> {
+    nom::combinator::recognize(nom::multi::many0(nom::branch::alt((nom::bytes::complete::tag(" "), nom::bytes::complete::tag("\t")))))(input)
+}
@@ -0,0 +1,211 @@
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "and",
+    "as",
+    "assert",
+    "begin",
+    "class",
+    "constraint",
+    "do",
+    "done",
+    "downto",
+    "else",
+    "end",
+    "exception",
+    "external",
+    "false",
+    "for",
+    "fun",
+    "function",
+    "functor",
+    "if",
+    "in",
+    "include",
+    "inherit",
+    "initializer",
+    "lazy",
+    "let",
+    "match",
+    "method",
+    "module",
+    "mutable",
+    "new",
+    "nonrec",
+    "object",
+    "of",
+    "open",
+    "or",
+    "private",
+    "rec",
+    "sig",
+    "struct",
+    "then",
+    "to",
+    "true",
+    "try",
+    "type",
+    "val",
+    "virtual",
+    "when",
+    "while",
+    "with",
+];
+
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\{:03}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// `function_call` → `Function_call`, with anything OCaml doesn't allow in a
+// constructor name replaced by `_`.
+fn constructor(head: &str) -> String {
+    let mut name: String = head
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '\'' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, 'N');
+    }
+    name[..1].to_ascii_uppercase() + &name[1..]
+}
+
+// tree-sitter shows fields as a `name:` atom before the value.
+fn field(sexp: &Sexp) -> Option<&str> {
+    match sexp {
+        Sexp::Atom(text) => text.strip_suffix(':').filter(|name| is_identifier(name)),
+        _ => None,
+    }
+}
+
+// The sole argument of a constructor, in parentheses when it's a negative
+// number or a constructor applied to arguments of its own, so `Neg (Integer
+// 1)` doesn't read as `Neg` applied to two.
+fn argument(sexp: &Sexp) -> String {
+    let value = sexp.to_ocaml_string();
+    match sexp.items() {
+        [Sexp::Atom(head), _, ..] if !head.starts_with('"') => format!("({})", value),
+        _ => match sexp {
+            Sexp::Atom(text) if text.starts_with('-') => format!("({})", value),
+            _ => value,
+        },
+    }
+}
+
+impl Sexp {
+    /// The tree as an OCaml value. A list headed by a symbol is a
+    /// constructor application, `(binary_op a b)` becoming
+    /// `Binary_op (a, b)`; when every argument is a tree-sitter field, the
+    /// constructor gets an inline record, `Call { name = f; args = [] }`.
+    /// Other lists become OCaml lists. Numbers stay numbers, atoms that are
+    /// valid lowercase names become identifiers, and everything else becomes
+    /// a string literal.
+    pub fn to_ocaml_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::Symbol(name)
+                    if is_identifier(name)
+                        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                        && !KEYWORDS.contains(&name) =>
+                {
+                    name.to_string()
+                }
+                Atom::Symbol(name) => string(name),
+                Atom::String(text) => string(&text),
+            },
+            Sexp::List(_) => {
+                let items = self.items();
+                let head = match items.first() {
+                    Some(Sexp::Atom(head)) if !head.starts_with('"') => head,
+                    _ => {
+                        let items: Vec<String> = items.iter().map(Sexp::to_ocaml_string).collect();
+                        return format!("[{}]", items.join("; "));
+                    }
+                };
+                let args = &items[1..];
+                let fields: Option<Vec<String>> = args
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [name, value] => field(name).map(|name| {
+                            // Keywords get the usual trailing underscore.
+                            let suffix = if KEYWORDS.contains(&name) { "_" } else { "" };
+                            format!("{}{} = {}", name, suffix, value.to_ocaml_string())
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+                match fields {
+                    _ if args.is_empty() => constructor(head),
+                    Some(fields) => format!("{} {{ {} }}", constructor(head), fields.join("; ")),
+                    None if args.len() == 1 => {
+                        format!("{} {}", constructor(head), argument(&args[0]))
+                    }
+                    None => {
+                        let args: Vec<String> = args.iter().map(Sexp::to_ocaml_string).collect();
+                        format!("{} ({})", constructor(head), args.join(", "))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ocaml_string() {
+        let sexp =
+            Sexp::of_str(r#"(binary_op (integer 1) + (identifier "x") (neg -2) (unit))"#).unwrap();
+        assert_eq!(
+            sexp.to_ocaml_string(),
+            r#"Binary_op (Integer 1, "+", Identifier "x", Neg (-2), Unit)"#
+        );
+    }
+
+    #[test]
+    fn test_to_ocaml_string_records_and_lists() {
+        let sexp = Sexp::of_str("(call function: (identifier f) arguments: ((a) b))").unwrap();
+        assert_eq!(
+            sexp.to_ocaml_string(),
+            "Call { function_ = Identifier f; arguments = [A; b] }"
+        );
+    }
+
+    #[test]
+    fn test_to_ocaml_string_nested() {
+        let sexp = Sexp::of_str("(program (expression (term (neg (integer 1)))))").unwrap();
+        assert_eq!(
+            sexp.to_ocaml_string(),
+            "Program (Expression (Term (Neg (Integer 1))))"
+        );
+        let sexp = Sexp::of_str("(neg (call name: f) (unit))").unwrap();
+        assert_eq!(sexp.to_ocaml_string(), "Neg (Call { name = f }, Unit)");
+        let sexp = Sexp::of_str("(some (call name: f))").unwrap();
+        assert_eq!(sexp.to_ocaml_string(), "Some (Call { name = f })");
+        let sexp = Sexp::of_str("(some (unit))").unwrap();
+        assert_eq!(sexp.to_ocaml_string(), "Some Unit");
+    }
+}
@@ -0,0 +1,222 @@
+use super::{atom, unquoted, Atom};
+use crate::Sexp;
+use serde_json::{Map, Value};
+
+const VERSION: &str = "3.0.3";
+
+/// Fields that hold an array even with a single element.
+const ARRAYS: &[&str] = &[
+    "allOf",
+    "anyOf",
+    "enum",
+    "oneOf",
+    "parameters",
+    "required",
+    "security",
+    "servers",
+    "tags",
+];
+
+/// Fields whose value is a string even when it looks like a number.
+const STRINGS: &[&str] = &[
+    "description",
+    "format",
+    "name",
+    "openapi",
+    "operationId",
+    "summary",
+    "title",
+    "type",
+    "url",
+    "version",
+];
+
+fn key(field: &Sexp) -> Option<String> {
+    match field.items().first() {
+        Some(Sexp::Atom(head)) => Some(unquoted(head)),
+        _ => None,
+    }
+}
+
+/// Whether `items` are all fields like `(type string)` or `(200 ...)`.
+fn is_object(items: &[Sexp]) -> bool {
+    !items.is_empty() && items.iter().all(|item| key(item).is_some())
+}
+
+fn scalar(sexp: &Sexp, string: bool) -> Value {
+    let text = match sexp {
+        Sexp::Atom(text) => text,
+        _ => return Value::Null,
+    };
+    match atom(text) {
+        Atom::Number(number) if !string => {
+            serde_json::from_str(number).unwrap_or_else(|_| Value::String(number.to_string()))
+        }
+        Atom::Symbol("true") if !string => Value::Bool(true),
+        Atom::Symbol("false") if !string => Value::Bool(false),
+        Atom::Symbol("null") if !string => Value::Null,
+        Atom::Number(text) | Atom::Symbol(text) => Value::String(text.to_string()),
+        Atom::String(text) => Value::String(text),
+    }
+}
+
+fn item(sexp: &Sexp) -> Value {
+    match sexp {
+        Sexp::List(_) if is_object(sexp.items()) => object(sexp.items()),
+        Sexp::List(_) => sexp.items().iter().map(item).collect(),
+        _ => scalar(sexp, false),
+    }
+}
+
+/// The fields among `fields` as an object, a repeated field keeping its
+/// last value.
+fn object(fields: &[Sexp]) -> Value {
+    let mut object = Map::new();
+    for field in fields {
+        let key = match key(field) {
+            Some(key) => key,
+            None => continue,
+        };
+        let elements = &field.items()[1..];
+        let value = match elements {
+            // `(required true)` on a parameter, rather than a schema's list.
+            [flag @ Sexp::Atom(text)]
+                if key == "required" && (text == "true" || text == "false") =>
+            {
+                scalar(flag, false)
+            }
+            [Sexp::Atom(name)] if key == "$ref" => {
+                let name = unquoted(name);
+                if name.contains('/') {
+                    Value::String(name)
+                } else {
+                    Value::String(format!("#/components/schemas/{}", name))
+                }
+            }
+            _ if ARRAYS.contains(&key.as_str()) => elements
+                .iter()
+                .map(|element| match element {
+                    Sexp::Atom(_) => scalar(element, true),
+                    _ => item(element),
+                })
+                .collect(),
+            [value @ Sexp::Atom(_)] => scalar(value, STRINGS.contains(&key.as_str())),
+            _ if is_object(elements) => self::object(elements),
+            _ => elements.iter().map(item).collect(),
+        };
+        object.insert(key, value);
+    }
+    Value::Object(object)
+}
+
+impl Sexp {
+    /// An OpenAPI 3.0 document in JSON, from fields shaped like the
+    /// document itself: `(openapi (info (title Pets) (version 1.0))
+    /// (paths ...) (components ...))`. Fields become properties, with the
+    /// elements after a field's name an object when they're all fields
+    /// (response codes like `(200 ...)` included), a single value, or an
+    /// array, and fields like `parameters` and `required` always arrays. A
+    /// bare `($ref Pet)` points into `#/components/schemas`. Numbers and
+    /// booleans keep their types except in string fields like `version`.
+    /// The `openapi` version defaults to 3.0.3. Items that aren't fields
+    /// are skipped, and a repeated field keeps its last value; a missing
+    /// `info.title`, `info.version` or `paths` is left for a validator to
+    /// report.
+    pub fn to_openapi_json(&self) -> String {
+        let fields = match self.items().split_first() {
+            Some((Sexp::Atom(head), fields)) if head == "openapi" => fields,
+            _ => self.items(),
+        };
+        let mut document = match object(fields) {
+            Value::Object(document) => document,
+            _ => unreachable!(),
+        };
+        document
+            .entry("openapi")
+            .or_insert_with(|| Value::String(VERSION.to_string()));
+        // `openapi` goes first, as in most documents.
+        let mut ordered = Map::new();
+        ordered.insert("openapi".to_string(), document.remove("openapi").unwrap());
+        ordered.extend(document);
+        // A Value always serializes.
+        serde_json::to_string_pretty(&Value::Object(ordered)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_openapi_json() {
+        let sexp = Sexp::of_str(
+            r#"(openapi
+                 (info (title "Pet store") (version 1.0))
+                 (paths
+                   ("/pets/{id}"
+                     (get
+                       (operationId getPet)
+                       (tags pets)
+                       (parameters ((name id) (in path) (required true) (schema (type integer))))
+                       (responses
+                         (200 (description OK)
+                              (content (application/json (schema ($ref Pet)))))
+                         (404 (description "Not found"))))))
+                 (components
+                   (schemas
+                     (Pet (type object)
+                          (required id)
+                          (properties (id (type integer) (minimum 1)) (name (type string)))))))"#,
+        )
+        .unwrap();
+        let document: Value = serde_json::from_str(&sexp.to_openapi_json()).unwrap();
+        assert_eq!(
+            document,
+            json!({
+                "openapi": "3.0.3",
+                "info": {"title": "Pet store", "version": "1.0"},
+                "paths": {
+                    "/pets/{id}": {
+                        "get": {
+                            "operationId": "getPet",
+                            "tags": ["pets"],
+                            "parameters": [
+                                {"name": "id", "in": "path", "required": true, "schema": {"type": "integer"}}
+                            ],
+                            "responses": {
+                                "200": {
+                                    "description": "OK",
+                                    "content": {
+                                        "application/json": {
+                                            "schema": {"$ref": "#/components/schemas/Pet"}
+                                        }
+                                    }
+                                },
+                                "404": {"description": "Not found"}
+                            }
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "Pet": {
+                            "type": "object",
+                            "required": ["id"],
+                            "properties": {
+                                "id": {"type": "integer", "minimum": 1},
+                                "name": {"type": "string"}
+                            }
+                        }
+                    }
+                }
+            })
+        );
+        let malformed = Sexp::of_str("(openapi (info (title x) (title y)) z)").unwrap();
+        let document: Value = serde_json::from_str(&malformed.to_openapi_json()).unwrap();
+        assert_eq!(
+            document,
+            json!({"openapi": "3.0.3", "info": {"title": "y"}})
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// How `to_org_mode` lays a tree out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrgStyle {
+    /// An outline with a headline for every list, named after its head,
+    /// and the list's atoms as the paragraph under it.
+    Headlines,
+    /// The pretty-printed tree in a `#+begin_src lisp` block.
+    SourceBlock,
+}
+
+fn headlines(sexp: &Sexp, depth: usize, org: &mut String) {
+    let items = sexp.items();
+    let (title, children) = match items.split_first() {
+        Some((Sexp::Atom(head), children)) => (head, children),
+        // A list without a head has no headline of its own; its elements
+        // belong to the enclosing one.
+        _ => {
+            for item in items {
+                headlines(item, depth, org);
+            }
+            return;
+        }
+    };
+    org.push_str(&format!("{} {}\n", "*".repeat(depth), unquoted(title)));
+
+    let words: Vec<String> = children
+        .iter()
+        .filter_map(|child| match child {
+            Sexp::Atom(atom) => Some(unquoted(atom)),
+            _ => None,
+        })
+        .collect();
+    for line in words.join(" ").lines() {
+        // Keep paragraph lines from being read as headlines or keywords.
+        if line.starts_with('*') || line.starts_with('#') {
+            org.push(' ');
+        }
+        org.push_str(line);
+        org.push('\n');
+    }
+    for child in children {
+        if let Sexp::List(_) = child {
+            headlines(child, depth + 1, org);
+        }
+    }
+}
+
+impl Sexp {
+    pub fn to_org_mode(&self, style: OrgStyle) -> String {
+        match style {
+            OrgStyle::Headlines => match self {
+                Sexp::Atom(atom) => format!("{}\n", unquoted(atom)),
+                _ => {
+                    let mut org = String::new();
+                    headlines(self, 1, &mut org);
+                    org
+                }
+            },
+            OrgStyle::SourceBlock => {
+                let mut org = String::from("#+begin_src lisp\n");
+                for line in self.to_string().lines() {
+                    // Org's own escape for lines it would otherwise interpret.
+                    if line.starts_with('*') || line.starts_with("#+") {
+                        org.push(',');
+                    }
+                    org.push_str(line);
+                    org.push('\n');
+                }
+                org.push_str("#+end_src\n");
+                org
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_org_mode_headlines() {
+        let sexp = Sexp::of_str(
+            r#"(project "Plan for Q3" (library (name core) (modules a b)) (notes "*bold* start" ((x 1) (y 2))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_org_mode(OrgStyle::Headlines),
+            include_str!("./org_mode.org")
+        );
+    }
+
+    #[test]
+    fn test_to_org_mode_source_block() {
+        let sexp = Sexp::of_str("(a \"x\n* not a headline\" b)").unwrap();
+        assert_eq!(
+            sexp.to_org_mode(OrgStyle::SourceBlock),
+            "#+begin_src lisp\n(a \"x\n,* not a headline\" b)\n#+end_src\n"
+        );
+    }
+}
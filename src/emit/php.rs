@@ -0,0 +1,382 @@
+use super::infix::{arguments, indented, write, Syntax};
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "and",
+    "array",
+    "as",
+    "break",
+    "callable",
+    "case",
+    "catch",
+    "class",
+    "clone",
+    "const",
+    "continue",
+    "declare",
+    "default",
+    "do",
+    "echo",
+    "else",
+    "elseif",
+    "empty",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "fn",
+    "for",
+    "foreach",
+    "function",
+    "global",
+    "goto",
+    "if",
+    "implements",
+    "include",
+    "instanceof",
+    "insteadof",
+    "interface",
+    "isset",
+    "list",
+    "match",
+    "namespace",
+    "new",
+    "or",
+    "print",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "require",
+    "return",
+    "static",
+    "switch",
+    "throw",
+    "trait",
+    "try",
+    "unset",
+    "use",
+    "var",
+    "while",
+    "xor",
+    "yield",
+];
+
+/// The heads of the shared forms that PHP would otherwise take for the
+/// names of functions.
+const SHARED: &[&str] = &["lambda", "modulo", "not", "table"];
+
+fn is_name(name: &str) -> bool {
+    is_identifier(name) && !KEYWORDS.contains(&name)
+}
+
+/// Whether `name` is a name, or a static member like `DateTime::createFromFormat`.
+fn is_path(name: &str) -> bool {
+    name.split("::").all(is_name)
+}
+
+/// Whether `name` reads as a constant, like `PHP_EOL`, rather than a
+/// variable.
+fn is_constant(name: &str) -> bool {
+    name.contains(|c: char| c.is_ascii_uppercase()) && !name.contains(|c: char| c.is_lowercase())
+}
+
+/// `text` as a PHP string. A double-quoted string interpolates a `$`, so
+/// that's escaped, and control characters are `\x` escapes, padded to the
+/// two digits one reads.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_ascii_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "null".to_string(),
+        Atom::Symbol(name) if is_name(name) && is_constant(name) => name.to_string(),
+        Atom::Symbol(name) if is_name(name) => format!("${}", name),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` in braces after `opening`, as in `if ($ok) { ... }`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}{{\n{}\n}}", opening, indented(forms, "    "))
+}
+
+/// Whether `form` is written as a statement that has no value to return.
+fn is_statement(form: &Sexp) -> bool {
+    match form.items() {
+        [Sexp::Atom(head), _, _] if head == "if" => true,
+        [Sexp::Atom(head), ..] => matches!(
+            head.as_str(),
+            "define" | "set!" | "return" | "echo" | "begin" | "let" | "let*" | "cond"
+        ),
+        _ => false,
+    }
+}
+
+/// The statements of a body, with the last one's value returned if
+/// `returns`, from within it where it's a block.
+fn statements(forms: &[Sexp], returns: bool) -> Vec<String> {
+    let mut lines = vec![];
+    for (index, form) in forms.iter().enumerate() {
+        let last = returns && index == forms.len() - 1;
+        let block = match form.items().split_first() {
+            Some((Sexp::Atom(head), rest)) => statement(head, rest, last),
+            _ => None,
+        };
+        lines.push(match block {
+            Some(block) => block,
+            None if last && !is_statement(form) => format!("return {};", write(form, &PHP)),
+            None => format!("{};", write(form, &PHP)),
+        });
+    }
+    lines
+}
+
+/// The forms written as statements, returning their last value if
+/// `returns`. PHP's variables are scoped to their function, so `begin` is
+/// its statements in turn and `let` assignments before its body.
+fn statement(head: &str, rest: &[Sexp], returns: bool) -> Option<String> {
+    match (head, rest) {
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [Sexp::Atom(name), params @ ..] if is_name(name) => {
+                let params: Vec<String> = params.iter().map(|param| write(param, &PHP)).collect();
+                Some(format!(
+                    "function {}({})\n{}",
+                    name,
+                    params.join(", "),
+                    block("", &statements(body, true))
+                ))
+            }
+            _ => None,
+        },
+        ("begin", [_, ..]) => Some(statements(rest, returns).join("\n")),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut lines = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => {
+                        lines.push(format!("{} = {};", write(name, &PHP), write(init, &PHP)))
+                    }
+                    _ => return None,
+                }
+            }
+            lines.extend(statements(body, returns));
+            Some(lines.join("\n"))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if ({}) ", write(test, &PHP)),
+            &statements(std::slice::from_ref(consequent), returns),
+        )),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let opening = match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => "else ".to_string(),
+                    _ if index == 0 => format!("if ({}) ", write(test, &PHP)),
+                    _ => format!("elseif ({}) ", write(test, &PHP)),
+                };
+                branches.push(block(&opening, &statements(consequent, returns)));
+            }
+            Some(branches.join(" "))
+        }
+        _ => None,
+    }
+}
+
+fn is_conditional(sexp: &Sexp) -> bool {
+    matches!(sexp.items(), [Sexp::Atom(head), _, _, _] if head == "if")
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    match (head, rest) {
+        ("define", [name @ Sexp::Atom(_), init]) | ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &PHP), write(init, &PHP)))
+        }
+        ("return", [value]) => Some(format!("return {}", write(value, &PHP))),
+        // `echo` is a statement rather than a function, and takes a list.
+        ("echo", [_, ..]) => {
+            let args: Vec<String> = rest.iter().map(|arg| write(arg, &PHP)).collect();
+            Some(format!("echo {}", args.join(", ")))
+        }
+        ("new", [Sexp::Atom(class), args @ ..]) if is_path(class) => {
+            Some(format!("new {}({})", class, arguments(args, &PHP)))
+        }
+        // Before PHP 8.4, a member of a new object needs it in parentheses.
+        (_, [object, args @ ..])
+            if head.len() > 1 && head.starts_with('.') && object.head() == Some("new") =>
+        {
+            let object = write(object, &PHP);
+            Some(match head.strip_prefix(".-") {
+                Some(field) if args.is_empty() => format!("({})->{}", object, field),
+                _ => format!("({})->{}({})", object, &head[1..], arguments(args, &PHP)),
+            })
+        }
+        // PHP 8 won't read a conditional nested in another without
+        // parentheses.
+        ("if", [test, consequent, alternative])
+            if is_conditional(consequent) || is_conditional(alternative) =>
+        {
+            let arm = |arm: &Sexp| match write(arm, &PHP) {
+                text if is_conditional(arm) => format!("({})", text),
+                text => text,
+            };
+            Some(format!(
+                "({} ? {} : {})",
+                write(test, &PHP),
+                arm(consequent),
+                arm(alternative)
+            ))
+        }
+        // A function's name is written bare, where a variable's would take
+        // a `$`.
+        _ => statement(head, rest, false).or_else(|| {
+            if is_path(head) && !SHARED.contains(&head) {
+                Some(format!("{}({})", head, arguments(rest, &PHP)))
+            } else {
+                None
+            }
+        }),
+    }
+}
+
+/// An arrow function for a single form, and otherwise an anonymous
+/// function returning the last, which unlike the arrow doesn't capture the
+/// variables around it.
+fn lambda(params: &[String], body: &[String]) -> String {
+    match body {
+        [single] => format!("fn({}) => {}", params.join(", "), single),
+        _ => {
+            let mut lines: Vec<String> = body.iter().map(|form| format!("{};", form)).collect();
+            if let Some(last) = lines.last_mut() {
+                *last = format!("return {}", last);
+            }
+            block(&format!("function ({}) ", params.join(", ")), &lines)
+        }
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{} => {}", string(key), value))
+        .collect();
+    format!("[{}]", fields.join(", "))
+}
+
+const PHP: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: "->",
+    field: "->",
+    named: Some(": "),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("[", "]"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as PHP, reading it as Scheme-style code: lists become
+    /// `fn(args)` calls, `(.method object args...)` `$object->method(args)`,
+    /// `(.-field object)` `$object->field` and `(new Point 1 2)` a
+    /// constructor call. Names become `$` variables, except those written
+    /// in capitals, which are kept as constants like `PHP_EOL`. `(array
+    /// ...)` becomes `[a, b]` and `(table (key value)...)` `["key" =>
+    /// value]`. `(define (f x) ...)` becomes a function returning its last
+    /// form, `define` and `set!` of a variable assignments, `begin` and
+    /// `let` their statements in turn, and `if` without an alternative and
+    /// `cond` `if` statements. `lambda` becomes `fn($x) => ...`, and `:name
+    /// value` in a call a named argument.
+    pub fn to_php_string(&self) -> String {
+        write(self, &PHP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_php_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (area w h) (* w h))
+                 (define (describe n)
+                   (echo n PHP_EOL)
+                   (cond ((< n 0) "negative") ((== n 0) "zero") (else "$n big")))
+                 (define config (table (name "demo") ("max size" 10) (sizes (array 1 2 3))))
+                 (let ((total (area 2 3)))
+                   (if (and (!= total 0) (not (== (% total 2) 1))) (echo "even\n")))
+                 (define doubled (array_map (lambda (x) (* x 2)) (.-sizes box)))
+                 (usort doubled (lambda (a b) (define d (- b a)) d))
+                 (echo (.format (new DateTime "now") "Y") (if (> n 0) "yes" (if (< n 0) "no" "zero")))
+                 (str_pad "7" 3 :pad_string "0"))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_php_string(),
+            r#"function area($w, $h)
+{
+    return $w * $h;
+}
+function describe($n)
+{
+    echo $n, PHP_EOL;
+    if ($n < 0) {
+        return "negative";
+    } elseif ($n == 0) {
+        return "zero";
+    } else {
+        return "\$n big";
+    }
+}
+$config = ["name" => "demo", "max size" => 10, "sizes" => [1, 2, 3]];
+$total = area(2, 3);
+if ($total != 0 && !($total % 2 == 1)) {
+    echo "even\n";
+}
+$doubled = array_map(fn($x) => $x * 2, $box->sizes);
+usort($doubled, function ($a, $b) {
+    $d = $b - $a;
+    return $d;
+});
+echo (new DateTime("now"))->format("Y"), ($n > 0 ? "yes" : ($n < 0 ? "no" : "zero"));
+str_pad("7", 3, pad_string: "0");"#
+        );
+        assert_eq!(
+            Sexp::string("\u{1}2\u{7f}").to_php_string(),
+            r#""\x012\x7f""#
+        );
+    }
+}
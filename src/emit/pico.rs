@@ -0,0 +1,154 @@
+use super::lisp::{write, Dialect};
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// `text` as a PicoLisp transient symbol, its string type. `^` introduces
+/// a control character there, as in `^J` for a line break, so a literal one
+/// is escaped like `"` and `\`.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' | '^' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\u{7f}' => quoted.push_str("^?"),
+            c if (c as u32) < 0x20 => {
+                quoted.push('^');
+                quoted.push(char::from(c as u8 + 0x40));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Whether PicoLisp reads `name` back as this internal symbol: no
+/// delimiters, and no leading digit, which would start a number.
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    let second = chars.next();
+    let numeric = first.is_ascii_digit()
+        || ("+-".contains(first) && second.is_some_and(|c| c.is_ascii_digit()));
+    !numeric
+        && name
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && !"()[]{}\"'`,~#\\".contains(c))
+}
+
+/// PicoLisp's booleans are `T` and `NIL`, so Scheme's are written as those.
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "T".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") | Atom::Symbol("nil") => "NIL".to_string(),
+        Atom::Symbol(name) if is_symbol(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+fn key(key: &Sexp) -> String {
+    format!("'{}", write(key, &PICO))
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &PICO)).collect() };
+    match (head, rest) {
+        // A function is a list of its parameters and body, quoted.
+        ("lambda", [params @ Sexp::List(_), body @ ..]) => {
+            let mut function = vec![format!("({})", all(params.items()).join(" "))];
+            function.extend(all(body));
+            Some(format!("'({})", function.join(" ")))
+        }
+        ("quote", [quoted]) => Some(format!("'{}", write(quoted, &PICO))),
+        // `cond` keeps Scheme's clauses, with `T` as the catch-all.
+        ("cond", [_, ..]) => {
+            let mut clauses = vec![];
+            for clause in rest {
+                let (test, consequent) = clause.items().split_first()?;
+                let test = match test {
+                    Sexp::Atom(test) if test == "else" => "T".to_string(),
+                    _ => write(test, &PICO),
+                };
+                let mut clause = vec![test];
+                clause.extend(all(consequent));
+                clauses.push(format!("({})", clause.join(" ")));
+            }
+            Some(format!("(cond {})", clauses.join(" ")))
+        }
+        // There's no table syntax, so a table is an association list.
+        ("table", _) => {
+            let mut pairs = vec![];
+            for field in rest {
+                match field.items() {
+                    [name, init] => {
+                        pairs.push(format!("(cons {} {})", key(name), write(init, &PICO)))
+                    }
+                    _ => return None,
+                }
+            }
+            Some(format!("(list {})", pairs.join(" ")))
+        }
+        _ => None,
+    }
+}
+
+const PICO: Dialect = Dialect {
+    atom: atom_text,
+    key,
+    forms,
+    params: ("(", ")"),
+    defun: "de",
+    define: "setq",
+    set: "setq",
+    begin: "prog",
+    bindings: "let",
+    cond: "cond",
+    table: ("(list ", ")"),
+    array: ("(list ", ")"),
+};
+
+impl Sexp {
+    /// The tree as PicoLisp, reading it as Scheme-style code: `(define (f
+    /// x) ...)` becomes `(de f (x) ...)`, `define` and `set!` of a variable
+    /// `setq`, `lambda` a quoted `'((x) ...)` function, `let` bindings a
+    /// flat `(let (x 1 y 2) ...)`, `begin` `prog`, and `cond` keeps its
+    /// clauses with `T` for `else`. `(array ...)` becomes `(list ...)` and
+    /// `(table (key value)...)` an association list. Booleans become `T`
+    /// and `NIL`, and strings transient symbols.
+    pub fn to_pico_lisp_string(&self) -> String {
+        write(self, &PICO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pico_lisp_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (square x) (* x x))
+                 (define limit 10)
+                 (let ((a 1) (b 2)) (set! limit (+ a b)))
+                 (mapcar (lambda (n) (square n)) (array 1 2 3))
+                 (cond ((> limit 5) (prinl "big") true) (else false))
+                 (define config (table (name "a^b") (port 80)))
+                 (prinl "line\nbreak" nil))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_pico_lisp_string(),
+            r#"(prog (de square (x) (* x x)) (setq limit 10) (let (a 1 b 2) (setq limit (+ a b))) (mapcar '((n) (square n)) (list 1 2 3)) (cond ((> limit 5) (prinl "big") T) (T NIL)) (setq config (list (cons 'name "a\^b") (cons 'port 80))) (prinl "line^Jbreak" NIL))"#
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use super::{fields, unquoted};
+use crate::Sexp;
+use anyhow::{anyhow, Error};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "amends",
+    "as",
+    "case",
+    "class",
+    "const",
+    "delete",
+    "else",
+    "extends",
+    "external",
+    "false",
+    "fixed",
+    "for",
+    "function",
+    "hidden",
+    "if",
+    "import",
+    "in",
+    "is",
+    "let",
+    "local",
+    "module",
+    "new",
+    "nothing",
+    "null",
+    "open",
+    "out",
+    "outer",
+    "override",
+    "protected",
+    "record",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "trace",
+    "true",
+    "typealias",
+    "unknown",
+    "vararg",
+    "when",
+];
+
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !KEYWORDS.contains(&name)
+}
+
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            // Also keeps `\(` from starting an interpolation.
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn value(sexp: &Sexp, indent: &str) -> String {
+    let inner = format!("{}  ", indent);
+    let members: Vec<String> = match sexp {
+        Sexp::Nil => return "null".to_string(),
+        Sexp::Atom(atom) if atom == "null" => return "null".to_string(),
+        Sexp::Atom(atom) => return string(&unquoted(atom)),
+        Sexp::List(_) => match fields(sexp) {
+            Some(fields) => {
+                let members = fields.iter().map(|(name, field)| {
+                    let name = if is_name(name) {
+                        name.clone()
+                    } else {
+                        format!("`{}`", name)
+                    };
+                    format!("{}{} = {}\n", inner, name, value(field, &inner))
+                });
+                return format!(
+                    "new Dynamic {{\n{}{}}}",
+                    members.collect::<String>(),
+                    indent
+                );
+            }
+            None => sexp
+                .items()
+                .iter()
+                .map(|item| format!("{}{}\n", inner, value(item, &inner)))
+                .collect(),
+        },
+    };
+    if members.is_empty() {
+        "new Listing {}".to_string()
+    } else {
+        format!("new Listing {{\n{}{}}}", members.concat(), indent)
+    }
+}
+
+impl Sexp {
+    /// The tree as a Pkl expression: a list whose elements are all fields
+    /// (lists headed by distinct names, like `(name core)`) becomes a
+    /// `Dynamic` object with a property for each, any other list a
+    /// `Listing`, the atom `null` and `Nil` `null`, and every other atom a
+    /// string.
+    pub fn to_pkl(&self) -> String {
+        value(self, "")
+    }
+
+    /// Reads back the output of `to_pkl`: `Listing`s become lists, and
+    /// objects lists of fields, with a property holding a `Listing` or an
+    /// object becoming a field with its elements, like `(deps a b)`.
+    /// Strings become atoms (string atoms when the text isn't a bare atom),
+    /// and `null` the atom `null`.
+    /// This inverts `to_pkl` except for a field whose only element is a
+    /// list that isn't itself a field: `(x (1 2))` comes back as `(x 1 2)`.
+    pub fn of_pkl(input: &str) -> Result<Sexp, Error> {
+        let mut reader = Reader {
+            input,
+            chars: input.char_indices().peekable(),
+        };
+        let sexp = reader.value()?;
+        reader.skip_whitespace();
+        match reader.chars.peek() {
+            None => Ok(sexp),
+            Some(&(at, _)) => Err(anyhow!("Unexpected input at byte {}", at)),
+        }
+    }
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Reader<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((at, c)) => Err(anyhow!(
+                "Expected {:?} at byte {}, found {:?}",
+                expected,
+                at,
+                c
+            )),
+            None => Err(anyhow!(
+                "Expected {:?}, found the end of the input",
+                expected
+            )),
+        }
+    }
+
+    fn value(&mut self) -> Result<Sexp, Error> {
+        match self.peek() {
+            Some('"') => match self.string()?.as_str() {
+                // Kept apart from the `null` a bare `null` reads as.
+                "null" => Ok(Sexp::string("null")),
+                text => Ok(Sexp::atom_or_string(text)),
+            },
+            Some(_) => {
+                let at = self.chars.peek().unwrap().0;
+                match self.word() {
+                    "null" => Ok(Sexp::atom("null")),
+                    "new" => {
+                        self.skip_whitespace();
+                        match self.word() {
+                            "Listing" => self.listing(),
+                            "Dynamic" => self.object(),
+                            class => Err(anyhow!("Unknown class {:?} at byte {}", class, at)),
+                        }
+                    }
+                    _ => Err(anyhow!("Expected a value at byte {}", at)),
+                }
+            }
+            None => Err(anyhow!("Expected a value, found the end of the input")),
+        }
+    }
+
+    fn listing(&mut self) -> Result<Sexp, Error> {
+        self.expect('{')?;
+        let mut items = vec![];
+        while self.peek() != Some('}') {
+            items.push(self.value()?);
+        }
+        self.expect('}')?;
+        Ok(Sexp::list(items))
+    }
+
+    fn object(&mut self) -> Result<Sexp, Error> {
+        self.expect('{')?;
+        let mut fields = vec![];
+        while self.peek() != Some('}') {
+            let at = self.chars.peek().map_or(self.input.len(), |&(at, _)| at);
+            let name = if self.chars.next_if(|&(_, c)| c == '`').is_some() {
+                let start = at + 1;
+                while self.chars.next_if(|&(_, c)| c != '`').is_some() {}
+                let end = self.chars.peek().map_or(self.input.len(), |&(at, _)| at);
+                self.expect('`')?;
+                &self.input[start..end]
+            } else {
+                self.word()
+            };
+            if name.is_empty() {
+                return Err(anyhow!("Expected a property at byte {}", at));
+            }
+            self.expect('=')?;
+            let mut field = vec![Sexp::atom_or_string(name)];
+            match self.value()? {
+                value @ Sexp::List(_) => field.extend(value.items().iter().cloned()),
+                value => field.push(value),
+            }
+            fields.push(Sexp::list(field));
+        }
+        self.expect('}')?;
+        Ok(Sexp::list(fields))
+    }
+
+    fn word(&mut self) -> &'a str {
+        let start = self.chars.peek().map_or(self.input.len(), |&(at, _)| at);
+        let mut end = start;
+        while let Some((at, c)) = self
+            .chars
+            .next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        {
+            end = at + c.len_utf8();
+        }
+        &self.input[start..end]
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.chars.next().map(|(_, c)| c) {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.chars.next().map(|(_, c)| c) {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('u') => {
+                        self.expect('{')?;
+                        let mut hex = String::new();
+                        while let Some((_, c)) = self.chars.next_if(|&(_, c)| c != '}') {
+                            hex.push(c);
+                        }
+                        self.expect('}')?;
+                        let code = u32::from_str_radix(&hex, 16)?;
+                        text.push(char::from_u32(code).ok_or_else(|| anyhow!("Invalid escape"))?);
+                    }
+                    Some(c) => text.push(c),
+                    None => return Err(anyhow!("Unterminated string")),
+                },
+                Some(c) => text.push(c),
+                None => return Err(anyhow!("Unterminated string")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pkl() {
+        let sexp =
+            Sexp::of_str(r#"((name core) (deps a "b c") (labels (app web)) ("in" ()))"#).unwrap();
+        assert_eq!(
+            sexp.to_pkl(),
+            "new Dynamic {\n  name = \"core\"\n  deps = new Listing {\n    \"a\"\n    \"b c\"\n  }\n  labels = new Dynamic {\n    app = \"web\"\n  }\n  `in` = new Listing {}\n}"
+        );
+    }
+
+    #[test]
+    fn test_of_pkl_round_trip() {
+        for input in &[
+            r#"((name core) (deps a "b c") (labels (app web)) (in) ("x y" z))"#,
+            r#"(("a\\(x)" "say \"hi\"\n") (list (x 1) (y 2)))"#,
+            "(1 (2 3) ())",
+            r#"((name null) (tags a null) (label "null"))"#,
+        ] {
+            let sexp = Sexp::of_str(input).unwrap();
+            assert_eq!(Sexp::of_pkl(&sexp.to_pkl()).unwrap(), sexp);
+        }
+        assert_eq!(
+            Sexp::of_str("((name null))").unwrap().to_pkl(),
+            "new Dynamic {\n  name = null\n}"
+        );
+        let lossy = Sexp::of_str("((x (1 2)))").unwrap();
+        assert_eq!(
+            Sexp::of_pkl(&lossy.to_pkl()).unwrap().to_string(),
+            "((x 1 2))"
+        );
+        assert!(Sexp::of_pkl("new Mapping {}").is_err());
+        assert!(Sexp::of_pkl("new Dynamic { = 1 }").is_err());
+    }
+}
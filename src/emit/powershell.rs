@@ -0,0 +1,345 @@
+use super::{atom, is_identifier, Atom};
+use crate::Sexp;
+
+/// `text` as a literal string. Nothing in single quotes is special but the
+/// quote itself, which is doubled.
+fn quoted(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// A name as a variable, with or without its `$`.
+fn variable(name: &str) -> String {
+    match name.strip_prefix('$') {
+        Some(_) => name.to_string(),
+        None => format!("${}", name),
+    }
+}
+
+/// The atoms that mean the same as arguments and in expressions.
+fn constant(text: &str) -> Option<String> {
+    match atom(text) {
+        Atom::Number(number) => Some(number.to_string()),
+        Atom::String(text) => Some(quoted(&text)),
+        Atom::Symbol("true") | Atom::Symbol("#t") => Some("$true".to_string()),
+        Atom::Symbol("false") | Atom::Symbol("#f") => Some("$false".to_string()),
+        Atom::Symbol("nil") => Some("$null".to_string()),
+        Atom::Symbol(name) if name.starts_with('$') => Some(name.to_string()),
+        Atom::Symbol(_) => None,
+    }
+}
+
+/// `sexp` as an argument to a command, where a symbol is a bare word as it
+/// is, like `*.txt`, and `:Name` is the parameter `-Name`. A list is a
+/// command whose output is the argument, or a literal or script block.
+fn argument(sexp: &Sexp) -> String {
+    match sexp {
+        Sexp::Atom(text) => constant(text).unwrap_or_else(|| match text.strip_prefix(':') {
+            Some(name) if !name.is_empty() => format!("-{}", name),
+            _ => text.to_string(),
+        }),
+        _ => value(sexp),
+    }
+}
+
+/// `sexp` in an expression, where a bare word would be a command to run,
+/// so symbols are strings there.
+fn value(sexp: &Sexp) -> String {
+    match sexp {
+        Sexp::Atom(text) => constant(text).unwrap_or_else(|| quoted(text)),
+        Sexp::Nil => "$null".to_string(),
+        Sexp::List(_) => match sexp.items().split_first() {
+            Some((Sexp::Atom(head), items)) if head == "array" => {
+                let items: Vec<String> = items.iter().map(value).collect();
+                format!("@({})", items.join(", "))
+            }
+            Some((Sexp::Atom(head), fields)) if head == "table" => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|field| match field.items() {
+                        [Sexp::Atom(key), value] => {
+                            let key = match atom(key) {
+                                Atom::String(key) if is_identifier(&key) => key,
+                                Atom::String(key) => quoted(&key),
+                                _ => key.to_string(),
+                            };
+                            format!("{} = {}", key, self::value(value))
+                        }
+                        _ => self::value(field),
+                    })
+                    .collect();
+                match fields.as_slice() {
+                    [] => "@{}".to_string(),
+                    _ => format!("@{{ {} }}", fields.join("; ")),
+                }
+            }
+            Some((Sexp::Atom(head), [params @ Sexp::List(_), body @ ..]))
+                if head == "lambda" || head == "fn" =>
+            {
+                script_block(params.items(), body)
+            }
+            _ => format!("({})", command(sexp)),
+        },
+    }
+}
+
+/// `lines` one to a line, with each indented for a block.
+fn indented(lines: &[String]) -> String {
+    lines
+        .join("\n")
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn body(commands: &[Sexp]) -> String {
+    indented(&commands.iter().map(command).collect::<Vec<_>>())
+}
+
+/// `commands` in braces after `opening`, as in `if ($ok) { ... }`.
+fn block(opening: &str, commands: &[Sexp]) -> String {
+    format!("{}{{\n{}\n}}", opening, body(commands))
+}
+
+/// A parameter, from a name or `(Name Type)`.
+fn param(sexp: &Sexp) -> String {
+    match sexp.items() {
+        [Sexp::Atom(name), Sexp::Atom(ty)] => format!("[{}]{}", ty, variable(name)),
+        _ => variable(&sexp.to_compact_string()),
+    }
+}
+
+fn params(params: &[Sexp]) -> String {
+    let params: Vec<String> = params.iter().map(param).collect();
+    format!("param({})", params.join(", "))
+}
+
+fn script_block(params: &[Sexp], body: &[Sexp]) -> String {
+    let mut lines: Vec<String> = body.iter().map(command).collect();
+    if !params.is_empty() {
+        lines.insert(0, self::params(params));
+    }
+    match lines.as_slice() {
+        [single] if !single.contains('\n') => format!("{{ {} }}", single),
+        _ => format!("{{\n{}\n}}", indented(&lines)),
+    }
+}
+
+fn conditional(clauses: &[(Option<&Sexp>, &[Sexp])]) -> String {
+    let branches: Vec<String> = clauses
+        .iter()
+        .enumerate()
+        .map(|(index, (test, consequent))| {
+            let opening = match test {
+                None => "else ".to_string(),
+                Some(test) if index == 0 => format!("if ({}) ", command(test)),
+                Some(test) => format!("elseif ({}) ", command(test)),
+            };
+            block(&opening, consequent)
+        })
+        .collect();
+    branches.join(" ")
+}
+
+/// The comparison operators, which are parameters like `-gt` rather than
+/// symbols so that `>` can redirect.
+fn comparison(head: &str) -> Option<&'static str> {
+    match head {
+        "=" | "==" | "eq?" | "equal?" => Some("-eq"),
+        "!=" | "not=" => Some("-ne"),
+        "<" => Some("-lt"),
+        ">" => Some("-gt"),
+        "<=" => Some("-le"),
+        ">=" => Some("-ge"),
+        _ => None,
+    }
+}
+
+fn command(sexp: &Sexp) -> String {
+    let (head, rest) = match sexp.items().split_first() {
+        Some((Sexp::Atom(head), rest)) => (head.as_str(), rest),
+        // A list of commands is a script of them in turn.
+        Some(_) => {
+            return sexp
+                .items()
+                .iter()
+                .map(command)
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        None if matches!(sexp, Sexp::List(_)) => return String::new(),
+        None => return argument(sexp),
+    };
+    let joined = |operands: &[Sexp], operator: &str| -> String {
+        operands
+            .iter()
+            .map(value)
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", operator))
+    };
+    if let (Some(operator), [_, _]) = (comparison(head), rest) {
+        return joined(rest, operator);
+    }
+    match (head, rest) {
+        ("begin", _) => rest.iter().map(command).collect::<Vec<_>>().join("\n"),
+        ("pipe", [_, ..]) => {
+            let stages: Vec<String> = rest.iter().map(command).collect();
+            stages.join(" | ")
+        }
+        ("and", [_, ..]) => joined(rest, "-and"),
+        ("or", [_, ..]) => joined(rest, "-or"),
+        ("not", [single]) => format!("-not {}", value(single)),
+        ("define", [signature @ Sexp::List(_), commands @ ..]) => match signature.items() {
+            [Sexp::Atom(name), params @ ..] => {
+                let mut lines = vec![];
+                if !params.is_empty() {
+                    lines.push(self::params(params));
+                }
+                lines.extend(commands.iter().map(command));
+                format!("function {} {{\n{}\n}}", name, indented(&lines))
+            }
+            _ => words(sexp.items()),
+        },
+        // An assignment takes a pipeline as it is, without the parentheses
+        // an argument needs.
+        ("define", [Sexp::Atom(name), init]) | ("set!", [Sexp::Atom(name), init]) => {
+            let init = match init {
+                Sexp::List(_) if !matches!(init.head(), Some("array") | Some("table")) => {
+                    command(init)
+                }
+                _ => value(init),
+            };
+            format!("{} = {}", variable(name), init)
+        }
+        ("if", [test, consequent]) => {
+            conditional(&[(Some(test), std::slice::from_ref(consequent))])
+        }
+        ("if", [test, consequent, alternative]) => conditional(&[
+            (Some(test), std::slice::from_ref(consequent)),
+            (None, std::slice::from_ref(alternative)),
+        ]),
+        ("cond", [_, ..]) => {
+            let clauses: Vec<(Option<&Sexp>, &[Sexp])> = rest
+                .iter()
+                .enumerate()
+                .map(|(index, clause)| match clause.items().split_first() {
+                    Some((Sexp::Atom(test), consequent)) if test == "else" && index > 0 => {
+                        (None, consequent)
+                    }
+                    Some((test, consequent)) => (Some(test), consequent),
+                    None => (Some(clause), &[][..]),
+                })
+                .collect();
+            conditional(&clauses)
+        }
+        ("while", [test, commands @ ..]) => block(&format!("while ({}) ", command(test)), commands),
+        ("for", [Sexp::Atom(name), items, commands @ ..]) => {
+            let items = match items {
+                Sexp::List(_) => items
+                    .items()
+                    .iter()
+                    .map(value)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => value(items),
+            };
+            block(
+                &format!("foreach ({} in {}) ", variable(name), items),
+                commands,
+            )
+        }
+        ("array", _) | ("table", _) => value(sexp),
+        ("lambda", [Sexp::List(_), ..]) | ("fn", [Sexp::List(_), ..]) => value(sexp),
+        _ => words(sexp.items()),
+    }
+}
+
+/// A command as its words, each an argument.
+fn words(items: &[Sexp]) -> String {
+    items.iter().map(argument).collect::<Vec<_>>().join(" ")
+}
+
+impl Sexp {
+    /// The tree as a PowerShell script: `(Verb-Noun :Param value args...)`
+    /// becomes the command `Verb-Noun -Param value args`, where symbols are
+    /// bare words and strings literal `'...'` ones. A list as an argument
+    /// is a command in parentheses, and a list of commands a script of them
+    /// in turn. `(pipe a b)` becomes the pipeline `a | b`. Symbols starting
+    /// with `$` are variables, and
+    /// `true`, `false` and `nil` `$true`, `$false` and `$null`. Operators
+    /// are PowerShell's: `and`, `or` and `not` become `-and`, `-or` and
+    /// `-not`, and `==` or `>` `-eq` or `-gt`. `(define NAME value)` and
+    /// `set!` assign `$NAME`, and `(define (Verb-Noun params...)
+    /// commands...)` becomes a function, with a `(Name Type)` parameter
+    /// typed. `if`, `cond`, `while` and `(for x items commands...)` become
+    /// `if`/`elseif`/`else`, `while` and `foreach`, `lambda` a script block
+    /// `{ ... }`, and `(array ...)` and `(table (key value)...)` `@(...)`
+    /// and `@{ ... }`.
+    pub fn to_powershell_string(&self) -> String {
+        command(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_powershell_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (Show-Files (Path string) Limit)
+                   (define files (pipe (Get-ChildItem :Path $Path :Filter *.txt :Recurse)
+                                       (Where-Object (lambda () (> $_.Length $Limit)))
+                                       (Sort-Object :Property Length :Descending)))
+                   (if (== $files.Count 0)
+                       (Write-Output "no files in it's place")
+                       (for file $files (Write-Host $file.Name :ForegroundColor Green))))
+                 (define config (table (name "demo") ("max size" 10) (tags (array a "b"))))
+                 (cond ((and (Test-Path config.json) (not $env:CI)) (Write-Output "local"))
+                       ((!= $config.name nil) (Write-Output (Get-Date)))
+                       (else (Write-Error "missing")))
+                 (Show-Files :Path . :Limit 100))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_powershell_string(),
+            r#"function Show-Files {
+    param([string]$Path, $Limit)
+    $files = Get-ChildItem -Path $Path -Filter *.txt -Recurse | Where-Object { $_.Length -gt $Limit } | Sort-Object -Property Length -Descending
+    if ($files.Count -eq 0) {
+        Write-Output 'no files in it''s place'
+    } else {
+        foreach ($file in $files) {
+            Write-Host $file.Name -ForegroundColor Green
+        }
+    }
+}
+$config = @{ name = 'demo'; 'max size' = 10; tags = @('a', 'b') }
+if ((Test-Path config.json) -and (-not $env:CI)) {
+    Write-Output 'local'
+} elseif ($config.name -ne $null) {
+    Write-Output (Get-Date)
+} else {
+    Write-Error 'missing'
+}
+Show-Files -Path . -Limit 100"#
+        );
+    }
+
+    #[test]
+    fn test_to_powershell_string_malformed() {
+        let cases = [
+            ("(())", ""),
+            ("((a) (b))", "a\nb"),
+            ("(lambda)", "lambda"),
+            ("(fn)", "fn"),
+            ("(define ())", "define ()"),
+            ("(Write-Output (lambda))", "Write-Output (lambda)"),
+        ];
+        for (input, expected) in cases {
+            let sexp = Sexp::of_str(input).unwrap();
+            assert_eq!(sexp.to_powershell_string(), expected, "{}", input);
+        }
+    }
+}
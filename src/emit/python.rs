@@ -0,0 +1,50 @@
+use super::{atom, is_identifier, quoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+impl Sexp {
+    /// The tree as a Python literal: lists become tuples, numbers stay
+    /// numbers, atoms that are valid names become bare identifiers, and
+    /// everything else becomes a string literal.
+    pub fn to_python_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::Symbol(name) if is_identifier(name) && !KEYWORDS.contains(&name) => {
+                    name.to_string()
+                }
+                Atom::Symbol(name) => quoted(name, '\''),
+                Atom::String(text) => quoted(&text, '\''),
+            },
+            Sexp::List(_) => {
+                let items: Vec<String> = self.items().iter().map(Sexp::to_python_string).collect();
+                match items.len() {
+                    1 => format!("({},)", items[0]),
+                    _ => format!("({})", items.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_python_string() {
+        let sexp =
+            Sexp::of_str(r#"(call print (args "it's" 42 -1.5 x-y if) (single) ())"#).unwrap();
+        assert_eq!(
+            sexp.to_python_string(),
+            r"(call, print, (args, 'it\'s', 42, -1.5, 'x-y', 'if'), (single,), ())"
+        );
+    }
+}
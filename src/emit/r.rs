@@ -0,0 +1,165 @@
+use super::infix::{write, Syntax};
+use super::{atom, quoted, Atom};
+use crate::Sexp;
+
+const RESERVED: &[&str] = &[
+    "if", "else", "repeat", "while", "function", "for", "in", "next", "break",
+];
+
+/// Whether `name` is a syntactic R name: letters, digits, `.` and `_`,
+/// starting with a letter or with a `.` that isn't followed by a digit.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let leads = match (chars.next(), chars.next()) {
+        (Some('.'), Some(second)) => !second.is_ascii_digit(),
+        (Some(first), _) => first.is_ascii_alphabetic() || first == '.',
+        (None, _) => false,
+    };
+    leads
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+        && !RESERVED.contains(&name)
+}
+
+/// R's constants are `TRUE`, `FALSE` and `NULL`, so Scheme's booleans and
+/// `nil` are written as those.
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => quoted(&text, '"'),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "TRUE".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "FALSE".to_string(),
+        Atom::Symbol("nil") => "NULL".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => quoted(name, '"'),
+    }
+}
+
+/// `forms` as one expression: a single one as it is, and more in braces.
+fn block(forms: &[String]) -> String {
+    match forms {
+        [single] => single.clone(),
+        _ => format!("{{{}}}", forms.join("; ")),
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &R)).collect() };
+    match (head, rest) {
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), params @ ..] => Some(format!(
+                "{} <- {}",
+                write(name, &R),
+                lambda(&all(params), &all(body))
+            )),
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) | ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} <- {}", write(name, &R), write(init, &R)))
+        }
+        ("begin", _) => Some(format!("{{{}}}", all(rest).join("; "))),
+        ("if", [test, consequent]) => Some(format!(
+            "if ({}) {}",
+            write(test, &R),
+            write(consequent, &R)
+        )),
+        // `local` evaluates its block in a new environment, as a `let`
+        // body runs with its own bindings.
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut forms = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => {
+                        forms.push(format!("{} <- {}", write(name, &R), write(init, &R)))
+                    }
+                    _ => return None,
+                }
+            }
+            forms.extend(all(body));
+            Some(format!("local({{{}}})", forms.join("; ")))
+        }
+        _ => None,
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    format!("function({}) {}", params.join(", "), block(body))
+}
+
+/// A named list, with names that aren't syntactic in backticks.
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| {
+            if is_name(name) {
+                format!("{} = {}", name, value)
+            } else {
+                let name = name.replace('\\', "\\\\").replace('`', "\\`");
+                format!("`{}` = {}", name, value)
+            }
+        })
+        .collect();
+    format!("list({})", fields.join(", "))
+}
+
+const R: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%%",
+    method: "$",
+    field: "$",
+    named: Some(" = "),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("if ({}) {} else {}", test, consequent, alternative)
+    },
+    array: ("c(", ")"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as an R expression, reading it as Scheme-style code:
+    /// `lambda` becomes `function(x) ...`, `define` and `set!` assignments
+    /// with `<-`, `begin` a `{...}` block and `let` a `local` one, `if` R's
+    /// `if (test) ... else ...`, and arithmetic, comparisons and logic R's
+    /// operators. `(array ...)` becomes `c(...)`, `(table (key value)...)`
+    /// a named `list(key = value)`, and `:name value` in a call a named
+    /// argument. Booleans and `nil` become `TRUE`, `FALSE` and `NULL`, and
+    /// atoms that aren't syntactic names strings.
+    pub fn to_r_string(&self) -> String {
+        write(self, &R)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_r_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (define (square x) (* x x))
+                 (define xs (array 1 2 3))
+                 (define config (table (name "demo") ("max size" 10)))
+                 (let ((total (sum (sapply xs square))))
+                   (if (and (> total 5) (not (is.null config))) (print "big") (print "small")))
+                 (paste "a" "b" :sep ", ")
+                 (- (* 2 (+ 1 x)) (- y) (% 7 3))
+                 (if (= (.-name config) "demo") (cat true) nil)
+                 (sapply xs (lambda (x) (+ x 1))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_r_string(),
+            r#"{square <- function(x) x * x; xs <- c(1, 2, 3); config <- list(name = "demo", `max size` = 10); local({total <- sum(sapply(xs, square)); if (total > 5 && !is.null(config)) print("big") else print("small")}); paste("a", "b", sep = ", "); 2 * (1 + x) - -y - 7 %% 3; if (config$name == "demo") cat(TRUE) else NULL; sapply(xs, function(x) x + 1)}"#
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// How tightly a piece of a pattern binds, loosest first: an operand that
+/// binds looser than its operator needs a `(?:...)` group.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Alternation,
+    Concatenation,
+    Repetition,
+    Single,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn text(sexp: &Sexp) -> String {
+    unquoted(&sexp.to_compact_string())
+}
+
+fn grouped(sexp: &Sexp, at_least: Precedence) -> String {
+    let (pattern, precedence) = pattern(sexp);
+    if precedence < at_least {
+        format!("(?:{})", pattern)
+    } else {
+        pattern
+    }
+}
+
+/// The text of `sexp` matched literally.
+fn literal(sexp: &Sexp) -> (String, Precedence) {
+    let literal = text(sexp);
+    let precedence = if literal.chars().count() == 1 {
+        Precedence::Single
+    } else {
+        Precedence::Concatenation
+    };
+    (escape(&literal), precedence)
+}
+
+fn pattern(sexp: &Sexp) -> (String, Precedence) {
+    let (operator, operands) = match sexp.items().split_first() {
+        Some((Sexp::Atom(operator), operands)) => (operator.as_str(), operands),
+        _ => return literal(sexp),
+    };
+    let all = |at_least| -> Vec<String> {
+        operands
+            .iter()
+            .map(|operand| grouped(operand, at_least))
+            .collect()
+    };
+    let quantified = |operand, quantifier: &str| -> (String, Precedence) {
+        (
+            format!("{}{}", grouped(operand, Precedence::Single), quantifier),
+            Precedence::Repetition,
+        )
+    };
+    match (operator, operands) {
+        ("concat", _) => (
+            all(Precedence::Concatenation).concat(),
+            Precedence::Concatenation,
+        ),
+        ("alt", [_, _, ..]) => (
+            all(Precedence::Alternation).join("|"),
+            Precedence::Alternation,
+        ),
+        ("star", [operand]) => quantified(operand, "*"),
+        ("plus", [operand]) => quantified(operand, "+"),
+        ("opt", [operand]) => quantified(operand, "?"),
+        ("repeat", [operand, counts @ ..])
+            if !counts.is_empty()
+                && counts.len() <= 2
+                && counts
+                    .iter()
+                    .all(|count| text(count).parse::<u32>().is_ok()) =>
+        {
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|count| text(count).parse::<u32>().unwrap().to_string())
+                .collect();
+            quantified(operand, &format!("{{{}}}", counts.join(",")))
+        }
+        ("group", [operand]) => (
+            format!("({})", grouped(operand, Precedence::Alternation)),
+            Precedence::Single,
+        ),
+        ("class", [_, ..]) => {
+            let mut class = String::from("[");
+            for member in operands {
+                match member.items() {
+                    [Sexp::Atom(range), from, to] if range == "range" => {
+                        class.push_str(&format!("{}-{}", escape(&text(from)), escape(&text(to))))
+                    }
+                    _ => class.push_str(&escape(&text(member))),
+                }
+            }
+            class.push(']');
+            (class, Precedence::Single)
+        }
+        ("any", []) => (".".to_string(), Precedence::Single),
+        ("start", []) => ("^".to_string(), Precedence::Single),
+        ("end", []) => ("$".to_string(), Precedence::Single),
+        _ => literal(sexp),
+    }
+}
+
+impl Sexp {
+    /// The tree as a regular expression, from a small pattern language:
+    /// an atom matches its text literally, `(concat a b)` is `ab`, `(alt a
+    /// b)` is `a|b`, `(star a)`, `(plus a)` and `(opt a)` are `a*`, `a+`
+    /// and `a?`, `(repeat a 2 5)` is `a{2,5}`, `(group a)` is a capturing
+    /// `(a)`, `(class abc (range 0 9))` is `[abc0-9]`, and `(any)`,
+    /// `(start)` and `(end)` are `.`, `^` and `$`. Operands are wrapped in
+    /// `(?:...)` where they'd otherwise bind differently, as in `(?:ab)*`.
+    /// Any other list, like `(repeat a x)`, matches its text literally.
+    pub fn to_regex_string(&self) -> String {
+        pattern(self).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::regex::Regex;
+
+    #[test]
+    fn test_to_regex_string() {
+        let sexp = Sexp::of_str(
+            r#"(concat (start)
+                       (group (alt http https))
+                       "://"
+                       (plus (class (range a z) (range 0 9) . -))
+                       (opt (concat : (repeat (class (range 0 9)) 1 5)))
+                       (star (concat / (star (any))))
+                       (end))"#,
+        )
+        .unwrap();
+        let pattern = sexp.to_regex_string();
+        assert_eq!(
+            pattern,
+            r"^(http|https)://[a-z0-9\.\-]+(?::[0-9]{1,5})?(?:/.*)*$"
+        );
+        let regex = Regex::new(&pattern).unwrap();
+        assert!(regex.is_match("https://example.com:8080/a/b"));
+        assert!(!regex.is_match("ftp://example.com"));
+        assert!(!regex.is_match("http://example.com:123456"));
+    }
+
+    #[test]
+    fn test_to_regex_string_grouping() {
+        for (input, expected) in &[
+            ("(star ab)", "(?:ab)*"),
+            ("(concat a (alt b c) d)", "a(?:b|c)d"),
+            ("(alt (concat a b) c)", "ab|c"),
+            ("(opt (star a))", "(?:a*)?"),
+            (r#"(concat "1+1" "(x)")"#, r"1\+1\(x\)"),
+        ] {
+            let pattern = Sexp::of_str(input).unwrap().to_regex_string();
+            assert_eq!(&pattern, expected);
+            Regex::new(&pattern).unwrap();
+        }
+        let pattern = |input| Sexp::of_str(input).unwrap().to_regex_string();
+        assert_eq!(pattern("(repeat a x)"), r"\(repeat a x\)");
+        assert_eq!(pattern("(star (lookahead a))"), r"(?:\(lookahead a\))*");
+    }
+}
@@ -0,0 +1,62 @@
+use super::outline;
+use crate::Sexp;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        // Inline markup characters anywhere, and any punctuation that could
+        // start a nested list or other construct at the start of the item.
+        if matches!(c, '\\' | '*' | '_' | '`' | '|') || (i == 0 && c.is_ascii_punctuation()) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl Sexp {
+    /// The tree as nested reStructuredText bullet lists, laid out like
+    /// `to_asciidoc`. Items are separated by blank lines, which rST needs
+    /// around every nested list, and inline markup characters are escaped.
+    pub fn to_restructured_text(&self) -> String {
+        let mut rst = String::new();
+        outline(self, 0, &mut |text, depth| {
+            rst.push_str(&format!("{}- {}\n\n", "  ".repeat(depth), escape(text)))
+        });
+        rst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_restructured_text() {
+        let sexp = Sexp::of_str(r#"(function_call (name "*ptr") -x)"#).unwrap();
+        assert_eq!(
+            sexp.to_restructured_text(),
+            "- function\\_call\n\n  - name\n\n    - \\*ptr\n\n  - \\-x\n\n"
+        );
+    }
+
+    #[test]
+    fn test_to_restructured_text_big_fixture() {
+        let sexp = Sexp::of_str(include_str!("../big_fixture.in.sexp")).unwrap();
+        let rst = sexp.to_restructured_text();
+        assert!(rst.starts_with(
+            "- source\\_file\n\n  - module\\_name\n\n    - atom\n\n      - unquoted\\_atom\n\n"
+        ));
+        let mut depth = 0;
+        for line in rst.lines().filter(|line| !line.is_empty()) {
+            let text = line.trim_start();
+            let indent = line.len() - text.len();
+            assert!(text.starts_with("- "), "{:?}", line);
+            assert!(indent % 2 == 0 && indent <= depth + 2, "{:?}", line);
+            assert!(!text.replace("\\_", "").contains('_'), "{:?}", line);
+            depth = indent;
+        }
+        let atoms = sexp.count(|node| matches!(node, Sexp::Atom(_)));
+        assert_eq!(rst.lines().filter(|line| !line.is_empty()).count(), atoms);
+    }
+}
@@ -0,0 +1,235 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, quoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "BEGIN", "END", "alias", "and", "begin", "break", "case", "class", "def", "do", "else",
+    "elsif", "end", "ensure", "false", "for", "if", "in", "module", "next", "nil", "not", "or",
+    "redo", "rescue", "retry", "return", "super", "then", "true", "undef", "unless", "until",
+    "when", "while", "yield",
+];
+
+/// Whether `name` is a Ruby identifier, which may end in the `?` of a
+/// predicate or the `!` of a mutating method.
+fn is_name(name: &str) -> bool {
+    let stem = name.strip_suffix(['?', '!']).unwrap_or(name);
+    is_identifier(stem) && !KEYWORDS.contains(&name)
+}
+
+/// `text` as a Ruby string, where a `#` could start an interpolation.
+fn string(text: &str) -> String {
+    quoted(text, '"').replace('#', "\\#")
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "nil".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        // `:name` is a Ruby symbol too.
+        Atom::Symbol(name) if name.strip_prefix(':').is_some_and(is_name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` indented between `opening` and `end`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}\n{}\nend", opening, indented(forms, "  "))
+}
+
+/// A block taking `params`: in braces for a single form, and as `do ...
+/// end` for more.
+fn ruby_block(params: &[Sexp], body: &[Sexp]) -> String {
+    let params: Vec<String> = params.iter().map(|param| write(param, &RUBY)).collect();
+    let params = match params.as_slice() {
+        [] => String::new(),
+        _ => format!(" |{}|", params.join(", ")),
+    };
+    let body: Vec<String> = body.iter().map(|form| write(form, &RUBY)).collect();
+    match body.as_slice() {
+        [single] => format!("{{{} {} }}", params, single),
+        _ => block(&format!("do{}", params), &body),
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &RUBY)).collect() };
+    match (head, rest) {
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), params @ ..] => Some(block(
+                &format!("def {}({})", write(name, &RUBY), all(params).join(", ")),
+                &all(body),
+            )),
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) | ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &RUBY), write(init, &RUBY)))
+        }
+        ("begin", [_, ..]) => Some(block("begin", &all(rest))),
+        // Ruby has no block scope, so the bindings are plain assignments
+        // at the start of a `begin`.
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut lines = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => {
+                        lines.push(format!("{} = {}", write(name, &RUBY), write(init, &RUBY)))
+                    }
+                    _ => return None,
+                }
+            }
+            lines.extend(all(body));
+            Some(block("begin", &lines))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if {}", write(test, &RUBY)),
+            &[write(consequent, &RUBY)],
+        )),
+        ("cond", [_, ..]) => {
+            let mut lines = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                lines.push(match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => "else".to_string(),
+                    _ if index == 0 => format!("if {}", write(test, &RUBY)),
+                    _ => format!("elsif {}", write(test, &RUBY)),
+                });
+                lines.push(indented(&all(consequent), "  "));
+            }
+            lines.push("end".to_string());
+            Some(lines.join("\n"))
+        }
+        // A function as the last argument of a call is the block it's
+        // called with, as in `xs.map { |x| ... }`.
+        (_, [args @ .., function]) if is_name(head) || head.starts_with('.') => {
+            let (params, body) = match function.items() {
+                [Sexp::Atom(lambda), params @ Sexp::List(_), body @ ..]
+                    if lambda == "lambda" || lambda == "fn" =>
+                {
+                    (params.items(), body)
+                }
+                _ => return None,
+            };
+            let mut call = vec![Sexp::atom(head)];
+            call.extend(args.iter().cloned());
+            let call = write(&Sexp::list(call), &RUBY);
+            let call = call.strip_suffix("()").unwrap_or(&call);
+            Some(format!("{} {}", call, ruby_block(params, body)))
+        }
+        _ => None,
+    }
+}
+
+/// A lambda literal, as in `->(x) { ... }`.
+fn lambda(params: &[String], body: &[String]) -> String {
+    let arrow = format!("->({})", params.join(", "));
+    match body {
+        [single] => format!("{} {{ {} }}", arrow, single),
+        _ => block(&format!("{} do", arrow), body),
+    }
+}
+
+/// A hash with symbol keys, quoted where they aren't names.
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| {
+            if is_name(key) {
+                format!("{}: {}", key, value)
+            } else {
+                format!("{}: {}", string(key), value)
+            }
+        })
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+const RUBY: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: Some(": "),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("[", "]"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as Ruby, reading it as Scheme-style code: lists become
+    /// `method(args)` calls, `(.method object args...)` method calls, and a
+    /// `lambda` as the last argument of one the block it's called with, as
+    /// in `xs.map { |x| ... }`, or `do |x| ... end` for a longer one.
+    /// `(table (key value)...)` becomes a hash `{key: value}` with symbol
+    /// keys and `(array ...)` an array. `(define (f x) ...)` becomes a `def`,
+    /// `define` and `set!` of a variable assignments, `begin` and `let`
+    /// `begin` blocks, `if` and `cond` `if`/`elsif`/`else`, and any other
+    /// `lambda` `->(x) { ... }`. `:name value` in a call is a keyword
+    /// argument, and `:name` on its own a symbol.
+    pub fn to_ruby_string(&self) -> String {
+        write(self, &RUBY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ruby_string() {
+        let sexp = Sexp::of_str(
+            r##"(begin
+                 (define (area w h) (* w h))
+                 (define (describe n)
+                   (puts n)
+                   (cond ((< n 0) "negative") ((== n 0) "zero") (else "#{n} big")))
+                 (define config (table (name "demo") ("max size" 10)))
+                 (define doubled (.map (array 1 2 3) (lambda (x) (* x 2))))
+                 (.each config (lambda (key value) (puts key) (puts value)))
+                 (define square (lambda (x) (* x x)))
+                 (puts (.call square 3) (if (.empty? doubled) "none" "some") :done)
+                 (sort xs :reverse true))"##,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_ruby_string(),
+            r##"begin
+  def area(w, h)
+    w * h
+  end
+  def describe(n)
+    puts(n)
+    if n < 0
+      "negative"
+    elsif n == 0
+      "zero"
+    else
+      "\#{n} big"
+    end
+  end
+  config = {name: "demo", "max size": 10}
+  doubled = [1, 2, 3].map { |x| x * 2 }
+  config.each do |key, value|
+    puts(key)
+    puts(value)
+  end
+  square = ->(x) { x * x }
+  puts(square.call(3), doubled.empty?() ? "none" : "some", :done)
+  sort(xs, reverse: true)
+end"##
+        );
+    }
+}
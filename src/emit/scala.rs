@@ -0,0 +1,245 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, quoted, unquoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "abstract",
+    "case",
+    "catch",
+    "class",
+    "def",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "for",
+    "given",
+    "if",
+    "implicit",
+    "import",
+    "lazy",
+    "match",
+    "new",
+    "null",
+    "object",
+    "override",
+    "package",
+    "private",
+    "protected",
+    "return",
+    "sealed",
+    "super",
+    "then",
+    "throw",
+    "trait",
+    "true",
+    "try",
+    "type",
+    "val",
+    "var",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Whether `name` is an identifier, or a path of them like `math.sqrt`.
+fn is_name(name: &str) -> bool {
+    name.split('.')
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => quoted(&text, '"'),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "None".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => quoted(name, '"'),
+    }
+}
+
+/// `forms` as one expression: a single one as it is, and more in braces.
+fn braces(forms: &[String]) -> String {
+    match forms {
+        [single] => single.clone(),
+        _ => format!("{{\n{}\n}}", indented(forms, "  ")),
+    }
+}
+
+/// A parameter or field: `(name Type)`, or a bare name taking `Any`.
+fn param(param: &Sexp) -> Option<String> {
+    match param {
+        Sexp::Atom(name) if is_name(name) => Some(format!("{}: Any", name)),
+        Sexp::Atom(_) => None,
+        _ => match param.items() {
+            [name @ Sexp::Atom(_), Sexp::Atom(ty)] => {
+                Some(format!("{}: {}", write(name, &SCALA), unquoted(ty)))
+            }
+            _ => None,
+        },
+    }
+}
+
+fn params(params: &[Sexp]) -> Option<String> {
+    let params = params.iter().map(param).collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", params.join(", ")))
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &SCALA)).collect() };
+    match (head, rest) {
+        ("case-class", [name @ Sexp::Atom(_), fields @ ..]) => Some(format!(
+            "case class {}{}",
+            write(name, &SCALA),
+            params(fields)?
+        )),
+        // A body of several statements is indented under the `=`, which
+        // Scala 3 reads without braces.
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name @ Sexp::Atom(_), rest @ ..] => {
+                let signature = format!("def {}{}", write(name, &SCALA), params(rest)?);
+                match all(body).as_slice() {
+                    [single] => Some(format!("{} = {}", signature, single)),
+                    body => Some(format!("{} =\n{}", signature, indented(body, "  "))),
+                }
+            }
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "val {} = {}",
+            write(name, &SCALA),
+            write(init, &SCALA)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &SCALA), write(init, &SCALA)))
+        }
+        ("begin", [_, ..]) => Some(format!("{{\n{}\n}}", indented(&all(rest), "  "))),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut forms = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => forms.push(format!(
+                        "val {} = {}",
+                        write(name, &SCALA),
+                        write(init, &SCALA)
+                    )),
+                    _ => return None,
+                }
+            }
+            forms.extend(all(body));
+            Some(format!("{{\n{}\n}}", indented(&forms, "  ")))
+        }
+        ("if", [test, consequent]) => Some(format!(
+            "if {} then {}",
+            write(test, &SCALA),
+            write(consequent, &SCALA)
+        )),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let consequent = braces(&all(consequent));
+                match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => branches.push(consequent),
+                    _ => branches.push(format!("if {} then {}", write(test, &SCALA), consequent)),
+                }
+            }
+            Some(branches.join(" else "))
+        }
+        _ => None,
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    match params {
+        [single] => format!("{} => {}", single, braces(body)),
+        _ => format!("({}) => {}", params.join(", "), braces(body)),
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{} -> {}", quoted(key, '"'), value))
+        .collect();
+    format!("Map({})", fields.join(", "))
+}
+
+const SCALA: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: Some(" = "),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("if {} then {} else {}", test, consequent, alternative)
+    },
+    array: ("List(", ")"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as a Scala 3 expression, reading it as Scheme-style code:
+    /// calls become `head(args...)` and `(.method object args...)` method
+    /// calls, `(case-class Point (x Int) (y Int))` a `case class`, and
+    /// `(Point :x 1 :y 2)` an instance of it with named arguments. `(define
+    /// (f (x Int)) ...)` becomes `def f(x: Int) = ...`, with a body of
+    /// several statements indented under the `=` as Scala 3 allows and
+    /// untyped parameters taking `Any`, `define` of a variable a `val`, and
+    /// `if` and `cond` `if ... then ... else` expressions. `lambda` becomes
+    /// `x => ...`, `(array ...)` a `List`, `(table (key value)...)` a `Map`
+    /// with string keys, and `nil` `None`.
+    pub fn to_scala_string(&self) -> String {
+        write(self, &SCALA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_scala_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (case-class Point (x Int) (y Int))
+                 (define (norm (p Point)) (math.sqrt (+ (* (.-x p) (.-x p)) (* (.-y p) (.-y p)))))
+                 (define (describe (n Int))
+                   (println n)
+                   (cond ((< n 0) "negative") ((== n 0) "zero") (else "positive")))
+                 (define origin (Point :x 0 :y 0))
+                 (define config (table (name "demo") (port 80)))
+                 (.map (array 1 2 3) (lambda (x) (* x 2)))
+                 (if (!= (norm origin) 0) (println "moved") (println "home")))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_scala_string(),
+            r#"{
+  case class Point(x: Int, y: Int)
+  def norm(p: Point) = math.sqrt(p.x * p.x + p.y * p.y)
+  def describe(n: Int) =
+    println(n)
+    if n < 0 then "negative" else if n == 0 then "zero" else "positive"
+  val origin = Point(x = 0, y = 0)
+  val config = Map("name" -> "demo", "port" -> 80)
+  List(1, 2, 3).map(x => x * 2)
+  if norm(origin) != 0 then println("moved") else println("home")
+}"#
+        );
+    }
+}
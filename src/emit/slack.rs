@@ -0,0 +1,38 @@
+use super::outline;
+use crate::Sexp;
+
+/// Slack renders at most three levels of nested bullets.
+const MAX_DEPTH: usize = 2;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl Sexp {
+    /// The tree as Slack mrkdwn bullets, laid out like `to_asciidoc`.
+    /// Anything nested deeper than Slack's three levels is kept at the third.
+    pub fn to_slack_mrkdwn(&self) -> String {
+        let mut mrkdwn = String::new();
+        outline(self, 0, &mut |text, depth| {
+            let indent = "    ".repeat(depth.min(MAX_DEPTH));
+            mrkdwn.push_str(&format!("{}• {}\n", indent, escape(text)))
+        });
+        mrkdwn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_slack_mrkdwn() {
+        let sexp = Sexp::of_str(r#"(a (b (c (d "<x> & y"))))"#).unwrap();
+        assert_eq!(
+            sexp.to_slack_mrkdwn(),
+            "• a\n    • b\n        • c\n        • d\n        • &lt;x&gt; &amp; y\n"
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use super::unquoted;
+use crate::Sexp;
+
+/// A keyword atom like `create-table` or `PRIMARY_KEY` in SQL's spelling.
+fn keyword(atom: &str) -> String {
+    atom.to_ascii_uppercase().replace(['_', '-'], " ")
+}
+
+fn head(sexp: &Sexp) -> Option<String> {
+    match sexp.items().first() {
+        Some(Sexp::Atom(head)) => Some(keyword(head)),
+        _ => None,
+    }
+}
+
+fn identifier(sexp: &Sexp) -> String {
+    let name = unquoted(&sexp.to_compact_string());
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn identifiers(sexps: &[Sexp]) -> String {
+    let names: Vec<String> = sexps.iter().map(identifier).collect();
+    format!("({})", names.join(", "))
+}
+
+/// A literal: string atoms become SQL strings, anything else is as written.
+fn literal(sexp: &Sexp) -> String {
+    match sexp {
+        Sexp::Atom(atom) if atom.starts_with('"') => {
+            format!("'{}'", unquoted(atom).replace('\'', "''"))
+        }
+        _ => sexp.to_compact_string(),
+    }
+}
+
+/// A list like `(DROP_TABLE users)` as its keyword and its literals.
+fn words(sexp: &Sexp) -> String {
+    let mut words = vec![];
+    if let Some((head, rest)) = sexp.items().split_first() {
+        words.push(match head {
+            Sexp::Atom(head) => keyword(head),
+            _ => literal(head),
+        });
+        words.extend(rest.iter().map(literal));
+    }
+    words.join(" ")
+}
+
+/// A type like `INTEGER`, or `(VARCHAR 255)` for `VARCHAR(255)`.
+fn column_type(sexp: &Sexp) -> String {
+    match sexp.items().split_first() {
+        None => unquoted(&sexp.to_compact_string()),
+        Some((name, sizes)) => {
+            let sizes: Vec<String> = sizes.iter().map(literal).collect();
+            format!("{}({})", name.to_compact_string(), sizes.join(", "))
+        }
+    }
+}
+
+/// `(REFERENCES table column...)`.
+fn references(sexp: &Sexp) -> String {
+    match sexp.items() {
+        [_, table] => format!("REFERENCES {}", identifier(table)),
+        [_, table, columns @ ..] => {
+            format!("REFERENCES {} {}", identifier(table), identifiers(columns))
+        }
+        _ => words(sexp),
+    }
+}
+
+/// A column constraint: a keyword like `NOT_NULL`, `(REFERENCES ...)`, or
+/// a list like `(DEFAULT value)` written as its keyword and its values.
+fn constraint(sexp: &Sexp) -> String {
+    match (sexp, head(sexp).as_deref()) {
+        (Sexp::Atom(atom), _) => keyword(atom),
+        (_, Some("REFERENCES")) => references(sexp),
+        _ => words(sexp),
+    }
+}
+
+/// A column like `(name TEXT NOT_NULL (DEFAULT "x"))`, or a table
+/// constraint like `(PRIMARY_KEY a b)`.
+fn column(sexp: &Sexp) -> String {
+    let items = sexp.items();
+    match head(sexp).as_deref() {
+        Some(kind @ ("PRIMARY KEY" | "UNIQUE")) => {
+            return format!("{} {}", kind, identifiers(&items[1..]));
+        }
+        Some("FOREIGN KEY") => match items[1..].split_last() {
+            Some((reference, columns)) => {
+                return format!(
+                    "FOREIGN KEY {} {}",
+                    identifiers(columns),
+                    references(reference)
+                )
+            }
+            None => return "FOREIGN KEY".to_string(),
+        },
+        _ => {}
+    }
+    let mut column = vec![];
+    if let Some((name, rest)) = items.split_first() {
+        column.push(identifier(name));
+        if let Some((ty, constraints)) = rest.split_first() {
+            column.push(column_type(ty));
+            column.extend(constraints.iter().map(constraint));
+        }
+    }
+    column.join(" ")
+}
+
+fn statement(sexp: &Sexp) -> String {
+    let items = sexp.items();
+    match (head(sexp).as_deref(), items) {
+        (Some("CREATE TABLE"), [_, table, columns @ ..]) => {
+            let columns: Vec<String> = columns
+                .iter()
+                .map(|sexp| format!("  {}", column(sexp)))
+                .collect();
+            format!(
+                "CREATE TABLE {} (\n{}\n);\n",
+                identifier(table),
+                columns.join(",\n")
+            )
+        }
+        (
+            Some(create @ ("CREATE INDEX" | "CREATE UNIQUE INDEX")),
+            [_, index, table, columns @ ..],
+        ) => format!(
+            "{} {} ON {} {};\n",
+            create,
+            identifier(index),
+            identifier(table),
+            identifiers(columns)
+        ),
+        _ => format!("{};\n", words(sexp)),
+    }
+}
+
+const CLAUSES: &[&str] = &["FROM", "WHERE", "GROUP BY", "HAVING", "ORDER BY", "LIMIT"];
+
+const OPERATORS: &[&str] = &[
+    "=", "<>", "!=", "<", ">", "<=", ">=", "+", "-", "*", "/", "%", "||", "LIKE", "AND", "OR",
+];
+
+/// A column or table reference: `u.name` is `"u"."name"`.
+fn reference(name: &str) -> String {
+    if name == "*" {
+        return name.to_string();
+    }
+    let parts: Vec<String> = name
+        .split('.')
+        .map(|part| match part {
+            "*" => part.to_string(),
+            _ => format!("\"{}\"", part.replace('"', "\"\"")),
+        })
+        .collect();
+    parts.join(".")
+}
+
+/// A condition or value. Operators like `(= a 1)` and `(and ...)` are
+/// written infix, and parenthesized when nested in another expression.
+fn expression(sexp: &Sexp, nested: bool) -> String {
+    let items = match sexp {
+        Sexp::Nil => return "NULL".to_string(),
+        Sexp::Atom(atom) if atom.starts_with('"') || super::is_number(atom) => {
+            return literal(sexp)
+        }
+        Sexp::Atom(atom) => {
+            return match keyword(atom).as_str() {
+                word @ ("TRUE" | "FALSE" | "NULL") => word.to_string(),
+                _ => reference(atom),
+            }
+        }
+        Sexp::List(_) => sexp.items(),
+    };
+    let (name, operands) = match items.split_first() {
+        Some((Sexp::Atom(name), operands)) => (name, operands),
+        _ => return literal(sexp),
+    };
+    let operator = keyword(name);
+    let operand = |sexp| expression(sexp, true);
+    let written = match (operator.as_str(), operands) {
+        ("SELECT", _) => return format!("({})", select(sexp)),
+        ("NOT", [negated]) => format!("NOT {}", operand(negated)),
+        ("IS NULL" | "IS NOT NULL", [value]) => format!("{} {}", operand(value), operator),
+        ("IN" | "NOT IN", [value, list @ ..]) => {
+            let list = match list {
+                [query] if head(query).as_deref() == Some("SELECT") => select(query),
+                _ => {
+                    let values: Vec<String> = list.iter().map(operand).collect();
+                    values.join(", ")
+                }
+            };
+            format!("{} {} ({})", operand(value), operator, list)
+        }
+        ("BETWEEN", [value, low, high]) => format!(
+            "{} BETWEEN {} AND {}",
+            operand(value),
+            operand(low),
+            operand(high)
+        ),
+        (operator, [_, _, ..]) if OPERATORS.contains(&operator) => {
+            let operands: Vec<String> = operands.iter().map(operand).collect();
+            operands.join(&format!(" {} ", operator))
+        }
+        // Anything else is a function call, like `(count *)`.
+        (_, _) => {
+            let args: Vec<String> = operands.iter().map(|arg| expression(arg, false)).collect();
+            return format!("{}({})", name.to_ascii_uppercase(), args.join(", "));
+        }
+    };
+    if nested {
+        format!("({})", written)
+    } else {
+        written
+    }
+}
+
+/// A selected column or table, with `(as thing alias)` for an alias.
+fn aliased(sexp: &Sexp) -> String {
+    match (head(sexp).as_deref(), sexp.items()) {
+        (Some("AS"), [_, thing, alias]) => {
+            format!("{} AS {}", expression(thing, true), identifier(alias))
+        }
+        _ => expression(sexp, false),
+    }
+}
+
+/// A `FROM` item: a table, an aliased table or subquery, or a join like
+/// `(left-join posts (= users.id posts.user_id))`. A join without a
+/// condition, like `(cross_join tags)`, is just the join and its tables.
+fn source(sexp: &Sexp) -> String {
+    match head(sexp) {
+        Some(join) if join.ends_with("JOIN") => match sexp.items() {
+            [_, table, condition] => format!(
+                "{} {} ON {}",
+                join,
+                aliased(table),
+                expression(condition, false)
+            ),
+            [_, tables @ ..] => {
+                let tables: Vec<String> = tables.iter().map(aliased).collect();
+                format!("{} {}", join, tables.join(" "))
+            }
+            [] => unreachable!(),
+        },
+        _ => aliased(sexp),
+    }
+}
+
+/// A query: the elements after `SELECT`, or all of them without it.
+fn select(sexp: &Sexp) -> String {
+    let mut query = String::from("SELECT");
+    let mut rest = match head(sexp).as_deref() {
+        Some("SELECT") => &sexp.items()[1..],
+        _ => sexp.items(),
+    };
+    if let Some((Sexp::Atom(distinct), tail)) = rest.split_first() {
+        if keyword(distinct) == "DISTINCT" {
+            query.push_str(" DISTINCT");
+            rest = tail;
+        }
+    }
+    let mut clauses = vec![("SELECT".to_string(), vec![])];
+    for item in rest {
+        match item {
+            Sexp::Atom(atom) if CLAUSES.contains(&keyword(atom).as_str()) => {
+                clauses.push((keyword(atom), vec![]))
+            }
+            _ => clauses.last_mut().unwrap().1.push(item.clone()),
+        }
+    }
+    for (n, (clause, terms)) in clauses.iter().enumerate() {
+        if n > 0 {
+            query.push(' ');
+            query.push_str(clause);
+        }
+        if let ("WHERE" | "HAVING", [_, _, ..]) = (clause.as_str(), terms.as_slice()) {
+            let mut conditions = vec![Sexp::atom("and")];
+            conditions.extend(terms.iter().cloned());
+            query.push(' ');
+            query.push_str(&expression(&Sexp::list(conditions), false));
+            continue;
+        }
+        for (n, term) in terms.iter().enumerate() {
+            let join = clause == "FROM" && head(term).is_some_and(|head| head.ends_with("JOIN"));
+            // A join follows the table before it without a comma.
+            query.push_str(if n == 0 || join { " " } else { ", " });
+            query.push_str(&match clause.as_str() {
+                "SELECT" => aliased(term),
+                "FROM" => source(term),
+                "ORDER BY" => match (head(term).as_deref(), term.items()) {
+                    (Some(order @ ("ASC" | "DESC")), [_, column]) => {
+                        format!("{} {}", expression(column, false), order)
+                    }
+                    _ => expression(term, false),
+                },
+                _ => expression(term, false),
+            });
+        }
+    }
+    query
+}
+
+impl Sexp {
+    /// The tree as SQL DDL: `(CREATE_TABLE users (id INTEGER PRIMARY_KEY)
+    /// (email (VARCHAR 255) NOT_NULL UNIQUE))` becomes a `CREATE TABLE`
+    /// statement with those columns, and `(CREATE_INDEX name table
+    /// columns...)` or `CREATE_UNIQUE_INDEX` an index. A column is its
+    /// name, its type and its constraints, where keywords are written with
+    /// `_` for spaces and `(DEFAULT value)` and `(REFERENCES table column)`
+    /// take arguments. `(PRIMARY_KEY ...)`, `(UNIQUE ...)` and
+    /// `(FOREIGN_KEY columns... (REFERENCES ...))` among the columns are
+    /// table constraints. Names are double-quoted, and a list of statements
+    /// is written one after the other. Any other statement or constraint
+    /// is written as its keyword and its elements, so `(DROP_TABLE users)`
+    /// is `DROP TABLE users;` and `(CHECK "x > 0")` is `CHECK 'x > 0'`.
+    pub fn to_sql_ddl(&self) -> String {
+        match self.items().first() {
+            Some(Sexp::Atom(_)) => statement(self),
+            _ => {
+                let statements: Vec<String> = self.items().iter().map(statement).collect();
+                statements.join("\n")
+            }
+        }
+    }
+
+    /// The tree as a SQL query: `(SELECT name email FROM users WHERE (and
+    /// (= active true) (> age 18)) ORDER_BY (desc age) LIMIT 10)`. The
+    /// clauses `FROM`, `WHERE`, `GROUP_BY`, `HAVING`, `ORDER_BY` and
+    /// `LIMIT` are atoms among the elements, each followed by its terms,
+    /// with several `WHERE` or `HAVING` conditions all required.
+    /// In expressions `(= a b)`, `(and ...)`, `(or ...)` and other
+    /// operators are written infix, nested ones in parentheses, besides
+    /// `(not x)`, `(in x values...)`, `(between x low high)` and
+    /// `(is_null x)`, and any other list is a function call like `(count
+    /// *)`. Atoms are column names, double-quoted, except numbers, strings
+    /// and `true`, `false` and `null`. `(as thing alias)` names a column or
+    /// table, `FROM` takes joins like `(left_join table condition)`, and a
+    /// nested `(SELECT ...)` is a subquery. Without the `SELECT` head
+    /// all the elements are the query's, and a clause with nothing after
+    /// it is written as it is, for the database to reject.
+    pub fn to_sql_select(&self) -> String {
+        select(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_sql_ddl() {
+        let sexp = Sexp::of_str(
+            r#"((CREATE_TABLE users
+                  (id INTEGER PRIMARY_KEY)
+                  (email (VARCHAR 255) NOT_NULL UNIQUE)
+                  (role TEXT (DEFAULT "it's me"))
+                  (team_id INTEGER (REFERENCES teams id)))
+                (create-table "order items"
+                  (order_id INTEGER)
+                  ("user" (NUMERIC 10 2))
+                  (PRIMARY_KEY order_id "user"))
+                (CREATE_UNIQUE_INDEX users_email users email))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_sql_ddl(),
+            r#"CREATE TABLE "users" (
+  "id" INTEGER PRIMARY KEY,
+  "email" VARCHAR(255) NOT NULL UNIQUE,
+  "role" TEXT DEFAULT 'it''s me',
+  "team_id" INTEGER REFERENCES "teams" ("id")
+);
+
+CREATE TABLE "order items" (
+  "order_id" INTEGER,
+  "user" NUMERIC(10, 2),
+  PRIMARY KEY ("order_id", "user")
+);
+
+CREATE UNIQUE INDEX "users_email" ON "users" ("email");
+"#
+        );
+        let index = Sexp::of_str("(CREATE_INDEX by_name users last first)").unwrap();
+        assert_eq!(
+            index.to_sql_ddl(),
+            "CREATE INDEX \"by_name\" ON \"users\" (\"last\", \"first\");\n"
+        );
+        let ddl = |input| Sexp::of_str(input).unwrap().to_sql_ddl();
+        assert_eq!(ddl("(DROP_TABLE users)"), "DROP TABLE users;\n");
+        assert_eq!(
+            ddl("(CREATE_TABLE t (a) (b INTEGER (CHECK \"b > 0\")) (FOREIGN_KEY))"),
+            "CREATE TABLE \"t\" (\n  \"a\",\n  \"b\" INTEGER CHECK 'b > 0',\n  FOREIGN KEY\n);\n"
+        );
+        assert_eq!(ddl("(CREATE_INDEX i)"), "CREATE INDEX i;\n");
+    }
+
+    #[test]
+    fn test_to_sql_select() {
+        let simple = Sexp::of_str(r#"(SELECT * FROM users WHERE (= name "O'Brien"))"#).unwrap();
+        assert_eq!(
+            simple.to_sql_select(),
+            r#"SELECT * FROM "users" WHERE "name" = 'O''Brien'"#
+        );
+
+        let compound = Sexp::of_str(
+            r#"(SELECT DISTINCT u.name (as (count p.id) posts)
+                FROM (as users u) (left_join (as posts p) (= u.id p.user_id))
+                WHERE (= u.active true)
+                      (or (> u.age 18) (is_null u.age))
+                      (not (in u.id (SELECT user_id FROM bans)))
+                GROUP_BY u.name
+                ORDER_BY (desc posts) u.name
+                LIMIT 10)"#,
+        )
+        .unwrap();
+        assert_eq!(
+            compound.to_sql_select(),
+            "SELECT DISTINCT \"u\".\"name\", COUNT(\"p\".\"id\") AS \"posts\" \
+             FROM \"users\" AS \"u\" LEFT JOIN \"posts\" AS \"p\" ON \"u\".\"id\" = \"p\".\"user_id\" \
+             WHERE (\"u\".\"active\" = TRUE) AND ((\"u\".\"age\" > 18) OR (\"u\".\"age\" IS NULL)) \
+             AND (NOT (\"u\".\"id\" IN (SELECT \"user_id\" FROM \"bans\"))) \
+             GROUP BY \"u\".\"name\" ORDER BY \"posts\" DESC, \"u\".\"name\" LIMIT 10"
+        );
+        let tables =
+            Sexp::of_str("(SELECT a.x b.y FROM a b WHERE (and (= a.id b.id) (<> a.x 0)))").unwrap();
+        assert_eq!(
+            tables.to_sql_select(),
+            r#"SELECT "a"."x", "b"."y" FROM "a", "b" WHERE ("a"."id" = "b"."id") AND ("a"."x" <> 0)"#
+        );
+        let select = |input| Sexp::of_str(input).unwrap().to_sql_select();
+        assert_eq!(select("(SELECT FROM users)"), r#"SELECT FROM "users""#);
+        assert_eq!(
+            select("(x FROM a (cross_join b) WHERE ((y)))"),
+            r#"SELECT "x" FROM "a" CROSS JOIN "b" WHERE ((y))"#
+        );
+    }
+}
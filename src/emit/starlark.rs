@@ -0,0 +1,320 @@
+use super::{atom, is_identifier, quoted, Atom};
+use crate::Sexp;
+use anyhow::{anyhow, Error};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "break", "continue", "def", "elif", "else", "for", "if", "in",
+    "lambda", "not", "or", "pass", "return", "while",
+];
+
+fn is_name(name: &str) -> bool {
+    is_identifier(name) && !KEYWORDS.contains(&name)
+}
+
+/// The function a list calls, when it's headed by a name like `glob` or
+/// `native.cc_library`.
+fn callee(sexp: &Sexp) -> Option<&str> {
+    match sexp.items().first() {
+        Some(Sexp::Atom(head)) if head.split('.').all(is_name) => Some(head),
+        _ => None,
+    }
+}
+
+/// The entries of a list of `("key" value)` pairs, which is read as a dict.
+fn entries(sexp: &Sexp) -> Option<Vec<(&str, &Sexp)>> {
+    let entries: Option<Vec<(&str, &Sexp)>> = sexp
+        .items()
+        .iter()
+        .map(|entry| match entry.items() {
+            [Sexp::Atom(key), value] if key.starts_with('"') => Some((key.as_str(), value)),
+            _ => None,
+        })
+        .collect();
+    entries.filter(|entries| !entries.is_empty())
+}
+
+fn argument(arg: &Sexp) -> String {
+    match arg.items().split_first() {
+        Some((Sexp::Atom(key), values)) if is_name(key) => {
+            let value = match values {
+                [value] => value.to_starlark(),
+                _ => list(values),
+            };
+            format!("{} = {}", key, value)
+        }
+        _ => arg.to_starlark(),
+    }
+}
+
+fn list(items: &[Sexp]) -> String {
+    let items: Vec<String> = items.iter().map(Sexp::to_starlark).collect();
+    format!("[{}]", items.join(", "))
+}
+
+impl Sexp {
+    /// The tree as Starlark, as in Bazel and Buck2 `BUILD` files. A list
+    /// headed by a name is a call, with the elements that are lists headed
+    /// by a name as keyword arguments: `(cc_library (name core) (srcs a.cc
+    /// b.cc))` becomes `cc_library(name = "core", srcs = ["a.cc", "b.cc"])`.
+    /// A keyword argument with a single element has just that as its value,
+    /// so a one-element list needs a list of its own, as in
+    /// `(srcs ("a.cc"))`. A list of `("key" value)` pairs is a dict, and any
+    /// other list a list. Numbers, `True`, `False` and `None` stay as they
+    /// are and every other atom becomes a string. A list of calls, like a
+    /// whole `BUILD` file, is written as one statement per line.
+    pub fn to_starlark(&self) -> String {
+        match self {
+            Sexp::Nil => "None".to_string(),
+            Sexp::Atom(text) => match atom(text) {
+                Atom::Number(number) => number.to_string(),
+                Atom::Symbol(name @ ("True" | "False" | "None")) => name.to_string(),
+                Atom::Symbol(name) => quoted(name, '"'),
+                Atom::String(text) => quoted(&text, '"'),
+            },
+            Sexp::List(_) => {
+                if let Some(callee) = callee(self) {
+                    let args: Vec<String> = self.items()[1..].iter().map(argument).collect();
+                    return format!("{}({})", callee, args.join(", "));
+                }
+                if let Some(entries) = entries(self) {
+                    let entries: Vec<String> = entries
+                        .iter()
+                        .map(|(key, value)| format!("{}: {}", key, value.to_starlark()))
+                        .collect();
+                    return format!("{{{}}}", entries.join(", "));
+                }
+                let items = self.items();
+                if !items.is_empty() && items.iter().all(|item| callee(item).is_some()) {
+                    let statements: Vec<String> =
+                        items.iter().map(|item| item.to_starlark() + "\n").collect();
+                    return statements.concat();
+                }
+                list(items)
+            }
+        }
+    }
+
+    /// Reads Starlark calls, lists, dicts and literals back into the shape
+    /// `to_starlark` writes, with several statements read as a list of
+    /// them. Strings become string atoms and names like variable references
+    /// symbols, which `to_starlark` writes as strings, so only output of
+    /// `to_starlark` reads back to the same Starlark.
+    pub fn of_starlark(input: &str) -> Result<Sexp, Error> {
+        let mut reader = Reader {
+            input,
+            chars: input.char_indices().peekable(),
+        };
+        let mut statements = vec![];
+        while reader.peek().is_some() {
+            statements.push(reader.expression()?);
+        }
+        match statements.len() {
+            0 => Err(anyhow!("There are no statements")),
+            1 => Ok(statements.pop().unwrap()),
+            _ => Ok(Sexp::list(statements)),
+        }
+    }
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Reader<'a> {
+    /// Skips whitespace and comments and peeks at what follows.
+    fn peek(&mut self) -> Option<char> {
+        loop {
+            while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            if self.chars.next_if(|&(_, c)| c == '#').is_none() {
+                return self.chars.peek().map(|&(_, c)| c);
+            }
+            while self.chars.next_if(|&(_, c)| c != '\n').is_some() {}
+        }
+    }
+
+    fn at(&mut self) -> usize {
+        self.chars.peek().map_or(self.input.len(), |&(at, _)| at)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            Some(c) => Err(anyhow!(
+                "Expected {:?} at byte {}, found {:?}",
+                expected,
+                self.at(),
+                c
+            )),
+            None => Err(anyhow!(
+                "Expected {:?}, found the end of the input",
+                expected
+            )),
+        }
+    }
+
+    // Comma-separated items up to `close`, which is consumed. A trailing
+    // comma is allowed, as in Python.
+    fn items<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut items = vec![];
+        loop {
+            if self.peek() == Some(close) {
+                self.chars.next();
+                return Ok(items);
+            }
+            items.push(item(self)?);
+            match self.peek() {
+                Some(',') => {
+                    self.chars.next();
+                }
+                Some(c) if c == close => {}
+                Some(c) => return Err(anyhow!("Unexpected {:?} at byte {}", c, self.at())),
+                None => return Err(anyhow!("Expected {:?}, found the end of the input", close)),
+            }
+        }
+    }
+
+    fn expression(&mut self) -> Result<Sexp, Error> {
+        let at = self.at();
+        match self.peek() {
+            None => Err(anyhow!(
+                "Expected an expression, found the end of the input"
+            )),
+            Some('"') | Some('\'') => Ok(Sexp::string(&self.string()?)),
+            Some('[') => {
+                self.chars.next();
+                Ok(Sexp::list(self.items(']', Self::expression)?))
+            }
+            Some('{') => {
+                self.chars.next();
+                let entries = self.items('}', |reader| {
+                    let key = reader.expression()?;
+                    reader.expect(':')?;
+                    Ok(Sexp::list(vec![key, reader.expression()?]))
+                })?;
+                Ok(Sexp::list(entries))
+            }
+            Some(_) => {
+                let word = self.word();
+                if word.is_empty() {
+                    return Err(anyhow!("Unexpected input at byte {}", at));
+                }
+                if self.peek() != Some('(') {
+                    return Ok(Sexp::atom(word));
+                }
+                self.chars.next();
+                let mut call = vec![Sexp::atom(word)];
+                call.extend(self.items(')', Self::argument)?);
+                Ok(Sexp::list(call))
+            }
+        }
+    }
+
+    fn argument(&mut self) -> Result<Sexp, Error> {
+        let value = self.expression()?;
+        match (&value, self.peek()) {
+            (Sexp::Atom(key), Some('=')) if is_name(key) => {
+                self.chars.next();
+                let mut argument = vec![value.clone()];
+                match self.expression()? {
+                    list @ Sexp::List(_)
+                        if callee(&list).is_none()
+                            && entries(&list).is_none()
+                            && list.items().len() != 1 =>
+                    {
+                        argument.extend(list.items().iter().cloned())
+                    }
+                    value => argument.push(value),
+                }
+                Ok(Sexp::list(argument))
+            }
+            _ => Ok(value),
+        }
+    }
+
+    fn word(&mut self) -> &'a str {
+        let start = self.at();
+        let mut end = start;
+        while let Some((at, c)) = self
+            .chars
+            .next_if(|&(_, c)| c.is_ascii_alphanumeric() || "_.+-".contains(c))
+        {
+            end = at + c.len_utf8();
+        }
+        &self.input[start..end]
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let (_, quote) = self.chars.next().unwrap();
+        let mut text = String::new();
+        loop {
+            match self.chars.next().map(|(_, c)| c) {
+                Some(c) if c == quote => return Ok(text),
+                Some('\\') => match self.chars.next().map(|(_, c)| c) {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('u') => {
+                        let hex: String = (0..4)
+                            .filter_map(|_| self.chars.next())
+                            .map(|(_, c)| c)
+                            .collect();
+                        let code = u32::from_str_radix(&hex, 16)?;
+                        text.push(char::from_u32(code).ok_or_else(|| anyhow!("Invalid escape"))?);
+                    }
+                    Some(c) => text.push(c),
+                    None => return Err(anyhow!("Unterminated string")),
+                },
+                Some(c) => text.push(c),
+                None => return Err(anyhow!("Unterminated string")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_starlark() {
+        let sexp = Sexp::of_str(
+            r#"((load "//tools:defs.bzl" my_rule)
+                (cc_library (name core) (srcs (glob ("*.cc"))) (deps //base :util) (linkstatic True)
+                  (copts ("-O2")) (defines (("DEBUG" 0)))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_starlark(),
+            "load(\"//tools:defs.bzl\", \"my_rule\")\n\
+             cc_library(name = \"core\", srcs = glob([\"*.cc\"]), deps = [\"//base\", \":util\"], \
+             linkstatic = True, copts = [\"-O2\"], defines = {\"DEBUG\": 0})\n"
+        );
+    }
+
+    #[test]
+    fn test_of_starlark_round_trip() {
+        let build = "load(\"//tools:defs.bzl\", \"my_rule\")\n\
+             cc_library(name = \"core\", srcs = glob([\"*.cc\"]), deps = [\"//base\", \":util\"], \
+             linkstatic = True, copts = [\"-O2\"], defines = {\"DEBUG\": 0}, empty = [])\n";
+        let sexp = Sexp::of_starlark(build).unwrap();
+        assert_eq!(sexp.to_starlark(), build);
+
+        let formatted = "# A comment\ncc_binary(\n    name = 'main',\n    deps = [\n        \":core\",\n    ],\n)\n";
+        let sexp = Sexp::of_starlark(formatted).unwrap();
+        assert_eq!(
+            sexp.to_string(),
+            r#"(cc_binary (name "main") (deps (":core")))"#
+        );
+        assert!(Sexp::of_starlark("f(a = )").is_err());
+    }
+}
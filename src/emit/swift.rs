@@ -0,0 +1,314 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, unquoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "continue",
+    "default",
+    "defer",
+    "do",
+    "else",
+    "enum",
+    "extension",
+    "fallthrough",
+    "false",
+    "for",
+    "func",
+    "guard",
+    "if",
+    "import",
+    "in",
+    "init",
+    "is",
+    "let",
+    "nil",
+    "protocol",
+    "repeat",
+    "return",
+    "self",
+    "struct",
+    "super",
+    "switch",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "var",
+    "where",
+    "while",
+];
+
+/// Whether `name` is an identifier, or a path of them like `Shape.circle`.
+fn is_name(name: &str) -> bool {
+    name.split('.')
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+/// `text` as a Swift string. Its Unicode escapes are `\u{...}`, and
+/// escaping every `\` keeps a `\(` from starting an interpolation.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => string(&text),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "nil".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => string(name),
+    }
+}
+
+/// `forms` in braces after `opening`, as in `do { ... }`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}{{\n{}\n}}", opening, indented(forms, "    "))
+}
+
+/// A name with its type, from `(name Type)`, or a bare name taking `Any`.
+fn typed(item: &Sexp) -> Option<(String, String)> {
+    match item {
+        Sexp::Atom(name) if is_name(name) => Some((name.to_string(), "Any".to_string())),
+        Sexp::Atom(_) => None,
+        _ => match item.items() {
+            [Sexp::Atom(name), Sexp::Atom(ty)] if is_name(name) => {
+                Some((name.to_string(), unquoted(ty)))
+            }
+            _ => None,
+        },
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    let all =
+        |forms: &[Sexp]| -> Vec<String> { forms.iter().map(|form| write(form, &SWIFT)).collect() };
+    match (head, rest) {
+        ("struct", [Sexp::Atom(name), fields @ ..]) if is_name(name) => {
+            let fields = fields
+                .iter()
+                .map(|field| typed(field).map(|(name, ty)| format!("let {}: {}", name, ty)))
+                .collect::<Option<Vec<_>>>()?;
+            Some(block(&format!("struct {} ", name), &fields))
+        }
+        // An enum's cases are names, or calls of a name on the types of its
+        // associated values.
+        ("enum", [Sexp::Atom(name), cases @ ..]) if is_name(name) => {
+            let mut lines = vec![];
+            for case in cases {
+                match case.items().split_first() {
+                    None => lines.push(format!("case {}", write(case, &SWIFT))),
+                    Some((Sexp::Atom(case), types)) => {
+                        let types: Vec<String> =
+                            types.iter().map(|ty| ty.as_text()).collect::<Option<_>>()?;
+                        lines.push(format!("case {}({})", case, types.join(", ")))
+                    }
+                    Some(_) => return None,
+                }
+            }
+            Some(block(&format!("enum {} ", name), &lines))
+        }
+        // A bare case is an implicit member, as in `.circle`.
+        ("case", [Sexp::Atom(name), values @ ..]) if is_name(name) => {
+            let case = if name.contains('.') {
+                name.to_string()
+            } else {
+                format!(".{}", name)
+            };
+            match values {
+                [] => Some(case),
+                _ => Some(format!("{}({})", case, all(values).join(", "))),
+            }
+        }
+        // Parameters take no argument labels, to be called as the other
+        // calls are, and a typed name returns its type.
+        ("define", [signature @ Sexp::List(_), body @ ..]) => match signature.items() {
+            [name, params @ ..] => {
+                let (name, returns) = match name {
+                    Sexp::Atom(name) if is_name(name) => (name.to_string(), None),
+                    _ => typed(name).map(|(name, ty)| (name, Some(ty)))?,
+                };
+                let params = params
+                    .iter()
+                    .map(|param| typed(param).map(|(name, ty)| format!("_ {}: {}", name, ty)))
+                    .collect::<Option<Vec<_>>>()?;
+                let returns = returns.map(|ty| format!(" -> {}", ty)).unwrap_or_default();
+                let signature = format!("func {}({}){} ", name, params.join(", "), returns);
+                Some(block(&signature, &all(body)))
+            }
+            _ => None,
+        },
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "let {} = {}",
+            write(name, &SWIFT),
+            write(init, &SWIFT)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => {
+            Some(format!("{} = {}", write(name, &SWIFT), write(init, &SWIFT)))
+        }
+        ("return", [value]) => Some(format!("return {}", write(value, &SWIFT))),
+        ("begin", [_, ..]) => Some(block("do ", &all(rest))),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut forms = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => forms.push(format!(
+                        "let {} = {}",
+                        write(name, &SWIFT),
+                        write(init, &SWIFT)
+                    )),
+                    _ => return None,
+                }
+            }
+            forms.extend(all(body));
+            Some(block("do ", &forms))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if {} ", write(test, &SWIFT)),
+            &[write(consequent, &SWIFT)],
+        )),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let opening = match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => String::new(),
+                    _ => format!("if {} ", write(test, &SWIFT)),
+                };
+                branches.push(block(&opening, &all(consequent)));
+            }
+            Some(branches.join(" else "))
+        }
+        _ => None,
+    }
+}
+
+fn lambda(params: &[String], body: &[String]) -> String {
+    let params = match params {
+        [] => String::new(),
+        _ => format!(" {} in", params.join(", ")),
+    };
+    match body {
+        [single] => format!("{{{} {} }}", params, single),
+        _ => format!("{{{}\n{}\n}}", params, indented(body, "    ")),
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    if fields.is_empty() {
+        return "[:]".to_string();
+    }
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", string(key), value))
+        .collect();
+    format!("[{}]", fields.join(", "))
+}
+
+const SWIFT: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: Some(": "),
+    lambda,
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("[", "]"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as Swift, reading it as Scheme-style code: calls become
+    /// `f(args...)` and `(.method object args...)` method calls, with
+    /// `:label value` pairs as labelled arguments, so `(Point :x 1 :y 2)`
+    /// is a struct literal. `(struct Point (x Int) (y Int))` and `(enum
+    /// Shape circle (rect Int Int))` declare those, and `(case rect 1 2)`
+    /// is the case `.rect(1, 2)`. `(define ((f Int) (x Int)) ...)` becomes
+    /// `func f(_ x: Int) -> Int { ... }`, with untyped parameters taking
+    /// `Any` and an untyped name returning nothing, `define` of a variable
+    /// a `let`, `begin` and `let` `do` blocks, `if` with an alternative
+    /// `? :` and `cond` an `if`/`else` chain. `lambda` becomes `{ x in ...
+    /// }`, `(array ...)` an array literal, and `(table (key value)...)` a
+    /// dictionary with string keys.
+    pub fn to_swift_string(&self) -> String {
+        write(self, &SWIFT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_swift_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (struct Point (x Double) (y Double))
+                 (enum Shape circle (rect Double Double))
+                 (define ((norm Double) (p Point))
+                   (return (.squareRoot (+ (* (.-x p) (.-x p)) (* (.-y p) (.-y p))))))
+                 (define origin (Point :x 0 :y 0))
+                 (define shapes (array (case Shape.circle) (case Shape.rect 1 2)))
+                 (define config (table (name "demo") (port 80)))
+                 (let ((doubled (.map (array 1 2 3) (lambda (x) (* x 2)))))
+                   (cond ((> (.-count doubled) 2) (print "many")) (else (print "few"))))
+                 (print (if (== (norm origin) 0) "home" "moved") "say \"\\(hi)\""))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_swift_string(),
+            r#"do {
+    struct Point {
+        let x: Double
+        let y: Double
+    }
+    enum Shape {
+        case circle
+        case rect(Double, Double)
+    }
+    func norm(_ p: Point) -> Double {
+        return (p.x * p.x + p.y * p.y).squareRoot()
+    }
+    let origin = Point(x: 0, y: 0)
+    let shapes = [Shape.circle, Shape.rect(1, 2)]
+    let config = ["name": "demo", "port": 80]
+    do {
+        let doubled = [1, 2, 3].map({ x in x * 2 })
+        if doubled.count > 2 {
+            print("many")
+        } else {
+            print("few")
+        }
+    }
+    print(norm(origin) == 0 ? "home" : "moved", "say \"\\(hi)\"")
+}"#
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use super::{atom, Atom};
+use crate::Sexp;
+
+/// An element of a value as it is when it's the only one, since systemd
+/// takes the rest of the line, and quoted when one of several that it
+/// would otherwise split, as in `ExecStart=/bin/echo "hello world"`.
+fn element(sexp: &Sexp, alone: bool) -> String {
+    let text = match sexp {
+        Sexp::Atom(text) => match atom(text) {
+            Atom::Number(number) => number.to_string(),
+            Atom::String(text) => text,
+            Atom::Symbol(text) => text.to_string(),
+        },
+        _ => sexp.to_compact_string(),
+    };
+    if alone || !(text.is_empty() || text.contains(|c: char| c.is_whitespace() || c == '"')) {
+        text
+    } else {
+        format!("\"{}\"", text.replace('\\', r"\\").replace('"', "\\\""))
+    }
+}
+
+/// A section's `[Name]` header and its `Key=Value` lines, where a key
+/// without a value resets what it was, as an empty `ExecStart=` does.
+fn section(name: &str, entries: &[Sexp]) -> String {
+    let mut lines = vec![format!("[{}]", name)];
+    for entry in entries {
+        if let Some((key, values)) = entry.items().split_first() {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| element(value, values.len() == 1))
+                .collect();
+            lines.push(format!("{}={}", element(key, true), values.join(" ")));
+        }
+    }
+    lines.join("\n")
+}
+
+impl Sexp {
+    /// The tree as a systemd unit file: `(unit (Unit (Description "Web
+    /// app")) (Service (ExecStart /usr/bin/app --port 8080)) (Install
+    /// (WantedBy multi-user.target)))` has a `[Unit]`, `[Service]` and
+    /// `[Install]` section, each `(Key values...)` in one a line
+    /// `Key=value`, and a single `(Service ...)` is a unit of that section
+    /// alone. A value of several elements is them joined by spaces, those
+    /// with spaces of their own quoted, and one of a single element is it
+    /// as it is. Keys can repeat, as `ExecStartPre` does, and one without
+    /// a value resets it.
+    pub fn to_systemd_unit(&self) -> String {
+        let sections = match self.items().split_first() {
+            Some((Sexp::Atom(head), sections)) if head == "unit" => sections,
+            Some((Sexp::Atom(_), _)) => std::slice::from_ref(self),
+            _ => self.items(),
+        };
+        let sections: Vec<String> = sections
+            .iter()
+            .filter_map(|form| match form.items().split_first() {
+                Some((name @ Sexp::Atom(_), entries)) => {
+                    Some(section(&element(name, true), entries))
+                }
+                _ => None,
+            })
+            .collect();
+        let mut unit = sections.join("\n\n");
+        unit.push('\n');
+        unit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn test_to_systemd_unit() {
+        let sexp = Sexp::of_str(
+            r#"(Service
+                 (Type oneshot)
+                 (ExecStart)
+                 (ExecStart /bin/echo "hello world" --port 8080)
+                 (Environment "GREETING=hi there" MODE=demo))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_systemd_unit(),
+            r#"[Service]
+Type=oneshot
+ExecStart=
+ExecStart=/bin/echo "hello world" --port 8080
+Environment="GREETING=hi there" MODE=demo
+"#
+        );
+
+        let sexp = Sexp::of_str(
+            r#"(unit
+                 (Unit (Description "Demo web app") (After network.target))
+                 (Service (ExecStart /bin/echo serving) (Restart on-failure) (RestartSec 5))
+                 (Install (WantedBy multi-user.target)))"#,
+        )
+        .unwrap();
+        let unit = sexp.to_systemd_unit();
+        assert_eq!(
+            unit,
+            r#"[Unit]
+Description=Demo web app
+After=network.target
+
+[Service]
+ExecStart=/bin/echo serving
+Restart=on-failure
+RestartSec=5
+
+[Install]
+WantedBy=multi-user.target
+"#
+        );
+
+        // Check that systemd takes it, where there's one to do that.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("demo.service");
+        fs::write(&path, &unit).unwrap();
+        if let Ok(output) = Command::new("systemd-analyze")
+            .arg("verify")
+            .arg(&path)
+            .output()
+        {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+}
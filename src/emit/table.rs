@@ -0,0 +1,67 @@
+use crate::Sexp;
+
+fn cell(sexp: &Sexp) -> String {
+    let text = sexp.as_text().unwrap_or_else(|| sexp.to_compact_string());
+    let text: Vec<&str> = text.lines().collect();
+    text.join(" ").replace('|', "\\|")
+}
+
+impl Sexp {
+    /// An alist like `((name core) (version 1.2))` as a two-column ASCII
+    /// table, one row per pair, with columns as wide as their widest cell.
+    /// An element with more than one value has them all in its value cell,
+    /// and an element that isn't a list is a key with an empty value.
+    pub fn to_terminal_table(&self) -> String {
+        let rows: Vec<(String, String)> = self
+            .items()
+            .iter()
+            .map(|pair| match pair.items().split_first() {
+                Some((key, values)) => {
+                    let values: Vec<String> = values.iter().map(cell).collect();
+                    (cell(key), values.join(" "))
+                }
+                None => (cell(pair), String::new()),
+            })
+            .collect();
+        let width = |column: fn(&(String, String)) -> &String| {
+            rows.iter()
+                .map(|row| column(row).chars().count())
+                .max()
+                .unwrap_or(0)
+        };
+        let (keys, values) = (width(|row| &row.0), width(|row| &row.1));
+
+        let border = format!("+{}+{}+\n", "-".repeat(keys + 2), "-".repeat(values + 2));
+        let mut table = border.clone();
+        for (key, value) in &rows {
+            let pad =
+                |text: &str, width| format!("{}{}", text, " ".repeat(width - text.chars().count()));
+            table.push_str(&format!(
+                "| {} | {} |\n",
+                pad(key, keys),
+                pad(value, values)
+            ));
+        }
+        table.push_str(&border);
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_terminal_table() {
+        let sexp = Sexp::of_str(r#"((name core) (flags -w +a) ("a|b" (c d)) bare)"#).unwrap();
+        assert_eq!(
+            sexp.to_terminal_table(),
+            "+-------+-------+\n\
+             | name  | core  |\n\
+             | flags | -w +a |\n\
+             | a\\|b  | (c d) |\n\
+             | bare  |       |\n\
+             +-------+-------+\n"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use super::{atom, unquoted, Atom};
+use crate::Sexp;
+use serde_json::{json, Map, Value};
+
+/// The state format these are, and the Terraform that wrote them unless
+/// the state says otherwise.
+const VERSION: u64 = 4;
+const TERRAFORM_VERSION: &str = "1.5.7";
+
+/// A resource's fields that say how Terraform manages it rather than being
+/// attributes of it.
+const META: &[&str] = &["depends_on", "provider"];
+
+fn key(field: &Sexp) -> Option<String> {
+    match field.items().first() {
+        Some(Sexp::Atom(head)) => Some(unquoted(head)),
+        _ => None,
+    }
+}
+
+/// Whether `items` are all fields like `(ami "ami-123")`.
+fn is_object(items: &[Sexp]) -> bool {
+    !items.is_empty()
+        && items
+            .iter()
+            .all(|item| matches!(item, Sexp::List(_)) && key(item).is_some())
+}
+
+fn scalar(sexp: &Sexp) -> Value {
+    let text = match sexp {
+        Sexp::Atom(text) => text,
+        _ => return Value::Null,
+    };
+    match atom(text) {
+        Atom::Number(number) => {
+            serde_json::from_str(number).unwrap_or_else(|_| Value::String(number.to_string()))
+        }
+        Atom::Symbol("true") => Value::Bool(true),
+        Atom::Symbol("false") => Value::Bool(false),
+        Atom::Symbol("null") | Atom::Symbol("nil") => Value::Null,
+        Atom::Symbol(text) => Value::String(text.to_string()),
+        Atom::String(text) => Value::String(text),
+    }
+}
+
+/// What the elements after a field's name stand for: an object when
+/// they're fields themselves, a single value, or a list.
+fn value(elements: &[Sexp]) -> Value {
+    match elements {
+        _ if is_object(elements) => object(elements),
+        [single @ Sexp::Atom(_)] => scalar(single),
+        _ => Value::Array(elements.iter().map(item).collect()),
+    }
+}
+
+fn item(sexp: &Sexp) -> Value {
+    match sexp {
+        Sexp::List(_) => value(sexp.items()),
+        _ => scalar(sexp),
+    }
+}
+
+fn fields<'a>(fields: impl IntoIterator<Item = &'a Sexp>) -> Map<String, Value> {
+    let mut object = Map::new();
+    for field in fields {
+        if let Some(key) = key(field) {
+            object.insert(key, value(&field.items()[1..]));
+        }
+    }
+    object
+}
+
+fn object(fields: &[Sexp]) -> Value {
+    Value::Object(self::fields(fields))
+}
+
+/// The type Terraform records for an output's value.
+fn type_of(value: &Value) -> Value {
+    match value {
+        Value::Bool(_) => json!("bool"),
+        Value::Number(_) => json!("number"),
+        Value::String(_) | Value::Null => json!("string"),
+        Value::Array(items) => json!(["tuple", items.iter().map(type_of).collect::<Vec<_>>()]),
+        Value::Object(fields) => {
+            let fields: Map<String, Value> = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), type_of(value)))
+                .collect();
+            json!(["object", fields])
+        }
+    }
+}
+
+/// The provider that manages a resource: its `(provider source)`, or, as
+/// Terraform assumes, the HashiCorp one named by its type's prefix, as in
+/// `aws` for `aws_instance`.
+fn provider(kind: &str, meta: Option<&Value>) -> String {
+    let source = match meta {
+        Some(Value::String(source)) if source.contains('/') => source.clone(),
+        Some(Value::String(name)) => format!("registry.terraform.io/hashicorp/{}", name),
+        _ => {
+            let name = kind.split('_').next().unwrap_or(kind);
+            format!("registry.terraform.io/hashicorp/{}", name)
+        }
+    };
+    format!("provider[\"{}\"]", source)
+}
+
+fn resource(mode: &str, kind: &str, name: &str, fields: &[Sexp]) -> Value {
+    let (meta, attributes): (Vec<&Sexp>, Vec<&Sexp>) = fields
+        .iter()
+        .partition(|field| key(field).is_some_and(|key| META.contains(&key.as_str())));
+    let meta = self::fields(meta);
+    let dependencies = match meta.get("depends_on") {
+        Some(Value::Array(dependencies)) => dependencies.clone(),
+        Some(dependency) => vec![dependency.clone()],
+        None => vec![],
+    };
+    let mut instance = json!({
+        "schema_version": 0,
+        "attributes": self::fields(attributes),
+        "sensitive_attributes": [],
+    });
+    if !dependencies.is_empty() {
+        instance["dependencies"] = Value::Array(dependencies);
+    }
+    json!({
+        "mode": mode,
+        "type": kind,
+        "name": name,
+        "provider": provider(kind, meta.get("provider")),
+        "instances": [instance],
+    })
+}
+
+impl Sexp {
+    /// The tree as Terraform state JSON, for mock state files in tests:
+    /// `(state (resource aws_instance web (ami "ami-123") ...) (data aws_ami
+    /// ubuntu ...) (output ip "10.0.0.1"))`. Each resource and data source
+    /// becomes one instance with those fields as attributes, objects where
+    /// a field's elements are fields themselves and lists where there are
+    /// several. `(depends_on aws_vpc.main)` lists its dependencies, and
+    /// `(provider source)` sets its provider, which is otherwise the
+    /// HashiCorp one named by its type's prefix. Outputs record the type of
+    /// their value. Any other field is a property of the state, with
+    /// `version` 4, `terraform_version` 1.5.7, `serial` 1 and an empty
+    /// `lineage` where the state doesn't give them.
+    pub fn to_terraform_state(&self) -> String {
+        let forms = match self.items().split_first() {
+            Some((Sexp::Atom(head), forms)) if head == "state" => forms,
+            _ => self.items(),
+        };
+        let mut state = Map::new();
+        state.insert("version".to_string(), json!(VERSION));
+        state.insert("terraform_version".to_string(), json!(TERRAFORM_VERSION));
+        state.insert("serial".to_string(), json!(1));
+        state.insert("lineage".to_string(), json!(""));
+        let mut outputs = Map::new();
+        let mut resources = vec![];
+        for form in forms {
+            match form.items() {
+                [Sexp::Atom(head), Sexp::Atom(kind), Sexp::Atom(name), fields @ ..]
+                    if head == "resource" || head == "data" =>
+                {
+                    let mode = if head == "resource" {
+                        "managed"
+                    } else {
+                        "data"
+                    };
+                    resources.push(resource(mode, &unquoted(kind), &unquoted(name), fields));
+                }
+                [Sexp::Atom(head), Sexp::Atom(name), elements @ ..] if head == "output" => {
+                    let value = value(elements);
+                    outputs.insert(
+                        unquoted(name),
+                        json!({"value": value, "type": type_of(&value)}),
+                    );
+                }
+                [Sexp::Atom(head), elements @ ..] => {
+                    state.insert(unquoted(head), value(elements));
+                }
+                _ => {}
+            }
+        }
+        state.insert("outputs".to_string(), Value::Object(outputs));
+        state.insert("resources".to_string(), Value::Array(resources));
+        serde_json::to_string_pretty(&Value::Object(state)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_terraform_state() {
+        let sexp = Sexp::of_str(
+            r#"(state
+                 (serial 3)
+                 (lineage "3f2a9c1e-7d4b-4a8e-9c6f-1b2d3e4f5a6b")
+                 (resource aws_vpc main (cidr_block "10.0.0.0/16"))
+                 (resource aws_instance web
+                   (ami "ami-0c55b159")
+                   (instance_type t3.micro)
+                   (monitoring false)
+                   (security_groups sg-1 sg-2)
+                   (tags (Name web) (Tier frontend))
+                   (depends_on aws_vpc.main))
+                 (resource github_repository site
+                   (provider "registry.terraform.io/integrations/github")
+                   (name site))
+                 (data aws_ami ubuntu (id "ami-0c55b159"))
+                 (output ip "10.0.0.1")
+                 (output ports 80 443))"#,
+        )
+        .unwrap();
+        let state: Value = serde_json::from_str(&sexp.to_terraform_state()).unwrap();
+        assert_eq!(
+            state,
+            json!({
+                "version": 4,
+                "terraform_version": "1.5.7",
+                "serial": 3,
+                "lineage": "3f2a9c1e-7d4b-4a8e-9c6f-1b2d3e4f5a6b",
+                "outputs": {
+                    "ip": {"value": "10.0.0.1", "type": "string"},
+                    "ports": {"value": [80, 443], "type": ["tuple", ["number", "number"]]}
+                },
+                "resources": [
+                    {
+                        "mode": "managed",
+                        "type": "aws_vpc",
+                        "name": "main",
+                        "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+                        "instances": [{
+                            "schema_version": 0,
+                            "attributes": {"cidr_block": "10.0.0.0/16"},
+                            "sensitive_attributes": []
+                        }]
+                    },
+                    {
+                        "mode": "managed",
+                        "type": "aws_instance",
+                        "name": "web",
+                        "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+                        "instances": [{
+                            "schema_version": 0,
+                            "attributes": {
+                                "ami": "ami-0c55b159",
+                                "instance_type": "t3.micro",
+                                "monitoring": false,
+                                "security_groups": ["sg-1", "sg-2"],
+                                "tags": {"Name": "web", "Tier": "frontend"}
+                            },
+                            "sensitive_attributes": [],
+                            "dependencies": ["aws_vpc.main"]
+                        }]
+                    },
+                    {
+                        "mode": "managed",
+                        "type": "github_repository",
+                        "name": "site",
+                        "provider": "provider[\"registry.terraform.io/integrations/github\"]",
+                        "instances": [{
+                            "schema_version": 0,
+                            "attributes": {"name": "site"},
+                            "sensitive_attributes": []
+                        }]
+                    },
+                    {
+                        "mode": "data",
+                        "type": "aws_ami",
+                        "name": "ubuntu",
+                        "provider": "provider[\"registry.terraform.io/hashicorp/aws\"]",
+                        "instances": [{
+                            "schema_version": 0,
+                            "attributes": {"id": "ami-0c55b159"},
+                            "sensitive_attributes": []
+                        }]
+                    }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use super::infix::{indented, write, Syntax};
+use super::{atom, is_identifier, quoted, unquoted, Atom};
+use crate::Sexp;
+
+const KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Whether `name` is an identifier, or a path of them like `console.log`.
+/// `this` is a keyword, but reads as a name.
+fn is_name(name: &str) -> bool {
+    name.split('.')
+        .all(|part| is_identifier(part) && !KEYWORDS.contains(&part))
+}
+
+fn atom_text(text: &str) -> String {
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::String(text) => quoted(&text, '"'),
+        Atom::Symbol("true") | Atom::Symbol("#t") => "true".to_string(),
+        Atom::Symbol("false") | Atom::Symbol("#f") => "false".to_string(),
+        Atom::Symbol("nil") => "null".to_string(),
+        Atom::Symbol(name) if is_name(name) => name.to_string(),
+        Atom::Symbol(name) => quoted(name, '"'),
+    }
+}
+
+/// `forms` in braces after `opening`, as in `if (ok) { ... }`.
+fn block(opening: &str, forms: &[String]) -> String {
+    format!("{}{{\n{}\n}}", opening, indented(forms, "  "))
+}
+
+/// A name with its type, from `(name Type)`, or a bare name taking `any`.
+/// The name of an interface's field can end in `?` for an optional one.
+fn typed(item: &Sexp) -> Option<(String, String)> {
+    match item {
+        Sexp::Atom(name) if is_name(name) => Some((name.to_string(), "any".to_string())),
+        Sexp::Atom(_) => None,
+        _ => match item.items() {
+            [Sexp::Atom(name), Sexp::Atom(ty)]
+                if is_name(name.strip_suffix('?').unwrap_or(name)) =>
+            {
+                Some((name.to_string(), unquoted(ty)))
+            }
+            _ => None,
+        },
+    }
+}
+
+fn params(params: &[Sexp]) -> Option<String> {
+    let params = params
+        .iter()
+        .map(|param| typed(param).map(|(name, ty)| format!("{}: {}", name, ty)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", params.join(", ")))
+}
+
+/// Whether `form` is written as a statement that has no value to return.
+fn is_statement(form: &Sexp) -> bool {
+    match form.items() {
+        [Sexp::Atom(head), _, _] if head == "if" => true,
+        [Sexp::Atom(head), ..] => matches!(
+            head.as_str(),
+            "define" | "return" | "begin" | "let" | "let*" | "cond" | "interface"
+        ),
+        _ => false,
+    }
+}
+
+/// The statements of a body, with the last one's value returned if
+/// `returns`, from within it where it's a block.
+fn statements(forms: &[Sexp], returns: bool) -> Vec<String> {
+    let mut lines = vec![];
+    for (index, form) in forms.iter().enumerate() {
+        let last = returns && index == forms.len() - 1;
+        let block = match form.items().split_first() {
+            Some((Sexp::Atom(head), rest)) => statement(head, rest, last),
+            _ => None,
+        };
+        lines.push(match block {
+            Some(block) => block,
+            None if last && !is_statement(form) => format!("return {};", write(form, &TYPESCRIPT)),
+            None => format!("{};", write(form, &TYPESCRIPT)),
+        });
+    }
+    lines
+}
+
+/// A function declaration, from `(define ((name Type) params...)
+/// body...)` returning its last form, or without a type one returning
+/// nothing.
+fn function(signature: &Sexp, body: &[Sexp]) -> Option<String> {
+    let (name, params) = signature.items().split_first()?;
+    let (name, returns) = match name {
+        Sexp::Atom(name) if is_name(name) => (name.to_string(), None),
+        _ => typed(name).map(|(name, ty)| (name, Some(ty)))?,
+    };
+    let opening = format!(
+        "function {}{}{} ",
+        name,
+        self::params(params)?,
+        returns
+            .as_ref()
+            .map(|ty| format!(": {}", ty))
+            .unwrap_or_default()
+    );
+    Some(block(&opening, &statements(body, returns.is_some())))
+}
+
+/// The forms written as statements, returning their last value if
+/// `returns`.
+fn statement(head: &str, rest: &[Sexp], returns: bool) -> Option<String> {
+    match (head, rest) {
+        ("interface", [Sexp::Atom(name), fields @ ..]) if is_name(name) => {
+            let fields = fields
+                .iter()
+                .map(|field| typed(field).map(|(name, ty)| format!("{}: {};", name, ty)))
+                .collect::<Option<Vec<_>>>()?;
+            Some(block(&format!("interface {} ", name), &fields))
+        }
+        ("define", [signature @ Sexp::List(_), body @ ..]) => function(signature, body),
+        ("begin", [_, ..]) => Some(statements(rest, returns).join("\n")),
+        ("let", [pairs @ Sexp::List(_), body @ ..])
+        | ("let*", [pairs @ Sexp::List(_), body @ ..]) => {
+            let mut lines = vec![];
+            for binding in pairs.items() {
+                match binding.items() {
+                    [name @ Sexp::Atom(_), init] => lines.push(format!(
+                        "const {} = {};",
+                        write(name, &TYPESCRIPT),
+                        write(init, &TYPESCRIPT)
+                    )),
+                    _ => return None,
+                }
+            }
+            lines.extend(statements(body, returns));
+            Some(block("", &lines))
+        }
+        ("if", [test, consequent]) => Some(block(
+            &format!("if ({}) ", write(test, &TYPESCRIPT)),
+            &statements(std::slice::from_ref(consequent), returns),
+        )),
+        ("cond", [_, ..]) => {
+            let mut branches = vec![];
+            for (index, clause) in rest.iter().enumerate() {
+                let (test, consequent) = clause.items().split_first()?;
+                let opening = match test {
+                    Sexp::Atom(test) if test == "else" && index > 0 => String::new(),
+                    _ => format!("if ({}) ", write(test, &TYPESCRIPT)),
+                };
+                branches.push(block(&opening, &statements(consequent, returns)));
+            }
+            Some(branches.join(" else "))
+        }
+        _ => None,
+    }
+}
+
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    match (head, rest) {
+        ("define", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "const {} = {}",
+            write(name, &TYPESCRIPT),
+            write(init, &TYPESCRIPT)
+        )),
+        ("set!", [name @ Sexp::Atom(_), init]) => Some(format!(
+            "({} = {})",
+            write(name, &TYPESCRIPT),
+            write(init, &TYPESCRIPT)
+        )),
+        ("return", [value]) => Some(format!("return {}", write(value, &TYPESCRIPT))),
+        ("new", [Sexp::Atom(class), args @ ..]) if is_name(class) => {
+            let args: Vec<String> = args.iter().map(|arg| write(arg, &TYPESCRIPT)).collect();
+            Some(format!("new {}({})", class, args.join(", ")))
+        }
+        // A function with typed parameters, as in `(lambda ((x number))
+        // ...)`, in parentheses since an arrow function isn't a primary
+        // expression.
+        ("lambda", [params @ Sexp::List(_), body @ ..])
+            if params
+                .items()
+                .iter()
+                .any(|param| matches!(param, Sexp::List(_))) =>
+        {
+            let body: Vec<String> = body.iter().map(|form| write(form, &TYPESCRIPT)).collect();
+            Some(format!(
+                "({})",
+                arrow(&self::params(params.items())?, &body)
+            ))
+        }
+        _ => statement(head, rest, false),
+    }
+}
+
+/// An arrow function, returning its last form from a block where there
+/// are more.
+fn arrow(params: &str, body: &[String]) -> String {
+    match body {
+        [single] => format!("{} => {}", params, single),
+        _ => {
+            let mut lines: Vec<String> = body.iter().map(|form| format!("{};", form)).collect();
+            if let Some(last) = lines.last_mut() {
+                *last = format!("return {}", last);
+            }
+            block(&format!("{} => ", params), &lines)
+        }
+    }
+}
+
+fn table(fields: &[(String, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| {
+            if is_identifier(key) {
+                format!("{}: {}", key, value)
+            } else {
+                format!("{}: {}", quoted(key, '"'), value)
+            }
+        })
+        .collect();
+    match fields.as_slice() {
+        [] => "{}".to_string(),
+        _ => format!("{{ {} }}", fields.join(", ")),
+    }
+}
+
+const TYPESCRIPT: Syntax = Syntax {
+    atom: atom_text,
+    forms,
+    and: "&&",
+    or: "||",
+    not: "!",
+    not_equal: "!=",
+    remainder: "%",
+    method: ".",
+    field: ".",
+    named: None,
+    lambda: |params, body| arrow(&format!("({})", params.join(", ")), body),
+    conditional: |test, consequent, alternative| {
+        format!("{} ? {} : {}", test, consequent, alternative)
+    },
+    array: ("[", "]"),
+    table,
+};
+
+impl Sexp {
+    /// The tree as TypeScript, reading it as Scheme-style code: lists
+    /// become `f(args)` calls, `(.method object args...)` method calls,
+    /// `(.-field object)` a property and `(new Point 1 2)` a constructor
+    /// call. `(interface Point (x number) (label? string))` becomes an
+    /// interface with those properties, where a `?` makes one optional.
+    /// `(define ((name Type) (param Type)...) body...)` becomes a function
+    /// returning its last form, or without a type one returning nothing,
+    /// and untyped parameters take `any`. `lambda` becomes an arrow
+    /// function `(x: T) => ...`, typed where its parameters are, `define`
+    /// of a variable a `const`, `let` a block of them, and `if` without an
+    /// alternative and `cond` `if` statements. `(array ...)` and `(table
+    /// (key value)...)` become array and object literals.
+    pub fn to_typescript_string(&self) -> String {
+        write(self, &TYPESCRIPT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_typescript_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (interface Point (x number) (y number) (label? string))
+                 (define ((norm number) (p Point)) (+ (* (.-x p) (.-x p)) (* (.-y p) (.-y p))))
+                 (define ((describe string) (p Point) (limit number))
+                   (define n (norm p))
+                   (cond ((> n limit) "far") ((== n 0) "origin") (else "near")))
+                 (define (show (p Point))
+                   (let ((scaled (.map (array (.-x p) (.-y p)) (lambda (n) (* n 2)))))
+                     (if (!= (% (.-x p) 2) 0) (console.log "odd\tx"))
+                     (console.log (describe p 10) scaled)))
+                 (define origin (table (x 0) (y 0) ("max size" nil)))
+                 (define twice (lambda ((n number)) (* n 2)))
+                 (show origin))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_typescript_string(),
+            r#"interface Point {
+  x: number;
+  y: number;
+  label?: string;
+}
+function norm(p: Point): number {
+  return p.x * p.x + p.y * p.y;
+}
+function describe(p: Point, limit: number): string {
+  const n = norm(p);
+  if (n > limit) {
+    return "far";
+  } else if (n == 0) {
+    return "origin";
+  } else {
+    return "near";
+  }
+}
+function show(p: Point) {
+  {
+    const scaled = [p.x, p.y].map((n) => n * 2);
+    if (p.x % 2 != 0) {
+      console.log("odd\tx");
+    }
+    console.log(describe(p, 10), scaled);
+  }
+}
+const origin = { x: 0, y: 0, "max size": null };
+const twice = ((n: number) => n * 2);
+show(origin);"#
+        );
+    }
+}
@@ -0,0 +1,587 @@
+#[cfg(feature = "wasm")]
+use crate::SexpPath;
+use crate::{Sexp, SexpError};
+
+/// Heads of WAT forms that aren't instructions: module fields and the parts
+/// of their headers.
+const FORMS: &[&str] = &[
+    "module", "type", "import", "func", "table", "memory", "global", "export", "start", "elem",
+    "data", "param", "result", "local", "mut", "offset", "item", "declare", "ref", "then", "else",
+];
+
+/// What an instruction takes: the fewest and most immediates, like the
+/// index of `local.get`, and how many operands it pops, when that's fixed.
+/// Calls, branches and blocks take however many values their types say.
+type Arity = (usize, usize, Option<usize>);
+
+const INSTRUCTIONS: &[(&str, Arity)] = &[
+    ("unreachable", (0, 0, Some(0))),
+    ("nop", (0, 0, Some(0))),
+    ("block", (0, 1, None)),
+    ("loop", (0, 1, None)),
+    ("if", (0, 1, None)),
+    ("br", (1, 1, None)),
+    ("br_if", (1, 1, None)),
+    ("br_table", (1, usize::MAX, None)),
+    ("return", (0, 0, None)),
+    ("call", (1, 1, None)),
+    ("call_indirect", (0, 1, None)),
+    ("drop", (0, 0, Some(1))),
+    ("select", (0, 0, Some(3))),
+    ("local.get", (1, 1, Some(0))),
+    ("local.set", (1, 1, Some(1))),
+    ("local.tee", (1, 1, Some(1))),
+    ("global.get", (1, 1, Some(0))),
+    ("global.set", (1, 1, Some(1))),
+    ("memory.size", (0, 1, Some(0))),
+    ("memory.grow", (0, 1, Some(1))),
+    ("ref.null", (1, 1, Some(0))),
+    ("ref.is_null", (0, 0, Some(1))),
+    ("ref.func", (1, 1, Some(0))),
+];
+
+/// Operations every numeric type has, then those of integers and of floats.
+const NUMERIC: &[&str] = &["const", "load", "store", "add", "sub", "mul", "eq", "ne"];
+const INTEGER: &[&str] = &[
+    "clz",
+    "ctz",
+    "popcnt",
+    "eqz",
+    "div_s",
+    "div_u",
+    "rem_s",
+    "rem_u",
+    "and",
+    "or",
+    "xor",
+    "shl",
+    "shr_s",
+    "shr_u",
+    "rotl",
+    "rotr",
+    "lt_s",
+    "lt_u",
+    "gt_s",
+    "gt_u",
+    "le_s",
+    "le_u",
+    "ge_s",
+    "ge_u",
+    "load8_s",
+    "load8_u",
+    "load16_s",
+    "load16_u",
+    "store8",
+    "store16",
+    "trunc_f32_s",
+    "trunc_f32_u",
+    "trunc_f64_s",
+    "trunc_f64_u",
+    "extend8_s",
+    "extend16_s",
+];
+const FLOAT: &[&str] = &[
+    "abs",
+    "neg",
+    "sqrt",
+    "ceil",
+    "floor",
+    "trunc",
+    "nearest",
+    "div",
+    "min",
+    "max",
+    "copysign",
+    "lt",
+    "gt",
+    "le",
+    "ge",
+    "convert_i32_s",
+    "convert_i32_u",
+    "convert_i64_s",
+    "convert_i64_u",
+];
+const UNARY: &[&str] = &[
+    "clz",
+    "ctz",
+    "popcnt",
+    "eqz",
+    "extend8_s",
+    "extend16_s",
+    "extend32_s",
+    "abs",
+    "neg",
+    "sqrt",
+    "ceil",
+    "floor",
+    "trunc",
+    "nearest",
+];
+
+fn arity(name: &str) -> Option<Arity> {
+    if let Some((_, arity)) = INSTRUCTIONS
+        .iter()
+        .find(|(instruction, _)| *instruction == name)
+    {
+        return Some(*arity);
+    }
+    let (ty, operation) = name.split_once('.')?;
+    let known = match (ty, operation) {
+        ("i32", "wrap_i64") | ("f32", "demote_f64") | ("f64", "promote_f32") => true,
+        ("i64", "load32_s") | ("i64", "load32_u") | ("i64", "store32") => true,
+        ("i64", "extend_i32_s") | ("i64", "extend_i32_u") | ("i64", "extend32_s") => true,
+        ("i32", "reinterpret_f32") | ("i64", "reinterpret_f64") => true,
+        ("f32", "reinterpret_i32") | ("f64", "reinterpret_i64") => true,
+        ("i32", _) | ("i64", _) => INTEGER.contains(&operation) || NUMERIC.contains(&operation),
+        ("f32", _) | ("f64", _) => FLOAT.contains(&operation) || NUMERIC.contains(&operation),
+        _ => false,
+    };
+    if !known {
+        return None;
+    }
+    // Conversions are named after the type they convert from, like
+    // `i64.extend_i32_s`.
+    Some(if operation == "const" {
+        (1, 1, Some(0))
+    } else if operation.starts_with("load") {
+        (0, 2, Some(1))
+    } else if operation.starts_with("store") {
+        (0, 2, Some(2))
+    } else if UNARY.contains(&operation) || operation.contains("_f") || operation.contains("_i") {
+        (0, 0, Some(1))
+    } else {
+        (0, 0, Some(2))
+    })
+}
+
+/// Forms whose list elements go on lines of their own, after the atoms and
+/// header forms that name and type them.
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "module" | "func" | "global" | "block" | "loop" | "if" | "then" | "else"
+    )
+}
+
+fn is_header(name: &str) -> bool {
+    matches!(
+        name,
+        "type" | "import" | "export" | "param" | "result" | "local"
+    )
+}
+
+fn write(sexp: &Sexp, indent: &str, wat: &mut String) {
+    let items = sexp.items();
+    match sexp.head() {
+        Some(head) if is_block(head) => {
+            wat.push('(');
+            wat.push_str(head);
+            let inner = format!("{}  ", indent);
+            for item in &items[1..] {
+                match item.head() {
+                    Some(name) if !is_header(name) => {
+                        wat.push('\n');
+                        wat.push_str(&inner);
+                        write(item, &inner, wat);
+                    }
+                    _ => {
+                        wat.push(' ');
+                        wat.push_str(&item.to_compact_string());
+                    }
+                }
+            }
+            wat.push(')');
+        }
+        _ => wat.push_str(&sexp.to_compact_string()),
+    }
+}
+
+#[cfg(feature = "wasm")]
+const MODULE_FIELDS: &[&str] = &[
+    "type", "import", "func", "table", "memory", "global", "export", "start", "elem", "data",
+];
+
+#[cfg(feature = "wasm")]
+const VALUE_TYPES: &[&str] = &["i32", "i64", "f32", "f64", "v128", "funcref", "externref"];
+
+#[cfg(feature = "wasm")]
+/// Where a header form may go in a function, blocks and `call_indirect`:
+/// before everything of a higher rank, and before the body.
+fn header_rank(name: &str) -> Option<usize> {
+    match name {
+        "export" => Some(0),
+        "import" => Some(1),
+        "type" => Some(2),
+        "param" => Some(3),
+        "result" => Some(4),
+        "local" => Some(5),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[derive(Default)]
+struct Validator {
+    errors: Vec<SexpError>,
+}
+
+#[cfg(feature = "wasm")]
+impl Validator {
+    fn error(&mut self, path: &SexpPath, message: String) {
+        self.errors.push(SexpError::InvalidWat {
+            message,
+            path: path.clone(),
+        });
+    }
+
+    fn module(&mut self, sexp: &Sexp, path: &SexpPath) {
+        for (index, field) in sexp.items().iter().enumerate().skip(1) {
+            let path = path.child(index);
+            match (field, field.head()) {
+                (Sexp::Atom(name), _) if index == 1 && name.starts_with('$') => {}
+                (_, Some("func")) => self.func(field, &path),
+                (_, Some("global")) => {
+                    let init = self.headers(field, &path, &["export", "import"]);
+                    self.body(&field.items()[init..], &path, init);
+                }
+                (_, Some(head)) if MODULE_FIELDS.contains(&head) => {}
+                _ => self.error(&path, format!("Expected a module field, found {}", field)),
+            }
+        }
+    }
+
+    fn func(&mut self, sexp: &Sexp, path: &SexpPath) {
+        let body = self.headers(
+            sexp,
+            path,
+            &["export", "import", "type", "param", "result", "local"],
+        );
+        self.body(&sexp.items()[body..], path, body);
+    }
+
+    /// Checks the optional `$label` and then the headers `allowed` in
+    /// `sexp`, returning where they end. A `global`'s type is the one
+    /// non-header that counts as a header.
+    fn headers(&mut self, sexp: &Sexp, path: &SexpPath, allowed: &[&str]) -> usize {
+        let items = sexp.items();
+        let mut index = 1;
+        if let Some(Sexp::Atom(label)) = items.get(index) {
+            if label.starts_with('$') {
+                index += 1;
+            }
+        }
+        if sexp.head() == Some("global") {
+            match items.get(index) {
+                Some(Sexp::Atom(ty)) if VALUE_TYPES.contains(&ty.as_str()) => index += 1,
+                Some(ty) if ty.head() == Some("mut") => index += 1,
+                _ => self.error(path, format!("Expected a global type in {}", sexp)),
+            }
+        }
+        let mut rank = 0;
+        while let Some(header) = items.get(index) {
+            let name = match header.head() {
+                Some(name) if allowed.contains(&name) => name,
+                _ => break,
+            };
+            let path = path.child(index);
+            let this = header_rank(name).unwrap_or(0);
+            if this < rank {
+                self.error(&path, format!("({}) is out of order", name));
+            }
+            rank = rank.max(this);
+            if matches!(name, "param" | "result" | "local") {
+                let types = header.items();
+                let named = types
+                    .get(1)
+                    .and_then(Sexp::as_text)
+                    .is_some_and(|name| name.starts_with('$'));
+                let types = &types[if named { 2 } else { 1 }..];
+                if named && types.len() != 1 {
+                    self.error(&path, format!("A named ({}) needs exactly one type", name));
+                }
+                for ty in types {
+                    match ty {
+                        Sexp::Atom(ty) if VALUE_TYPES.contains(&ty.as_str()) => {}
+                        _ => self.error(&path, format!("Expected a value type, found {}", ty)),
+                    }
+                }
+            }
+            index += 1;
+        }
+        index
+    }
+
+    /// Instructions, one folded `(instruction ...)` list or flat atom at a
+    /// time, with a flat instruction's immediates following it.
+    fn body(&mut self, instructions: &[Sexp], path: &SexpPath, from: usize) {
+        let mut flat = false;
+        for (offset, instruction) in instructions.iter().enumerate() {
+            let path = path.child(from + offset);
+            match (instruction, instruction.head()) {
+                (Sexp::Atom(name), _) if arity(name).is_some() => flat = true,
+                (Sexp::Atom(_), _) if flat => {}
+                (Sexp::List(_), Some(name)) if header_rank(name).is_some() => {
+                    self.error(&path, format!("({}) must come before the body", name));
+                }
+                (Sexp::List(_), _) => {
+                    flat = false;
+                    self.instruction(instruction, &path);
+                }
+                _ => self.error(
+                    &path,
+                    format!("Expected an instruction, found {}", instruction),
+                ),
+            }
+        }
+    }
+
+    fn instruction(&mut self, sexp: &Sexp, path: &SexpPath) {
+        let items = sexp.items();
+        let name = match sexp.head() {
+            Some(name) => name,
+            None => return self.error(path, format!("Expected an instruction, found {}", sexp)),
+        };
+        let (fewest, most, operands) = match arity(name) {
+            Some(arity) => arity,
+            None => return self.error(path, format!("Unknown WAT instruction {}", name)),
+        };
+        let after = match name {
+            "block" | "loop" | "if" | "call_indirect" => {
+                self.headers(sexp, path, &["type", "param", "result"])
+            }
+            "select" => self.headers(sexp, path, &["result"]),
+            _ => 1,
+        };
+        match name {
+            "block" | "loop" => return self.body(&items[after..], path, after),
+            "if" => return self.branches(sexp, path, after),
+            _ => {}
+        }
+        // A label-like `$index` before the headers, as in `call_indirect`,
+        // is an immediate too.
+        let rest = &items[after..];
+        let atoms = rest
+            .iter()
+            .take_while(|item| matches!(item, Sexp::Atom(_)))
+            .count();
+        let immediates = atoms
+            + items[1..after]
+                .iter()
+                .filter(|item| matches!(item, Sexp::Atom(_)))
+                .count();
+        if immediates < fewest || immediates > most {
+            let expected = if fewest == most {
+                fewest.to_string()
+            } else if most == usize::MAX {
+                format!("at least {}", fewest)
+            } else {
+                format!("{} to {}", fewest, most)
+            };
+            self.error(
+                path,
+                format!(
+                    "{} takes {} immediates, found {}",
+                    name, expected, immediates
+                ),
+            );
+        }
+        let folded = &rest[atoms..];
+        if let Some(operands) = operands {
+            if !folded.is_empty() && folded.len() != operands {
+                self.error(
+                    path,
+                    format!(
+                        "{} takes {} operands, found {}",
+                        name,
+                        operands,
+                        folded.len()
+                    ),
+                );
+            }
+        }
+        let from = after + atoms;
+        for (offset, operand) in folded.iter().enumerate() {
+            let path = path.child(from + offset);
+            match operand {
+                Sexp::List(_) => self.instruction(operand, &path),
+                _ => self.error(
+                    &path,
+                    format!("Expected a folded instruction, found {}", operand),
+                ),
+            }
+        }
+    }
+
+    /// An `if`'s condition, then its `(then ...)` and optional `(else ...)`.
+    fn branches(&mut self, sexp: &Sexp, path: &SexpPath, from: usize) {
+        let items = sexp.items();
+        let then = items[from..]
+            .iter()
+            .position(|item| item.head() == Some("then"))
+            .map(|offset| from + offset);
+        let then = match then {
+            Some(then) => then,
+            None => return self.error(path, "if needs a (then ...)".to_string()),
+        };
+        self.body(&items[from..then], path, from);
+        for (index, branch) in items.iter().enumerate().skip(then) {
+            let path = path.child(index);
+            match (index - then, branch.head()) {
+                (0, Some("then")) | (1, Some("else")) => self.body(&branch.items()[1..], &path, 1),
+                _ => self.error(&path, format!("Expected (else ...), found {}", branch)),
+            }
+        }
+    }
+}
+
+impl Sexp {
+    /// The tree as WebAssembly text, which is already made of
+    /// s-expressions, so this lays a module out the way WAT usually is:
+    /// module fields, function bodies and blocks one form per line, and
+    /// folded instructions like `(i32.add (local.get 0) (i32.const 1))` on
+    /// one. Every list must be headed by a WAT keyword or instruction.
+    pub fn to_wasm_text_format(&self) -> Result<String, SexpError> {
+        for (path, node) in self.descendants() {
+            if let Sexp::List(_) = node {
+                match node.head() {
+                    Some(head) if FORMS.contains(&head) || arity(head).is_some() => {}
+                    _ => {
+                        return Err(SexpError::InvalidWatInstruction {
+                            instruction: node
+                                .items()
+                                .first()
+                                .map_or(String::new(), Sexp::to_string),
+                            path,
+                        })
+                    }
+                }
+            }
+        }
+        let mut wat = String::new();
+        write(self, "", &mut wat);
+        Ok(wat)
+    }
+
+    /// Checks that the tree is a well-formed WAT module: fields where a
+    /// module expects them, headers like `(param i32)` before the body of
+    /// a function and in order, value types where types go, and each folded
+    /// instruction with as many immediates and operands as it takes. Every
+    /// problem is reported, each with the path of the form it's in.
+    #[cfg(feature = "wasm")]
+    pub fn validate_wat(&self) -> Result<(), Vec<SexpError>> {
+        let mut validator = Validator::default();
+        if self.head() == Some("module") {
+            validator.module(self, &SexpPath::root());
+        } else {
+            validator.error(
+                &SexpPath::root(),
+                format!("Expected a module, found {}", self),
+            );
+        }
+        if validator.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_wasm_text_format() {
+        let sexp = Sexp::of_str(
+            r#"(module (memory 1) (func $add (export "add") (param $a i32) (param i32) (result i32)
+                 (local $t i32)
+                 (local.set $t (i32.add (local.get $a) (local.get 1)))
+                 (if (result i32) (i32.gt_s (local.get $t) (i32.const 9))
+                   (then (i32.const 9))
+                   (else (local.get $t)))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_wasm_text_format().unwrap(),
+            r#"(module
+  (memory 1)
+  (func $add (export "add") (param $a i32) (param i32) (result i32) (local $t i32)
+    (local.set $t (i32.add (local.get $a) (local.get 1)))
+    (if (result i32)
+      (i32.gt_s (local.get $t) (i32.const 9))
+      (then
+        (i32.const 9))
+      (else
+        (local.get $t)))))"#
+        );
+    }
+
+    #[test]
+    fn test_to_wasm_text_format_unknown_instruction() {
+        let error = Sexp::of_str("(module (func (i32.plus (i32.const 1) (i32.const 2))))")
+            .unwrap()
+            .to_wasm_text_format()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown WAT instruction i32.plus at /1/1"
+        );
+        assert_eq!(
+            error,
+            SexpError::InvalidWatInstruction {
+                instruction: "i32.plus".to_string(),
+                path: "/1/1".parse().unwrap(),
+            }
+        );
+        assert!(Sexp::of_str("(module (func (f32.popcnt (f32.const 1))))")
+            .unwrap()
+            .to_wasm_text_format()
+            .is_err());
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_validate_wat() {
+        let sexp = Sexp::of_str(
+            r#"(module $m
+                 (global $count (mut i32) (i32.const 0))
+                 (func $step (export "step") (param $by i32) (result i32)
+                   (global.set $count (i32.add (global.get $count) (local.get $by)))
+                   (if (result i32) (i32.eqz (global.get $count))
+                     (then (i32.const 0))
+                     (else local.get $by))
+                   (block $done (br $done))))"#,
+        )
+        .unwrap();
+        sexp.validate_wat().unwrap();
+
+        let sexp = Sexp::of_str(
+            "(module (func (result i32) (param i32) (i32.add (i32.const 1)) (local.get) (local i32)) (memory 1) (loop))",
+        )
+        .unwrap();
+        let errors: Vec<String> = sexp
+            .validate_wat()
+            .unwrap_err()
+            .iter()
+            .map(SexpError::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "(param) is out of order at /1/2",
+                "i32.add takes 2 operands, found 1 at /1/3",
+                "local.get takes 1 immediates, found 0 at /1/4",
+                "(local) must come before the body at /1/5",
+                "Expected a module field, found (loop) at /3",
+            ]
+        );
+        assert_eq!(
+            sexp.validate_wat().unwrap_err()[0],
+            SexpError::InvalidWat {
+                message: "(param) is out of order".to_string(),
+                path: "/1/2".parse().unwrap(),
+            }
+        );
+        assert!(Sexp::of_str("(func)").unwrap().validate_wat().is_err());
+    }
+}
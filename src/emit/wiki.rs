@@ -0,0 +1,43 @@
+use super::outline;
+use crate::Sexp;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '[' | ']' | '{' | '}' | '|' | '=' => escaped.push_str(&format!("&#{};", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Sexp {
+    /// The tree as a MediaWiki bulleted list, laid out like `to_asciidoc`,
+    /// with one more `*` for every level. Characters that would start links,
+    /// templates, tables or headings are written as HTML entities.
+    pub fn to_wiki_markup(&self) -> String {
+        let mut wiki = String::new();
+        outline(self, 1, &mut |text, depth| {
+            wiki.push_str(&format!("{} {}\n", "*".repeat(depth), escape(text)))
+        });
+        wiki
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_wiki_markup() {
+        let sexp =
+            Sexp::of_str(r#"(page (link "[[Main]]") (template "{{a|b=c}}") "x & <y>")"#).unwrap();
+        assert_eq!(
+            sexp.to_wiki_markup(),
+            "* page\n** link\n*** &#91;&#91;Main&#93;&#93;\n** template\n*** &#123;&#123;a&#124;b&#61;c&#125;&#125;\n** x &amp; &lt;y>\n"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use super::{atom, fields, is_number, unquoted, Atom};
+use crate::Sexp;
+
+/// Words YAML 1.1 would read as booleans or null if left unquoted.
+const RESERVED: &[&str] = &[
+    "true", "false", "yes", "no", "on", "off", "y", "n", "null", "~",
+];
+
+/// A field's name and the elements after it.
+type Entry<'a> = (String, &'a [Sexp]);
+
+enum Value<'a> {
+    Scalar(&'a Sexp),
+    Mapping(Vec<Entry<'a>>),
+    Sequence(&'a [Sexp]),
+}
+
+/// Whether `items` are fields like `(name web)`, and so a mapping.
+pub(super) fn is_mapping(items: &[Sexp]) -> bool {
+    fields(&Sexp::list(items.to_vec())).is_some()
+}
+
+/// A field as its name and elements, when it's a list headed by an atom.
+fn entry(field: &Sexp) -> Option<Entry<'_>> {
+    match field.items().split_first() {
+        Some((Sexp::Atom(name), elements)) => Some((unquoted(name), elements)),
+        _ => None,
+    }
+}
+
+/// `items` as the entries of a mapping, when they're fields with distinct
+/// names.
+fn entries(items: &[Sexp]) -> Option<Vec<Entry<'_>>> {
+    if !is_mapping(items) {
+        return None;
+    }
+    items.iter().map(entry).collect()
+}
+
+/// The name of a field, when it's a list headed by an atom.
+#[cfg(feature = "k8s")]
+pub(super) fn key(field: &Sexp) -> Option<String> {
+    entry(field).map(|(name, _)| name)
+}
+
+/// The items of an `(array ...)`, which is a sequence even of one.
+fn array(sexp: &Sexp) -> Option<&[Sexp]> {
+    match sexp.items().split_first() {
+        Some((Sexp::Atom(head), items)) if head == "array" => Some(items),
+        _ => None,
+    }
+}
+
+/// What the elements after a field's name stand for: a mapping when they
+/// are fields themselves, a scalar when there's a single atom, and a
+/// sequence otherwise.
+fn field_value(elements: &[Sexp]) -> Value<'_> {
+    if let [single] = elements {
+        if let Some(items) = array(single) {
+            return Value::Sequence(items);
+        }
+    }
+    if let Some(entries) = entries(elements) {
+        return Value::Mapping(entries);
+    }
+    match elements {
+        [scalar @ Sexp::Atom(_)] => Value::Scalar(scalar),
+        _ => Value::Sequence(elements),
+    }
+}
+
+fn item_value(item: &Sexp) -> Value<'_> {
+    if let Some(items) = array(item) {
+        return Value::Sequence(items);
+    }
+    match item {
+        Sexp::List(_) => match entries(item.items()) {
+            Some(entries) => Value::Mapping(entries),
+            None => Value::Sequence(item.items()),
+        },
+        _ => Value::Scalar(item),
+    }
+}
+
+fn plain(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphanumeric() || "_./".contains(c))
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_./:=+-@".contains(c))
+        && !text.ends_with(':')
+        && !text.contains(": ")
+        && !RESERVED.contains(&text.to_ascii_lowercase().as_str())
+        && !is_number(text)
+}
+
+pub(super) fn scalar(sexp: &Sexp) -> String {
+    let text = match sexp {
+        Sexp::Atom(text) => text,
+        _ => return "null".to_string(),
+    };
+    match atom(text) {
+        Atom::Number(number) => number.to_string(),
+        Atom::Symbol(name @ ("true" | "false" | "null")) => name.to_string(),
+        Atom::Symbol(text) if plain(text) => text.to_string(),
+        Atom::String(text) if plain(&text) => text,
+        Atom::Symbol(text) => serde_json::to_string(text).unwrap(),
+        Atom::String(text) => serde_json::to_string(&text).unwrap(),
+    }
+}
+
+/// Writes the lists headed by their names among `fields` as a block
+/// mapping indented by `indent`. YAML has no way to write anything else,
+/// so it's left out, and a name repeated is written again, for the reader
+/// of the YAML to reject.
+#[cfg(feature = "ci")]
+pub(super) fn mapping<'a>(
+    fields: impl IntoIterator<Item = &'a Sexp>,
+    indent: usize,
+    yaml: &mut String,
+) {
+    let entries: Vec<Entry> = fields.into_iter().filter_map(entry).collect();
+    write_mapping(&entries, indent, yaml);
+}
+
+/// `sexp` as a YAML document, read the way a sequence item is: a mapping
+/// when it's a list of fields with distinct names, a scalar for an atom,
+/// and a sequence otherwise.
+#[cfg(feature = "k8s")]
+pub(super) fn document(sexp: &Sexp) -> String {
+    let mut yaml = String::new();
+    match item_value(sexp) {
+        Value::Scalar(value) => yaml = format!("{}\n", scalar(value)),
+        Value::Mapping(entries) => write_mapping(&entries, 0, &mut yaml),
+        Value::Sequence([]) => yaml.push_str("[]\n"),
+        Value::Sequence(items) => sequence(items, 0, &mut yaml),
+    }
+    yaml
+}
+
+fn write_mapping(entries: &[Entry], indent: usize, yaml: &mut String) {
+    for (name, elements) in entries {
+        let key = if plain(name) {
+            name.clone()
+        } else {
+            serde_json::to_string(name).unwrap()
+        };
+        let pad = " ".repeat(indent);
+        match field_value(elements) {
+            Value::Scalar(value) => yaml.push_str(&format!("{}{}: {}\n", pad, key, scalar(value))),
+            Value::Mapping(entries) => {
+                yaml.push_str(&format!("{}{}:\n", pad, key));
+                write_mapping(&entries, indent + 2, yaml);
+            }
+            Value::Sequence([]) => yaml.push_str(&format!("{}{}: []\n", pad, key)),
+            Value::Sequence(items) => {
+                // Like kubectl, sequence items line up with their key.
+                yaml.push_str(&format!("{}{}:\n", pad, key));
+                sequence(items, indent, yaml);
+            }
+        }
+    }
+}
+
+/// Writes `items` as a block sequence indented by `indent`.
+pub(super) fn sequence(items: &[Sexp], indent: usize, yaml: &mut String) {
+    let pad = " ".repeat(indent);
+    for item in items {
+        let mut nested = String::new();
+        match item_value(item) {
+            Value::Scalar(value) => nested = format!("{}  {}\n", pad, scalar(value)),
+            Value::Mapping(entries) => write_mapping(&entries, indent + 2, &mut nested),
+            Value::Sequence([]) => nested = format!("{}  []\n", pad),
+            Value::Sequence(items) => sequence(items, indent + 2, &mut nested),
+        }
+        // The item's first line goes after its dash.
+        yaml.push_str(&format!("{}- {}", pad, &nested[indent + 2..]));
+    }
+}
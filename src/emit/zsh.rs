@@ -0,0 +1,79 @@
+use super::bash::{words, write, Shell};
+use crate::Sexp;
+
+/// Zsh's own commands: `autoload` of bare names marks them functions to
+/// load as zsh ones, without aliases, the `-Uz` every zsh setup uses.
+fn forms(head: &str, rest: &[Sexp]) -> Option<String> {
+    match (head, rest) {
+        ("autoload", [_, ..])
+            if !rest
+                .iter()
+                .any(|name| name.as_text().is_some_and(|name| name.starts_with('-'))) =>
+        {
+            Some(format!("autoload -Uz {}", words(rest, &ZSH)))
+        }
+        _ => None,
+    }
+}
+
+const ZSH: Shell = Shell {
+    splits: false,
+    table: |name, fields| {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("{} {}", key, value))
+            .collect();
+        format!("typeset -A {}=({})", name, fields.join(" "))
+    },
+    forms,
+};
+
+impl Sexp {
+    /// The tree as a Zsh script, written as [`Sexp::to_bash_string`] writes
+    /// Bash with Zsh's differences: a variable's value isn't split, so `$VAR`
+    /// is left unquoted, `(define NAME (array ...))` is an array assignment
+    /// `NAME=(a b)` and `(table (key value)...)` an associative array
+    /// `typeset -A NAME=(key value)`. `(autoload name...)` becomes
+    /// `autoload -Uz name`, and configuration like `(zstyle ":completion:*"
+    /// menu select)` is a command like any other.
+    pub fn to_zsh_string(&self) -> String {
+        write(self, &ZSH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_zsh_string() {
+        let sexp = Sexp::of_str(
+            r#"(begin
+                 (autoload compinit colors)
+                 (compinit)
+                 (zstyle ":completion:*" menu select)
+                 (define FILES (array *.txt "my notes.md"))
+                 (define CONFIG (table (name demo) ("max size" 10)))
+                 (define (greet)
+                   (local who $1)
+                   (echo "Hello," $who))
+                 (for f ($FILES) (echo $f (date +%s))))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sexp.to_zsh_string(),
+            r#"autoload -Uz compinit colors
+compinit
+zstyle ':completion:*' menu select
+FILES=(*.txt 'my notes.md')
+typeset -A CONFIG=(name demo 'max size' 10)
+greet() {
+  local who=$1
+  echo Hello, $who
+}
+for f in $FILES; do
+  echo $f "$(date +%s)"
+done"#
+        );
+    }
+}
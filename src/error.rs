@@ -0,0 +1,38 @@
+use crate::SexpPath;
+use std::fmt;
+
+/// Errors from converting a tree to or from another format, for callers
+/// that want to tell them apart rather than just report them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SexpError {
+    /// `Sexp::from_pest_pairs` was given no pairs.
+    NoPestPairs,
+    /// A tree `Sexp::to_syn_expr` can't build a Rust expression from, and
+    /// why.
+    InvalidRustExpr(String),
+    /// A row `Sexp::from_csv_row` can't read, with the byte offset into it
+    /// where that became clear.
+    CsvParseError { at: usize, message: String },
+    /// A list in `Sexp::to_wasm_text_format`'s input headed by something
+    /// that isn't a WAT keyword or instruction.
+    InvalidWatInstruction { instruction: String, path: SexpPath },
+    /// One of the problems `Sexp::validate_wat` found, in the form at
+    /// `path`.
+    InvalidWat { message: String, path: SexpPath },
+}
+
+impl fmt::Display for SexpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SexpError::NoPestPairs => write!(f, "There are no pairs to convert"),
+            SexpError::InvalidRustExpr(message) => write!(f, "{}", message),
+            SexpError::CsvParseError { at, message } => write!(f, "{} at byte {}", message, at),
+            SexpError::InvalidWatInstruction { instruction, path } => {
+                write!(f, "Unknown WAT instruction {} at {}", instruction, path)
+            }
+            SexpError::InvalidWat { message, path } => write!(f, "{} at {}", message, path),
+        }
+    }
+}
+
+impl std::error::Error for SexpError {}
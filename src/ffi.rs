@@ -4,9 +4,13 @@ extern "C" {
     fn tree_sitter_sexp() -> Language;
 }
 
+pub fn language() -> Language {
+    unsafe { tree_sitter_sexp() }
+}
+
 pub fn parser() -> Parser {
-    let language = unsafe { tree_sitter_sexp() };
+    let language = language();
     let mut parser = Parser::new();
-    parser.set_language(language).unwrap();
+    parser.set_language(&language).unwrap();
     parser
 }
@@ -0,0 +1,318 @@
+use crate::comment::{comments, TrailingComments};
+use crate::{ffi, PrettyPrinter, Sexp};
+use anyhow::{anyhow, Context, Error};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Tree};
+
+/// Parses `input` and pretty-prints it back, with a trailing newline. The
+/// input must be exactly one complete form: anything the parser had to
+/// recover from, like a second form or an unbalanced paren, is an error
+/// rather than something to print, since what it would print is the
+/// recovered tree and not the input. Comments after an atom or list on its
+/// line are kept after it, and one on a line of its own is an error, as
+/// there's nothing to print it with.
+pub fn format_str(input: &str) -> Result<String, Error> {
+    let (sexp, comments) = parse_form(input)?;
+    let printed = PrettyPrinter::new().print_with_comments(&sexp, &comments);
+    Ok(format!("{}\n", printed))
+}
+
+fn parse_form(input: &str) -> Result<(Sexp, TrailingComments), Error> {
+    let mut parser = ffi::parser();
+    let tree = parser
+        .parse(input, None)
+        .context("Could not parse anything")?;
+    form(&tree, input)
+}
+
+/// Reads `tree`, a parse of `text`, as the one complete form `format_str`
+/// takes, with its trailing comments. Shared with `Document::form`, so that
+/// the stdin server refuses what `format_str` refuses.
+pub(crate) fn form(tree: &Tree, text: &str) -> Result<(Sexp, TrailingComments), Error> {
+    if text.trim().is_empty() {
+        return Err(anyhow!("Expected a form, found nothing"));
+    }
+    let root = tree.root_node();
+    if let Some(node) = first_error(root) {
+        let point = node.start_position();
+        let problem = if node.is_missing() {
+            format!("Missing {:?}", node.kind())
+        } else {
+            "Syntax error".to_string()
+        };
+        return Err(anyhow!(
+            "{} at line {}, column {}",
+            problem,
+            point.row + 1,
+            point.column + 1
+        ));
+    }
+    let mut walker = root.walk();
+    let forms = root
+        .children(&mut walker)
+        .filter(|child| child.kind() != "comment")
+        .count();
+    if forms != 1 {
+        return Err(anyhow!("Expected exactly one form"));
+    }
+    let trailing = comments(tree, text)?;
+    Ok((Sexp::of_tree(tree, text)?, trailing))
+}
+
+/// The first node, in document order, that the parser made up or skipped
+/// over to recover from an error.
+fn first_error(node: Node) -> Option<Node> {
+    if node.is_error() || node.is_missing() {
+        return Some(node);
+    }
+    if !node.has_error() {
+        return None;
+    }
+    let mut walker = node.walk();
+    let children: Vec<Node> = node.children(&mut walker).collect();
+    children.into_iter().find_map(first_error)
+}
+
+/// Replaces the file at `path` with `contents` without ever leaving it half
+/// written. The new contents go to a temporary file next to it, which is
+/// synced and then renamed over the original, keeping its permissions (and
+/// on Unix, its owner when we're allowed to). On failure the original is
+/// left as it was.
+pub fn write_formatted_atomic<P: AsRef<Path>>(path: P, contents: &str) -> Result<(), Error> {
+    write_atomic(path.as_ref(), contents, None)
+}
+
+/// Like `write_formatted_atomic`, but first copies the original to the same
+/// path with `suffix` appended, e.g. `dune.orig` for a suffix of `.orig`.
+pub fn write_formatted_atomic_with_backup<P: AsRef<Path>>(
+    path: P,
+    contents: &str,
+    suffix: &str,
+) -> Result<(), Error> {
+    write_atomic(path.as_ref(), contents, Some(suffix))
+}
+
+/// Formats every file in `paths` in place, optionally keeping backups. A
+/// failing file doesn't stop the others; each failure is returned next to
+/// the path it happened on.
+pub fn format_in_place(paths: &[PathBuf], backup: Option<&str>) -> Vec<(PathBuf, Error)> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let result = fs::read_to_string(path)
+                .context("Could not read file")
+                .and_then(|input| format_str(&input))
+                .and_then(|formatted| write_atomic(path, &formatted, backup));
+            result.err().map(|err| (path.clone(), err))
+        })
+        .collect()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_owned()).unwrap_or_default();
+    let mut temp = OsString::from(".");
+    temp.push(name);
+    temp.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(temp)
+}
+
+fn write_atomic(path: &Path, contents: &str, backup: Option<&str>) -> Result<(), Error> {
+    let temp = temp_path(path);
+    let result = replace(path, &temp, contents, backup);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.with_context(|| format!("Could not write {}", path.display()))
+}
+
+fn replace(path: &Path, temp: &Path, contents: &str, backup: Option<&str>) -> Result<(), Error> {
+    let original = fs::metadata(path).ok();
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp)
+        .with_context(|| format!("Could not create {}", temp.display()))?;
+    file.write_all(contents.as_bytes())?;
+    if let Some(metadata) = &original {
+        file.set_permissions(metadata.permissions())?;
+        preserve_owner(&file, metadata);
+    }
+    file.sync_all()?;
+    drop(file);
+
+    if let Some(suffix) = backup {
+        if original.is_some() {
+            fs::copy(path, with_suffix(path, suffix)).context("Could not write backup")?;
+        }
+    }
+
+    fs::rename(temp, path)?;
+    sync_parent(path);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn preserve_owner(file: &File, metadata: &fs::Metadata) {
+    use std::os::unix::fs::{fchown, MetadataExt};
+    // Only the superuser may hand a file to someone else, so this is best
+    // effort; the permissions are what really matter.
+    let _ = fchown(file, Some(metadata.uid()), Some(metadata.gid()));
+}
+
+#[cfg(not(unix))]
+fn preserve_owner(_file: &File, _metadata: &fs::Metadata) {}
+
+#[cfg(unix)]
+fn sync_parent(path: &Path) {
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_formatted_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dune");
+        fs::write(&path, "(a   b)").unwrap();
+
+        write_formatted_atomic(&path, "(a b)\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "(a b)\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_formatted_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dune");
+        fs::write(&path, "(a   b)").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_formatted_atomic(&path, "(a b)\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[test]
+    fn test_write_formatted_atomic_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dune");
+        fs::write(&path, "(a   b)").unwrap();
+
+        write_formatted_atomic_with_backup(&path, "(a b)\n", ".orig").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "(a b)\n");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("dune.orig")).unwrap(),
+            "(a   b)"
+        );
+    }
+
+    #[test]
+    fn test_format_in_place_reports_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        let good = dir.path().join("good");
+        let broken = dir.path().join("broken");
+        fs::write(&good, "(a   b)").unwrap();
+        fs::write(&broken, "(a (").unwrap();
+
+        let failures = format_in_place(&[missing.clone(), good.clone(), broken.clone()], None);
+        let failed: Vec<&PathBuf> = failures.iter().map(|(path, _)| path).collect();
+        assert_eq!(failed, vec![&missing, &broken]);
+        assert_eq!(fs::read_to_string(&good).unwrap(), "(a b)\n");
+        assert_eq!(fs::read_to_string(&broken).unwrap(), "(a (");
+    }
+
+    #[test]
+    fn test_format_str_rejects_recovered_input() {
+        assert_eq!(format_str("(a   (b c))").unwrap(), "(a (b c))\n");
+        for input in ["(a b) (c d)", "", "  \n", "(a ) b)", "(a (", "; comment"] {
+            assert!(format_str(input).is_err(), "{:?} was formatted", input);
+        }
+    }
+
+    #[test]
+    fn test_format_str_keeps_trailing_comments() {
+        assert_eq!(
+            format_str("(a  (b c) ; why\n d) ; done").unwrap(),
+            "(a\n (b c) ; why\n d) ; done\n"
+        );
+        let err = format_str("(a\n ; own line\n b)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Comment on a line of its own at line 2, column 2"
+        );
+    }
+
+    #[test]
+    fn test_format_in_place_leaves_unparsable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = ["(a b) (c d)", "", "(a ) b)", "; comment"];
+        let paths: Vec<PathBuf> = (0..inputs.len())
+            .map(|index| dir.path().join(format!("dune{}", index)))
+            .collect();
+        for (path, input) in paths.iter().zip(&inputs) {
+            fs::write(path, input).unwrap();
+        }
+
+        let failures = format_in_place(&paths, None);
+        let failed: Vec<&PathBuf> = failures.iter().map(|(path, _)| path).collect();
+        assert_eq!(failed, paths.iter().collect::<Vec<_>>());
+        for (path, input) in paths.iter().zip(&inputs) {
+            assert_eq!(fs::read_to_string(path).unwrap(), *input);
+        }
+    }
+
+    #[test]
+    fn test_write_formatted_atomic_failed_rename() {
+        // Renaming a file over a non-empty directory fails even for the
+        // superuser, so this exercises the cleanup path everywhere.
+        let dir = tempfile::tempdir().unwrap();
+        let blocked = dir.path().join("blocked");
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("inner"), "(x)").unwrap();
+
+        let err = write_formatted_atomic(&blocked, "(a b)\n").unwrap_err();
+        assert!(err.to_string().contains(&blocked.display().to_string()));
+        assert!(blocked.join("inner").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_formatted_atomic_read_only_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dune");
+        fs::write(&path, "(a   b)").unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Permission bits don't apply to the superuser, so there is nothing
+        // to check there.
+        let privileged = File::create(dir.path().join("probe")).is_ok();
+        if !privileged {
+            let err = write_formatted_atomic(&path, "(a b)\n").unwrap_err();
+            assert!(err.to_string().contains(&path.display().to_string()));
+            assert_eq!(fs::read_to_string(&path).unwrap(), "(a   b)");
+        }
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
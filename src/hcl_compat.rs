@@ -0,0 +1,189 @@
+use crate::Sexp;
+use hcl::format::{Format, Formatter};
+use hcl::{Attribute, Block, Body, Expression, Identifier, Number, Object, ObjectKey, Structure};
+
+fn text(atom: &str) -> String {
+    Sexp::Atom(atom.to_string()).as_text().unwrap()
+}
+
+fn expression(sexp: &Sexp) -> Expression {
+    match sexp {
+        Sexp::Nil => Expression::Null,
+        Sexp::Atom(atom) => match atom.as_str() {
+            "true" => Expression::Bool(true),
+            "false" => Expression::Bool(false),
+            "null" => Expression::Null,
+            _ if atom.starts_with('"') => Expression::String(text(atom)),
+            _ => match (atom.parse::<i64>(), atom.parse::<f64>()) {
+                (Ok(integer), _) => Expression::Number(Number::from(integer)),
+                (_, Ok(float)) if atom.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                    Number::from_f64(float)
+                        .map(Expression::Number)
+                        .unwrap_or_else(|| Expression::String(atom.clone()))
+                }
+                _ => Expression::String(atom.clone()),
+            },
+        },
+        Sexp::List(_) => values(sexp.items()),
+    }
+}
+
+/// The value of the elements after an attribute's name: an object when
+/// they're all `(key value)` lists, the value itself when there's just one,
+/// and an array otherwise.
+fn values(values: &[Sexp]) -> Expression {
+    let keyed = values
+        .iter()
+        .all(|value| matches!(value.items().first(), Some(Sexp::Atom(_))));
+    match values {
+        [value] if !keyed => expression(value),
+        _ if keyed && !values.is_empty() => {
+            let mut object = Object::new();
+            for value in values {
+                let (key, rest) = value.items().split_first().unwrap();
+                let key = match key {
+                    Sexp::Atom(atom) => text(atom),
+                    _ => unreachable!(),
+                };
+                let key = match Identifier::new(key.clone()) {
+                    Ok(identifier) => ObjectKey::Identifier(identifier),
+                    Err(_) => ObjectKey::Expression(Expression::String(key)),
+                };
+                object.insert(key, self::values(rest));
+            }
+            Expression::Object(object)
+        }
+        _ => Expression::Array(values.iter().map(expression).collect()),
+    }
+}
+
+/// A block or attribute name, with whatever HCL doesn't allow in an
+/// identifier replaced by underscores, as `hcl-rs` sanitizes them.
+fn identifier(name: &Sexp) -> Identifier {
+    match name {
+        Sexp::Atom(atom) => Identifier::sanitized(text(atom)),
+        _ => Identifier::sanitized(name.to_compact_string()),
+    }
+}
+
+fn block(name: &Sexp, rest: &[Sexp]) -> Block {
+    let labels = rest.iter().take_while(|item| matches!(item, Sexp::Atom(_)));
+    let mut block = Block::builder(identifier(name));
+    for label in labels.clone() {
+        block = block.add_label(label.as_text().unwrap());
+    }
+    let body = body(&rest[labels.count()..]);
+    block.add_structures(body).build()
+}
+
+/// The blocks of `items`, leaving out empty lists, which have no name.
+fn blocks(items: &[Sexp]) -> Vec<Block> {
+    items
+        .iter()
+        .filter_map(|item| item.items().split_first())
+        .map(|(name, rest)| block(name, rest))
+        .collect()
+}
+
+fn body(items: &[Sexp]) -> Body {
+    let mut body = Body::builder();
+    for item in items {
+        let (name, rest) = match item.items().split_first() {
+            Some(split) => split,
+            None => continue,
+        };
+        let structure = match rest {
+            [Sexp::Atom(equals), values @ ..] if equals == "=" => {
+                Structure::Attribute(Attribute::new(identifier(name), self::values(values)))
+            }
+            _ if rest.iter().all(|item| matches!(item, Sexp::Atom(_))) => {
+                let value = match rest {
+                    [value] => expression(value),
+                    _ => Expression::Array(rest.iter().map(expression).collect()),
+                };
+                Structure::Attribute(Attribute::new(identifier(name), value))
+            }
+            _ => Structure::Block(block(name, rest)),
+        };
+        body = body.add_structure(structure);
+    }
+    body.build()
+}
+
+impl Sexp {
+    /// The tree as Terraform HCL, written by `hcl-rs`. A list like
+    /// `(resource aws_instance web (ami "ami-123") (tags = (Name web)))` is
+    /// a block, with the atoms after its type as labels and the lists after
+    /// them as its body, and a list of blocks is a whole file. In a body, a
+    /// list holding only atoms is an attribute (an array when there are
+    /// several), one with `=` after its name an attribute whose value is
+    /// the rest, as an object when it is all `(key value)` lists, and any
+    /// other list a nested block. Numbers, `true`, `false` and `null` keep
+    /// their types and every other atom is a string. Names HCL doesn't
+    /// allow have the offending characters replaced by underscores, and
+    /// empty lists are left out.
+    pub fn to_terraform_hcl(&self) -> String {
+        let blocks = match self.items().first() {
+            Some(Sexp::Atom(_)) => blocks(std::slice::from_ref(self)),
+            _ => blocks(self.items()),
+        };
+        let body = Body::builder().add_blocks(blocks).build();
+        let mut formatter = Formatter::builder().compact_arrays(true).build_vec();
+        // Formatting into memory can't fail.
+        body.format_string(&mut formatter).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_terraform_hcl() {
+        let sexp = Sexp::of_str(
+            r#"((provider aws (region us-east-1))
+                (resource aws_instance web
+                  (ami "ami-123")
+                  (instance_type t2.micro)
+                  (count 2)
+                  (monitoring true)
+                  (security_groups sg-1 sg-2)
+                  (tags = (Name web) ("team:owner" infra))
+                  (ebs_block_device (device_name /dev/sdb) (volume_size 10))))"#,
+        )
+        .unwrap();
+        let hcl = sexp.to_terraform_hcl();
+        assert_eq!(
+            hcl,
+            r#"provider "aws" {
+  region = "us-east-1"
+}
+
+resource "aws_instance" "web" {
+  ami = "ami-123"
+  instance_type = "t2.micro"
+  count = 2
+  monitoring = true
+  security_groups = ["sg-1", "sg-2"]
+  tags = {
+    Name = "web"
+    "team:owner" = "infra"
+  }
+
+  ebs_block_device {
+    device_name = "/dev/sdb"
+    volume_size = 10
+  }
+}
+"#
+        );
+        let body: Body = hcl::from_str(&hcl).unwrap();
+        assert_eq!(body.blocks().count(), 2);
+        assert_eq!(
+            Sexp::of_str(r#"(resource "my box" () (1 x) ("team:owner" = infra))"#)
+                .unwrap()
+                .to_terraform_hcl(),
+            "resource \"my box\" {\n  _1 = \"x\"\n  team_owner = \"infra\"\n}\n"
+        );
+    }
+}
@@ -1,9 +1,47 @@
 use anyhow::{anyhow, Context, Error};
 use std::fmt;
 
+mod anchor;
+mod budget;
+mod comment;
+#[cfg(feature = "cstree-compat")]
+mod cstree_compat;
+mod document;
+mod emit;
+mod error;
 mod ffi;
+mod format;
+#[cfg(feature = "hcl")]
+mod hcl_compat;
+mod lint;
+mod merge;
+mod minimize;
+mod path;
+#[cfg(feature = "pest-compat")]
+mod pest_compat;
+#[cfg(feature = "rowan-compat")]
+mod rowan_compat;
+mod sample;
+mod select;
+#[cfg(feature = "syn-compat")]
+mod syn_compat;
+mod transform;
+mod ts_dump;
 
-#[derive(Clone, Debug)]
+pub use anchor::Anchor;
+pub use comment::TrailingComments;
+pub use document::Document;
+pub use emit::OrgStyle;
+pub use error::SexpError;
+pub use format::{
+    format_in_place, format_str, write_formatted_atomic, write_formatted_atomic_with_backup,
+};
+pub use merge::{merge3, Conflict, MergeOptions, MergeResult};
+pub use minimize::{minimize, minimize_with_budget, Budget};
+pub use path::SexpPath;
+pub use select::{Descendants, Matches, Query, QueryMatches};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Sexp {
     Atom(String),
     List(Vec<Sexp>),
@@ -16,17 +54,22 @@ impl Sexp {
         let tree = parser
             .parse(input, None)
             .context("Could not parse anything")?;
+        Sexp::of_tree(&tree, input)
+    }
+
+    pub(crate) fn of_tree(tree: &tree_sitter::Tree, input: &str) -> Result<Sexp, Error> {
         let root = tree.root_node();
 
         let mut walker = root.walk();
         walker.goto_first_child(); // we skip the top-level `sexp` node
+        while walker.node().kind() == "comment" && walker.goto_next_sibling() {}
         Sexp::build_tree(walker.node(), input.as_bytes())
     }
 
     fn build_tree(root: tree_sitter::Node, bytes: &[u8]) -> Result<Sexp, Error> {
         match root.kind() {
             "atom" => {
-                let text = root.utf8_text(&bytes)?.to_string();
+                let text = root.utf8_text(bytes)?.to_string();
                 Ok(Sexp::Atom(text))
             }
             kind @ "list" | kind @ "ERROR" | kind @ "MISSING" => {
@@ -38,6 +81,9 @@ impl Sexp {
                 };
                 while walker.goto_next_sibling() {
                     let child = walker.node();
+                    if child.kind() == "comment" {
+                        continue;
+                    }
                     children.push(Sexp::build_tree(child, bytes)?);
                 }
                 Ok(Sexp::List(children))
@@ -47,6 +93,139 @@ impl Sexp {
         }
     }
 
+    pub fn atom<S: Into<String>>(name: S) -> Sexp {
+        Sexp::Atom(name.into())
+    }
+
+    /// A double-quoted atom holding `text`, escaped so it reads back the same.
+    pub fn string(text: &str) -> Sexp {
+        let mut quoted = String::with_capacity(text.len() + 2);
+        quoted.push('"');
+        for c in text.chars() {
+            match c {
+                '"' | '\\' => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                '\n' => quoted.push_str("\\n"),
+                _ => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        Sexp::Atom(quoted)
+    }
+
+    /// The text of an atom, with string atoms unquoted and unescaped.
+    pub fn as_text(&self) -> Option<String> {
+        let atom = match self {
+            Sexp::Atom(atom) => atom,
+            _ => return None,
+        };
+        let inner = match atom.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+            Some(inner) if atom.len() >= 2 => inner,
+            _ => return Some(atom.clone()),
+        };
+        let mut text = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some(escaped) => text.push(escaped),
+                None => text.push('\\'),
+            }
+        }
+        Some(text)
+    }
+
+    /// A bare atom if the grammar would read `text` back as one, otherwise a
+    /// string atom.
+    pub fn atom_or_string(text: &str) -> Sexp {
+        let bare = !text.is_empty()
+            && text.chars().all(|c| {
+                c.is_ascii_alphanumeric()
+                    || "_@:!$%&*+./<=>?^~-".contains(c)
+                    || (('\u{C0}'..='\u{FF}').contains(&c) && c != '\u{D7}' && c != '\u{F7}')
+            });
+        if bare {
+            Sexp::atom(text)
+        } else {
+            Sexp::string(text)
+        }
+    }
+
+    /// Builds a list the same way the parser does, terminated by a `Nil`.
+    pub fn list(mut parts: Vec<Sexp>) -> Sexp {
+        parts.push(Sexp::Nil);
+        Sexp::List(parts)
+    }
+
+    /// The elements of a list, without the closing `Nil`.
+    pub fn items(&self) -> &[Sexp] {
+        match self {
+            Sexp::List(parts) => match parts.last() {
+                Some(Sexp::Nil) => &parts[..parts.len() - 1],
+                _ => parts,
+            },
+            _ => &[],
+        }
+    }
+
+    pub fn head(&self) -> Option<&str> {
+        match self.items().first() {
+            Some(Sexp::Atom(head)) => Some(head),
+            _ => None,
+        }
+    }
+
+    /// Number of atoms and lists in this tree, counting itself.
+    pub fn node_count(&self) -> usize {
+        match self {
+            Sexp::Nil => 0,
+            Sexp::Atom(_) => 1,
+            Sexp::List(parts) => 1 + parts.iter().map(Sexp::node_count).sum::<usize>(),
+        }
+    }
+
+    /// The whole tree on a single line, with one space between elements.
+    pub fn to_compact_string(&self) -> String {
+        match self {
+            Sexp::Nil => String::new(),
+            Sexp::Atom(atom) => atom.clone(),
+            Sexp::List(_) => {
+                let items: Vec<String> = self.items().iter().map(Sexp::to_compact_string).collect();
+                format!("({})", items.join(" "))
+            }
+        }
+    }
+
+    /// The length of `to_compact_string`, without building it.
+    pub fn compact_len(&self) -> usize {
+        self.compact_len_within(usize::MAX).unwrap()
+    }
+
+    /// `compact_len`, or `None` as soon as it is known to exceed `limit`,
+    /// without measuring the rest of the tree.
+    pub(crate) fn compact_len_within(&self, limit: usize) -> Option<usize> {
+        let len = match self {
+            Sexp::Nil => 0,
+            Sexp::Atom(atom) => atom.len(),
+            Sexp::List(_) => {
+                let items = self.items();
+                let mut len = 2 + items.len().saturating_sub(1);
+                for item in items {
+                    len += item.compact_len_within(limit.checked_sub(len)?)?;
+                }
+                len
+            }
+        };
+        Some(len).filter(|&len| len <= limit)
+    }
+
     pub fn size(&self) -> u32 {
         match self {
             Sexp::Nil => 0,
@@ -62,12 +241,19 @@ impl Sexp {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct PrettyPrinter {
     max_width: u32,
     current_width: u32,
     indent_size: u32,
     current_depth: u32,
+    align_trailing_comments: bool,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> PrettyPrinter {
+        PrettyPrinter::new()
+    }
 }
 
 impl PrettyPrinter {
@@ -77,7 +263,51 @@ impl PrettyPrinter {
             max_width: 150,
             current_width: 0,
             indent_size: 1,
+            align_trailing_comments: false,
+        }
+    }
+
+    pub fn with_max_width(mut self, max_width: u32) -> PrettyPrinter {
+        self.max_width = max_width;
+        self
+    }
+
+    pub fn with_indent_size(mut self, indent_size: u32) -> PrettyPrinter {
+        self.indent_size = indent_size;
+        self
+    }
+
+    /// Whether the trailing comments in a broken list line up, two columns
+    /// past the end of its longest element. A comment that would then go
+    /// past `max_width` keeps a single space before it instead.
+    pub fn with_align_trailing_comments(mut self, align: bool) -> PrettyPrinter {
+        self.align_trailing_comments = align;
+        self
+    }
+
+    /// Renders `sexp` with this printer's settings, starting from a fresh
+    /// line.
+    pub fn print(&self, sexp: &Sexp) -> String {
+        self.print_with_comments(sexp, &TrailingComments::new())
+    }
+
+    /// Renders `sexp` like `print`, with each of `comments` after the atom
+    /// or list at its path. A list with a comment after any of its elements
+    /// is broken into one element per line, so that each comment ends one.
+    pub fn print_with_comments(&self, sexp: &Sexp, comments: &TrailingComments) -> String {
+        let mut printer = PrettyPrinter {
+            current_width: 0,
+            current_depth: 0,
+            ..self.clone()
+        };
+        let mut printed = String::new();
+        // Writing to a String can't fail.
+        let _ = printer.pp_at(sexp, &SexpPath::root(), comments, &mut printed);
+        if let Some(comment) = comments.get(&SexpPath::root()) {
+            printed.push(' ');
+            printed.push_str(comment);
         }
+        printed
     }
 
     fn padding(&self) -> u32 {
@@ -89,6 +319,16 @@ impl PrettyPrinter {
     }
 
     pub fn pp(&mut self, sexp: &Sexp, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.pp_at(sexp, &SexpPath::root(), &TrailingComments::new(), fmt)
+    }
+
+    fn pp_at(
+        &mut self,
+        sexp: &Sexp,
+        path: &SexpPath,
+        comments: &TrailingComments,
+        fmt: &mut dyn fmt::Write,
+    ) -> Result<(), fmt::Error> {
         match sexp {
             Sexp::Atom(atom) => {
                 self.current_width += atom.len() as u32;
@@ -98,7 +338,13 @@ impl PrettyPrinter {
                 self.current_depth -= 1;
                 Ok(())
             }
-            Sexp::List(parts) if parts.len() > 0 => {
+            Sexp::List(parts)
+                if !comments.is_empty()
+                    && (0..parts.len()).any(|index| comments.contains_key(&path.child(index))) =>
+            {
+                self.pp_commented(parts, path, comments, fmt)
+            }
+            Sexp::List(parts) if !parts.is_empty() => {
                 self.current_depth += 1;
                 let next_term_width = self.current_width + self.padding() + sexp.size();
                 let term_overflows = next_term_width > self.max_width / 2;
@@ -108,26 +354,25 @@ impl PrettyPrinter {
                 }
 
                 write!(fmt, "(")?;
-                self.pp(&parts[0], fmt)?;
+                self.pp_at(&parts[0], &path.child(0), comments, fmt)?;
 
-                for p in parts[1..].iter() {
+                for (index, p) in parts.iter().enumerate().skip(1) {
                     match p {
                         Sexp::Nil => {
-                            self.pp(&p, fmt)?;
+                            self.pp_at(p, path, comments, fmt)?;
                         }
                         _ => {
                             let part_size = next_term_width + self.padding() + p.size();
                             let part_will_overflow = part_size > self.max_width;
                             if part_will_overflow {
-                                write!(fmt, "\n")?;
+                                writeln!(fmt)?;
                                 for _ in 0..(self.padding() + self.indent_size) {
                                     write!(fmt, " ")?
                                 }
-                                self.pp(&p, fmt)?;
                             } else {
                                 write!(fmt, " ")?;
-                                self.pp(&p, fmt)?;
                             }
+                            self.pp_at(p, &path.child(index), comments, fmt)?;
                         }
                     }
                 }
@@ -138,6 +383,74 @@ impl PrettyPrinter {
             Sexp::List(_) => write!(fmt, "()"),
         }
     }
+
+    /// Writes a list that has comments after some of its elements with each
+    /// element after the head on a line of its own, and the closing paren
+    /// too when the last element has a comment.
+    fn pp_commented(
+        &mut self,
+        parts: &[Sexp],
+        path: &SexpPath,
+        comments: &TrailingComments,
+        fmt: &mut dyn fmt::Write,
+    ) -> Result<(), fmt::Error> {
+        self.current_depth += 1;
+        let indent = self.padding() + self.indent_size;
+        // Each element printed, with the column it ends at after the head,
+        // whose start column isn't known.
+        let mut lines = vec![];
+        for (index, part) in parts.iter().enumerate() {
+            if let Sexp::Nil = part {
+                continue;
+            }
+            if index > 0 {
+                self.current_width = indent;
+            }
+            let mut text = String::new();
+            self.pp_at(part, &path.child(index), comments, &mut text)?;
+            let end = match text.rfind('\n') {
+                Some(newline) => text.len() - newline - 1,
+                None => indent as usize + text.len(),
+            };
+            let end = Some(end).filter(|_| index > 0);
+            lines.push((text, end, comments.get(&path.child(index))));
+        }
+        let column = lines
+            .iter()
+            .filter_map(|(_, end, _)| *end)
+            .max()
+            .unwrap_or(0)
+            + 2;
+
+        write!(fmt, "(")?;
+        for (text, end, comment) in &lines {
+            if end.is_some() {
+                write!(fmt, "\n{:1$}", "", indent as usize)?;
+            }
+            write!(fmt, "{}", text)?;
+            if let Some(comment) = comment {
+                let spaces = match end {
+                    Some(end)
+                        if self.align_trailing_comments
+                            && column + comment.len() <= self.max_width as usize =>
+                    {
+                        column - end
+                    }
+                    _ => 1,
+                };
+                write!(fmt, "{:1$}{2}", "", spaces, comment)?;
+            }
+        }
+        if let Some((_, _, Some(_))) = lines.last() {
+            write!(fmt, "\n{:1$}", "", self.padding() as usize)?;
+        }
+        write!(fmt, ")")?;
+        self.current_width = indent;
+        if let Some(Sexp::Nil) = parts.last() {
+            self.current_depth -= 1;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for Sexp {
@@ -152,13 +465,13 @@ mod tests {
 
     #[test]
     fn test_error_sexpr() {
-        assert_eq!(Sexp::of_str(&"(sexp (").is_err(), true,);
+        assert!(Sexp::of_str("(sexp (").is_err());
     }
 
     #[test]
     fn test_single_sexpr() {
         assert_eq!(
-            Sexp::of_str(&"source_file").unwrap().to_string(),
+            Sexp::of_str("source_file").unwrap().to_string(),
             r#"source_file"#.trim().to_string()
         );
     }
@@ -166,11 +479,11 @@ mod tests {
     #[test]
     fn test_sibling_sexpr() {
         assert_eq!(
-            Sexp::of_str(&"(source file)").unwrap().to_string(),
+            Sexp::of_str("(source file)").unwrap().to_string(),
             r#"(source file)"#.trim().to_string()
         );
         assert_eq!(
-            Sexp::of_str(&"(source file tree)").unwrap().to_string(),
+            Sexp::of_str("(source file tree)").unwrap().to_string(),
             r#"(source file tree)"#.trim().to_string()
         );
     }
@@ -178,7 +491,7 @@ mod tests {
     #[test]
     fn test_nested_sexpr() {
         assert_eq!(
-            Sexp::of_str(&"(source (file))").unwrap().to_string(),
+            Sexp::of_str("(source (file))").unwrap().to_string(),
             r#"(source (file))"#.trim().to_string()
         );
     }
@@ -186,15 +499,39 @@ mod tests {
     #[test]
     fn test_nested_sibling_sexpr() {
         assert_eq!(
-            Sexp::of_str(&"(source (file tree))").unwrap().to_string(),
+            Sexp::of_str("(source (file tree))").unwrap().to_string(),
             r#"(source (file tree))"#.trim().to_string()
         );
     }
 
+    #[test]
+    fn test_string_atoms() {
+        let text = "say \"hi\"\n\\o/";
+        let atom = Sexp::string(text);
+        assert_eq!(atom.to_string(), r#""say \"hi\"\n\\o/""#);
+        assert_eq!(atom.as_text().unwrap(), text);
+        let parsed = Sexp::of_str(&atom.to_string()).unwrap();
+        assert_eq!(parsed.as_text().unwrap(), text);
+        assert_eq!(Sexp::atom("plain").as_text().unwrap(), "plain");
+        assert_eq!(Sexp::atom_or_string("a b").to_string(), r#""a b""#);
+        assert_eq!(Sexp::atom_or_string("a-b").to_string(), "a-b");
+    }
+
+    #[test]
+    fn test_printer_settings() {
+        let sexp = Sexp::of_str("(library (name core) (libraries unix))").unwrap();
+        let printer = PrettyPrinter::new().with_max_width(40).with_indent_size(2);
+        assert_eq!(
+            printer.print(&sexp),
+            "(library (name core)\n  (libraries unix))"
+        );
+        assert_eq!(PrettyPrinter::new().print(&sexp), sexp.to_string());
+    }
+
     #[test]
     fn test_field_sexpr() {
         assert_eq!(
-            Sexp::of_str(&"(source file: test)").unwrap().to_string(),
+            Sexp::of_str("(source file: test)").unwrap().to_string(),
             r#"(source file: test)"#.trim().to_string()
         );
     }
@@ -212,7 +549,7 @@ mod tests {
     #[test]
     fn test_pretty_printing_sexpr() {
         let sexp = Sexp::of_str(
-            &"
+            "
 
 
 
@@ -0,0 +1,87 @@
+use crate::{Sexp, SexpPath};
+
+impl Sexp {
+    /// Paths of `ERROR` lists that sit right next to another `ERROR` in the
+    /// same list, which usually means the parser lost track well before
+    /// either of them. Each such node is reported once, in pre-order.
+    pub fn lint_no_consecutive_error_nodes(&self) -> Vec<SexpPath> {
+        let is_error = |node: &Sexp| node.head() == Some("ERROR");
+        let mut paths = vec![];
+        for (path, node) in self.descendants() {
+            let items = node.items();
+            for (index, item) in items.iter().enumerate() {
+                let before = index > 0 && is_error(&items[index - 1]);
+                let after = items.get(index + 1).is_some_and(is_error);
+                if is_error(item) && (before || after) {
+                    paths.push(path.child(index));
+                }
+            }
+        }
+        paths
+    }
+
+    /// The number of `ERROR` and `MISSING` lists in the tree, for measuring
+    /// how much of an input parsed cleanly.
+    pub fn count_error_nodes(&self) -> usize {
+        self.count(|node| matches!(node.head(), Some("ERROR") | Some("MISSING")))
+    }
+
+    /// Atoms with a different number of `(` and `)`, which suggest the
+    /// grammar swallowed part of a sub-expression into a single token. Only
+    /// string atoms can hold parentheses at all when the tree comes from
+    /// this crate's parser, but dumps from other tools may have bare ones.
+    pub fn lint_balanced_parens_in_atoms(&self) -> Vec<&str> {
+        self.descendants()
+            .filter_map(|(_, node)| match node {
+                Sexp::Atom(atom) if atom.matches('(').count() != atom.matches(')').count() => {
+                    Some(atom.as_str())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_no_consecutive_error_nodes() {
+        let sexp = Sexp::of_str(
+            "(source_file (ERROR (a)) (ERROR b) (call (ERROR c) (x) (ERROR d) (ERROR e) (ERROR f)))",
+        )
+        .unwrap();
+        let paths: Vec<String> = sexp
+            .lint_no_consecutive_error_nodes()
+            .iter()
+            .map(SexpPath::to_string)
+            .collect();
+        assert_eq!(paths, ["/1", "/2", "/3/3", "/3/4", "/3/5"]);
+    }
+
+    #[test]
+    fn test_lint_no_consecutive_error_nodes_clean() {
+        let sexp = Sexp::of_str("(source_file (ERROR a) (b) (ERROR c) ERROR)").unwrap();
+        assert!(sexp.lint_no_consecutive_error_nodes().is_empty());
+    }
+
+    #[test]
+    fn test_lint_balanced_parens_in_atoms() {
+        let sexp = Sexp::of_str(r#"(call "f(x" "(ok)" (args "g(h(y)" plain ")("))"#).unwrap();
+        assert_eq!(
+            sexp.lint_balanced_parens_in_atoms(),
+            [r#""f(x""#, r#""g(h(y)""#]
+        );
+        let sexp = Sexp::list(vec![Sexp::atom("a)"), Sexp::atom("b")]);
+        assert_eq!(sexp.lint_balanced_parens_in_atoms(), ["a)"]);
+    }
+
+    #[test]
+    fn test_count_error_nodes() {
+        let sexp =
+            Sexp::of_str("(source_file (ERROR (MISSING x)) (ok ERROR) (call (ERROR)))").unwrap();
+        assert_eq!(sexp.count_error_nodes(), 3);
+        assert_eq!(Sexp::of_str("(a (b))").unwrap().count_error_nodes(), 0);
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{Sexp, SexpPath};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, Default)]
+pub struct MergeOptions {
+    /// Heads of lists whose children are a set, like `deps`: children added
+    /// on both sides are all kept instead of conflicting.
+    pub union_heads: HashSet<String>,
+}
+
+/// A place where both sides changed the same part of `base` differently.
+/// `ours` and `theirs` are the elements each side has there, with an empty
+/// side meaning it deleted them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// Where the conflicting elements start in `MergeResult::merged`.
+    pub path: SexpPath,
+    pub ours: Vec<Sexp>,
+    pub theirs: Vec<Sexp>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MergeResult {
+    /// The merge, with our side taken wherever there's a conflict.
+    pub merged: Sexp,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merges the changes `ours` and `theirs` each made to `base`, node by node.
+///
+/// Lists of the same length on all three sides are merged element by
+/// element. Lists that grew or shrank are matched up by their elements'
+/// keys, an atom's text or a list's head and first argument, keeping
+/// `base`'s order: elements removed on one side and untouched on the other
+/// are dropped, and elements added on one side are inserted after the same
+/// neighbour. When a list can't be matched up like that, or both sides
+/// change the same element or insert at the same spot, it's a conflict.
+pub fn merge3(base: &Sexp, ours: &Sexp, theirs: &Sexp, options: &MergeOptions) -> MergeResult {
+    let mut merge = Merge {
+        options,
+        conflicts: vec![],
+    };
+    let merged = merge.node(SexpPath::root(), base, ours, theirs);
+    MergeResult {
+        merged,
+        conflicts: merge.conflicts,
+    }
+}
+
+impl MergeResult {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// The merged tree pretty-printed, with every conflict replaced by both
+    /// sides between git-style conflict markers.
+    pub fn to_string_with_markers(&self) -> String {
+        let mut marked = self.merged.clone();
+        let mut placeholders = vec![];
+        let mut conflicts: Vec<&Conflict> = self.conflicts.iter().collect();
+        // Later conflicts first, so the paths of earlier ones stay valid.
+        conflicts.sort_by(|a, b| b.path.cmp(&a.path));
+        for (n, conflict) in conflicts.into_iter().enumerate() {
+            let placeholder = format!("__sexpfmt_conflict_{}__", n);
+            marked = splice(&marked, &conflict.path, conflict.ours.len(), &placeholder);
+            placeholders.push((placeholder, conflict));
+        }
+
+        let mut text = format!("{}\n", marked);
+        for (placeholder, conflict) in placeholders {
+            let side = |sexps: &[Sexp]| -> String {
+                sexps.iter().map(|sexp| format!("{}\n", sexp)).collect()
+            };
+            let markers = format!(
+                "\n<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+                side(&conflict.ours),
+                side(&conflict.theirs)
+            );
+            text = text.replace(&placeholder, &markers);
+        }
+        text
+    }
+}
+
+// Swaps the `len` elements starting at `path` for a single atom.
+fn splice(root: &Sexp, path: &SexpPath, len: usize, atom: &str) -> Sexp {
+    let (index, parent) = match (path.indices().last(), path.parent()) {
+        (Some(&index), Some(parent)) => (index, parent),
+        _ => return Sexp::atom(atom),
+    };
+    let mut items = root.get(&parent).unwrap().items().to_vec();
+    items.splice(index..index + len, vec![Sexp::atom(atom)]);
+    root.with_replaced(&parent, Sexp::list(items)).unwrap()
+}
+
+fn key(sexp: &Sexp) -> String {
+    match sexp.items() {
+        [] => sexp.to_compact_string(),
+        [head] => head.to_compact_string(),
+        [head, first, ..] => format!("{} {}", head.to_compact_string(), first.to_compact_string()),
+    }
+}
+
+fn keyed(items: &[Sexp]) -> Option<HashMap<String, usize>> {
+    let mut keys = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        if keys.insert(key(item), index).is_some() {
+            return None;
+        }
+    }
+    Some(keys)
+}
+
+// Elements one side added, grouped by the key of the nearest preceding
+// element that's also in base (`None` for the start of the list).
+fn insertions<'a>(
+    items: &'a [Sexp],
+    base: &HashMap<String, usize>,
+) -> HashMap<Option<String>, Vec<&'a Sexp>> {
+    let mut insertions: HashMap<Option<String>, Vec<&Sexp>> = HashMap::new();
+    let mut anchor = None;
+    for item in items {
+        let key = key(item);
+        if base.contains_key(&key) {
+            anchor = Some(key);
+        } else {
+            insertions.entry(anchor.clone()).or_default().push(item);
+        }
+    }
+    insertions
+}
+
+struct Merge<'a> {
+    options: &'a MergeOptions,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merge<'_> {
+    fn conflict(&mut self, path: SexpPath, ours: Vec<Sexp>, theirs: Vec<Sexp>) {
+        self.conflicts.push(Conflict { path, ours, theirs });
+    }
+
+    fn node(&mut self, path: SexpPath, base: &Sexp, ours: &Sexp, theirs: &Sexp) -> Sexp {
+        if ours == theirs || base == theirs {
+            return ours.clone();
+        }
+        if base == ours {
+            return theirs.clone();
+        }
+
+        let lists = matches!(
+            (base, ours, theirs),
+            (Sexp::List(_), Sexp::List(_), Sexp::List(_))
+        );
+        let same_head =
+            base.head().is_some() && base.head() == ours.head() && base.head() == theirs.head();
+        let union = same_head && self.options.union_heads.contains(base.head().unwrap());
+        let (b, o, t) = (base.items(), ours.items(), theirs.items());
+
+        if lists && !union && b.len() == o.len() && b.len() == t.len() {
+            let items = (0..b.len())
+                .map(|i| self.node(path.child(i), &b[i], &o[i], &t[i]))
+                .collect();
+            return Sexp::list(items);
+        }
+        if lists {
+            if let (Some(bk), Some(ok), Some(tk)) = (keyed(b), keyed(o), keyed(t)) {
+                return self.keyed(path, b, (o, &ok), (t, &tk), &bk, union);
+            }
+        }
+        self.conflict(path, vec![ours.clone()], vec![theirs.clone()]);
+        ours.clone()
+    }
+
+    fn keyed(
+        &mut self,
+        path: SexpPath,
+        base: &[Sexp],
+        (ours, our_keys): (&[Sexp], &HashMap<String, usize>),
+        (theirs, their_keys): (&[Sexp], &HashMap<String, usize>),
+        base_keys: &HashMap<String, usize>,
+        union: bool,
+    ) -> Sexp {
+        let mut our_insertions = insertions(ours, base_keys);
+        let mut their_insertions = insertions(theirs, base_keys);
+        let mut merged: Vec<Sexp> = vec![];
+
+        let mut insert = |merge: &mut Self, merged: &mut Vec<Sexp>, anchor: Option<String>| {
+            let our = our_insertions.remove(&anchor).unwrap_or_default();
+            let their = their_insertions.remove(&anchor).unwrap_or_default();
+            if !our.is_empty() && !their.is_empty() && our != their && !union {
+                let owned = |items: Vec<&Sexp>| items.into_iter().cloned().collect::<Vec<_>>();
+                merge.conflict(path.child(merged.len()), owned(our.clone()), owned(their));
+                merged.extend(our.into_iter().cloned());
+                return;
+            }
+            let seen: HashSet<String> = our.iter().map(|item| key(item)).collect();
+            merged.extend(our.into_iter().cloned());
+            merged.extend(
+                their
+                    .into_iter()
+                    .filter(|item| !seen.contains(&key(item)))
+                    .cloned(),
+            );
+        };
+
+        insert(self, &mut merged, None);
+        for item in base {
+            let key = key(item);
+            let our = our_keys.get(&key).map(|&i| &ours[i]);
+            let their = their_keys.get(&key).map(|&i| &theirs[i]);
+            match (our, their) {
+                (Some(our), Some(their)) => {
+                    let child = path.child(merged.len());
+                    let node = self.node(child, item, our, their);
+                    merged.push(node);
+                }
+                (Some(kept), None) | (None, Some(kept)) if kept == item => {}
+                (Some(our), None) => {
+                    self.conflict(path.child(merged.len()), vec![our.clone()], vec![]);
+                    merged.push(our.clone());
+                }
+                (None, Some(their)) => {
+                    self.conflict(path.child(merged.len()), vec![], vec![their.clone()]);
+                }
+                (None, None) => {}
+            }
+            insert(self, &mut merged, Some(key));
+        }
+        Sexp::list(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, ours: &str, theirs: &str, options: &MergeOptions) -> MergeResult {
+        let parse = |input| Sexp::of_str(input).unwrap();
+        merge3(&parse(base), &parse(ours), &parse(theirs), options)
+    }
+
+    #[test]
+    fn test_clean_merge() {
+        let result = merge(
+            "(project (library (name core) (modules a b)) (test (name t1)))",
+            "(project (library (name core) (modules a b c)) (test (name t1)))",
+            "(project (executable (name main)) (library (name core) (modules a b)) (test (name t2)))",
+            &MergeOptions::default(),
+        );
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged.to_compact_string(),
+            "(project (executable (name main)) (library (name core) (modules a b c)) (test (name t2)))"
+        );
+    }
+
+    #[test]
+    fn test_deletion_merge() {
+        let result = merge(
+            "(project (library (name core)) (test (name t1)) (bench (name b)))",
+            "(project (library (name core)) (bench (name b)))",
+            "(project (library (name core)) (test (name t1)) (bench (name b2)))",
+            &MergeOptions::default(),
+        );
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged.to_compact_string(),
+            "(project (library (name core)) (bench (name b2)))"
+        );
+    }
+
+    #[test]
+    fn test_conflict() {
+        let result = merge(
+            "(project (library (name core) (flags -w)))",
+            "(project (library (name core) (flags -O2)))",
+            "(project (library (name core) (flags -O3)))",
+            &MergeOptions::default(),
+        );
+        assert_eq!(
+            result.conflicts,
+            vec![Conflict {
+                path: vec![1, 2, 1].into(),
+                ours: vec![Sexp::atom("-O2")],
+                theirs: vec![Sexp::atom("-O3")],
+            }]
+        );
+        assert_eq!(
+            result.to_string_with_markers(),
+            "(project (library (name core) (flags \n<<<<<<< ours\n-O2\n=======\n-O3\n>>>>>>> theirs\n)))\n"
+        );
+    }
+
+    #[test]
+    fn test_delete_modify_conflict() {
+        let result = merge(
+            "(project (library (name core)) (test (name t1)))",
+            "(project (library (name core)))",
+            "(project (library (name core)) (test (name t1) (deps data)))",
+            &MergeOptions::default(),
+        );
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].path, vec![2].into());
+        assert!(result.conflicts[0].ours.is_empty());
+        assert!(result
+            .to_string_with_markers()
+            .contains("<<<<<<< ours\n=======\n(test (name t1) (deps data))\n>>>>>>> theirs\n"));
+    }
+
+    #[test]
+    fn test_union_resolution() {
+        let base = "(library (name core) (deps a b))";
+        let ours = "(library (name core) (deps a b c))";
+        let theirs = "(library (name core) (deps a b d))";
+        let result = merge(base, ours, theirs, &MergeOptions::default());
+        assert_eq!(result.conflicts.len(), 1);
+
+        let options = MergeOptions {
+            union_heads: vec!["deps".to_string()].into_iter().collect(),
+        };
+        let result = merge(base, ours, theirs, &options);
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged.to_compact_string(),
+            "(library (name core) (deps a b c d))"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{Sexp, SexpPath};
+use std::time::{Duration, Instant};
+
+/// How much work `minimize_with_budget` may do before settling for the
+/// smallest input found so far.
+#[derive(Clone, Debug)]
+pub struct Budget {
+    pub max_tests: usize,
+    pub max_time: Duration,
+}
+
+impl Default for Budget {
+    fn default() -> Budget {
+        Budget {
+            max_tests: 10_000,
+            max_time: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Shrinks `input` to a smaller s-expression for which `predicate` still
+/// holds, for turning a large private file into a shareable bug report.
+/// Uses the default `Budget`.
+pub fn minimize(input: &str, predicate: impl Fn(&str) -> bool) -> String {
+    minimize_with_budget(input, predicate, Budget::default())
+}
+
+/// Delta debugging over the tree: every list is tried with chunks of its
+/// elements removed (halves first, down to single elements) and replaced by
+/// each of its nested lists, keeping any change for which `predicate` still
+/// holds on the pretty-printed candidate, until nothing more can be removed
+/// or the budget runs out.
+///
+/// Input that doesn't parse, or that doesn't satisfy `predicate` to begin
+/// with, is returned as it is.
+pub fn minimize_with_budget(
+    input: &str,
+    predicate: impl Fn(&str) -> bool,
+    budget: Budget,
+) -> String {
+    let mut best = match Sexp::of_str(input) {
+        Ok(sexp) if predicate(input) => sexp,
+        _ => return input.to_string(),
+    };
+
+    let started = Instant::now();
+    let mut tests = 0;
+    'search: loop {
+        for path in list_paths(&best) {
+            let mut accepted = None;
+            for candidate in candidates(&best, &path) {
+                if tests >= budget.max_tests || started.elapsed() >= budget.max_time {
+                    break 'search;
+                }
+                tests += 1;
+                if predicate(&candidate.to_string()) {
+                    accepted = Some(candidate);
+                    break;
+                }
+            }
+            if let Some(candidate) = accepted {
+                best = candidate;
+                continue 'search;
+            }
+        }
+        break;
+    }
+    best.to_string()
+}
+
+fn list_paths(root: &Sexp) -> Vec<SexpPath> {
+    let mut paths = vec![];
+    let mut stack = vec![SexpPath::root()];
+    while let Some(path) = stack.pop() {
+        let node = root.get(&path).unwrap();
+        if let Sexp::List(_) = node {
+            for index in (0..node.items().len()).rev() {
+                stack.push(path.child(index));
+            }
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The trees to try for the list at `path`, built one at a time as they're
+/// asked for, so a search that accepts an early candidate doesn't pay for
+/// the rest.
+fn candidates<'a>(root: &'a Sexp, path: &'a SexpPath) -> impl Iterator<Item = Sexp> + 'a {
+    let items = root.get(path).unwrap().items();
+
+    let nested = items
+        .iter()
+        .filter(|child| matches!(child, Sexp::List(_)))
+        .cloned();
+
+    let chunks = std::iter::successors(Some(items.len().div_ceil(2)), |chunk| Some(chunk / 2))
+        .take_while(|&chunk| chunk > 0);
+    let removals = chunks.flat_map(move |chunk| {
+        (0..items.len()).step_by(chunk).map(move |start| {
+            let mut kept = items[..start].to_vec();
+            kept.extend_from_slice(&items[(start + chunk).min(items.len())..]);
+            Sexp::list(kept)
+        })
+    });
+
+    nested
+        .chain(removals)
+        .filter_map(move |replacement| root.with_replaced(path, replacement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize_to_enclosing_stanza() {
+        let input = "(project
+          (library (name core) (modules a b c))
+          (executable (name main)
+            (libraries core unix (select x.ml from (yes -> x.yes.ml) (no -> x.no.ml)))
+            (flags -w +a))
+          (test (name t1) (deps data)))";
+        let minimized = minimize(input, |candidate| candidate.contains("(yes -> x.yes.ml)"));
+        assert_eq!(minimized, "(yes -> x.yes.ml)");
+    }
+
+    #[test]
+    fn test_minimize_keeps_required_context() {
+        let input = "(a (b 1 2 3) (c (d 4) (e 5)) (f 6))";
+        let minimized = minimize(input, |candidate| {
+            candidate.contains("(c ") && candidate.contains("(e 5)")
+        });
+        assert_eq!(minimized, "(c (e 5))");
+    }
+
+    #[test]
+    fn test_minimize_respects_budget() {
+        let input = "(a (b 1 2 3) (c (d 4) (e 5)) (f 6))";
+        let budget = Budget {
+            max_tests: 0,
+            ..Budget::default()
+        };
+        let minimized = minimize_with_budget(input, |_| true, budget);
+        assert_eq!(minimized, Sexp::of_str(input).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_candidates() {
+        let root = Sexp::of_str("(a (b) c (d))").unwrap();
+        let candidates: Vec<String> = candidates(&root, &SexpPath::root())
+            .map(|candidate| candidate.to_string())
+            .collect();
+        assert_eq!(
+            candidates,
+            [
+                "(b)",
+                "(d)",
+                "(c (d))",
+                "(a (b))",
+                "((b) c (d))",
+                "(a c (d))",
+                "(a (b) (d))",
+                "(a (b) c)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minimize_uninteresting_input() {
+        assert_eq!(minimize("(a b)", |_| false), "(a b)");
+    }
+}
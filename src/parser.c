@@ -1,46 +1,53 @@
-#include <tree_sitter/parser.h>
+/* Automatically @generated by tree-sitter */
+
+#include "tree_sitter/parser.h"
 
 #if defined(__GNUC__) || defined(__clang__)
-#pragma GCC diagnostic push
 #pragma GCC diagnostic ignored "-Wmissing-field-initializers"
 #endif
 
-#define LANGUAGE_VERSION 12
+#define LANGUAGE_VERSION 15
 #define STATE_COUNT 9
-#define LARGE_STATE_COUNT 5
-#define SYMBOL_COUNT 8
+#define LARGE_STATE_COUNT 7
+#define SYMBOL_COUNT 9
 #define ALIAS_COUNT 0
-#define TOKEN_COUNT 4
+#define TOKEN_COUNT 5
 #define EXTERNAL_TOKEN_COUNT 0
 #define FIELD_COUNT 0
 #define MAX_ALIAS_SEQUENCE_LENGTH 3
+#define MAX_RESERVED_WORD_SET_SIZE 0
+#define PRODUCTION_ID_COUNT 1
+#define SUPERTYPE_COUNT 0
 
-enum {
+enum ts_symbol_identifiers {
   sym_atom = 1,
   anon_sym_LPAREN = 2,
   anon_sym_RPAREN = 3,
-  sym_sexp = 4,
-  sym__sexp = 5,
-  sym_list = 6,
-  aux_sym_list_repeat1 = 7,
+  sym_comment = 4,
+  sym_sexp = 5,
+  sym__sexp = 6,
+  sym_list = 7,
+  aux_sym_list_repeat1 = 8,
 };
 
-static const char *ts_symbol_names[] = {
+static const char * const ts_symbol_names[] = {
   [ts_builtin_sym_end] = "end",
   [sym_atom] = "atom",
   [anon_sym_LPAREN] = "(",
   [anon_sym_RPAREN] = ")",
+  [sym_comment] = "comment",
   [sym_sexp] = "sexp",
   [sym__sexp] = "_sexp",
   [sym_list] = "list",
   [aux_sym_list_repeat1] = "list_repeat1",
 };
 
-static TSSymbol ts_symbol_map[] = {
+static const TSSymbol ts_symbol_map[] = {
   [ts_builtin_sym_end] = ts_builtin_sym_end,
   [sym_atom] = sym_atom,
   [anon_sym_LPAREN] = anon_sym_LPAREN,
   [anon_sym_RPAREN] = anon_sym_RPAREN,
+  [sym_comment] = sym_comment,
   [sym_sexp] = sym_sexp,
   [sym__sexp] = sym__sexp,
   [sym_list] = sym_list,
@@ -64,6 +71,10 @@ static const TSSymbolMetadata ts_symbol_metadata[] = {
     .visible = true,
     .named = false,
   },
+  [sym_comment] = {
+    .visible = true,
+    .named = true,
+  },
   [sym_sexp] = {
     .visible = true,
     .named = true,
@@ -82,61 +93,81 @@ static const TSSymbolMetadata ts_symbol_metadata[] = {
   },
 };
 
-static TSSymbol ts_alias_sequences[1][MAX_ALIAS_SEQUENCE_LENGTH] = {
+static const TSSymbol ts_alias_sequences[PRODUCTION_ID_COUNT][MAX_ALIAS_SEQUENCE_LENGTH] = {
   [0] = {0},
 };
 
-static uint16_t ts_non_terminal_alias_map[] = {
+static const uint16_t ts_non_terminal_alias_map[] = {
   0,
 };
 
+static const TSStateId ts_primary_state_ids[STATE_COUNT] = {
+  [0] = 0,
+  [1] = 1,
+  [2] = 2,
+  [3] = 3,
+  [4] = 4,
+  [5] = 5,
+  [6] = 6,
+  [7] = 7,
+  [8] = 8,
+};
+
+static const TSCharacterRange sym_atom_character_set_2[] = {
+  {'!', '!'}, {'$', '&'}, {'*', '+'}, {'-', ':'}, {'<', 'Z'}, {'^', '_'}, {'a', 'z'}, {'~', '~'},
+  {0xc0, 0xd6}, {0xd8, 0xf6}, {0xf8, 0xff},
+};
+
 static bool ts_lex(TSLexer *lexer, TSStateId state) {
   START_LEXER();
   eof = lexer->eof(lexer);
   switch (state) {
     case 0:
-      if (eof) ADVANCE(1);
-      if (lookahead == '(') ADVANCE(3);
-      if (lookahead == ')') ADVANCE(4);
-      if (lookahead == '\t' ||
-          lookahead == '\n' ||
-          lookahead == '\r' ||
-          lookahead == ' ') SKIP(0)
-      if (lookahead == '-' ||
-          ('0' <= lookahead && lookahead <= ':') ||
-          ('@' <= lookahead && lookahead <= 'Z') ||
-          lookahead == '_' ||
-          ('a' <= lookahead && lookahead <= 'z') ||
-          (192 <= lookahead && lookahead <= 214) ||
-          (216 <= lookahead && lookahead <= 246) ||
-          (248 <= lookahead && lookahead <= 255)) ADVANCE(2);
+      if (eof) ADVANCE(3);
+      if (lookahead == '"') ADVANCE(1);
+      if (lookahead == '(') ADVANCE(6);
+      if (lookahead == ')') ADVANCE(7);
+      if (lookahead == ';') ADVANCE(8);
+      if (('\t' <= lookahead && lookahead <= '\r') ||
+          lookahead == ' ') SKIP(0);
+      if (set_contains(sym_atom_character_set_2, 11, lookahead)) ADVANCE(5);
       END_STATE();
     case 1:
-      ACCEPT_TOKEN(ts_builtin_sym_end);
+      if (lookahead == '"') ADVANCE(4);
+      if (lookahead == '\\') ADVANCE(2);
+      if (lookahead != 0) ADVANCE(1);
       END_STATE();
     case 2:
-      ACCEPT_TOKEN(sym_atom);
-      if (lookahead == '-' ||
-          ('0' <= lookahead && lookahead <= ':') ||
-          ('@' <= lookahead && lookahead <= 'Z') ||
-          lookahead == '_' ||
-          ('a' <= lookahead && lookahead <= 'z') ||
-          (192 <= lookahead && lookahead <= 214) ||
-          (216 <= lookahead && lookahead <= 246) ||
-          (248 <= lookahead && lookahead <= 255)) ADVANCE(2);
+      if (lookahead != 0 &&
+          lookahead != '\n') ADVANCE(1);
       END_STATE();
     case 3:
-      ACCEPT_TOKEN(anon_sym_LPAREN);
+      ACCEPT_TOKEN(ts_builtin_sym_end);
       END_STATE();
     case 4:
+      ACCEPT_TOKEN(sym_atom);
+      END_STATE();
+    case 5:
+      ACCEPT_TOKEN(sym_atom);
+      if (set_contains(sym_atom_character_set_2, 11, lookahead)) ADVANCE(5);
+      END_STATE();
+    case 6:
+      ACCEPT_TOKEN(anon_sym_LPAREN);
+      END_STATE();
+    case 7:
       ACCEPT_TOKEN(anon_sym_RPAREN);
       END_STATE();
+    case 8:
+      ACCEPT_TOKEN(sym_comment);
+      if (lookahead != 0 &&
+          lookahead != '\n') ADVANCE(8);
+      END_STATE();
     default:
       return false;
   }
 }
 
-static TSLexMode ts_lex_modes[STATE_COUNT] = {
+static const TSLexerMode ts_lex_modes[STATE_COUNT] = {
   [0] = {.lex_state = 0},
   [1] = {.lex_state = 0},
   [2] = {.lex_state = 0},
@@ -148,121 +179,145 @@ static TSLexMode ts_lex_modes[STATE_COUNT] = {
   [8] = {.lex_state = 0},
 };
 
-static uint16_t ts_parse_table[LARGE_STATE_COUNT][SYMBOL_COUNT] = {
-  [0] = {
+static const uint16_t ts_parse_table[LARGE_STATE_COUNT][SYMBOL_COUNT] = {
+  [STATE(0)] = {
     [ts_builtin_sym_end] = ACTIONS(1),
     [sym_atom] = ACTIONS(1),
     [anon_sym_LPAREN] = ACTIONS(1),
     [anon_sym_RPAREN] = ACTIONS(1),
+    [sym_comment] = ACTIONS(3),
   },
-  [1] = {
+  [STATE(1)] = {
     [sym_sexp] = STATE(7),
     [sym__sexp] = STATE(8),
     [sym_list] = STATE(8),
-    [sym_atom] = ACTIONS(3),
-    [anon_sym_LPAREN] = ACTIONS(5),
+    [sym_atom] = ACTIONS(5),
+    [anon_sym_LPAREN] = ACTIONS(7),
+    [sym_comment] = ACTIONS(3),
   },
-  [2] = {
+  [STATE(2)] = {
     [sym__sexp] = STATE(3),
     [sym_list] = STATE(3),
     [aux_sym_list_repeat1] = STATE(3),
-    [sym_atom] = ACTIONS(7),
-    [anon_sym_LPAREN] = ACTIONS(5),
-    [anon_sym_RPAREN] = ACTIONS(9),
+    [sym_atom] = ACTIONS(9),
+    [anon_sym_LPAREN] = ACTIONS(7),
+    [anon_sym_RPAREN] = ACTIONS(11),
+    [sym_comment] = ACTIONS(3),
   },
-  [3] = {
+  [STATE(3)] = {
     [sym__sexp] = STATE(4),
     [sym_list] = STATE(4),
     [aux_sym_list_repeat1] = STATE(4),
-    [sym_atom] = ACTIONS(11),
-    [anon_sym_LPAREN] = ACTIONS(5),
-    [anon_sym_RPAREN] = ACTIONS(13),
+    [sym_atom] = ACTIONS(13),
+    [anon_sym_LPAREN] = ACTIONS(7),
+    [anon_sym_RPAREN] = ACTIONS(15),
+    [sym_comment] = ACTIONS(3),
   },
-  [4] = {
+  [STATE(4)] = {
     [sym__sexp] = STATE(4),
     [sym_list] = STATE(4),
     [aux_sym_list_repeat1] = STATE(4),
-    [sym_atom] = ACTIONS(15),
-    [anon_sym_LPAREN] = ACTIONS(18),
-    [anon_sym_RPAREN] = ACTIONS(21),
+    [sym_atom] = ACTIONS(17),
+    [anon_sym_LPAREN] = ACTIONS(20),
+    [anon_sym_RPAREN] = ACTIONS(23),
+    [sym_comment] = ACTIONS(3),
+  },
+  [STATE(5)] = {
+    [ts_builtin_sym_end] = ACTIONS(25),
+    [sym_atom] = ACTIONS(25),
+    [anon_sym_LPAREN] = ACTIONS(25),
+    [anon_sym_RPAREN] = ACTIONS(25),
+    [sym_comment] = ACTIONS(3),
+  },
+  [STATE(6)] = {
+    [ts_builtin_sym_end] = ACTIONS(27),
+    [sym_atom] = ACTIONS(27),
+    [anon_sym_LPAREN] = ACTIONS(27),
+    [anon_sym_RPAREN] = ACTIONS(27),
+    [sym_comment] = ACTIONS(3),
   },
 };
 
-static uint16_t ts_small_parse_table[] = {
-  [0] = 1,
-    ACTIONS(23), 4,
-      ts_builtin_sym_end,
-      sym_atom,
-      anon_sym_LPAREN,
-      anon_sym_RPAREN,
-  [7] = 1,
-    ACTIONS(25), 4,
-      ts_builtin_sym_end,
-      sym_atom,
-      anon_sym_LPAREN,
-      anon_sym_RPAREN,
-  [14] = 1,
-    ACTIONS(27), 1,
-      ts_builtin_sym_end,
-  [18] = 1,
+static const uint16_t ts_small_parse_table[] = {
+  [0] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
     ACTIONS(29), 1,
       ts_builtin_sym_end,
+  [7] = 2,
+    ACTIONS(3), 1,
+      sym_comment,
+    ACTIONS(31), 1,
+      ts_builtin_sym_end,
 };
 
-static uint32_t ts_small_parse_table_map[] = {
-  [SMALL_STATE(5)] = 0,
-  [SMALL_STATE(6)] = 7,
-  [SMALL_STATE(7)] = 14,
-  [SMALL_STATE(8)] = 18,
+static const uint32_t ts_small_parse_table_map[] = {
+  [SMALL_STATE(7)] = 0,
+  [SMALL_STATE(8)] = 7,
 };
 
-static TSParseActionEntry ts_parse_actions[] = {
+static const TSParseActionEntry ts_parse_actions[] = {
   [0] = {.entry = {.count = 0, .reusable = false}},
   [1] = {.entry = {.count = 1, .reusable = false}}, RECOVER(),
-  [3] = {.entry = {.count = 1, .reusable = true}}, SHIFT(8),
-  [5] = {.entry = {.count = 1, .reusable = true}}, SHIFT(2),
-  [7] = {.entry = {.count = 1, .reusable = true}}, SHIFT(3),
-  [9] = {.entry = {.count = 1, .reusable = true}}, SHIFT(5),
-  [11] = {.entry = {.count = 1, .reusable = true}}, SHIFT(4),
-  [13] = {.entry = {.count = 1, .reusable = true}}, SHIFT(6),
-  [15] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_list_repeat1, 2), SHIFT_REPEAT(4),
-  [18] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_list_repeat1, 2), SHIFT_REPEAT(2),
-  [21] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_list_repeat1, 2),
-  [23] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_list, 2),
-  [25] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_list, 3),
-  [27] = {.entry = {.count = 1, .reusable = true}},  ACCEPT_INPUT(),
-  [29] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_sexp, 1),
+  [3] = {.entry = {.count = 1, .reusable = true}}, SHIFT_EXTRA(),
+  [5] = {.entry = {.count = 1, .reusable = true}}, SHIFT(8),
+  [7] = {.entry = {.count = 1, .reusable = true}}, SHIFT(2),
+  [9] = {.entry = {.count = 1, .reusable = true}}, SHIFT(3),
+  [11] = {.entry = {.count = 1, .reusable = true}}, SHIFT(5),
+  [13] = {.entry = {.count = 1, .reusable = true}}, SHIFT(4),
+  [15] = {.entry = {.count = 1, .reusable = true}}, SHIFT(6),
+  [17] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_list_repeat1, 2, 0, 0), SHIFT_REPEAT(4),
+  [20] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_list_repeat1, 2, 0, 0), SHIFT_REPEAT(2),
+  [23] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_list_repeat1, 2, 0, 0),
+  [25] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_list, 2, 0, 0),
+  [27] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_list, 3, 0, 0),
+  [29] = {.entry = {.count = 1, .reusable = true}},  ACCEPT_INPUT(),
+  [31] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_sexp, 1, 0, 0),
 };
 
 #ifdef __cplusplus
 extern "C" {
 #endif
-#ifdef _WIN32
-#define extern __declspec(dllexport)
+#ifdef TREE_SITTER_HIDE_SYMBOLS
+#define TS_PUBLIC
+#elif defined(_WIN32)
+#define TS_PUBLIC __declspec(dllexport)
+#else
+#define TS_PUBLIC __attribute__((visibility("default")))
 #endif
 
-extern const TSLanguage *tree_sitter_sexp(void) {
-  static TSLanguage language = {
-    .version = LANGUAGE_VERSION,
+TS_PUBLIC const TSLanguage *tree_sitter_sexp(void) {
+  static const TSLanguage language = {
+    .abi_version = LANGUAGE_VERSION,
     .symbol_count = SYMBOL_COUNT,
     .alias_count = ALIAS_COUNT,
     .token_count = TOKEN_COUNT,
     .external_token_count = EXTERNAL_TOKEN_COUNT,
+    .state_count = STATE_COUNT,
+    .large_state_count = LARGE_STATE_COUNT,
+    .production_id_count = PRODUCTION_ID_COUNT,
+    .supertype_count = SUPERTYPE_COUNT,
+    .field_count = FIELD_COUNT,
+    .max_alias_sequence_length = MAX_ALIAS_SEQUENCE_LENGTH,
+    .parse_table = &ts_parse_table[0][0],
+    .small_parse_table = ts_small_parse_table,
+    .small_parse_table_map = ts_small_parse_table_map,
+    .parse_actions = ts_parse_actions,
     .symbol_names = ts_symbol_names,
     .symbol_metadata = ts_symbol_metadata,
-    .parse_table = (const uint16_t *)ts_parse_table,
-    .parse_actions = ts_parse_actions,
-    .lex_modes = ts_lex_modes,
-    .alias_sequences = (const TSSymbol *)ts_alias_sequences,
-    .max_alias_sequence_length = MAX_ALIAS_SEQUENCE_LENGTH,
-    .lex_fn = ts_lex,
-    .field_count = FIELD_COUNT,
-    .large_state_count = LARGE_STATE_COUNT,
-    .small_parse_table = (const uint16_t *)ts_small_parse_table,
-    .small_parse_table_map = (const uint32_t *)ts_small_parse_table_map,
     .public_symbol_map = ts_symbol_map,
     .alias_map = ts_non_terminal_alias_map,
-    .state_count = STATE_COUNT,
+    .alias_sequences = &ts_alias_sequences[0][0],
+    .lex_modes = (const void*)ts_lex_modes,
+    .lex_fn = ts_lex,
+    .primary_state_ids = ts_primary_state_ids,
+    .name = "sexp",
+    .max_reserved_word_set_size = 0,
+    .metadata = {
+      .major_version = 0,
+      .minor_version = 0,
+      .patch_version = 0,
+    },
   };
   return &language;
 }
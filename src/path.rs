@@ -0,0 +1,133 @@
+use crate::Sexp;
+use anyhow::{anyhow, Error};
+use std::fmt;
+use std::str::FromStr;
+
+/// The position of a node inside a tree, as the index of each list element
+/// to step into from the root (ignoring the closing `Nil`s). The empty path
+/// is the root itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SexpPath(Vec<usize>);
+
+impl SexpPath {
+    pub fn root() -> SexpPath {
+        SexpPath(vec![])
+    }
+
+    pub fn child(&self, index: usize) -> SexpPath {
+        let mut indices = self.0.clone();
+        indices.push(index);
+        SexpPath(indices)
+    }
+
+    pub fn parent(&self) -> Option<SexpPath> {
+        let (_, parent) = self.0.split_last()?;
+        Some(SexpPath(parent.to_vec()))
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl From<Vec<usize>> for SexpPath {
+    fn from(indices: Vec<usize>) -> SexpPath {
+        SexpPath(indices)
+    }
+}
+
+impl fmt::Display for SexpPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.0.is_empty() {
+            return write!(fmt, "/");
+        }
+        for index in &self.0 {
+            write!(fmt, "/{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads back the `Display` form, e.g. `/2/0`.
+impl FromStr for SexpPath {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<SexpPath, Error> {
+        let rest = text
+            .strip_prefix('/')
+            .ok_or_else(|| anyhow!("Path {:?} doesn't start with /", text))?;
+        if rest.is_empty() {
+            return Ok(SexpPath::root());
+        }
+        rest.split('/')
+            .map(|index| {
+                index
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid index {:?} in path {:?}", index, text))
+            })
+            .collect::<Result<_, _>>()
+            .map(SexpPath)
+    }
+}
+
+impl Sexp {
+    pub fn get(&self, path: &SexpPath) -> Option<&Sexp> {
+        path.indices()
+            .iter()
+            .try_fold(self, |node, &index| node.items().get(index))
+    }
+
+    /// A copy of this tree with the node at `path` swapped for `replacement`,
+    /// or `None` if there's no node there.
+    pub fn with_replaced(&self, path: &SexpPath, replacement: Sexp) -> Option<Sexp> {
+        match path.indices().split_first() {
+            None => Some(replacement),
+            Some((&index, rest)) => {
+                let mut items = self.items().to_vec();
+                let child = items.get(index)?;
+                items[index] = child.with_replaced(&SexpPath(rest.to_vec()), replacement)?;
+                Some(Sexp::list(items))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let sexp = Sexp::of_str("(a (b c) (d (e)))").unwrap();
+        assert_eq!(
+            sexp.get(&SexpPath::root()).unwrap().to_string(),
+            "(a (b c) (d (e)))"
+        );
+        assert_eq!(sexp.get(&vec![1, 1].into()).unwrap().to_string(), "c");
+        assert_eq!(sexp.get(&vec![2, 1, 0].into()).unwrap().to_string(), "e");
+        assert!(sexp.get(&vec![3].into()).is_none());
+        assert!(sexp.get(&vec![0, 0].into()).is_none());
+    }
+
+    #[test]
+    fn test_with_replaced() {
+        let sexp = Sexp::of_str("(a (b c) d)").unwrap();
+        let replaced = sexp.with_replaced(&vec![1, 0].into(), Sexp::atom("x"));
+        assert_eq!(replaced.unwrap().to_string(), "(a (x c) d)");
+        assert!(sexp.with_replaced(&vec![5].into(), Sexp::Nil).is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(SexpPath::root().to_string(), "/");
+        assert_eq!(SexpPath::root().child(2).child(0).to_string(), "/2/0");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("/".parse::<SexpPath>().unwrap(), SexpPath::root());
+        assert_eq!("/2/0".parse::<SexpPath>().unwrap(), vec![2, 0].into());
+        assert!("2/0".parse::<SexpPath>().is_err());
+        assert!("/a".parse::<SexpPath>().is_err());
+    }
+}
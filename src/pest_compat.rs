@@ -0,0 +1,79 @@
+use crate::{Sexp, SexpError};
+use pest::iterators::Pair;
+use pest::RuleType;
+
+impl Sexp {
+    /// Converts `pest` parse results the way tree-sitter nodes are shown:
+    /// every pair becomes `(rule children...)`, and pairs without inner pairs
+    /// keep the text they matched as a string atom, as in `(number "42")`.
+    /// Several top-level pairs are wrapped in a single list.
+    pub fn from_pest_pairs<'i, R: RuleType>(
+        pairs: impl Iterator<Item = Pair<'i, R>>,
+    ) -> Result<Sexp, SexpError> {
+        let mut sexps: Vec<Sexp> = pairs.map(Sexp::from_pest_pair).collect();
+        match sexps.len() {
+            0 => Err(SexpError::NoPestPairs),
+            1 => Ok(sexps.remove(0)),
+            _ => Ok(Sexp::list(sexps)),
+        }
+    }
+
+    fn from_pest_pair<R: RuleType>(pair: Pair<R>) -> Sexp {
+        let mut parts = vec![Sexp::atom(format!("{:?}", pair.as_rule()))];
+        let text = pair.as_str();
+        let mut inner = pair.into_inner().peekable();
+        if inner.peek().is_none() {
+            parts.push(Sexp::string(text));
+        } else {
+            parts.extend(inner.map(Sexp::from_pest_pair));
+        }
+        Sexp::list(parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pest::Parser;
+    use pest_derive::Parser;
+
+    #[derive(Parser)]
+    #[grammar_inline = r#"
+        WHITESPACE = _{ " " }
+        number = @{ ASCII_DIGIT+ }
+        sum = { number ~ ("+" ~ number)* }
+        sums = { sum ~ ("," ~ sum)* }
+    "#]
+    struct Calc;
+
+    #[test]
+    fn test_from_pest_pairs() {
+        let pairs = Calc::parse(Rule::sum, "1 + 22").unwrap();
+        assert_eq!(
+            Sexp::from_pest_pairs(pairs).unwrap().to_string(),
+            r#"(sum (number "1") (number "22"))"#
+        );
+    }
+
+    #[test]
+    fn test_from_pest_pairs_nested() {
+        let pairs = Calc::parse(Rule::sums, "1, 2 + 3").unwrap();
+        let sexp = Sexp::from_pest_pairs(pairs).unwrap();
+        assert_eq!(
+            sexp.to_string(),
+            r#"(sums (sum (number "1")) (sum (number "2") (number "3")))"#
+        );
+        assert_eq!(
+            Sexp::of_str(&sexp.to_string()).unwrap().to_string(),
+            sexp.to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_pest_pairs_empty() {
+        assert_eq!(
+            Sexp::from_pest_pairs(std::iter::empty::<Pair<Rule>>()),
+            Err(SexpError::NoPestPairs)
+        );
+    }
+}
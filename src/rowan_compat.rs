@@ -0,0 +1,83 @@
+use crate::Sexp;
+use rowan::{Language, NodeOrToken, SyntaxNode};
+
+impl Sexp {
+    /// Converts a `rowan` syntax tree the way tree-sitter nodes are shown:
+    /// every node becomes `(KIND children...)` and every token
+    /// `(KIND "text")`, with kinds named by their `Debug` form. Rowan trees
+    /// are lossless, so whitespace and comment tokens are kept too.
+    pub fn from_rowan<L: Language>(node: &SyntaxNode<L>) -> Sexp {
+        let mut parts = vec![Sexp::atom(format!("{:?}", node.kind()))];
+        for child in node.children_with_tokens() {
+            parts.push(match child {
+                NodeOrToken::Node(node) => Sexp::from_rowan(&node),
+                NodeOrToken::Token(token) => Sexp::list(vec![
+                    Sexp::atom(format!("{:?}", token.kind())),
+                    Sexp::string(token.text()),
+                ]),
+            });
+        }
+        Sexp::list(parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rowan::GreenNodeBuilder;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[repr(u16)]
+    #[allow(clippy::upper_case_acronyms)]
+    enum Kind {
+        ROOT,
+        CALL,
+        IDENT,
+        NUMBER,
+        WHITESPACE,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    enum Lang {}
+
+    impl Language for Lang {
+        type Kind = Kind;
+
+        fn kind_from_raw(raw: rowan::SyntaxKind) -> Kind {
+            [
+                Kind::ROOT,
+                Kind::CALL,
+                Kind::IDENT,
+                Kind::NUMBER,
+                Kind::WHITESPACE,
+            ][raw.0 as usize]
+        }
+
+        fn kind_to_raw(kind: Kind) -> rowan::SyntaxKind {
+            rowan::SyntaxKind(kind as u16)
+        }
+    }
+
+    #[test]
+    fn test_from_rowan() {
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(Lang::kind_to_raw(Kind::ROOT));
+        builder.start_node(Lang::kind_to_raw(Kind::CALL));
+        builder.token(Lang::kind_to_raw(Kind::IDENT), "max");
+        builder.token(Lang::kind_to_raw(Kind::WHITESPACE), " ");
+        builder.token(Lang::kind_to_raw(Kind::NUMBER), "42");
+        builder.finish_node();
+        builder.finish_node();
+        let root = SyntaxNode::<Lang>::new_root(builder.finish());
+
+        let sexp = Sexp::from_rowan(&root);
+        assert_eq!(
+            sexp.to_string(),
+            r#"(ROOT (CALL (IDENT "max") (WHITESPACE " ") (NUMBER "42")))"#
+        );
+        assert_eq!(
+            Sexp::of_str(&sexp.to_string()).unwrap().to_string(),
+            sexp.to_string()
+        );
+    }
+}
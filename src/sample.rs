@@ -0,0 +1,145 @@
+use crate::Sexp;
+
+// splitmix64, which is plenty for picking samples and keeps us free of a
+// `rand` dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+impl Sexp {
+    /// Picks up to `count` of the lists nested inside `self` (not `self`
+    /// itself) whose `node_count` is at most `max_nodes`, uniformly at random.
+    /// The same seed always yields the same sample. If fewer lists qualify
+    /// than requested, all of them are returned; if none do, for example
+    /// because `max_nodes` is smaller than every list, the result is empty.
+    /// Atoms are never sampled.
+    pub fn sample_subtrees(&self, count: usize, max_nodes: usize, seed: u64) -> Vec<&Sexp> {
+        let mut rng = Rng(seed);
+        let mut reservoir = Vec::with_capacity(count);
+        if count == 0 {
+            return reservoir;
+        }
+
+        let mut seen = 0;
+        for (node, size) in self.subtree_sizes().into_iter().skip(1) {
+            if !matches!(node, Sexp::List(_)) || size > max_nodes {
+                continue;
+            }
+            if reservoir.len() < count {
+                reservoir.push(node);
+            } else {
+                let slot = rng.below(seen + 1);
+                if slot < count {
+                    reservoir[slot] = node;
+                }
+            }
+            seen += 1;
+        }
+        reservoir
+    }
+
+    // Every node in pre-order next to its `node_count`, worked out in a single
+    // pass instead of recounting each subtree.
+    fn subtree_sizes(&self) -> Vec<(&Sexp, usize)> {
+        let mut nodes: Vec<(&Sexp, Option<usize>)> = vec![];
+        let mut stack = vec![(self, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push((node, parent));
+            if let Sexp::List(parts) = node {
+                for part in parts.iter().rev() {
+                    if !matches!(part, Sexp::Nil) {
+                        stack.push((part, Some(index)));
+                    }
+                }
+            }
+        }
+
+        let mut sizes = vec![1; nodes.len()];
+        for index in (0..nodes.len()).rev() {
+            if let Some(parent) = nodes[index].1 {
+                sizes[parent] += sizes[index];
+            }
+        }
+
+        nodes
+            .into_iter()
+            .zip(sizes)
+            .map(|((node, _), size)| (node, size))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big_fixture() -> Sexp {
+        Sexp::of_str(include_str!("./big_fixture.in.sexp")).unwrap()
+    }
+
+    #[test]
+    fn test_sample_is_deterministic() {
+        let sexp = big_fixture();
+        let render = |seed| {
+            sexp.sample_subtrees(5, 20, seed)
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(render(42), render(42));
+        assert_eq!(render(42).len(), 5);
+        assert_ne!(render(42), render(43));
+    }
+
+    #[test]
+    fn test_sample_respects_max_nodes() {
+        let sexp = big_fixture();
+        for seed in 0..20 {
+            for subtree in sexp.sample_subtrees(10, 8, seed) {
+                assert!(matches!(subtree, Sexp::List(_)));
+                assert!(subtree.node_count() <= 8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_edge_cases() {
+        let sexp = Sexp::of_str("(a (b) (c d) e)").unwrap();
+        assert_eq!(sexp.sample_subtrees(10, 100, 0).len(), 2);
+        assert!(sexp.sample_subtrees(10, 1, 0).is_empty());
+        assert!(sexp.sample_subtrees(0, 100, 0).is_empty());
+    }
+
+    #[test]
+    fn test_sample_is_roughly_uniform() {
+        let sexp = Sexp::of_str("(root (a) (b) (c) (d) (e) (f) (g) (h) (i) (j))").unwrap();
+        let mut counts = std::collections::HashMap::new();
+        let runs = 10_000;
+        for seed in 0..runs {
+            let picked = sexp.sample_subtrees(1, 2, seed)[0].to_string();
+            *counts.entry(picked).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 10);
+
+        let expected = runs as f64 / 10.0;
+        let chi_squared: f64 = counts
+            .values()
+            .map(|&observed| (observed as f64 - expected).powi(2) / expected)
+            .sum();
+        // 27.88 is the 99.9th percentile for 9 degrees of freedom.
+        assert!(chi_squared < 27.88, "chi squared was {}", chi_squared);
+    }
+}
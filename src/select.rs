@@ -0,0 +1,257 @@
+use crate::{ffi, Document, Sexp, SexpPath};
+use anyhow::{Context, Error};
+use tree_sitter::{Node, QueryCursor, StreamingIterator};
+
+/// Every atom and list in a tree with its path, in pre-order, walked one
+/// node at a time as the iterator is pulled.
+pub struct Descendants<'a> {
+    stack: Vec<(SexpPath, &'a Sexp)>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = (SexpPath, &'a Sexp);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, node) = self.stack.pop()?;
+        for (index, child) in node.items().iter().enumerate().rev() {
+            self.stack.push((path.child(index), child));
+        }
+        Some((path, node))
+    }
+}
+
+/// The nodes of a tree matching a predicate, found lazily: nothing past the
+/// last match pulled is visited.
+pub struct Matches<'a, F> {
+    descendants: Descendants<'a>,
+    predicate: F,
+}
+
+impl<'a, F: FnMut(&Sexp) -> bool> Iterator for Matches<'a, F> {
+    type Item = (SexpPath, &'a Sexp);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &mut self.predicate;
+        self.descendants.find(|(_, node)| predicate(node))
+    }
+}
+
+impl<F: FnMut(&Sexp) -> bool> Matches<'_, F> {
+    /// Whether there's any match, stopping at the first one.
+    pub fn exists(mut self) -> bool {
+        self.next().is_some()
+    }
+
+    /// The number of matches, counting no further than `limit`.
+    pub fn count_at_most(self, limit: usize) -> usize {
+        self.take(limit).count()
+    }
+}
+
+impl Sexp {
+    pub fn descendants(&self) -> Descendants<'_> {
+        Descendants {
+            stack: vec![(SexpPath::root(), self)],
+        }
+    }
+
+    /// The nodes for which `predicate` holds, in pre-order. The tree is
+    /// walked as matches are pulled, so `.take(n)`, `exists` and
+    /// `count_at_most` stop early on huge trees; `.collect()` gives every
+    /// match.
+    pub fn select<F: FnMut(&Sexp) -> bool>(&self, predicate: F) -> Matches<'_, F> {
+        Matches {
+            descendants: self.descendants(),
+            predicate,
+        }
+    }
+
+    /// The number of nodes for which `predicate` holds.
+    pub fn count<F: FnMut(&Sexp) -> bool>(&self, predicate: F) -> usize {
+        self.select(predicate).count()
+    }
+}
+
+/// A tree-sitter query such as `(list . (atom) @head (#eq? @head "library"))
+/// @stanza`, compiled once to run over any number of documents.
+pub struct Query {
+    query: tree_sitter::Query,
+    cursor: QueryCursor,
+}
+
+impl Query {
+    pub fn new(source: &str) -> Result<Query, Error> {
+        let query = tree_sitter::Query::new(&ffi::language(), source).context("Invalid query")?;
+        Ok(Query {
+            query,
+            cursor: QueryCursor::new(),
+        })
+    }
+
+    /// The query's matches in `document`, each as its captures' names and
+    /// trees. They're pulled from tree-sitter's cursor as the iterator is,
+    /// so `.take(n)`, `exists` and `count_at_most` stop matching early and
+    /// the document is never read as a whole `Sexp`.
+    pub fn matches<'a>(&'a mut self, document: &'a Document) -> Result<QueryMatches<'a>, Error> {
+        let tree = document.tree().context("Could not parse anything")?;
+        let text = document.text().as_bytes();
+        Ok(QueryMatches {
+            matches: self.cursor.matches(&self.query, tree.root_node(), text),
+            names: self.query.capture_names(),
+            text,
+        })
+    }
+}
+
+/// The matches of a `Query` in a document, found lazily.
+pub struct QueryMatches<'a> {
+    matches: tree_sitter::QueryMatches<'a, 'a, 'static, &'a [u8], &'a [u8]>,
+    names: &'a [&'a str],
+    text: &'a [u8],
+}
+
+/// A captured node as a tree, where the root of the parse stands for the
+/// tree in it.
+fn captured(node: Node, text: &[u8]) -> Result<Sexp, Error> {
+    match node.named_child(0) {
+        Some(child) if node.kind() == "sexp" => captured(child, text),
+        _ => Sexp::build_tree(node, text),
+    }
+}
+
+impl<'a> Iterator for QueryMatches<'a> {
+    type Item = Result<Vec<(&'a str, Sexp)>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (names, text) = (self.names, self.text);
+        let found = self.matches.next()?;
+        let captures = found
+            .captures()
+            .iter()
+            .map(|capture| Ok((names[capture.index as usize], captured(capture.node, text)?)))
+            .collect();
+        Some(captures)
+    }
+}
+
+impl QueryMatches<'_> {
+    /// Whether there's any match, stopping at the first one.
+    pub fn exists(mut self) -> bool {
+        self.next().is_some()
+    }
+
+    /// The number of matches, counting no further than `limit`.
+    pub fn count_at_most(self, limit: usize) -> usize {
+        self.take(limit).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn large_document() -> Sexp {
+        let stanzas: Vec<String> = (0..10_000)
+            .map(|i| format!("(library (name lib{}) (deps a b c))", i))
+            .collect();
+        Sexp::of_str(&format!("(project {})", stanzas.join(" "))).unwrap()
+    }
+
+    #[test]
+    fn test_descendants() {
+        let sexp = Sexp::of_str("(a (b c) d)").unwrap();
+        let visited: Vec<String> = sexp
+            .descendants()
+            .map(|(path, node)| format!("{} {}", path, node))
+            .collect();
+        assert_eq!(
+            visited,
+            [
+                "/ (a (b c) d)",
+                "/0 a",
+                "/1 (b c)",
+                "/1/0 b",
+                "/1/1 c",
+                "/2 d"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_stops_early() {
+        let sexp = large_document();
+        let visits = Cell::new(0);
+        let is_library = |node: &Sexp| {
+            visits.set(visits.get() + 1);
+            node.head() == Some("library")
+        };
+
+        assert!(sexp.select(is_library).exists());
+        assert_eq!(visits.get(), 3);
+
+        visits.set(0);
+        assert_eq!(sexp.select(is_library).count_at_most(3), 3);
+        let per_stanza = Sexp::of_str("(library (name lib0) (deps a b c))")
+            .unwrap()
+            .descendants()
+            .count();
+        assert_eq!(visits.get(), 2 + 2 * per_stanza + 1);
+
+        visits.set(0);
+        assert!(!sexp
+            .select(|node| node.head() == Some("executable"))
+            .exists());
+        assert_eq!(sexp.select(is_library).count_at_most(usize::MAX), 10_000);
+        assert!(visits.get() > 10 * per_stanza);
+    }
+
+    #[test]
+    fn test_streamed_equals_collected() {
+        let sexp = large_document();
+        let expected: Vec<(SexpPath, String)> = (0..10_000)
+            .map(|i| (vec![i + 1, 1].into(), format!("(name lib{})", i)))
+            .collect();
+        let mut streamed = sexp.select(|node| node.head() == Some("name"));
+        for (path, node) in &expected {
+            let (streamed_path, streamed_node) = streamed.next().unwrap();
+            assert_eq!(&streamed_path, path);
+            assert_eq!(&streamed_node.to_string(), node);
+        }
+        assert!(streamed.next().is_none());
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let document = Document::new(large_document().to_string());
+        let mut query =
+            Query::new(r#"(list . (atom) @head (#eq? @head "name") . (atom) @name) @field"#)
+                .unwrap();
+        let matches: Vec<_> = query
+            .matches(&document)
+            .unwrap()
+            .map(|captures| {
+                let captures: Vec<String> = captures
+                    .unwrap()
+                    .iter()
+                    .map(|(name, sexp)| format!("{} {}", name, sexp))
+                    .collect();
+                captures.join(", ")
+            })
+            .collect();
+        let expected: Vec<String> = (0..10_000)
+            .map(|i| format!("field (name lib{0}), head name, name lib{0}", i))
+            .collect();
+        assert_eq!(matches, expected);
+
+        let first: Vec<_> = query.matches(&document).unwrap().take(2).collect();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].as_ref().unwrap()[2].1.to_string(), "lib1");
+        assert!(query.matches(&document).unwrap().exists());
+        assert_eq!(query.matches(&document).unwrap().count_at_most(3), 3);
+
+        let mut none = Query::new(r#"(list . (atom) @head (#eq? @head "executable"))"#).unwrap();
+        assert!(!none.matches(&document).unwrap().exists());
+        assert!(Query::new("(list").is_err());
+    }
+}
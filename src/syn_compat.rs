@@ -0,0 +1,773 @@
+use crate::{Sexp, SexpError};
+use proc_macro2::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{Parse, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Fields, ImplItem, Item, Lit, Member, Pat, ReturnType, Stmt};
+
+fn node(kind: &str, mut children: Vec<Sexp>) -> Sexp {
+    children.insert(0, Sexp::atom(kind));
+    Sexp::list(children)
+}
+
+fn tokens<T: ToTokens>(tokens: &T) -> Sexp {
+    Sexp::string(&tokens.to_token_stream().to_string())
+}
+
+fn verbatim<T: ToTokens>(tokens: &T) -> Sexp {
+    node("verbatim", vec![self::tokens(tokens)])
+}
+
+fn pat(pat: &Pat) -> Sexp {
+    match pat {
+        Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => {
+            match ident.mutability {
+                Some(_) => node("mut", vec![Sexp::atom(ident.ident.to_string())]),
+                None => Sexp::atom(ident.ident.to_string()),
+            }
+        }
+        _ => tokens(pat),
+    }
+}
+
+fn block(block: &syn::Block) -> Sexp {
+    node("block", block.stmts.iter().map(stmt).collect())
+}
+
+fn stmt(stmt: &Stmt) -> Sexp {
+    match stmt {
+        Stmt::Local(local) if local.init.as_ref().is_none_or(|i| i.diverge.is_none()) => {
+            let mut children = vec![pat(&local.pat)];
+            if let Some(init) = &local.init {
+                children.push(Sexp::from_syn_expr(&init.expr));
+            }
+            node("let", children)
+        }
+        Stmt::Expr(expr, None) => Sexp::from_syn_expr(expr),
+        Stmt::Expr(expr, Some(_)) => node("semi", vec![Sexp::from_syn_expr(expr)]),
+        Stmt::Item(item) => Sexp::from_syn(item),
+        Stmt::Macro(mac) if mac.semi_token.is_some() => node(
+            "semi",
+            vec![node(
+                "macro",
+                vec![tokens(&mac.mac.path), tokens(&mac.mac.tokens)],
+            )],
+        ),
+        _ => verbatim(stmt),
+    }
+}
+
+fn function(sig: &syn::Signature, body: &syn::Block) -> Sexp {
+    let params = sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            syn::FnArg::Typed(typed) => node("param", vec![pat(&typed.pat), tokens(&typed.ty)]),
+            syn::FnArg::Receiver(receiver) => node("self", vec![tokens(receiver)]),
+        })
+        .collect();
+    let mut children = vec![Sexp::atom(sig.ident.to_string()), node("params", params)];
+    if let ReturnType::Type(_, ty) = &sig.output {
+        children.push(node("returns", vec![tokens(ty)]));
+    }
+    children.push(block(body));
+    node("fn", children)
+}
+
+fn fields(fields: &Fields) -> Vec<Sexp> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let name = match &field.ident {
+                Some(ident) => Sexp::atom(ident.to_string()),
+                None => Sexp::atom(index.to_string()),
+            };
+            node("field", vec![name, tokens(&field.ty)])
+        })
+        .collect()
+}
+
+fn invalid(message: String) -> SexpError {
+    SexpError::InvalidRustExpr(message)
+}
+
+fn text(sexp: &Sexp) -> Result<String, SexpError> {
+    sexp.as_text()
+        .ok_or_else(|| invalid(format!("Expected an atom, found {}", sexp)))
+}
+
+fn parse_text<T: Parse>(text: &str) -> Result<T, SexpError> {
+    syn::parse_str(text).map_err(|err| invalid(format!("Could not parse {:?}: {}", text, err)))
+}
+
+fn parse<T: Parse>(sexp: &Sexp) -> Result<T, SexpError> {
+    parse_text(&text(sexp)?)
+}
+
+fn to_pat(sexp: &Sexp) -> Result<Pat, SexpError> {
+    let text = match sexp.head() {
+        Some("mut") if sexp.items().len() == 2 => format!("mut {}", text(&sexp.items()[1])?),
+        _ => text(sexp)?,
+    };
+    Pat::parse_single
+        .parse_str(&text)
+        .map_err(|err| invalid(format!("Could not parse pattern {:?}: {}", text, err)))
+}
+
+fn to_exprs(sexps: &[Sexp]) -> Result<Punctuated<Expr, syn::Token![,]>, SexpError> {
+    sexps.iter().map(Sexp::to_syn_expr).collect()
+}
+
+fn to_block(sexp: &Sexp) -> Result<syn::Block, SexpError> {
+    if sexp.head() != Some("block") {
+        return Err(invalid(format!("Expected a block, found {}", sexp)));
+    }
+    let stmts = sexp.items()[1..]
+        .iter()
+        .map(to_stmt)
+        .collect::<Result<_, _>>()?;
+    Ok(syn::Block {
+        brace_token: Default::default(),
+        stmts,
+    })
+}
+
+fn to_stmt(sexp: &Sexp) -> Result<Stmt, SexpError> {
+    let items = sexp.items();
+    match sexp.head() {
+        Some("let") if items.len() == 2 || items.len() == 3 => Ok(Stmt::Local(syn::Local {
+            attrs: vec![],
+            let_token: Default::default(),
+            pat: to_pat(&items[1])?,
+            init: match items.get(2) {
+                Some(init) => Some(syn::LocalInit {
+                    eq_token: Default::default(),
+                    expr: Box::new(init.to_syn_expr()?),
+                    diverge: None,
+                }),
+                None => None,
+            },
+            semi_token: Default::default(),
+        })),
+        Some("semi") if items.len() == 2 => Ok(Stmt::Expr(
+            items[1].to_syn_expr()?,
+            Some(Default::default()),
+        )),
+        Some("verbatim") if items.len() == 2 => parse(&items[1]),
+        _ => Ok(Stmt::Expr(sexp.to_syn_expr()?, None)),
+    }
+}
+
+/// The heads of the lists that stand for groups.
+const DELIMITERS: [(Delimiter, &str); 4] = [
+    (Delimiter::Parenthesis, "()"),
+    (Delimiter::Bracket, "[]"),
+    (Delimiter::Brace, "{}"),
+    (Delimiter::None, ""),
+];
+
+/// The head of a `("tt" text)` list, for a token that can't be an atom.
+const TOKEN: &str = "tt";
+
+fn group(delimiter: Delimiter, stream: TokenStream) -> Sexp {
+    let (_, head) = DELIMITERS.iter().find(|(d, _)| *d == delimiter).unwrap();
+    let mut parts = vec![Sexp::string(head)];
+    let mut trees = stream.into_iter().peekable();
+    while let Some(tree) = trees.next() {
+        let text = match tree {
+            TokenTree::Group(inner) => {
+                parts.push(group(inner.delimiter(), inner.stream()));
+                continue;
+            }
+            TokenTree::Punct(punct) => {
+                // Punctuation is joined to the next character when it was
+                // written against it, as in `::`, or the `'` of a lifetime.
+                let mut text = punct.as_char().to_string();
+                let mut spacing = punct.spacing();
+                while spacing == Spacing::Joint {
+                    match trees.peek() {
+                        Some(TokenTree::Punct(next)) => {
+                            text.push(next.as_char());
+                            spacing = next.spacing();
+                        }
+                        Some(TokenTree::Ident(next)) => {
+                            text.push_str(&next.to_string());
+                            spacing = Spacing::Alone;
+                        }
+                        _ => break,
+                    }
+                    trees.next();
+                }
+                text
+            }
+            TokenTree::Ident(ident) => ident.to_string(),
+            TokenTree::Literal(literal) => literal.to_string(),
+        };
+        parts.push(match Sexp::of_str(&text) {
+            Ok(Sexp::Atom(atom)) if atom == text => Sexp::Atom(atom),
+            _ => Sexp::list(vec![Sexp::string(TOKEN), Sexp::string(&text)]),
+        });
+    }
+    Sexp::list(parts)
+}
+
+/// The delimiter of a list `group` wrote.
+fn delimiter(sexp: &Sexp) -> Option<Delimiter> {
+    let head = match sexp {
+        Sexp::List(_) => sexp.items().first()?,
+        _ => return None,
+    };
+    DELIMITERS
+        .iter()
+        .find(|(_, text)| *head == Sexp::string(text))
+        .map(|(delimiter, _)| *delimiter)
+}
+
+fn lex(text: &str) -> TokenStream {
+    syn::parse_str(text).unwrap_or_else(|err: syn::Error| {
+        syn::Error::new(
+            err.span(),
+            format!("{} isn't a valid Rust token: {}", text, err),
+        )
+        .to_compile_error()
+    })
+}
+
+fn to_tokens(sexp: &Sexp) -> TokenStream {
+    let items = sexp.items();
+    match sexp {
+        Sexp::Nil => TokenStream::new(),
+        Sexp::Atom(atom) => lex(atom),
+        Sexp::List(_) => match items {
+            [head, token] if *head == Sexp::string(TOKEN) && token.as_text().is_some() => {
+                lex(&token.as_text().unwrap())
+            }
+            _ => {
+                let (delimiter, items) = match delimiter(sexp) {
+                    Some(delimiter) => (delimiter, &items[1..]),
+                    None => (Delimiter::Parenthesis, items),
+                };
+                let inner = items.iter().map(to_tokens).collect();
+                Group::new(delimiter, inner).into_token_stream()
+            }
+        },
+    }
+}
+
+impl Sexp {
+    /// Converts a Rust item into a tree headed by its kind, e.g.
+    /// `(fn add (params (param a "i32")) (returns "i32") (block ...))`.
+    /// Types and patterns other than plain bindings are kept as string atoms
+    /// of their tokens, and item kinds without a dedicated mapping become
+    /// `(verbatim "tokens")`.
+    pub fn from_syn(item: &Item) -> Sexp {
+        match item {
+            Item::Fn(item) => function(&item.sig, &item.block),
+            Item::Struct(item) => {
+                let mut children = vec![Sexp::atom(item.ident.to_string())];
+                children.extend(fields(&item.fields));
+                node("struct", children)
+            }
+            Item::Enum(item) => {
+                let mut children = vec![Sexp::atom(item.ident.to_string())];
+                children.extend(item.variants.iter().map(|variant| {
+                    let mut parts = vec![Sexp::atom(variant.ident.to_string())];
+                    parts.extend(fields(&variant.fields));
+                    node("variant", parts)
+                }));
+                node("enum", children)
+            }
+            Item::Const(item) => node(
+                "const",
+                vec![
+                    Sexp::atom(item.ident.to_string()),
+                    tokens(&item.ty),
+                    Sexp::from_syn_expr(&item.expr),
+                ],
+            ),
+            Item::Static(item) => node(
+                "static",
+                vec![
+                    Sexp::atom(item.ident.to_string()),
+                    tokens(&item.ty),
+                    Sexp::from_syn_expr(&item.expr),
+                ],
+            ),
+            Item::Use(item) => node("use", vec![tokens(&item.tree)]),
+            Item::Mod(item) => {
+                let mut children = vec![Sexp::atom(item.ident.to_string())];
+                if let Some((_, items)) = &item.content {
+                    children.extend(items.iter().map(Sexp::from_syn));
+                }
+                node("mod", children)
+            }
+            Item::Impl(item) => {
+                let mut children = vec![tokens(&item.self_ty)];
+                if let Some((_, path, _)) = &item.trait_ {
+                    children.push(node("for", vec![tokens(path)]));
+                }
+                children.extend(item.items.iter().map(|item| match item {
+                    ImplItem::Fn(method) => function(&method.sig, &method.block),
+                    _ => verbatim(item),
+                }));
+                node("impl", children)
+            }
+            _ => verbatim(item),
+        }
+    }
+
+    /// Converts a Rust expression into a tree headed by its kind, e.g.
+    /// `(binary "+" (path a) (lit 1))`. Expression kinds without a dedicated
+    /// mapping become `(verbatim "tokens")`, so nothing is lost.
+    pub fn from_syn_expr(expr: &Expr) -> Sexp {
+        match expr {
+            Expr::Lit(lit) => {
+                let text = match &lit.lit {
+                    Lit::Bool(b) => b.value.to_string(),
+                    other => other.to_token_stream().to_string(),
+                };
+                node("lit", vec![Sexp::atom_or_string(&text)])
+            }
+            Expr::Path(path) if path.qself.is_none() => {
+                let segments: Option<Vec<String>> = path
+                    .path
+                    .segments
+                    .iter()
+                    .map(|s| match s.arguments {
+                        syn::PathArguments::None => Some(s.ident.to_string()),
+                        _ => None,
+                    })
+                    .collect();
+                match segments {
+                    Some(segments) => {
+                        let mut text = segments.join("::");
+                        if path.path.leading_colon.is_some() {
+                            text.insert_str(0, "::");
+                        }
+                        node("path", vec![Sexp::atom_or_string(&text)])
+                    }
+                    None => verbatim(expr),
+                }
+            }
+            Expr::Call(call) => {
+                let mut children = vec![Sexp::from_syn_expr(&call.func)];
+                children.extend(call.args.iter().map(Sexp::from_syn_expr));
+                node("call", children)
+            }
+            Expr::MethodCall(call) if call.turbofish.is_none() => {
+                let mut children = vec![
+                    Sexp::from_syn_expr(&call.receiver),
+                    Sexp::atom(call.method.to_string()),
+                ];
+                children.extend(call.args.iter().map(Sexp::from_syn_expr));
+                node("method_call", children)
+            }
+            Expr::Binary(binary) => node(
+                "binary",
+                vec![
+                    tokens(&binary.op),
+                    Sexp::from_syn_expr(&binary.left),
+                    Sexp::from_syn_expr(&binary.right),
+                ],
+            ),
+            Expr::Unary(unary) => node(
+                "unary",
+                vec![tokens(&unary.op), Sexp::from_syn_expr(&unary.expr)],
+            ),
+            Expr::Paren(paren) => node("paren", vec![Sexp::from_syn_expr(&paren.expr)]),
+            Expr::Field(field) => {
+                let member = match &field.member {
+                    Member::Named(ident) => ident.to_string(),
+                    Member::Unnamed(index) => index.index.to_string(),
+                };
+                node(
+                    "field",
+                    vec![Sexp::from_syn_expr(&field.base), Sexp::atom(member)],
+                )
+            }
+            Expr::Index(index) => node(
+                "index",
+                vec![
+                    Sexp::from_syn_expr(&index.expr),
+                    Sexp::from_syn_expr(&index.index),
+                ],
+            ),
+            Expr::Tuple(tuple) => node(
+                "tuple",
+                tuple.elems.iter().map(Sexp::from_syn_expr).collect(),
+            ),
+            Expr::Array(array) => node(
+                "array",
+                array.elems.iter().map(Sexp::from_syn_expr).collect(),
+            ),
+            Expr::Reference(reference) => {
+                let kind = match reference.mutability {
+                    Some(_) => "reference_mut",
+                    None => "reference",
+                };
+                node(kind, vec![Sexp::from_syn_expr(&reference.expr)])
+            }
+            Expr::Assign(assign) => node(
+                "assign",
+                vec![
+                    Sexp::from_syn_expr(&assign.left),
+                    Sexp::from_syn_expr(&assign.right),
+                ],
+            ),
+            Expr::Return(ret) => node(
+                "return",
+                ret.expr.iter().map(|e| Sexp::from_syn_expr(e)).collect(),
+            ),
+            Expr::If(expr) => {
+                let mut children = vec![Sexp::from_syn_expr(&expr.cond), block(&expr.then_branch)];
+                if let Some((_, otherwise)) = &expr.else_branch {
+                    children.push(Sexp::from_syn_expr(otherwise));
+                }
+                node("if", children)
+            }
+            Expr::Block(expr) if expr.label.is_none() => block(&expr.block),
+            Expr::Macro(mac) => node(
+                "macro",
+                vec![tokens(&mac.mac.path), tokens(&mac.mac.tokens)],
+            ),
+            _ => verbatim(expr),
+        }
+    }
+
+    /// The inverse of `from_syn_expr`, building the `syn` tree directly so
+    /// it can be spliced into generated code with `quote!` or compared
+    /// against `parse_quote!` output. Macros come back with parentheses as
+    /// their delimiter.
+    pub fn to_syn_expr(&self) -> Result<Expr, SexpError> {
+        let items = self.items();
+        let arity = |n: usize| -> Result<(), SexpError> {
+            if items.len() == n + 1 {
+                Ok(())
+            } else {
+                Err(invalid(format!("Expected {} arguments in {}", n, self)))
+            }
+        };
+        let expr =
+            |i: usize| -> Result<Box<Expr>, SexpError> { Ok(Box::new(items[i].to_syn_expr()?)) };
+
+        let expr = match self.head() {
+            Some("lit") => {
+                arity(1)?;
+                Expr::Lit(syn::ExprLit {
+                    attrs: vec![],
+                    lit: parse(&items[1])?,
+                })
+            }
+            Some("path") => {
+                arity(1)?;
+                Expr::Path(parse(&items[1])?)
+            }
+            Some("call") if items.len() >= 2 => Expr::Call(syn::ExprCall {
+                attrs: vec![],
+                func: expr(1)?,
+                paren_token: Default::default(),
+                args: to_exprs(&items[2..])?,
+            }),
+            Some("method_call") if items.len() >= 3 => Expr::MethodCall(syn::ExprMethodCall {
+                attrs: vec![],
+                receiver: expr(1)?,
+                dot_token: Default::default(),
+                method: parse(&items[2])?,
+                turbofish: None,
+                paren_token: Default::default(),
+                args: to_exprs(&items[3..])?,
+            }),
+            Some("binary") => {
+                arity(3)?;
+                Expr::Binary(syn::ExprBinary {
+                    attrs: vec![],
+                    left: expr(2)?,
+                    op: parse(&items[1])?,
+                    right: expr(3)?,
+                })
+            }
+            Some("unary") => {
+                arity(2)?;
+                Expr::Unary(syn::ExprUnary {
+                    attrs: vec![],
+                    op: parse(&items[1])?,
+                    expr: expr(2)?,
+                })
+            }
+            Some("paren") => {
+                arity(1)?;
+                Expr::Paren(syn::ExprParen {
+                    attrs: vec![],
+                    paren_token: Default::default(),
+                    expr: expr(1)?,
+                })
+            }
+            Some("field") => {
+                arity(2)?;
+                Expr::Field(syn::ExprField {
+                    attrs: vec![],
+                    base: expr(1)?,
+                    dot_token: Default::default(),
+                    member: parse(&items[2])?,
+                })
+            }
+            Some("index") => {
+                arity(2)?;
+                Expr::Index(syn::ExprIndex {
+                    attrs: vec![],
+                    expr: expr(1)?,
+                    bracket_token: Default::default(),
+                    index: expr(2)?,
+                })
+            }
+            Some("tuple") => {
+                let mut elems = to_exprs(&items[1..])?;
+                if elems.len() == 1 {
+                    elems.push_punct(Default::default());
+                }
+                Expr::Tuple(syn::ExprTuple {
+                    attrs: vec![],
+                    paren_token: Default::default(),
+                    elems,
+                })
+            }
+            Some("array") => Expr::Array(syn::ExprArray {
+                attrs: vec![],
+                bracket_token: Default::default(),
+                elems: to_exprs(&items[1..])?,
+            }),
+            Some(kind @ "reference") | Some(kind @ "reference_mut") => {
+                arity(1)?;
+                Expr::Reference(syn::ExprReference {
+                    attrs: vec![],
+                    and_token: Default::default(),
+                    mutability: match kind {
+                        "reference_mut" => Some(Default::default()),
+                        _ => None,
+                    },
+                    expr: expr(1)?,
+                })
+            }
+            Some("assign") => {
+                arity(2)?;
+                Expr::Assign(syn::ExprAssign {
+                    attrs: vec![],
+                    left: expr(1)?,
+                    eq_token: Default::default(),
+                    right: expr(2)?,
+                })
+            }
+            Some("return") if items.len() <= 2 => Expr::Return(syn::ExprReturn {
+                attrs: vec![],
+                return_token: Default::default(),
+                expr: match items.get(1) {
+                    Some(_) => Some(expr(1)?),
+                    None => None,
+                },
+            }),
+            Some("if") if items.len() == 3 || items.len() == 4 => Expr::If(syn::ExprIf {
+                attrs: vec![],
+                if_token: Default::default(),
+                cond: expr(1)?,
+                then_branch: to_block(&items[2])?,
+                else_branch: match items.get(3) {
+                    Some(_) => Some((Default::default(), expr(3)?)),
+                    None => None,
+                },
+            }),
+            Some("block") => Expr::Block(syn::ExprBlock {
+                attrs: vec![],
+                label: None,
+                block: to_block(self)?,
+            }),
+            Some("macro") => {
+                arity(2)?;
+                parse_text(&format!("{}!({})", text(&items[1])?, text(&items[2])?))?
+            }
+            Some("verbatim") => {
+                arity(1)?;
+                parse(&items[1])?
+            }
+            _ => return Err(invalid(format!("Not a Rust expression: {}", self))),
+        };
+        Ok(expr)
+    }
+
+    /// Maps every token to an atom and every group to a list headed by its
+    /// delimiters, as a string atom: `"()"`, `"[]"`, `"{}"`, or `""` for
+    /// invisible groups. Punctuation joined to what follows it stays in one
+    /// atom, like `::` or `+=`, and string literals keep their quotes. A
+    /// token whose text can't be an atom, like `;` or `'a`, is written
+    /// `("tt" ";")`. The stream itself is a list headed by `""`, which
+    /// `to_proc_macro_token_stream` turns back into the same stream.
+    pub fn from_proc_macro_token_stream(stream: &TokenStream) -> Sexp {
+        group(Delimiter::None, stream.clone())
+    }
+
+    /// Renders a template as Rust tokens. Each atom is lexed as Rust source,
+    /// so `"hi"` becomes a string literal and `println!` a macro name, and
+    /// each list becomes a parenthesized group of its elements' tokens. An
+    /// atom that doesn't lex becomes a `compile_error!` saying why, the way
+    /// a proc macro reports a problem, so the error surfaces where the
+    /// tokens are used.
+    ///
+    /// Lists headed by delimiters and `("tt" ...)` tokens, as
+    /// `from_proc_macro_token_stream` writes them, become the group or token
+    /// they stand for, and a list headed by `""` at the top is a whole
+    /// stream rather than a group.
+    pub fn to_proc_macro_token_stream(&self) -> TokenStream {
+        match delimiter(self) {
+            Some(Delimiter::None) => self.items()[1..].iter().map(to_tokens).collect(),
+            _ => to_tokens(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(source: &str) -> String {
+        Sexp::from_syn(&syn::parse_str(source).unwrap()).to_compact_string()
+    }
+
+    fn expr(source: &str) -> String {
+        Sexp::from_syn_expr(&syn::parse_str(source).unwrap()).to_compact_string()
+    }
+
+    fn round_trip(source: &str) -> String {
+        let expr: Expr = syn::parse_str(source).unwrap();
+        let converted = Sexp::from_syn_expr(&expr).to_syn_expr().unwrap();
+        assert_eq!(converted, expr);
+        converted.to_token_stream().to_string()
+    }
+
+    #[test]
+    fn test_to_syn_expr_round_trip() {
+        round_trip("a + b * (c - 1)");
+        round_trip(r#"v.push(std::mem::take(&mut x[0]), "hi")"#);
+        round_trip("if !done { let mut n = 1; n = n + 1; return; } else { (n.0, 2.5f32) }");
+        round_trip("[(x,), y, -z, ::core::f()]");
+        round_trip("|x| x + 1");
+    }
+
+    #[test]
+    fn test_to_syn_expr_macros() {
+        assert_eq!(
+            round_trip(r#"println!("{}", 1)"#),
+            r#"println ! ("{}" , 1)"#
+        );
+    }
+
+    #[test]
+    fn test_to_syn_expr_from_template() {
+        let sexp = Sexp::of_str(r#"(call (path f) (binary "*" (lit 2) (path x)))"#).unwrap();
+        let expected: Expr = syn::parse_quote!(f(2 * x));
+        assert_eq!(sexp.to_syn_expr().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_to_syn_expr_errors() {
+        assert!(Sexp::of_str("(lit)").unwrap().to_syn_expr().is_err());
+        assert!(Sexp::of_str("(frobnicate 1)")
+            .unwrap()
+            .to_syn_expr()
+            .is_err());
+        assert!(Sexp::of_str(r#"(path "not a path")"#)
+            .unwrap()
+            .to_syn_expr()
+            .is_err());
+        assert_eq!(
+            Sexp::of_str("(frobnicate 1)").unwrap().to_syn_expr(),
+            Err(SexpError::InvalidRustExpr(
+                "Not a Rust expression: (frobnicate 1)".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_to_proc_macro_token_stream() {
+        let sexp = Sexp::of_str(r#"(assert_eq! (x.len () "abc".len ()))"#).unwrap();
+        let expected = quote::quote!((assert_eq!(x.len() "abc".len())));
+        assert_eq!(
+            sexp.to_proc_macro_token_stream().to_string(),
+            expected.to_string()
+        );
+        let error = Sexp::atom(r#""\q""#).to_proc_macro_token_stream();
+        assert!(error.to_string().contains("compile_error !"), "{}", error);
+    }
+
+    #[test]
+    fn test_from_proc_macro_token_stream() {
+        let stream = quote::quote!(
+            fn f(x: u8) -> [u8; 2] {
+                g::<'a>(x, "hi", 1.5)
+            }
+        );
+        assert_eq!(
+            Sexp::from_proc_macro_token_stream(&stream).to_compact_string(),
+            r#"("" fn f ("()" x : u8) -> ("[]" u8 ("tt" ";") 2) ("{}" g :: < ("tt" "'a") > ("()" x ("tt" ",") "hi" ("tt" ",") 1.5)))"#
+        );
+        let sexp = Sexp::from_proc_macro_token_stream(&quote::quote!(r"raw" ? x));
+        assert_eq!(sexp.to_compact_string(), r#"("" ("tt" "r\"raw\"") ? x)"#);
+    }
+
+    #[test]
+    fn test_proc_macro_token_stream_round_trip() {
+        let streams = [
+            quote::quote!(a::b),
+            quote::quote!(x += 1; y -= 'c' as u8),
+            quote::quote!(
+                #[derive(Debug)]
+                struct S<'a>(&'a str, [u8; 2]);
+            ),
+            quote::quote!(|x| x || y, b"bytes", r#"raw"#, ..=),
+            quote::quote!(
+                fn f() -> Vec<u8> {
+                    vec![]
+                }
+            ),
+        ];
+        for stream in &streams {
+            let sexp = Sexp::from_proc_macro_token_stream(stream);
+            let back = sexp.to_proc_macro_token_stream();
+            assert_eq!(back.to_string(), stream.to_string());
+            assert_eq!(Sexp::from_proc_macro_token_stream(&back), sexp);
+            assert_eq!(Sexp::of_str(&sexp.to_string()).unwrap(), sexp);
+        }
+    }
+
+    #[test]
+    fn test_from_syn_fn() {
+        assert_eq!(
+            item("fn add(a: i32, mut b: i32) -> i32 { let c = a + b; c }"),
+            r#"(fn add (params (param a "i32") (param (mut b) "i32")) (returns "i32") (block (let c (binary "+" (path a) (path b))) (path c)))"#
+        );
+    }
+
+    #[test]
+    fn test_from_syn_struct_and_enum() {
+        assert_eq!(
+            item("struct Point { x: f64, y: f64 }"),
+            r#"(struct Point (field x "f64") (field y "f64"))"#
+        );
+        assert_eq!(
+            item("enum Shape { Dot, Circle(Point, f64) }"),
+            r#"(enum Shape (variant Dot) (variant Circle (field 0 "Point") (field 1 "f64")))"#
+        );
+    }
+
+    #[test]
+    fn test_from_syn_expr() {
+        assert_eq!(
+            expr(r#"v.push(std::mem::take(&mut x[0]), "hi")"#),
+            r#"(method_call (path v) push (call (path std::mem::take) (reference_mut (index (path x) (lit 0)))) (lit "\"hi\""))"#
+        );
+        assert_eq!(
+            expr("if !done { return; } else { println!(\"{}\", 1) }"),
+            r#"(if (unary "!" (path done)) (block (semi (return))) (block (macro "println" "\"{}\" , 1")))"#
+        );
+        assert_eq!(expr("|x| x + 1"), r#"(verbatim "| x | x + 1")"#);
+    }
+}
@@ -0,0 +1,510 @@
+use crate::Sexp;
+use std::collections::{HashMap, HashSet};
+
+const PRIMITIVES: &[&str] = &[
+    "+", "-", "*", "/", "=", "<", ">", "<=", ">=", "eq?", "not", "cons", "car", "cdr",
+];
+
+fn lambda(params: Vec<Sexp>, body: Sexp) -> Sexp {
+    Sexp::list(vec![Sexp::atom("lambda"), Sexp::list(params), body])
+}
+
+fn begin(body: &[Sexp]) -> Sexp {
+    match body {
+        [single] => single.clone(),
+        _ => {
+            let mut parts = vec![Sexp::atom("begin")];
+            parts.extend(body.iter().cloned());
+            Sexp::list(parts)
+        }
+    }
+}
+
+// The names and values a `let` binds, when each of its bindings is a `(name
+// value)` pair. A `(name)` alone binds `nil`.
+fn bindings(sexp: &Sexp) -> Option<Vec<(Sexp, Sexp)>> {
+    if let Sexp::Atom(_) = sexp {
+        return None;
+    }
+    sexp.items()
+        .iter()
+        .map(|binding| match binding.items() {
+            [name @ Sexp::Atom(_)] => Some((name.clone(), Sexp::atom("nil"))),
+            [name @ Sexp::Atom(_), value] => Some((name.clone(), value.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+type Rest = Box<dyn FnOnce(&mut Cps, Vec<Sexp>) -> Sexp>;
+
+#[derive(Default)]
+struct Cps {
+    fresh: usize,
+}
+
+impl Cps {
+    fn fresh(&mut self, prefix: &str) -> Sexp {
+        self.fresh += 1;
+        Sexp::atom(format!("_{}{}", prefix, self.fresh))
+    }
+
+    // Expressions that can be passed to a continuation as they are. Lambdas
+    // count, but get an extra continuation parameter on the way.
+    fn value(&mut self, expr: &Sexp) -> Option<Sexp> {
+        let items = expr.items();
+        match expr.head() {
+            _ if items.is_empty() => Some(expr.clone()),
+            Some("quote") => Some(expr.clone()),
+            Some("lambda") if items.len() >= 3 => {
+                let k = self.fresh("k");
+                let mut params = items[1].items().to_vec();
+                params.push(k.clone());
+                let body = self.cps(&begin(&items[2..]), k);
+                Some(lambda(params, body))
+            }
+            _ => None,
+        }
+    }
+
+    fn cps(&mut self, expr: &Sexp, k: Sexp) -> Sexp {
+        if let Some(value) = self.value(expr) {
+            return Sexp::list(vec![k, value]);
+        }
+
+        let items = expr.items().to_vec();
+        match expr.head() {
+            Some("if") if items.len() == 4 => self.named(k, |this, k| {
+                let test = items[1].clone();
+                this.bind(&test, move |this, test| {
+                    Sexp::list(vec![
+                        Sexp::atom("if"),
+                        test,
+                        this.cps(&items[2], k.clone()),
+                        this.cps(&items[3], k),
+                    ])
+                })
+            }),
+            Some("begin") if items.len() > 2 => {
+                let rest = begin(&items[2..]);
+                self.bind(&items[1], move |this, _| this.cps(&rest, k))
+            }
+            Some("begin") if items.len() == 2 => self.cps(&items[1], k),
+            Some("let") if items.len() >= 3 && bindings(&items[1]).is_some() => {
+                let (names, values): (Vec<Sexp>, Vec<Sexp>) =
+                    bindings(&items[1]).unwrap().into_iter().unzip();
+                let mut call = vec![lambda(names, begin(&items[2..]))];
+                call.extend(values);
+                self.cps(&Sexp::list(call), k)
+            }
+            Some("call/cc") if items.len() == 2 => self.named(k, |this, k| {
+                this.bind(&items[1], move |this, f| {
+                    let v = this.fresh("v");
+                    let ignored = this.fresh("k");
+                    let escape = lambda(vec![v.clone(), ignored], Sexp::list(vec![k.clone(), v]));
+                    Sexp::list(vec![f, escape, k])
+                })
+            }),
+            Some(op) if PRIMITIVES.contains(&op) => {
+                let op = Sexp::atom(op);
+                self.bind_all(
+                    &items[1..],
+                    vec![],
+                    Box::new(move |_, args| {
+                        let mut call = vec![op];
+                        call.extend(args);
+                        Sexp::list(vec![k, Sexp::list(call)])
+                    }),
+                )
+            }
+            _ => self.bind_all(
+                &items,
+                vec![],
+                Box::new(move |_, mut call| {
+                    call.push(k);
+                    Sexp::list(call)
+                }),
+            ),
+        }
+    }
+
+    // Evaluates `expr` and hands its value to `rest`, introducing a
+    // continuation lambda only when `expr` isn't already a value.
+    fn bind<F: FnOnce(&mut Cps, Sexp) -> Sexp>(&mut self, expr: &Sexp, rest: F) -> Sexp {
+        if let Some(value) = self.value(expr) {
+            return rest(self, value);
+        }
+        let v = self.fresh("v");
+        let body = rest(self, v.clone());
+        self.cps(expr, lambda(vec![v], body))
+    }
+
+    fn bind_all(&mut self, exprs: &[Sexp], mut values: Vec<Sexp>, rest: Rest) -> Sexp {
+        match exprs.split_first() {
+            None => rest(self, values),
+            Some((first, others)) => {
+                let others = others.to_vec();
+                self.bind(first, move |this, value| {
+                    values.push(value);
+                    this.bind_all(&others, values, rest)
+                })
+            }
+        }
+    }
+
+    // Continuations used more than once are bound to a name first, so they
+    // aren't duplicated.
+    fn named<F: FnOnce(&mut Cps, Sexp) -> Sexp>(&mut self, k: Sexp, rest: F) -> Sexp {
+        if let Sexp::Atom(_) = k {
+            return rest(self, k);
+        }
+        let name = self.fresh("k");
+        let body = rest(self, name.clone());
+        Sexp::list(vec![lambda(vec![name], body), k])
+    }
+}
+
+type Then = Box<dyn FnOnce(&mut Anf, Sexp) -> Sexp>;
+type ThenAll = Box<dyn FnOnce(&mut Anf, Vec<Sexp>) -> Sexp>;
+
+fn binding(name: Sexp, value: Sexp, body: Sexp) -> Sexp {
+    Sexp::list(vec![
+        Sexp::atom("let"),
+        Sexp::list(vec![Sexp::list(vec![name, value])]),
+        body,
+    ])
+}
+
+#[derive(Default)]
+struct Anf {
+    fresh: usize,
+}
+
+impl Anf {
+    fn fresh(&mut self) -> Sexp {
+        self.fresh += 1;
+        Sexp::atom(format!("_a{}", self.fresh))
+    }
+
+    fn is_value(expr: &Sexp) -> bool {
+        matches!(expr.head(), Some("quote") | Some("lambda")) || expr.items().is_empty()
+    }
+
+    fn term(&mut self, expr: &Sexp) -> Sexp {
+        self.normalize(expr, Box::new(|_, term| term))
+    }
+
+    fn normalize(&mut self, expr: &Sexp, then: Then) -> Sexp {
+        let items = expr.items().to_vec();
+        match expr.head() {
+            Some("lambda") if items.len() >= 3 => {
+                let body = self.term(&begin(&items[2..]));
+                then(self, lambda(items[1].items().to_vec(), body))
+            }
+            _ if Anf::is_value(expr) => then(self, expr.clone()),
+            Some("if") if items.len() == 4 => self.name(
+                &items[1].clone(),
+                Box::new(move |this, test| {
+                    let branches = Sexp::list(vec![
+                        Sexp::atom("if"),
+                        test,
+                        this.term(&items[2]),
+                        this.term(&items[3]),
+                    ]);
+                    then(this, branches)
+                }),
+            ),
+            Some("let") if items.len() >= 3 && bindings(&items[1]).is_some() => {
+                let body = begin(&items[2..]);
+                let (names, values): (Vec<Sexp>, Vec<Sexp>) =
+                    bindings(&items[1]).unwrap().into_iter().unzip();
+                if names.is_empty() {
+                    return self.normalize(&body, then);
+                }
+                // Every value is normalized before any name is bound, so
+                // that none of them sees the others, as in `let`.
+                self.terms(
+                    &values,
+                    vec![],
+                    Box::new(move |this, values| {
+                        let bindings = names
+                            .into_iter()
+                            .zip(values)
+                            .map(|(name, value)| Sexp::list(vec![name, value]))
+                            .collect();
+                        let body = this.normalize(&body, then);
+                        Sexp::list(vec![Sexp::atom("let"), Sexp::list(bindings), body])
+                    }),
+                )
+            }
+            _ => self.names(
+                &items,
+                vec![],
+                Box::new(move |this, call| then(this, Sexp::list(call))),
+            ),
+        }
+    }
+
+    // Like `normalize`, but makes sure `then` only ever sees a value, binding
+    // complex expressions to a fresh name first.
+    fn name(&mut self, expr: &Sexp, then: Then) -> Sexp {
+        self.normalize(
+            expr,
+            Box::new(move |this, term| {
+                if Anf::is_value(&term) {
+                    return then(this, term);
+                }
+                let name = this.fresh();
+                let body = then(this, name.clone());
+                binding(name, term, body)
+            }),
+        )
+    }
+
+    // Normalizes each of `exprs` in turn, leaving them terms rather than
+    // values, for the values of a `let`.
+    fn terms(&mut self, exprs: &[Sexp], mut terms: Vec<Sexp>, then: ThenAll) -> Sexp {
+        match exprs.split_first() {
+            None => then(self, terms),
+            Some((first, others)) => {
+                let others = others.to_vec();
+                self.normalize(
+                    first,
+                    Box::new(move |this, term| {
+                        terms.push(term);
+                        this.terms(&others, terms, then)
+                    }),
+                )
+            }
+        }
+    }
+
+    fn names(&mut self, exprs: &[Sexp], mut values: Vec<Sexp>, then: ThenAll) -> Sexp {
+        match exprs.split_first() {
+            None => then(self, values),
+            Some((first, others)) => {
+                let others = others.to_vec();
+                self.name(
+                    first,
+                    Box::new(move |this, value| {
+                        values.push(value);
+                        this.names(&others, values, then)
+                    }),
+                )
+            }
+        }
+    }
+}
+
+impl Sexp {
+    /// Wraps `self` in a `let` that binds every free variable found in `env`,
+    /// i.e. `(let ((x 1) (y 2)) body)`. Bindings are sorted by name so the
+    /// output is stable, and variables missing from `env` are left free.
+    pub fn close_over(&self, free_vars: &HashSet<String>, env: &HashMap<String, Sexp>) -> Sexp {
+        let mut names: Vec<&String> = free_vars.iter().filter(|v| env.contains_key(*v)).collect();
+        if names.is_empty() {
+            return self.clone();
+        }
+        names.sort();
+
+        let bindings = names
+            .into_iter()
+            .map(|name| Sexp::list(vec![Sexp::atom(name.as_str()), env[name].clone()]))
+            .collect();
+
+        Sexp::list(vec![Sexp::atom("let"), Sexp::list(bindings), self.clone()])
+    }
+
+    /// Rewrites a direct-style program into continuation-passing style. The
+    /// result is a `lambda` taking the final continuation; every other
+    /// `lambda` gets an extra continuation parameter, and calls pass one
+    /// along. Understands `quote`, `lambda`, `if`, `begin`, `let` and
+    /// `call/cc`, and applies the usual arithmetic and list primitives
+    /// directly. Everything else is treated as a function call.
+    pub fn cps_transform(&self) -> Sexp {
+        let mut cps = Cps::default();
+        let k = cps.fresh("k");
+        let body = cps.cps(self, k.clone());
+        lambda(vec![k], body)
+    }
+
+    /// Rewrites a program into administrative normal form: every argument of
+    /// a call, and every `if` test, becomes an atom, a `quote` or a `lambda`,
+    /// with the complex sub-expressions bound by nested `let`s in evaluation
+    /// order. A `let` keeps all of its bindings, with their values normalized
+    /// ahead of it, so none of them sees a name it binds.
+    pub fn a_normalize(&self) -> Sexp {
+        Anf::default().term(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_close_over() {
+        let body = Sexp::of_str("(add x y z)").unwrap();
+        let mut env = HashMap::new();
+        env.insert("y".to_string(), Sexp::atom("2"));
+        env.insert("x".to_string(), Sexp::atom("1"));
+        assert_eq!(
+            body.close_over(&vars(&["x", "y", "z"]), &env).to_string(),
+            "(let ((x 1) (y 2)) (add x y z))"
+        );
+    }
+
+    #[test]
+    fn test_cps_values() {
+        assert_eq!(
+            Sexp::of_str("x").unwrap().cps_transform().to_string(),
+            "(lambda (_k1) (_k1 x))"
+        );
+        assert_eq!(
+            Sexp::of_str("(lambda (x) x)")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) (_k1 (lambda (x _k2) (_k2 x))))"
+        );
+    }
+
+    #[test]
+    fn test_cps_calls() {
+        assert_eq!(
+            Sexp::of_str("(f (g x) 1)")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) (g x (lambda (_v2) (f _v2 1 _k1))))"
+        );
+        assert_eq!(
+            Sexp::of_str("(+ (* a b) c)")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) ((lambda (_v2) (_k1 (+ _v2 c))) (* a b)))"
+        );
+    }
+
+    #[test]
+    fn test_cps_if() {
+        assert_eq!(
+            Sexp::of_str("(if (p x) (f x) y)")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) (p x (lambda (_v2) (if _v2 (f x _k1) (_k1 y)))))"
+        );
+    }
+
+    #[test]
+    fn test_cps_call_cc() {
+        assert_eq!(
+            Sexp::of_str("(call/cc (lambda (return) (return 1)))")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) ((lambda (return _k2) (return 1 _k2)) (lambda (_v3 _k4) (_k1 _v3)) _k1))"
+        );
+    }
+
+    fn anf(input: &str) -> String {
+        Sexp::of_str(input).unwrap().a_normalize().to_string()
+    }
+
+    #[test]
+    fn test_a_normalize_calls() {
+        assert_eq!(anf("(f x 1)"), "(f x 1)");
+        assert_eq!(
+            anf("(f (g x) (h y))"),
+            "(let ((_a1 (g x))) (let ((_a2 (h y))) (f _a1 _a2)))"
+        );
+        assert_eq!(
+            anf("(+ 1 (* 2 (f x)))"),
+            "(let ((_a1 (f x))) (let ((_a2 (* 2 _a1))) (+ 1 _a2)))"
+        );
+    }
+
+    #[test]
+    fn test_a_normalize_let_and_if() {
+        assert_eq!(
+            anf("(let ((x (f (g y))) (z 1)) (h x z))"),
+            "(let ((_a1 (g y))) (let ((x (f _a1)) (z 1)) (h x z)))"
+        );
+        // y's value is the outer x, not the one bound next to it.
+        assert_eq!(
+            anf("(let ((x 2)) (let ((x 1) (y x)) y))"),
+            "(let ((x 2)) (let ((x 1) (y x)) y))"
+        );
+        assert_eq!(
+            anf("(let ((x 1) (y (f (g x)))) y)"),
+            "(let ((_a1 (g x))) (let ((x 1) (y (f _a1))) y))"
+        );
+        assert_eq!(
+            anf("(f (if (p x) a b))"),
+            "(let ((_a1 (p x))) (let ((_a2 (if _a1 a b))) (f _a2)))"
+        );
+        assert_eq!(
+            anf("(lambda (x) (f (g x)))"),
+            "(lambda (x) (let ((_a1 (g x))) (f _a1)))"
+        );
+    }
+
+    #[test]
+    fn test_cps_let() {
+        assert_eq!(
+            Sexp::of_str("(let ((x 1) (y)) (f x y))")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) ((lambda (x y _k2) (f x y _k2)) 1 nil _k1))"
+        );
+    }
+
+    #[test]
+    fn test_cps_malformed_let() {
+        // Bindings that aren't (name value) pairs make `let` an ordinary call.
+        for input in &[
+            "(let (x) x)",
+            "(let (()) x)",
+            "(let ((x 1 2)) x)",
+            "(let x x)",
+        ] {
+            let cps = Sexp::of_str(input).unwrap().cps_transform();
+            assert_eq!(Sexp::of_str(&cps.to_string()).unwrap(), cps, "{}", input);
+        }
+        assert_eq!(
+            Sexp::of_str("(let (x) x)")
+                .unwrap()
+                .cps_transform()
+                .to_string(),
+            "(lambda (_k1) (x (lambda (_v2) (let _v2 x _k1))))"
+        );
+    }
+
+    #[test]
+    fn test_a_normalize_malformed_let() {
+        assert_eq!(anf("(let ((x)) x)"), "(let ((x nil)) x)");
+        // Bindings that aren't (name value) pairs make `let` an ordinary call.
+        assert_eq!(anf("(let (x) x)"), "(let ((_a1 (x))) (let _a1 x))");
+        assert_eq!(anf("(let (()) x)"), "(let ((_a1 (()))) (let _a1 x))");
+        assert_eq!(
+            anf("(let ((x 1 2)) x)"),
+            "(let ((_a1 (x 1 2))) (let ((_a2 (_a1))) (let _a2 x)))"
+        );
+    }
+
+    #[test]
+    fn test_close_over_nothing_bound() {
+        let body = Sexp::of_str("(f a)").unwrap();
+        assert_eq!(
+            body.close_over(&vars(&["a"]), &HashMap::new()).to_string(),
+            "(f a)"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::Sexp;
+use anyhow::Error;
+
+impl Sexp {
+    /// Reads the output of tree-sitter's `Node::to_sexp()`, where anonymous
+    /// tokens show up as quoted one-element lists like `(")")`. Those are
+    /// unwrapped into plain string atoms, and `field:` prefixes (with or
+    /// without a space before the node) become the usual `field:` atom.
+    pub fn from_ts_dump(input: &str) -> Result<Sexp, Error> {
+        Ok(Sexp::of_str(input)?.normalize_ts_dump())
+    }
+
+    fn normalize_ts_dump(self) -> Sexp {
+        match self {
+            Sexp::List(parts) => {
+                let items: Vec<Sexp> = parts
+                    .into_iter()
+                    .filter(|part| !matches!(part, Sexp::Nil))
+                    .map(Sexp::normalize_ts_dump)
+                    .collect();
+                match items.as_slice() {
+                    [Sexp::Atom(token)] if token.starts_with('"') => Sexp::Atom(token.clone()),
+                    _ => Sexp::list(items),
+                }
+            }
+            sexp => sexp,
+        }
+    }
+
+    /// The node following a `name:` field label among this list's elements.
+    pub fn field(&self, name: &str) -> Option<&Sexp> {
+        let items = self.items();
+        items
+            .iter()
+            .position(|item| match item {
+                Sexp::Atom(label) => label.strip_suffix(':') == Some(name),
+                _ => false,
+            })
+            .and_then(|i| items.get(i + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoted_atoms() {
+        assert_eq!(
+            Sexp::of_str(r#"(a "b c" ")" "\"")"#).unwrap().to_string(),
+            r#"(a "b c" ")" "\"")"#
+        );
+    }
+
+    #[test]
+    fn test_ts_dump() {
+        let sexp = Sexp::from_ts_dump(include_str!("./ts_dump.in.sexp").trim()).unwrap();
+        assert_eq!(
+            sexp.to_string(),
+            include_str!("./ts_dump.out.sexp").trim().to_string()
+        );
+    }
+
+    #[test]
+    fn test_ts_dump_fields() {
+        let sexp = Sexp::from_ts_dump(include_str!("./ts_dump.in.sexp").trim()).unwrap();
+        let function = &sexp.items()[2];
+        assert_eq!(function.field("name").unwrap().to_string(), "(identifier)");
+
+        let body = function.field("body").unwrap();
+        let operator = body.items()[2].field("operator").unwrap();
+        assert_eq!(operator.to_string(), r#""+""#);
+        assert!(function.field("missing").is_none());
+    }
+}
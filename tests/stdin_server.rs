@@ -0,0 +1,172 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Server {
+    fn spawn() -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_sexpfmt"))
+            .arg("--stdin-server")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Server {
+            child,
+            stdin,
+            stdout,
+        }
+    }
+
+    fn send_raw(&mut self, frame: &[u8]) -> Value {
+        self.stdin.write_all(frame).unwrap();
+        self.stdin.flush().unwrap();
+
+        let mut length = None;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            self.stdout.read_line(&mut line).unwrap();
+            match line.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = Some(value.parse().unwrap()),
+                _ if line.trim_end().is_empty() => break,
+                _ => panic!("unexpected header {:?}", line),
+            }
+        }
+        let mut body = vec![0; length.unwrap()];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn send(&mut self, request: Value) -> Value {
+        let body = request.to_string();
+        self.send_raw(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).as_bytes())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_format() {
+    let mut server = Server::spawn();
+    let response = server.send(json!({"id": 1, "op": "format", "text": "(a\n   (b   c))"}));
+    assert_eq!(response, json!({"id": 1, "result": "(a (b c))\n"}));
+    let response = server.send(json!({"id": 2, "op": "check", "text": "(a (b c))\n"}));
+    assert_eq!(response, json!({"id": 2, "result": true}));
+}
+
+#[test]
+fn test_format_keeps_trailing_comments() {
+    let mut server = Server::spawn();
+    let response = server.send(json!({
+        "id": 1,
+        "op": "format",
+        "text": "(package (name foo) ; a\n (version 1.0) ; b\n)",
+        "config": {"align_trailing_comments": true},
+    }));
+    assert_eq!(
+        response,
+        json!({"id": 1, "result": "(package\n (name foo)     ; a\n (version 1.0)  ; b\n)\n"})
+    );
+}
+
+#[test]
+fn test_format_refuses_what_format_str_refuses() {
+    let mut server = Server::spawn();
+    let response = server.send(json!({"id": 1, "op": "format", "text": "(a b) (c d)"}));
+    assert_eq!(
+        response,
+        json!({"id": 1, "error": "Syntax error at line 1, column 1"})
+    );
+    let response = server.send(json!({"id": 2, "op": "check", "text": "(a (b"}));
+    assert_eq!(
+        response,
+        json!({"id": 2, "error": "Syntax error at line 1, column 1"})
+    );
+    let response = server.send(json!({"id": 3, "op": "check", "text": "(a (b c)"}));
+    assert_eq!(
+        response,
+        json!({"id": 3, "error": "Missing \")\" at line 1, column 9"})
+    );
+    let response = server.send(json!({"id": 4, "op": "format", "text": "  \n"}));
+    assert_eq!(
+        response,
+        json!({"id": 4, "error": "Expected a form, found nothing"})
+    );
+}
+
+#[test]
+fn test_format_refuses_own_line_comments() {
+    let mut server = Server::spawn();
+    let response = server.send(json!({"id": 1, "op": "format", "text": "(a\n ; keep me\n b)"}));
+    assert_eq!(
+        response,
+        json!({"id": 1, "error": "Comment on a line of its own at line 2, column 2"})
+    );
+}
+
+#[test]
+fn test_incremental_edit_then_format() {
+    let mut server = Server::spawn();
+    let response = server.send(json!({
+        "id": "open",
+        "op": "check",
+        "document": "dune",
+        "text": "(library (name core))",
+    }));
+    assert_eq!(response["result"], json!(false));
+
+    let response = server.send(json!({
+        "id": "edit",
+        "op": "format",
+        "document": "dune",
+        "edits": [{"start": 15, "end": 19, "text": "main"}, {"start": 20, "end": 20, "text": " (libraries unix)"}],
+    }));
+    assert_eq!(
+        response,
+        json!({"id": "edit", "result": "(library (name main) (libraries unix))\n"})
+    );
+
+    let response = server.send(json!({"id": 3, "op": "query", "document": "dune", "path": "/2/1"}));
+    assert_eq!(response, json!({"id": 3, "result": "unix"}));
+
+    let response = server.send(json!({"id": 4, "op": "format", "document": "other"}));
+    assert_eq!(response["error"], json!("Unknown document \"other\""));
+}
+
+#[test]
+fn test_malformed_frames() {
+    let mut server = Server::spawn();
+    let response = server.send_raw(b"Content-Length: 9\r\n\r\n{not json");
+    assert_eq!(response["id"], Value::Null);
+    assert!(response["error"].as_str().unwrap().contains("Invalid JSON"));
+
+    let response = server.send_raw(b"Content-Length: lots\r\n\r\n");
+    assert_eq!(response["id"], Value::Null);
+    assert!(response["error"].is_string());
+
+    let length = 64 * 1024 * 1024 + 1;
+    let mut frame = format!("Content-Length: {}\r\n\r\n", length).into_bytes();
+    frame.resize(frame.len() + length, b' ');
+    let response = server.send_raw(&frame);
+    assert_eq!(response["id"], Value::Null);
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .contains("longer than the limit"));
+
+    let response = server.send(json!({"id": 5, "op": "format", "text": "(still (here))"}));
+    assert_eq!(response, json!({"id": 5, "result": "(still (here))\n"}));
+}